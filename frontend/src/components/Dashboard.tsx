@@ -115,6 +115,11 @@ export default function Dashboard() {
                   Unrealized: {unrealizedPnl >= 0 ? '+' : ''}{unrealizedPnl.toFixed(9)} SOL ({unrealizedPnlPercent >= 0 ? '+' : ''}{unrealizedPnlPercent.toFixed(2)}%)
                 </p>
               )}
+              {stats?.paper_wallet && (
+                <p className="text-xs font-mono-tech mt-1" style={{ color: 'var(--theme-text-secondary)' }}>
+                  Paper wallet: ◎{stats.paper_wallet.balance_sol.toFixed(4)} (fees ◎{(stats.paper_wallet.pump_fees_sol + stats.paper_wallet.dev_fees_sol + stats.paper_wallet.network_fees_sol).toFixed(4)}{stats.paper_wallet.rejected_buys > 0 ? `, ${stats.paper_wallet.rejected_buys} buys rejected` : ''})
+                </p>
+              )}
             </div>
             {(stats?.total_profit || 0) >= 0 ? (
               <div className={`p-4 rounded-2xl ${celebrating ? 'animate-celebrate' : 'animate-float'}`} style={{ 
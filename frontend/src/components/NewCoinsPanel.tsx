@@ -13,6 +13,8 @@ interface DetectedCoin {
   metadata_uri?: string
   buy_price?: number
   status: 'detected' | 'bought' | 'skipped'
+  source?: 'pumpportal' | 'logs' | 'poll'
+  first_seen?: Record<string, string>
 }
 
 export default function NewCoinsPanel() {
@@ -117,6 +119,16 @@ export default function NewCoinsPanel() {
                     <span className="text-xs">
                       {new Date(coin.detected_at).toLocaleString()}
                     </span>
+                    {coin.source && (
+                      <span
+                        className="text-xs text-gray-500"
+                        title={Object.entries(coin.first_seen ?? {})
+                          .map(([src, at]) => `${src}: ${new Date(at).toLocaleTimeString()}`)
+                          .join('\n')}
+                      >
+                        via {coin.source}
+                      </span>
+                    )}
                   </div>
 
                   <div className="flex items-center gap-2">
@@ -46,6 +46,20 @@ export interface BotStats {
   last_activity: string
   running_state?: BotRunningState
   mode?: BotMode
+  paper_wallet?: PaperWallet
+}
+
+export interface PaperWallet {
+  starting_balance_sol: number
+  balance_sol: number
+  open_positions_cost_sol: number
+  realized_pnl_sol: number
+  pump_fees_sol: number
+  dev_fees_sol: number
+  network_fees_sol: number
+  buys: number
+  sells: number
+  rejected_buys: number
 }
 
 export interface HistoricalDataPoint {
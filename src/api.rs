@@ -1,5 +1,5 @@
 use axum::{
-    extract::{State, Json},
+    extract::{Path, State, Json},
     extract::ws::{WebSocket, WebSocketUpgrade, Message},
     routing::{get, post},
     Router,
@@ -18,6 +18,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::{
     models::Holding,
+    position::PositionRegistry,
     settings::Settings,
 };
 
@@ -93,6 +94,12 @@ pub struct DetectedCoin {
     pub metadata_uri: Option<String>,
     pub buy_price: Option<f64>,
     pub status: String, // "detected", "bought", "skipped"
+    /// Feed that reported the mint first: "pumpportal", "logs" or "poll"
+    #[serde(default)]
+    pub source: String,
+    /// First-seen time (RFC 3339) per feed that reported the mint
+    #[serde(default)]
+    pub first_seen: std::collections::BTreeMap<String, String>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -132,7 +139,7 @@ fn default_trade_decimals() -> u8 { 6 }
 #[serde(tag = "type")]
 pub enum WsMessage {
     #[serde(rename = "detected-coin")]
-    DetectedCoin { coin: DetectedCoin },
+    DetectedCoin { coin: Box<DetectedCoin> },
     #[serde(rename = "price-update")]
     PriceUpdate { mint: String, price: f64, profit_percent: Option<f64> },
     #[serde(rename = "holding-update")]
@@ -152,6 +159,8 @@ pub struct ApiState {
     pub is_real_flag: Arc<AtomicBool>,
     /// Whether a wallet keypair was loaded at startup (required for real mode).
     pub has_keypair: bool,
+    /// Lifecycle state of every live and recently closed position.
+    pub positions: PositionRegistry,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -171,6 +180,15 @@ pub struct BotStats {
     pub last_activity: String,
     pub running_state: Option<String>,
     pub mode: Option<String>,
+    /// Paper wallet balance and fees, in dry-run only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paper_wallet: Option<crate::paper::PaperStatus>,
+    /// Per-feed detection counters and time since each last reported a mint
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub detection_sources: Vec<crate::dedup::SourceStats>,
+    /// Websocket feeds and the age of their last data frame
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub websockets: Vec<crate::ws_supervisor::WsEndpointStatus>,
 }
 
 pub fn create_router(state: ApiState) -> Router {
@@ -186,6 +204,13 @@ pub fn create_router(state: ApiState) -> Router {
         .route("/logs", get(get_logs_handler))
         .route("/detected-coins", get(get_detected_coins_handler))
         .route("/trades", get(get_trades_handler))
+        .route("/positions", get(get_positions_handler))
+        .route("/positions/sell-all", post(sell_all_handler))
+        .route("/positions/:mint/sell", post(sell_position_handler))
+        .route("/execution/landing-stats", get(get_landing_stats_handler))
+        .route("/rpc/usage", get(get_rpc_usage_handler))
+        .route("/shadow/compare", get(get_shadow_compare_handler))
+        .route("/missed", get(get_missed_handler))
         .route("/ws", get(ws_handler))
         .with_state(state);
 
@@ -194,10 +219,19 @@ pub fn create_router(state: ApiState) -> Router {
         .layer(CorsLayer::permissive())
 }
 
-async fn health_handler() -> impl IntoResponse {
+async fn health_handler(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let rpc_urls = state.settings.lock().await.solana_rpc_urls.clone();
     Json(json!({
         "status": "ok",
-        "timestamp": Utc::now().to_rfc3339()
+        "timestamp": Utc::now().to_rfc3339(),
+        "rpc_pool": crate::rpc_pool::health(&rpc_urls).await,
+        "rpc_hedging": crate::hedge::metrics().await,
+        "hot_cache": crate::hot_cache::status().await,
+        "websockets": crate::ws_supervisor::status().await,
+        "detection_sources": crate::dedup::stats().await,
+        "poll_fallback": crate::poller::status().await
     }))
 }
 
@@ -215,6 +249,15 @@ async fn stats_handler(
         BotMode::DryRun => "dry-run".to_string(),
         BotMode::Real => "real".to_string(),
     });
+    let dry_run = matches!(*mode, BotMode::DryRun);
+    drop(mode);
+    drop(running_state);
+    if dry_run {
+        let settings = state.settings.lock().await.clone();
+        stats.paper_wallet = Some(crate::paper::status(&settings).await);
+    }
+    stats.detection_sources = crate::dedup::stats().await;
+    stats.websockets = crate::ws_supervisor::status().await;
     
     Json(stats)
 }
@@ -358,7 +401,7 @@ async fn start_bot_handler(
     
     // Simulate startup delay
     let bot_control_clone = bot_control.clone();
-    tokio::spawn(async move {
+    crate::tasks::spawn(async move {
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         let mut state = bot_control_clone.running_state.lock().await;
         *state = BotRunningState::Running;
@@ -542,6 +585,135 @@ async fn get_trades_handler(
     Json(trades.clone())
 }
 
+async fn get_positions_handler(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    Json(state.positions.list().await)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SellRequest {
+    /// Percent of the remaining amount to sell (default 100).
+    #[serde(default = "default_sell_percent")]
+    percent: f64,
+}
+
+fn default_sell_percent() -> f64 { 100.0 }
+
+/// Manual sells are executed by the position monitor, which only runs while
+/// the bot is running or draining.
+async fn manual_sell_allowed(state: &ApiState) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let running_state = state.bot_control.running_state.lock().await;
+    if matches!(*running_state, BotRunningState::Running | BotRunningState::Stopping) {
+        return Ok(());
+    }
+    Err((
+        StatusCode::CONFLICT,
+        Json(json!({
+            "status": "error",
+            "message": format!("Bot is {:?}; start it so the monitor can execute the sell", *running_state).to_lowercase()
+        })),
+    ))
+}
+
+async fn sell_position_handler(
+    State(state): State<ApiState>,
+    Path(mint): Path<String>,
+    Json(payload): Json<SellRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    if let Err(rejection) = manual_sell_allowed(&state).await {
+        return rejection;
+    }
+    match state.positions.request_manual_exit(&mint, payload.percent).await {
+        Ok(()) => {
+            state.bot_control.add_log(
+                "info",
+                format!("Manual sell of {:.0}% queued for {}", payload.percent, mint),
+                None,
+            ).await;
+            (
+                StatusCode::ACCEPTED,
+                Json(json!({
+                    "status": "success",
+                    "mint": mint,
+                    "percent": payload.percent
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": e
+            })),
+        ),
+    }
+}
+
+async fn sell_all_handler(
+    State(state): State<ApiState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    if let Err(rejection) = manual_sell_allowed(&state).await {
+        return rejection;
+    }
+    let mut queued = Vec::new();
+    for position in state.positions.list().await {
+        if position.state.is_terminal() {
+            continue;
+        }
+        if state.positions.request_manual_exit(&position.mint, 100.0).await.is_ok() {
+            queued.push(position.mint);
+        }
+    }
+    state.bot_control.add_log(
+        "info",
+        format!("Manual sell-all queued for {} position(s)", queued.len()),
+        None,
+    ).await;
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "status": "success",
+            "queued": queued
+        })),
+    )
+}
+
+async fn get_landing_stats_handler() -> impl IntoResponse {
+    Json(crate::fanout::landing_report().await)
+}
+
+async fn get_rpc_usage_handler(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let settings = state.settings.lock().await.clone();
+    Json(crate::rate_limit::usage(&settings).await)
+}
+
+async fn get_shadow_compare_handler(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let trades = state.trades.lock().await.clone();
+    let open_positions = state.positions.active_count().await;
+    match crate::shadow::compare(&trades, open_positions).await {
+        Some(comparison) => (StatusCode::OK, Json(json!(comparison))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "status": "error",
+                "message": "Shadow mode is off (shadow_config_path is empty)"
+            })),
+        ),
+    }
+}
+
+async fn get_missed_handler(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let settings = state.settings.lock().await.clone();
+    Json(crate::missed::report(&settings).await)
+}
+
 async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<ApiState>,
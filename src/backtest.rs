@@ -0,0 +1,538 @@
+//! Offline backtest of the entry filters and TP/SL/timeout exits.
+//!
+//! `sol_beast backtest <dataset> [--json] [--fill-delay-ms N]` replays recorded detections and
+//! their bonding-curve price paths through `buyer::check_entry_filters` and
+//! `monitor::evaluate_exit` using the configured settings, and reports win
+//! rate, PnL, drawdown and a breakdown by exit reason. Nothing touches the
+//! network.
+//!
+//! The dataset is JSONL (optionally gzip-compressed, `.gz`), one detected
+//! coin per line:
+//!
+//! ```json
+//! {"mint":"...","decimals":6,"path":[{"t_ms":0,"price":2.8e-8,"real_sol_reserves":0},{"t_ms":1500,"price":3.1e-8}]}
+//! ```
+//!
+//! `t_ms` is milliseconds since detection and `price` is SOL per token. The
+//! first point is the quote the bot sees: filters run on it and the token
+//! amount is `buy_amount` SOL at that price. The buy fills at the first point
+//! `fill_delay_ms` later, and fails if that price plus the pump.fun fee is
+//! more than `slippage_bps` above the quote, as the on-chain `max_sol_cost`
+//! check would. Buys and sells are costed with the paper wallet's fill math
+//! (`paper::buy_fill`/`paper::sell_fill`): pump.fun fee
+//! (`paper_pump_fee_bps`), dev fee, the signature, priority fee and tip of
+//! the configured execution backend, and ATA rent. The fill delay stands in
+//! for `paper_latency_slippage_bps`, which is not applied.
+//!
+//! `sol_beast export-dataset` builds a dataset from a feed recording, or from
+//! the missed-opportunity paths of a running bot.
+
+use crate::buyer::check_entry_filters;
+use crate::missed::Watch;
+use crate::models::Holding;
+use crate::monitor::evaluate_exit;
+use crate::paper;
+use crate::recorder::Record;
+use crate::settings::Settings;
+use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
+use chrono::Utc;
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::str::FromStr;
+
+/// One observation on a bonding curve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricePoint {
+    /// Milliseconds since detection
+    pub t_ms: u64,
+    /// SOL per token
+    pub price: f64,
+    #[serde(default)]
+    pub real_sol_reserves: Option<u64>,
+}
+
+/// A recorded detection with the price path that followed it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoinPath {
+    pub mint: String,
+    #[serde(default)]
+    pub decimals: Option<u8>,
+    pub path: Vec<PricePoint>,
+}
+
+/// One (partial) sell during a simulated position.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedExit {
+    pub t_ms: u64,
+    pub price: f64,
+    pub amount: u64,
+    pub reason: String,
+    pub pnl_sol: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "lowercase")]
+pub enum CoinOutcome {
+    /// An entry filter refused the buy, or the path had no usable price.
+    Skipped { mint: String, filter: String, reason: String },
+    Traded { mint: String, buy_price: f64, pnl_sol: f64, pnl_percent: f64, exits: Vec<SimulatedExit> },
+}
+
+/// Reason a position ended without a TP/SL/timeout sell.
+pub const END_OF_DATA: &str = "END_OF_DATA";
+
+/// Run one coin through the entry filters and the exit rules, with the buy
+/// landing `fill_delay_ms` after the quote.
+pub fn simulate(coin: &CoinPath, settings: &Settings, fill_delay_ms: u64) -> CoinOutcome {
+    let skipped = |filter: &str, reason: String| CoinOutcome::Skipped { mint: coin.mint.clone(), filter: filter.to_string(), reason };
+    let Some(quote) = coin.path.first().filter(|p| p.price > 0.0 && p.price.is_finite()) else {
+        return skipped("no_price", "price path is empty or starts without a price".to_string());
+    };
+    let decimals = coin.decimals.unwrap_or(settings.default_token_decimals);
+    let token_divisor = 10f64.powi(decimals as i32);
+    let token_amount = ((settings.buy_amount / quote.price) * token_divisor) as u64;
+    if let Err(rejected) = check_entry_filters(token_amount, quote.price, quote.real_sol_reserves, settings) {
+        return skipped(rejected.filter, rejected.reason);
+    }
+    if token_amount == 0 {
+        return skipped("no_price", "buy_amount buys zero tokens at the entry price".to_string());
+    }
+    let Some(fill_idx) = coin.path.iter().position(|p| p.t_ms >= quote.t_ms + fill_delay_ms) else {
+        return skipped("no_price", "price path ends before the buy lands".to_string());
+    };
+    let entry = &coin.path[fill_idx];
+    // The fill delay already moves the price along the path; don't add the
+    // paper wallet's latency slippage on top
+    let fee_settings = Settings { paper_latency_slippage_bps: 0, ..settings.clone() };
+    let buy = match paper::buy_fill(settings.buy_amount, token_amount, None, entry.price, decimals, &fee_settings) {
+        Ok(fill) => fill,
+        Err(reason) => return skipped("slippage_bps", reason),
+    };
+    let buy_cost_sol = -buy.net_sol();
+    // Buy cost (fees, tip and rent included) attributed to `amount` of the tokens
+    let cost_of = |amount: u64| buy_cost_sol * amount as f64 / token_amount as f64;
+
+    let mut holding = Holding {
+        amount: token_amount,
+        original_amount: token_amount,
+        buy_price: entry.price,
+        buy_time: Utc::now(),
+        decimals,
+        buy_cost_sol: Some(buy_cost_sol),
+        triggered_tp_levels: Vec::new(),
+        triggered_sl_levels: Vec::new(),
+        metadata: None,
+        onchain_raw: None,
+        onchain: None,
+    };
+    let timeout_ms = settings.timeout_secs.max(0) as u64 * 1000;
+    let mut exits = Vec::new();
+    let mut last_price = entry.price;
+    for point in &coin.path[fill_idx..] {
+        let elapsed_ms = point.t_ms.saturating_sub(entry.t_ms);
+        let is_timed_out = elapsed_ms >= timeout_ms;
+        // A timed-out position is sold at the last price known at the
+        // timeout, like the live monitor selling from its price cache
+        let (t_ms, price) = if is_timed_out && elapsed_ms > timeout_ms {
+            (entry.t_ms + timeout_ms, last_price)
+        } else {
+            (point.t_ms, point.price)
+        };
+        let profit_percent = (price - holding.buy_price) / holding.buy_price * 100.0;
+        let decision = evaluate_exit(&holding, profit_percent, is_timed_out, settings);
+        if decision.sell_amount > 0 {
+            let is_final_sell = decision.sell_amount >= holding.amount;
+            // A sell that breaks slippage_bps fails like the transaction
+            // would; the position stays open for the next point
+            if let Ok(sell) = paper::sell_fill(decision.sell_amount, None, price, decimals, is_final_sell, &fee_settings) {
+                exits.push(SimulatedExit {
+                    t_ms,
+                    price,
+                    amount: decision.sell_amount,
+                    pnl_sol: sell.net_sol() - cost_of(decision.sell_amount),
+                    reason: decision.reason,
+                });
+                holding.amount -= decision.sell_amount;
+                holding.triggered_tp_levels.extend(decision.triggered_tp);
+                holding.triggered_sl_levels.extend(decision.triggered_sl);
+            }
+        }
+        if holding.amount == 0 || is_timed_out {
+            break;
+        }
+        last_price = point.price;
+    }
+    if holding.amount > 0 {
+        let last = coin.path.last().unwrap_or(entry);
+        // Marked to a sell at the last price; tokens that cannot be sold
+        // within slippage_bps are worth nothing
+        let proceeds = paper::sell_fill(holding.amount, None, last.price, decimals, true, &fee_settings).map(|f| f.net_sol()).unwrap_or(0.0);
+        exits.push(SimulatedExit {
+            t_ms: last.t_ms,
+            price: last.price,
+            amount: holding.amount,
+            pnl_sol: proceeds - cost_of(holding.amount),
+            reason: END_OF_DATA.to_string(),
+        });
+    }
+
+    let pnl_sol: f64 = exits.iter().map(|e| e.pnl_sol).sum();
+    CoinOutcome::Traded {
+        mint: coin.mint.clone(),
+        buy_price: entry.price,
+        pnl_sol,
+        pnl_percent: pnl_sol / buy_cost_sol * 100.0,
+        exits,
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReasonStats {
+    pub count: usize,
+    pub pnl_sol: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BacktestReport {
+    pub coins: usize,
+    pub trades: usize,
+    /// Coins skipped, by the filter that refused them
+    pub skipped: BTreeMap<String, usize>,
+    pub wins: usize,
+    pub losses: usize,
+    pub win_rate: f64,
+    pub total_pnl_sol: f64,
+    pub avg_pnl_percent: f64,
+    /// Mean over standard deviation of per-trade PnL (0 with fewer than two trades)
+    pub sharpe: f64,
+    /// Largest peak-to-trough fall of cumulative PnL, in SOL
+    pub max_drawdown_sol: f64,
+    /// Sells by exit reason (`TP1`, `SL2`, `TIMEOUT`, ...); a sell that hit
+    /// several levels at once counts under the first
+    pub exit_reasons: BTreeMap<String, ReasonStats>,
+}
+
+/// Simulate every coin in dataset order and aggregate the results.
+pub fn run(coins: &[CoinPath], settings: &Settings, fill_delay_ms: u64) -> BacktestReport {
+    let mut report = BacktestReport { coins: coins.len(), ..Default::default() };
+    let (mut equity, mut peak) = (0.0f64, 0.0f64);
+    let mut pnl_percent_total = 0.0;
+    let mut trade_pnls = Vec::new();
+    for coin in coins {
+        match simulate(coin, settings, fill_delay_ms) {
+            CoinOutcome::Skipped { filter, .. } => *report.skipped.entry(filter).or_default() += 1,
+            CoinOutcome::Traded { pnl_sol, pnl_percent, exits, .. } => {
+                report.trades += 1;
+                if pnl_sol > 0.0 { report.wins += 1 } else { report.losses += 1 }
+                pnl_percent_total += pnl_percent;
+                trade_pnls.push(pnl_sol);
+                for exit in exits {
+                    let key = exit.reason.split_whitespace().next().unwrap_or_default().to_string();
+                    let stats = report.exit_reasons.entry(key).or_default();
+                    stats.count += 1;
+                    stats.pnl_sol += exit.pnl_sol;
+                }
+                equity += pnl_sol;
+                peak = peak.max(equity);
+                report.max_drawdown_sol = report.max_drawdown_sol.max(peak - equity);
+            }
+        }
+    }
+    report.total_pnl_sol = equity;
+    if report.trades > 0 {
+        report.win_rate = report.wins as f64 / report.trades as f64;
+        report.avg_pnl_percent = pnl_percent_total / report.trades as f64;
+    }
+    if trade_pnls.len() >= 2 {
+        let mean = equity / trade_pnls.len() as f64;
+        let variance = trade_pnls.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (trade_pnls.len() - 1) as f64;
+        if variance > 0.0 {
+            report.sharpe = mean / variance.sqrt();
+        }
+    }
+    report
+}
+
+/// Read a dataset; files ending in `.gz` are decompressed.
+pub fn load_dataset(path: &str) -> Result<Vec<CoinPath>, Box<dyn std::error::Error + Send + Sync>> {
+    let file = std::fs::File::open(path)?;
+    let reader: Box<dyn Read> = if path.ends_with(".gz") { Box::new(MultiGzDecoder::new(file)) } else { Box::new(file) };
+    let mut coins = Vec::new();
+    for (n, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut coin: CoinPath = serde_json::from_str(&line).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+        coin.path.sort_by_key(|p| p.t_ms);
+        coins.push(coin);
+    }
+    Ok(coins)
+}
+
+/// Write a dataset as JSONL; paths ending in `.gz` are compressed.
+pub fn save_dataset(coins: &[CoinPath], path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let file = std::fs::File::create(path)?;
+    if path.ends_with(".gz") {
+        let mut gz = GzEncoder::new(file, Compression::default());
+        write_dataset(coins, &mut gz)?;
+        gz.finish()?;
+    } else {
+        write_dataset(coins, &mut std::io::BufWriter::new(file))?;
+    }
+    Ok(())
+}
+
+pub fn write_dataset(coins: &[CoinPath], out: &mut impl Write) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    for coin in coins {
+        serde_json::to_writer(&mut *out, coin)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Dataset from a feed recording: each recorded first sighting, with the
+/// bonding-curve reads (`getAccountInfo`, `getMultipleAccounts`) of that
+/// coin that followed it as the price path. A coin is priced only as often
+/// as the bot read its curve; coins never read are left out.
+pub fn dataset_from_recording(records: &[Record], settings: &Settings) -> Result<Vec<CoinPath>, Box<dyn std::error::Error + Send + Sync>> {
+    let pump_program = Pubkey::from_str(&settings.pump_fun_program)?;
+    let mut coins: Vec<(i64, CoinPath)> = Vec::new();
+    let mut by_curve: HashMap<String, usize> = HashMap::new();
+    for record in records {
+        match record {
+            Record::Detection { t_ms, mint } => {
+                let Ok(mint_pubkey) = Pubkey::from_str(mint) else { continue };
+                let (curve_pda, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pubkey.as_ref()], &pump_program);
+                // A mint sighted again in a later run keeps its first path
+                if let std::collections::hash_map::Entry::Vacant(slot) = by_curve.entry(curve_pda.to_string()) {
+                    slot.insert(coins.len());
+                    coins.push((*t_ms, CoinPath { mint: mint.clone(), decimals: None, path: Vec::new() }));
+                }
+            }
+            Record::Rpc { t_ms, method, params, response } => {
+                let Ok(response) = serde_json::from_str::<Value>(response) else { continue };
+                let value = &response["result"]["value"];
+                let accounts: Vec<(&Value, &Value)> = match method.as_str() {
+                    "getAccountInfo" => vec![(&params[0], value)],
+                    "getMultipleAccounts" => match (params[0].as_array(), value.as_array()) {
+                        (Some(keys), Some(values)) => keys.iter().zip(values).collect(),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                for (key, account) in accounts {
+                    let Some(&idx) = key.as_str().and_then(|k| by_curve.get(k)) else { continue };
+                    let (detected_ms, coin) = &mut coins[idx];
+                    if *t_ms < *detected_ms {
+                        continue;
+                    }
+                    let Some(data) = account["data"][0].as_str() else { continue };
+                    let Some(state) = Base64Engine.decode(data).ok().and_then(|d| crate::rpc::parse_bonding_curve(&d)) else { continue };
+                    let Some(price) = state.spot_price_sol_per_token() else { continue };
+                    coin.path.push(PricePoint { t_ms: (*t_ms - *detected_ms) as u64, price, real_sol_reserves: Some(state.real_sol_reserves) });
+                }
+            }
+            Record::Feed { .. } | Record::Http { .. } => {}
+        }
+    }
+    Ok(coins
+        .into_iter()
+        .map(|(_, mut coin)| {
+            coin.path.sort_by_key(|p| p.t_ms);
+            coin
+        })
+        .filter(|coin| !coin.path.is_empty())
+        .collect())
+}
+
+/// Dataset from finished missed-opportunity watches (`GET /api/missed`).
+/// Each path starts at the skip: with the skip-time quote when the bot had
+/// one, otherwise with the first poll.
+pub fn dataset_from_missed(watches: &[Watch]) -> Vec<CoinPath> {
+    watches
+        .iter()
+        .filter(|watch| !watch.path.is_empty())
+        .map(|watch| CoinPath { mint: watch.mint.clone(), decimals: None, path: watch.path.clone() })
+        .collect()
+}
+
+/// Human-readable report for the terminal.
+pub fn format_report(report: &BacktestReport) -> String {
+    let mut out = format!(
+        "Coins: {}  Trades: {}  Skipped: {}\n\
+         Win rate: {:.1}% ({} wins / {} losses)\n\
+         Total PnL: {:+.6} SOL  Avg PnL: {:+.2}%  Sharpe: {:.3}  Max drawdown: {:.6} SOL\n",
+        report.coins,
+        report.trades,
+        report.skipped.values().sum::<usize>(),
+        report.win_rate * 100.0,
+        report.wins,
+        report.losses,
+        report.total_pnl_sol,
+        report.avg_pnl_percent,
+        report.sharpe,
+        report.max_drawdown_sol,
+    );
+    if !report.exit_reasons.is_empty() {
+        out.push_str("Exit reasons:\n");
+        for (reason, stats) in &report.exit_reasons {
+            out.push_str(&format!("  {:<12} {:>6}  {:+.6} SOL\n", reason, stats.count, stats.pnl_sol));
+        }
+    }
+    if !report.skipped.is_empty() {
+        out.push_str("Skipped by filter:\n");
+        for (filter, count) in &report.skipped {
+            out.push_str(&format!("  {:<22} {:>6}\n", filter, count));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{SlLevel, TpLevel};
+
+    fn point(t_ms: u64, price: f64) -> PricePoint {
+        PricePoint { t_ms, price, real_sol_reserves: None }
+    }
+
+    fn test_settings() -> Settings {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.buy_amount = 1.0;
+        settings.timeout_secs = 60;
+        settings.tp_levels = vec![TpLevel { trigger_percent: 50.0, sell_percent: 50.0 }];
+        settings.sl_levels = vec![SlLevel { trigger_percent: -20.0, sell_percent: 100.0 }];
+        settings.enable_safer_sniping = false;
+        // Only the signature fee is left, so PnL follows the price moves
+        settings.paper_pump_fee_bps = 0;
+        settings.dev_fee_enabled = false;
+        settings.compute_unit_price_micro_lamports = 0;
+        settings.buy_execution_backend = "rpc".into();
+        settings.sell_execution_backend = "rpc".into();
+        settings
+    }
+
+    /// Signature fee of one transaction, in SOL.
+    const TX_FEE: f64 = 5e-6;
+
+    #[test]
+    fn partial_take_profit_then_timeout_at_last_known_price() {
+        let settings = test_settings();
+        let coin = CoinPath {
+            mint: "a".into(),
+            decimals: Some(6),
+            path: vec![point(0, 1e-6), point(5_000, 1.6e-6), point(30_000, 1.2e-6), point(90_000, 5e-7)],
+        };
+        let CoinOutcome::Traded { exits, pnl_sol, .. } = simulate(&coin, &settings, 0) else { panic!("expected a trade") };
+        let reasons: Vec<&str> = exits.iter().map(|e| e.reason.split_whitespace().next().unwrap()).collect();
+        assert_eq!(reasons, vec!["TP1", "TIMEOUT"]);
+        assert_eq!(exits[1].t_ms, 60_000);
+        assert_eq!(exits[1].price, 1.2e-6);
+        // Half sold at +60%, half at +20%, less a buy and two sells
+        assert!((pnl_sol - (0.4 - 3.0 * TX_FEE)).abs() < 1e-6, "pnl {}", pnl_sol);
+    }
+
+    #[test]
+    fn report_counts_filters_and_drawdown() {
+        let mut settings = test_settings();
+        settings.enable_safer_sniping = true;
+        settings.min_tokens_threshold = 0;
+        settings.max_sol_per_token = 1e-5;
+        settings.min_liquidity_sol = 0.0;
+        let coins = vec![
+            CoinPath { mint: "win".into(), decimals: Some(6), path: vec![point(0, 1e-6), point(1_000, 2e-6)] },
+            CoinPath { mint: "loss".into(), decimals: Some(6), path: vec![point(0, 1e-6), point(1_000, 7e-7)] },
+            CoinPath { mint: "pricey".into(), decimals: Some(6), path: vec![point(0, 1e-3)] },
+        ];
+        let report = run(&coins, &settings, 0);
+        assert_eq!(report.trades, 2);
+        assert_eq!(report.skipped.get("max_sol_per_token"), Some(&1));
+        assert_eq!(report.wins, 1);
+        assert_eq!(report.exit_reasons["SL1"].count, 1);
+        assert!((report.max_drawdown_sol - (0.3 + 2.0 * TX_FEE)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn buy_fails_when_fill_moves_past_slippage() {
+        let mut settings = test_settings();
+        settings.slippage_bps = 1000;
+        let coin = CoinPath { mint: "a".into(), decimals: Some(6), path: vec![point(0, 1e-6), point(400, 1.05e-6), point(900, 1.2e-6)] };
+        assert!(matches!(simulate(&coin, &settings, 300), CoinOutcome::Traded { buy_price, .. } if buy_price == 1.05e-6));
+        assert!(matches!(simulate(&coin, &settings, 500), CoinOutcome::Skipped { filter, .. } if filter == "slippage_bps"));
+    }
+
+    #[test]
+    fn pnl_pays_pump_fee_dev_fee_and_tip() {
+        let mut settings = test_settings();
+        settings.paper_pump_fee_bps = 100;
+        settings.dev_fee_enabled = true;
+        settings.sell_execution_backend = "jito".into();
+        settings.jito_tip_sol = 0.001;
+        settings.tp_levels = vec![TpLevel { trigger_percent: 50.0, sell_percent: 100.0 }];
+        let coin = CoinPath { mint: "a".into(), decimals: Some(6), path: vec![point(0, 1e-6), point(1_000, 2e-6)] };
+        let CoinOutcome::Traded { pnl_sol, .. } = simulate(&coin, &settings, 0) else { panic!("expected a trade") };
+        // 1 SOL in (+1% fee), 2 SOL out (-1% fee), dev fee on both legs, a
+        // signature fee per transaction and the jito tip on the sell
+        let dev_fees = (crate::dev_fee::calculate_dev_fee(1_000_000_000) + crate::dev_fee::calculate_dev_fee(2_000_000_000)) as f64 / 1e9;
+        let expected = 2.0 * 0.99 - 1.0 * 1.01 - dev_fees - 2.0 * TX_FEE - 0.001;
+        assert!((pnl_sol - expected).abs() < 1e-6, "pnl {} expected {}", pnl_sol, expected);
+    }
+
+    #[test]
+    fn recording_exports_curve_reads_after_each_detection() {
+        let settings = test_settings();
+        let mint = Pubkey::new_unique();
+        let pump_program = Pubkey::from_str(&settings.pump_fun_program).unwrap();
+        let curve = Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &pump_program).0.to_string();
+        let curve_data = |vsol: u64, real_sol: u64| {
+            let mut data = vec![0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+            for v in [1_000_000_000_000u64, vsol, 0, real_sol, 0] {
+                data.extend(v.to_le_bytes());
+            }
+            data.push(0);
+            Base64Engine.encode(data)
+        };
+        let account = |vsol: u64, real_sol: u64| serde_json::json!({ "data": [curve_data(vsol, real_sol), "base64"] });
+        let records = vec![
+            Record::Rpc {
+                t_ms: 900,
+                method: "getAccountInfo".into(),
+                params: serde_json::json!([curve, {}]),
+                response: serde_json::json!({ "result": { "value": account(10_000_000_000, 0) } }).to_string(),
+            },
+            Record::Detection { t_ms: 1_000, mint: mint.to_string() },
+            Record::Rpc {
+                t_ms: 1_200,
+                method: "getAccountInfo".into(),
+                params: serde_json::json!([curve, {}]),
+                response: serde_json::json!({ "result": { "value": account(30_000_000_000, 1_000) } }).to_string(),
+            },
+            Record::Rpc {
+                t_ms: 4_000,
+                method: "getMultipleAccounts".into(),
+                params: serde_json::json!([[Pubkey::new_unique().to_string(), curve], {}]),
+                response: serde_json::json!({ "result": { "value": [null, account(45_000_000_000, 2_000)] } }).to_string(),
+            },
+            Record::Detection { t_ms: 5_000, mint: Pubkey::new_unique().to_string() },
+        ];
+        let coins = dataset_from_recording(&records, &settings).unwrap();
+        assert_eq!(coins.len(), 1, "unread coins are left out");
+        assert_eq!(coins[0].mint, mint.to_string());
+        let path: Vec<(u64, Option<u64>)> = coins[0].path.iter().map(|p| (p.t_ms, p.real_sol_reserves)).collect();
+        assert_eq!(path, vec![(200, Some(1_000)), (3_000, Some(2_000))]);
+        assert!((coins[0].path[0].price - 3e-5).abs() < 1e-15 && (coins[0].path[1].price - 4.5e-5).abs() < 1e-15);
+
+        let mut out = Vec::new();
+        write_dataset(&coins, &mut out).unwrap();
+        let line = String::from_utf8(out).unwrap();
+        let read_back: CoinPath = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!((read_back.mint.as_str(), read_back.path.len()), (coins[0].mint.as_str(), 2));
+    }
+}
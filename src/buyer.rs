@@ -1,14 +1,14 @@
 use serde_json::{json, Value};
 use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
 use crate::{
-    models::{Holding, PriceCache},
+    models::{BondingCurveState, Holding, PriceCache},
     settings::Settings,
     rpc::{fetch_current_price, fetch_bonding_curve_state, fetch_fee_recipient_for_mint, detect_idl_for_mint, fetch_bonding_curve_creator, build_missing_ata_preinstructions, fetch_with_fallback, detect_token_program_for_mint},
-    tx_builder::{build_buy_instruction},
+    tx_builder::{build_buy_instruction, build_sell_instruction},
     idl::load_all_idls,
     onchain_idl::get_instruction_discriminator,
 };
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use std::{sync::Arc, collections::HashMap};
 use tokio::sync::Mutex;
 use solana_sdk::{
@@ -19,65 +19,167 @@ use log::{info, warn, debug};
 use std::str::FromStr;
 use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
 use spl_associated_token_account::get_associated_token_address_with_program_id;
-use chrono::Utc;
 
-pub async fn buy_token(
+/// A buy refused by one of the safer-sniping filters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryRejected {
+    /// Setting that rejected the buy, e.g. `max_sol_per_token`
+    pub filter: &'static str,
+    pub reason: String,
+}
+
+impl std::fmt::Display for EntryRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for EntryRejected {}
+
+/// The safer-sniping entry filters (no-op unless `enable_safer_sniping`).
+/// Pure, so the backtester applies exactly the checks a live buy does.
+/// Liquidity is only checked when the curve's `real_sol_reserves` is known.
+pub fn check_entry_filters(
+    token_amount: u64,
+    buy_price_sol: f64,
+    real_sol_reserves: Option<u64>,
+    settings: &Settings,
+) -> Result<(), EntryRejected> {
+    if !settings.enable_safer_sniping {
+        return Ok(());
+    }
+    // Check 1: Minimum tokens threshold
+    if token_amount < settings.min_tokens_threshold {
+        return Err(EntryRejected {
+            filter: "min_tokens_threshold",
+            reason: format!(
+                "Token amount {} is below minimum threshold {} (price too high: {:.18} SOL/token)",
+                token_amount, settings.min_tokens_threshold, buy_price_sol
+            ),
+        });
+    }
+
+    // Check 2: Maximum SOL per token (price ceiling)
+    if buy_price_sol > settings.max_sol_per_token {
+        return Err(EntryRejected {
+            filter: "max_sol_per_token",
+            reason: format!(
+                "Token price {:.18} SOL/token exceeds maximum {:.18} SOL/token (already too expensive)",
+                buy_price_sol, settings.max_sol_per_token
+            ),
+        });
+    }
+
+    // Check 3: Liquidity checks (requires bonding curve data)
+    if let Some(reserves) = real_sol_reserves {
+        let real_sol = reserves as f64 / 1_000_000_000.0;
+        if real_sol < settings.min_liquidity_sol {
+            return Err(EntryRejected {
+                filter: "min_liquidity_sol",
+                reason: format!(
+                    "Liquidity {:.4} SOL is below minimum {:.4} SOL (too risky)",
+                    real_sol, settings.min_liquidity_sol
+                ),
+            });
+        }
+        if real_sol > settings.max_liquidity_sol {
+            return Err(EntryRejected {
+                filter: "max_liquidity_sol",
+                reason: format!(
+                    "Liquidity {:.4} SOL exceeds maximum {:.4} SOL (too late)",
+                    real_sol, settings.max_liquidity_sol
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// What a buy is priced on: the spot price, the curve it came from when
+/// known, and the mint's decimals. Resolved once per detection and shared by
+/// the live buy and the shadow strategy.
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub price: f64,
+    pub curve: Option<BondingCurveState>,
+    pub decimals: u8,
+    /// The curve came from the detection event rather than an account read
+    pub curve_seeded: bool,
+}
+
+/// Quote `mint` for a buy. Curve state seeded from the detection event lets a
+/// fresh snipe skip the curve reads; otherwise the price comes from the price
+/// cache (or one RPC read) and the curve is read once.
+pub async fn quote(
     mint: &str,
-    sol_amount: f64,
-    is_real: bool,
-    keypair: Option<&Keypair>,
-    simulate_keypair: Option<&Keypair>,
-    price_cache: Arc<Mutex<PriceCache>>,
+    price_cache: &Arc<Mutex<PriceCache>>,
     rpc_client: &Arc<RpcClient>,
     settings: &Arc<Settings>,
-) -> Result<Holding, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Quote, Box<dyn std::error::Error + Send + Sync>> {
+    let seeded_curve = crate::hot_cache::take_seeded_curve(mint).await;
+    let curve_seeded = seeded_curve.is_some();
     // fetch_current_price now returns SOL per token
-    let buy_price_sol = fetch_current_price(mint, &price_cache, rpc_client, settings).await?;
-    // Compute token amount as SOL amount divided by SOL per token, using actual mint decimals
+    let price = match seeded_curve.as_ref().and_then(|s| s.spot_price_sol_per_token()) {
+        Some(price) => {
+            price_cache.lock().await.put(mint.to_string(), (std::time::Instant::now(), price));
+            price
+        }
+        None => fetch_current_price(mint, price_cache, rpc_client, settings).await?,
+    };
+    let curve = match seeded_curve {
+        Some(state) => Some(state),
+        None => fetch_bonding_curve_state(mint, rpc_client, settings).await.ok(),
+    };
     let decimals = match crate::rpc::fetch_mint_decimals(mint, rpc_client, settings).await {
-        Ok(d) => d as i32,
+        Ok(d) => d,
         Err(e) => {
             warn!("Failed to fetch mint decimals for {}: {} -- falling back to {}", mint, e, settings.default_token_decimals);
-            settings.default_token_decimals as i32
+            settings.default_token_decimals
         }
     };
+    Ok(Quote { price, curve, decimals, curve_seeded })
+}
+
+/// Part of a Jito buy sold again in the same bundle.
+#[derive(Debug, Clone)]
+pub struct SameSlotSell {
+    /// Base units sold
+    pub tokens: u64,
+    /// SOL the sell returned
+    pub proceeds_sol: f64,
+    /// Share of the buy's SOL cost carried by the sold tokens
+    pub cost_sol: f64,
+    pub signature: Option<String>,
+}
+
+/// A landed (or simulated) buy: the holding to open and, for a Jito bundle
+/// with `jito_same_slot_sell_percent`, the sell that landed with it.
+#[derive(Debug, Clone)]
+pub struct Bought {
+    /// The tokens still held; `amount` is 0 when the same-slot sell took them all
+    pub holding: Holding,
+    pub same_slot_sell: Option<SameSlotSell>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn buy_token(
+    mint: &str,
+    sol_amount: f64,
+    quote: Quote,
+    is_real: bool,
+    keypair: Option<&Keypair>,
+    simulate_keypair: Option<&Keypair>,
+    rpc_client: &Arc<RpcClient>,
+    settings: &Arc<Settings>,
+) -> Result<Bought, Box<dyn std::error::Error + Send + Sync>> {
+    let Quote { price: buy_price_sol, curve: curve_state, decimals, curve_seeded } = quote;
+    // Compute token amount as SOL amount divided by SOL per token, using actual mint decimals
+    let decimals = decimals as i32;
     let token_amount = ((sol_amount / buy_price_sol) * 10f64.powi(decimals)) as u64;
     
     // Safety checks when enabled
-    if settings.enable_safer_sniping {
-        // Check 1: Minimum tokens threshold
-        if token_amount < settings.min_tokens_threshold {
-            return Err(format!(
-                "Token amount {} is below minimum threshold {} (price too high: {:.18} SOL/token)",
-                token_amount, settings.min_tokens_threshold, buy_price_sol
-            ).into());
-        }
-        
-        // Check 2: Maximum SOL per token (price ceiling)
-        if buy_price_sol > settings.max_sol_per_token {
-            return Err(format!(
-                "Token price {:.18} SOL/token exceeds maximum {:.18} SOL/token (already too expensive)",
-                buy_price_sol, settings.max_sol_per_token
-            ).into());
-        }
-        
-        // Check 3: Liquidity checks (requires bonding curve data)
-        if let Ok(state) = fetch_bonding_curve_state(mint, rpc_client, settings).await {
-            let real_sol = state.real_sol_reserves as f64 / 1_000_000_000.0;
-            if real_sol < settings.min_liquidity_sol {
-                return Err(format!(
-                    "Liquidity {:.4} SOL is below minimum {:.4} SOL (too risky)",
-                    real_sol, settings.min_liquidity_sol
-                ).into());
-            }
-            if real_sol > settings.max_liquidity_sol {
-                return Err(format!(
-                    "Liquidity {:.4} SOL exceeds maximum {:.4} SOL (too late)",
-                    real_sol, settings.max_liquidity_sol
-                ).into());
-            }
-        }
-    }
+    let real_sol_reserves = curve_state.as_ref().map(|state| state.real_sol_reserves);
+    check_entry_filters(token_amount, buy_price_sol, real_sol_reserves, settings)?;
     
     info!(
         "Buy {}: {} tokens for {} SOL (price: {:.18} SOL/token)",
@@ -89,27 +191,38 @@ pub async fn buy_token(
 
     // Fetch fee_recipient from Global PDA (needed for both real and simulate modes)
     // Check bonding curve state for mayhem mode to get the correct fee recipient
-    let is_mayhem = match fetch_bonding_curve_state(mint, rpc_client, settings).await {
-        Ok(state) => state.is_mayhem_mode,
-        Err(_) => false,
-    };
+    let is_mayhem = curve_state.as_ref().map(|s| s.is_mayhem_mode).unwrap_or(false);
     let fee_recipient = fetch_fee_recipient_for_mint(is_mayhem, rpc_client, settings).await?;
 
     // Detect which token program this mint uses (Token-2022 vs legacy SPL Token)
     let token_program_id = detect_token_program_for_mint(mint, rpc_client, settings).await;
     info!("Buy {}: using token program {}", mint, token_program_id);
 
+    // Dry runs fill against the paper wallet, which refuses unaffordable buys
+    let paper_fill = if is_real {
+        None
+    } else {
+        Some(crate::paper::buy(mint, sol_amount, token_amount, curve_state.as_ref(), buy_price_sol, decimals as u8, settings).await?)
+    };
+
     if is_real {
-        let client = RpcClient::new(&settings.solana_rpc_urls[0]);
         let payer = keypair.ok_or("Keypair required")?;
         debug!("Preparing buy TX for mint {} amount {} SOL (real)", mint, sol_amount);
         
-        // Determine best IDL for this mint (try detect by PDA existence)
-        let detected_idl_opt = detect_idl_for_mint(mint, rpc_client, settings).await;
+        // Determine best IDL for this mint. A seeded curve already tells us the
+        // mint lives under the configured pump program; otherwise detect by PDA existence.
+        let detected_idl_opt = if curve_seeded {
+            load_all_idls().into_values().find(|idl| idl.address.to_string() == settings.pump_fun_program)
+        } else {
+            detect_idl_for_mint(mint, rpc_client, settings).await
+        };
         let mut built_instr: Option<solana_program::instruction::Instruction> = None;
         let mut last_err: Option<String> = None;
         let mint_pk = Pubkey::from_str(mint)?;
-        let creator_opt = fetch_bonding_curve_creator(mint, rpc_client, settings).await.ok().flatten();
+        let creator_opt = match curve_state.as_ref().and_then(|s| s.creator) {
+            Some(c) => Some(c),
+            None => fetch_bonding_curve_creator(mint, rpc_client, settings).await.ok().flatten(),
+        };
         let payer_pubkey = payer.pubkey();
         // Build a rich context that IDLs commonly expect
         let mut context: HashMap<String, Pubkey> = HashMap::new();
@@ -120,7 +233,7 @@ pub async fn buy_token(
             context.insert("bondingCurve.creator".to_string(), c); 
         }
         // bonding_curve PDA using configured pump program as fallback
-        let pump_program_pk = Pubkey::from_str(&settings.pump_fun_program)?;
+        let pump_program_pk = settings.address_book()?.pump_program;
         let (curve_pda_fallback, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_program_pk);
         context.insert("bonding_curve".to_string(), curve_pda_fallback);
         if let Some(creator) = context.get("bonding_curve.creator") {
@@ -176,7 +289,7 @@ pub async fn buy_token(
         let instruction = if let Some(instr) = built_instr { instr } else {
             if let Some(e) = last_err { debug!("IDL buy build errors: {}", e); }
             // fallback to legacy builder using configured pump program
-            let program_id = Pubkey::from_str(&settings.pump_fun_program)?;
+            let program_id = settings.address_book()?.pump_program;
             // Calculate max_sol_cost with slippage
             let base_cost_lamports = (sol_amount * 1_000_000_000.0) as u64;
             let slippage_multiplier = 1.0 + (settings.slippage_bps as f64 / 10000.0);
@@ -217,9 +330,10 @@ pub async fn buy_token(
         all_instrs.push(instruction);
         
         // Add conditional 1% dev fee on every buy (if enabled in config)
+        let mut fee_instrs: Vec<solana_program::instruction::Instruction> = Vec::new();
         {
             let transaction_lamports = (sol_amount * 1_000_000_000.0) as u64;
-            crate::dev_fee::add_dev_fee_to_instructions(&mut all_instrs, &payer.pubkey(), transaction_lamports, settings.dev_fee_enabled)?;
+            crate::dev_fee::add_dev_fee_to_instructions(&mut fee_instrs, &payer.pubkey(), transaction_lamports, settings)?;
             if settings.dev_fee_enabled {
                 info!("Added 1% dev fee to buy transaction ({} SOL)", sol_amount);
             } else {
@@ -227,103 +341,153 @@ pub async fn buy_token(
             }
         }
         
-        // Record pre-send SOL balance to compute actual cost after the transaction
-        let pre_sol_lamports = client.get_balance(&payer_pubkey)?;
+        // Submit through the configured execution backend
+        let backend = crate::execution::backend_for(crate::execution::Side::Buy, settings)?;
+        info!("Using {} execution backend for buy transaction of mint {}", backend.name(), mint);
 
-        // Choose transaction submission method
-        let mut final_token_amount_u64: Option<u64> = None;
-        if settings.helius_sender_enabled {
-            info!("Using Helius Sender for buy transaction of mint {}", mint);
-            let signature = crate::helius_sender::send_transaction_with_retry(
-                all_instrs,
-                payer,
-                settings,
-                &client,
-                3, // max retries
-            ).await?;
-            info!("Buy transaction sent via Helius Sender: {}", signature);
-            // Wait for the transaction to be confirmed before querying balance
-            // Helius sender uses skipPreflight=true, so the TX might not be confirmed yet
-            for wait_attempt in 0..12 {
-                let delay_ms = if wait_attempt == 0 { 2500 } else { 2000 };
-                tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                let owner_str = payer_pubkey.to_string();
-                if let Ok(Some(acc)) = crate::rpc::find_token_account_owned_by_owner(mint, &owner_str, rpc_client, settings).await {
-                    if let Ok(pk) = Pubkey::from_str(&acc) {
-                        if let Ok(balance) = client.get_token_account_balance(&pk) {
-                            if let Ok(amount_u64) = balance.amount.parse::<u64>() {
-                                if amount_u64 > 0 {
-                                    final_token_amount_u64 = Some(amount_u64);
-                                    info!("Buy confirmed on-chain for {} after {} ms: {} tokens", mint, delay_ms * (wait_attempt + 1) as u64, amount_u64);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-                if wait_attempt < 11 {
-                    debug!("Token balance not yet available for {} (attempt {}/12), retrying...", mint, wait_attempt + 1);
+        // Jito bundles can sell part of the buy again in the same slot
+        let mut followup_instrs: Vec<solana_program::instruction::Instruction> = Vec::new();
+        // Tokens and quoted lamports of the bundled sell
+        let mut same_slot: Option<(u64, u64)> = None;
+        if backend.name() == "jito" && settings.jito_same_slot_sell_percent > 0.0 {
+            let sell_amount = ((token_amount as f64 * settings.jito_same_slot_sell_percent / 100.0) as u64).min(token_amount);
+            let quote = curve_state
+                .as_ref()
+                .and_then(|curve| crate::paper::sell_after_buy_lamports(curve, token_amount, sell_amount, settings));
+            match quote {
+                Some(quote) if sell_amount > 0 => {
+                    let slippage_multiplier = 1.0 - (settings.slippage_bps as f64 / 10000.0);
+                    let min_sol_output = (quote as f64 * slippage_multiplier) as u64;
+                    same_slot = Some((sell_amount, quote));
+                    followup_instrs.push(build_sell_instruction(
+                        &pump_program_pk,
+                        mint,
+                        sell_amount,
+                        min_sol_output,
+                        &payer_pubkey,
+                        &fee_recipient,
+                        creator_opt,
+                        settings,
+                    )?);
+                    info!(
+                        "Bundling same-slot sell of {} of {} tokens for {} (min {:.9} SOL)",
+                        sell_amount, token_amount, mint, min_sol_output as f64 / 1_000_000_000.0
+                    );
                 }
+                _ => warn!("No curve state for {}; skipping the same-slot sell", mint),
+            }
+        }
+
+        let submission = backend.execute(crate::execution::ExecutionRequest {
+            side: crate::execution::Side::Buy,
+            mint,
+            payer,
+            instructions: all_instrs,
+            fee_instructions: fee_instrs,
+            followup_instructions: followup_instrs,
+            sol_amount,
+            token_amount,
+            decimals: decimals as u8,
+        }, settings).await?;
+        info!("Buy transaction sent via {}: {}", submission.backend, submission.signature);
+
+        match &submission.outcome {
+            crate::confirm::ConfirmationOutcome::Landed { slot } => {
+                info!("Buy {} landed in slot {}: {}", mint, slot, submission.signature);
             }
-            if final_token_amount_u64.is_none() {
-                warn!("Could not verify buy on-chain for {} after waiting — TX may have failed", mint);
+            crate::confirm::ConfirmationOutcome::Failed { err, .. } => {
+                return Err(format!("Buy TX {} for {} failed on-chain: {}", submission.signature, mint, err).into());
             }
-        } else {
-            let mut tx = Transaction::new_with_payer(&all_instrs, Some(&payer.pubkey()));
-            let blockhash = client.get_latest_blockhash()?;
-            tx.sign(&[payer], blockhash);
-            client.send_and_confirm_transaction(&tx)?;
-            // Query on-chain token accounts to find exact token balance for payer
-            let owner_str = payer_pubkey.to_string();
+            crate::confirm::ConfirmationOutcome::Expired => {
+                // Its blockhash expired unlanded — don't record a phantom holding
+                return Err(format!("Buy TX {} for {} expired: its blockhash is no longer valid", submission.signature, mint).into());
+            }
+        }
+
+        // The transaction landed, so the tokens are ours. Read the exact amount;
+        // the RPC may trail the confirmation by a moment. The bundle is atomic,
+        // so a same-slot sell landed with it.
+        let sold_same_slot = same_slot.map(|(tokens, _)| tokens).unwrap_or(0);
+        let expected_amount = token_amount - sold_same_slot;
+        let mut final_token_amount_u64: Option<u64> = None;
+        let owner_str = payer_pubkey.to_string();
+        // Nothing left to read when the same-slot sell took every token
+        let attempts = if expected_amount == 0 { 0 } else { 5 };
+        for attempt in 0..attempts {
             if let Ok(Some(acc)) = crate::rpc::find_token_account_owned_by_owner(mint, &owner_str, rpc_client, settings).await {
                 if let Ok(pk) = Pubkey::from_str(&acc) {
-                    if let Ok(balance) = client.get_token_account_balance(&pk) {
+                    if let Ok(balance) = rpc_client.get_token_account_balance(&pk).await {
                         if let Ok(amount_u64) = balance.amount.parse::<u64>() {
-                            final_token_amount_u64 = Some(amount_u64);
+                            if amount_u64 > 0 {
+                                final_token_amount_u64 = Some(amount_u64);
+                                break;
+                            }
                         }
                     }
                 }
             }
+            debug!("Token balance not yet indexed for {} (attempt {}/5)", mint, attempt + 1);
+            tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
         }
-        // If we fetched an on-chain amount, override token_amount returned to be exact
-        if let Some(exact) = final_token_amount_u64 {
-            info!("Buy complete: on-chain token amount for {} = {} (base units)", mint, exact);
-            // Compute actual SOL cost from on-chain balance delta
-            let post_sol_lamports = client.get_balance(&payer_pubkey).unwrap_or(pre_sol_lamports);
-            let buy_cost_sol = if pre_sol_lamports > post_sol_lamports {
-                (pre_sol_lamports - post_sol_lamports) as f64 / 1_000_000_000.0
-            } else {
-                warn!("Buy balance unchanged or increased for {} (pre={}, post={}), falling back to intended amount {}",
-                      mint, pre_sol_lamports, post_sol_lamports, sol_amount);
+        let exact = final_token_amount_u64.unwrap_or_else(|| {
+            if expected_amount > 0 {
+                warn!("Buy {} landed but token balance not readable yet, using expected amount {}", mint, expected_amount);
+            }
+            expected_amount
+        });
+        info!("Buy complete: on-chain token amount for {} = {} (base units)", mint, exact);
+        // Compute actual SOL cost from the landed transaction's fee payer balance delta
+        let buy_cost_sol = match crate::rpc::fetch_fee_payer_delta_lamports(&submission.signature, rpc_client, settings).await {
+            Some(lamports) if lamports > 0 => lamports as f64 / 1_000_000_000.0,
+            other => {
+                warn!("Buy cost for {} not readable from transaction meta ({:?}), falling back to intended amount {}",
+                      mint, other, sol_amount);
                 sol_amount // fallback to intended amount
-            };
-            info!("Buy accounting for {}: pre_sol={} post_sol={} cost={:.9} SOL (intended {:.9} SOL)",
-                  mint, pre_sol_lamports, post_sol_lamports, buy_cost_sol, sol_amount);
-            // Use this exact amount for returned holding
-            return Ok(Holding {
+            }
+        };
+        info!("Buy accounting for {}: cost={:.9} SOL (intended {:.9} SOL)", mint, buy_cost_sol, sol_amount);
+        // Split the cost basis between the sold and the held tokens
+        let same_slot_sell = match same_slot {
+            Some((tokens, quote)) => {
+                let followup = submission.followup_signature.clone();
+                let proceeds_lamports = match &followup {
+                    Some(sig) => crate::rpc::fetch_fee_payer_delta_lamports(sig, rpc_client, settings).await,
+                    None => None,
+                };
+                let proceeds_sol = match proceeds_lamports {
+                    Some(delta) if delta < 0 => (-delta) as f64 / 1_000_000_000.0,
+                    other => {
+                        warn!("Same-slot sell proceeds for {} not readable from transaction meta ({:?}), using the quote", mint, other);
+                        quote as f64 / 1_000_000_000.0
+                    }
+                };
+                let cost_sol = buy_cost_sol * tokens as f64 / (tokens + exact) as f64;
+                info!("Same-slot sell of {} tokens of {}: {:.9} SOL for a cost of {:.9} SOL", tokens, mint, proceeds_sol, cost_sol);
+                Some(SameSlotSell { tokens, proceeds_sol, cost_sol, signature: followup })
+            }
+            None => None,
+        };
+        let held_cost_sol = buy_cost_sol - same_slot_sell.as_ref().map(|sell| sell.cost_sol).unwrap_or(0.0);
+        // Use this exact amount for returned holding
+        return Ok(Bought {
+            holding: Holding {
                 amount: exact,
                 original_amount: exact,
                 buy_price: buy_price_sol,
-                buy_time: Utc::now(),
+                buy_time: crate::recorder::now(),
                 decimals: decimals as u8,
-                buy_cost_sol: Some(buy_cost_sol),
+                buy_cost_sol: Some(held_cost_sol),
                 metadata: None,
                 onchain_raw: None,
                 onchain: None,
                 triggered_tp_levels: vec![],
                 triggered_sl_levels: vec![],
-            });
-        } else if is_real {
-            // Real mode but couldn't confirm buy on-chain — don't record a phantom holding
-            return Err(format!(
-                "Buy TX sent for {} but could not verify tokens on-chain — transaction may have failed or not yet confirmed",
-                mint
-            ).into());
-        }
+            },
+            same_slot_sell,
+        });
     } else {
         // Dry-run simulation: construct same instruction and simulate it using
         // either the provided simulate_keypair or an ephemeral Keypair fallback.
-        let client = RpcClient::new(&settings.solana_rpc_urls[0]);
         // keep an owned Keypair alive in this scope if we need to create one
         let mut _maybe_owned_sim: Option<Keypair> = None;
         let sim_payer_ref: &Keypair = if let Some(k) = simulate_keypair {
@@ -334,7 +498,7 @@ pub async fn buy_token(
             _maybe_owned_sim.as_ref().ok_or_else(|| Box::<dyn std::error::Error + Send + Sync>::from("Failed to get sim keypair ref"))?
         };
         debug!("Preparing simulated buy TX for mint {} amount {} SOL (dry run)", mint, sol_amount);
-        let program_id = Pubkey::from_str(&settings.pump_fun_program)?;
+        let program_id = settings.address_book()?.pump_program;
         let creator_opt = fetch_bonding_curve_creator(mint, rpc_client, settings).await.ok().flatten();
         let sim_payer_pubkey = sim_payer_ref.pubkey();
         // Try to build accounts via IDL-aware builder for exactness
@@ -352,7 +516,7 @@ pub async fn buy_token(
                 context.insert("bondingCurve.creator".to_string(), c); 
             }
             // Add bonding_curve PDA
-            let pump_program_pk = Pubkey::from_str(&settings.pump_fun_program)?;
+            let pump_program_pk = settings.address_book()?.pump_program;
             let (curve_pda, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_program_pk);
             context.insert("bonding_curve".to_string(), curve_pda);
             // Add creator_vault PDA if creator exists
@@ -378,7 +542,7 @@ pub async fn buy_token(
                     let max_sol_cost_with_slippage = (base_cost_lamports as f64 * slippage_multiplier) as u64;
                     
                     // Get discriminator from IDL
-                    let discriminator = get_instruction_discriminator(&idl, "buy")
+                    let discriminator = get_instruction_discriminator(idl, "buy")
                         .unwrap_or_else(|_| crate::onchain_idl::compute_anchor_discriminator("buy"));
                     instruction_opt = Some(solana_program::instruction::Instruction { program_id, accounts: metas, data: {
                         let mut d = discriminator.to_vec();
@@ -453,7 +617,7 @@ pub async fn buy_token(
         debug!("  Payer (sim wallet): {}", sim_payer_pubkey);
         
         let mut tx = Transaction::new_with_payer(&tx_instructions, Some(&sim_payer_pubkey));
-        match client.get_latest_blockhash() {
+        match rpc_client.get_latest_blockhash().await {
             Ok(blockhash) => {
                 // For dry-run simulation with ephemeral keypair:
                 // We build the transaction correctly but cannot fully simulate because
@@ -468,7 +632,7 @@ pub async fn buy_token(
                 match bincode::serialize(&tx) {
                     Ok(serialized) => {
                         let tx_base64 = Base64Engine.encode(&serialized);
-                        match crate::helius_sender::simulate_transaction_via_helius(&tx_base64, &*settings).await {
+                        match crate::helius_sender::simulate_transaction_via_helius(&tx_base64, settings).await {
                             Ok(json) => {
                                 // Try to inspect error field inside result if present
                                 if let Some(err) = json.get("error") {
@@ -487,17 +651,20 @@ pub async fn buy_token(
         }
     }
     // Return simulated holding for dry runs
-    Ok(Holding {
-        amount: token_amount,
-        original_amount: token_amount,
-        buy_price: buy_price_sol,
-        buy_time: Utc::now(),
-        decimals: decimals as u8,
-        buy_cost_sol: None,
-        metadata: None,
-        onchain_raw: None,
-        onchain: None,
-        triggered_tp_levels: vec![],
-        triggered_sl_levels: vec![],
+    Ok(Bought {
+        holding: Holding {
+            amount: token_amount,
+            original_amount: token_amount,
+            buy_price: buy_price_sol,
+            buy_time: crate::recorder::now(),
+            decimals: decimals as u8,
+            buy_cost_sol: paper_fill.map(|fill| -fill.net_sol()),
+            metadata: None,
+            onchain_raw: None,
+            onchain: None,
+            triggered_tp_levels: vec![],
+            triggered_sl_levels: vec![],
+        },
+        same_slot_sell: None,
     })
 }
\ No newline at end of file
@@ -0,0 +1,311 @@
+//! Subcommands for operating sol_beast from a shell or cron job.
+//!
+//! `status`, `sell`, `sell-all` and `export-trades` talk to a running instance
+//! over its HTTP API (`--api <url>`, `SOL_BEAST_API_URL`, default
+//! `http://127.0.0.1:8080`), as does `export-dataset` without a recording;
+//! `check-config` works standalone.
+use crate::cli_value;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Signer};
+use sol_beast::{
+    api::{BotStats, TradeRecord},
+    backtest, missed, recorder, settings, AppError, Settings,
+};
+use std::{fs, time::Duration, time::Instant};
+
+pub const USAGE: &str = "\
+Usage: sol_beast [COMMAND] [OPTIONS]
+
+Commands:
+  run                        Run the bot and dashboard API (default)
+  replay <FILE>              Replay a feed recording in dry-run mode
+  check-config               Validate the config and check RPC/WSS connectivity
+  status                     Show state, stats and holdings of a running bot
+  sell <MINT> <PERCENT>      Sell PERCENT of a position on a running bot
+  sell-all                   Sell every open position on a running bot
+  export-trades              Print the trade history of a running bot
+  export-dataset [RECORDING] Write a backtest dataset from a feed recording,
+                             or from the missed-coin paths of a running bot
+  backtest <DATASET>         Backtest the configured strategy offline
+  optimize <DATASET> <SWEEP> Backtest a parameter sweep offline
+  help                       Print this help
+
+Options:
+      --real                 Enable real trading mode (run)
+      --api-host <HOST>      Interface the dashboard API listens on (run, default 127.0.0.1)
+      --replay-speed <X>     Replay speed multiplier (0 = no delay)
+      --api <URL>            API of the running bot (default $SOL_BEAST_API_URL or http://127.0.0.1:8080)
+      --format <csv|json>    export-trades output format (default csv)
+      --out <FILE>           Write export-trades/export-dataset/optimize output to FILE
+      --json                 Print status/backtest/optimize output as JSON
+
+The config file is read from $SOL_BEAST_CONFIG_PATH (default config.toml).
+";
+
+const DEFAULT_API_URL: &str = "http://127.0.0.1:8080";
+
+/// Connectivity checks give up after this long per endpoint.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn api_base() -> String {
+    cli_value("--api")
+        .or_else(|| std::env::var("SOL_BEAST_API_URL").ok())
+        .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Call `path` on the running bot's API and return the JSON body. Non-2xx
+/// responses become errors carrying the API's `message`.
+async fn api_request(method: reqwest::Method, path: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value, AppError> {
+    let url = format!("{}/api{}", api_base(), path);
+    let mut request = reqwest::Client::new().request(method, &url).timeout(CHECK_TIMEOUT);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::Init(format!("cannot reach sol_beast API at {}: {}", url, e)))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| AppError::Init(format!("failed to read response from {}: {}", url, e)))?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+            .unwrap_or(text);
+        return Err(AppError::Validation(format!("{} ({})", message, status)));
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// `sol_beast check-config`: validate the config, load the wallet and check
+/// that every RPC and WSS endpoint answers. Fails if any check fails.
+pub async fn check_config(config_path: &str) -> Result<(), AppError> {
+    let settings = Settings::from_file(config_path)?;
+    settings.validate()?;
+    println!("config   {}: ok (cluster {})", config_path, settings.cluster);
+
+    let mut failures = 0;
+    let wallet = settings::load_wallet_keypair(&settings).map(|keypair| keypair.map(|k| k.pubkey()));
+    if let Err(e) = &wallet {
+        println!("wallet   FAILED: {}", e);
+        failures += 1;
+    }
+
+    for url in &settings.solana_rpc_urls {
+        let client = RpcClient::new(url.clone());
+        let started = Instant::now();
+        match tokio::time::timeout(CHECK_TIMEOUT, client.get_version()).await {
+            Ok(Ok(version)) => {
+                println!("rpc      {}: ok, solana-core {} ({} ms)", url, version.solana_core, started.elapsed().as_millis())
+            }
+            Ok(Err(e)) => {
+                println!("rpc      {}: FAILED: {}", url, e);
+                failures += 1;
+            }
+            Err(_) => {
+                println!("rpc      {}: FAILED: no answer within {}s", url, CHECK_TIMEOUT.as_secs());
+                failures += 1;
+            }
+        }
+    }
+
+    let mut ws_urls: Vec<&String> = settings.solana_ws_urls.iter().collect();
+    if settings.pumpportal_enabled {
+        ws_urls.extend(settings.pumpportal_wss.iter());
+    }
+    for url in ws_urls {
+        let started = Instant::now();
+        match tokio::time::timeout(CHECK_TIMEOUT, tokio_tungstenite::connect_async(url.as_str())).await {
+            Ok(Ok((mut stream, _))) => {
+                let _ = stream.close(None).await;
+                println!("wss      {}: ok ({} ms)", url, started.elapsed().as_millis());
+            }
+            Ok(Err(e)) => {
+                println!("wss      {}: FAILED: {}", url, e);
+                failures += 1;
+            }
+            Err(_) => {
+                println!("wss      {}: FAILED: no handshake within {}s", url, CHECK_TIMEOUT.as_secs());
+                failures += 1;
+            }
+        }
+    }
+
+    match (&wallet, settings.solana_rpc_urls.first()) {
+        (Ok(Some(pubkey)), Some(url)) => match RpcClient::new(url.clone()).get_balance(pubkey).await {
+            Ok(lamports) => println!("wallet   {}: {:.6} SOL", pubkey, lamports as f64 / LAMPORTS_PER_SOL as f64),
+            Err(e) => println!("wallet   {}: balance unavailable: {}", pubkey, e),
+        },
+        (Ok(None), _) => println!("wallet   not configured (dry-run only)"),
+        _ => {}
+    }
+
+    if failures > 0 {
+        return Err(AppError::Validation(format!("{} check(s) failed", failures)));
+    }
+    Ok(())
+}
+
+/// `sol_beast status [--json]`
+pub async fn status() -> Result<(), AppError> {
+    let value = api_request(reqwest::Method::GET, "/stats", None).await?;
+    if std::env::args().any(|arg| arg == "--json") {
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+    let stats: BotStats = serde_json::from_value(value)?;
+    println!(
+        "state {}, mode {}, up {}s",
+        stats.running_state.as_deref().unwrap_or("unknown"),
+        stats.mode.as_deref().unwrap_or("unknown"),
+        stats.uptime_secs
+    );
+    println!(
+        "{} buys, {} sells, profit {:+.6} SOL, last activity {}",
+        stats.total_buys, stats.total_sells, stats.total_profit, stats.last_activity
+    );
+    println!("{} holding(s)", stats.current_holdings.len());
+    for h in &stats.current_holdings {
+        let tokens = h.holding.amount as f64 / 10f64.powi(h.holding.decimals as i32);
+        let symbol = h.holding.metadata.as_ref().and_then(|m| m.symbol.clone()).unwrap_or_default();
+        println!(
+            "  {} {:<10} {:>16.2} tokens @ {:.10} SOL, held {}s",
+            h.mint,
+            symbol,
+            tokens,
+            h.holding.buy_price,
+            (chrono::Utc::now() - h.holding.buy_time).num_seconds()
+        );
+    }
+    Ok(())
+}
+
+/// `sol_beast sell <mint> <percent>`
+pub async fn sell() -> Result<(), AppError> {
+    let usage = || AppError::Validation("usage: sol_beast sell <mint> <percent>".to_string());
+    let mint = std::env::args().nth(2).filter(|arg| !arg.starts_with("--")).ok_or_else(usage)?;
+    let percent: f64 = std::env::args()
+        .nth(3)
+        .ok_or_else(usage)?
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| usage())?;
+    api_request(
+        reqwest::Method::POST,
+        &format!("/positions/{}/sell", mint),
+        Some(serde_json::json!({ "percent": percent })),
+    )
+    .await?;
+    println!("Queued sell of {}% of {}", percent, mint);
+    Ok(())
+}
+
+/// `sol_beast sell-all`
+pub async fn sell_all() -> Result<(), AppError> {
+    let value = api_request(reqwest::Method::POST, "/positions/sell-all", None).await?;
+    let queued = value.get("queued").and_then(|q| q.as_array()).cloned().unwrap_or_default();
+    println!("Queued sell of {} position(s)", queued.len());
+    for mint in queued {
+        println!("  {}", mint.as_str().unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Quote a CSV field if it contains a separator, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn trades_csv(trades: &[TradeRecord]) -> String {
+    let opt = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+    let mut out = String::from(
+        "timestamp,type,mint,symbol,amount_sol,amount_tokens,price_per_token,profit_loss,profit_loss_percent,reason,simulated,tx_signature\n",
+    );
+    for t in trades {
+        let row = [
+            t.timestamp.clone(),
+            t.trade_type.clone(),
+            t.mint.clone(),
+            t.symbol.clone().unwrap_or_default(),
+            t.amount_sol.to_string(),
+            t.amount_tokens.to_string(),
+            t.price_per_token.to_string(),
+            opt(t.profit_loss),
+            opt(t.profit_loss_percent),
+            t.reason.clone().unwrap_or_default(),
+            t.simulated.to_string(),
+            t.tx_signature.clone().unwrap_or_default(),
+        ];
+        out.push_str(&row.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// `sol_beast export-trades [--format csv|json] [--out FILE]`: oldest first.
+pub async fn export_trades() -> Result<(), AppError> {
+    let format = cli_value("--format").unwrap_or_else(|| "csv".to_string());
+    let value = api_request(reqwest::Method::GET, "/trades", None).await?;
+    let mut trades: Vec<TradeRecord> = serde_json::from_value(value)?;
+    trades.reverse();
+    let output = match format.as_str() {
+        "csv" => trades_csv(&trades),
+        "json" => serde_json::to_string_pretty(&trades)? + "\n",
+        other => return Err(AppError::Validation(format!("--format must be csv or json (got '{}')", other))),
+    };
+    match cli_value("--out") {
+        Some(out) => {
+            fs::write(&out, output)?;
+            eprintln!("{} trade(s) written to {}", trades.len(), out);
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+/// `sol_beast export-dataset [RECORDING] [--out FILE]`: write a backtest
+/// dataset (JSONL, gzip when FILE ends in `.gz`) from a feed recording, or
+/// without one from the finished missed-opportunity watches of a running bot.
+pub async fn export_dataset(config_path: &str) -> Result<(), AppError> {
+    let coins = match std::env::args().nth(2).filter(|arg| !arg.starts_with("--")) {
+        Some(recording) => {
+            let settings = Settings::from_file(config_path)?;
+            let records = recorder::read_recording(&recording)?;
+            backtest::dataset_from_recording(&records, &settings).map_err(|e| AppError::Validation(e.to_string()))?
+        }
+        None => {
+            let mut report = api_request(reqwest::Method::GET, "/missed", None).await?;
+            let watches: Vec<missed::Watch> = serde_json::from_value(report["recent"].take())?;
+            backtest::dataset_from_missed(&watches)
+        }
+    };
+    match cli_value("--out") {
+        Some(out) => {
+            backtest::save_dataset(&coins, &out).map_err(|e| AppError::Validation(e.to_string()))?;
+            eprintln!("{} coin path(s) written to {}", coins.len(), out);
+        }
+        None => backtest::write_dataset(&coins, &mut std::io::stdout().lock()).map_err(|e| AppError::Validation(e.to_string()))?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_with_separators_are_quoted() {
+        assert_eq!(csv_field("TP1 (50% @ +100.0%)"), "TP1 (50% @ +100.0%)");
+        assert_eq!(csv_field("SL1, forced"), "\"SL1, forced\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,206 @@
+//! Per-cluster address book.
+//!
+//! Every program, fee and tip account the bot sends lamports to or lists in an
+//! instruction is resolved here for `cluster`, so the same binary can run
+//! against mainnet, devnet or a `solana-test-validator` localnet. The book is
+//! built once when the settings are loaded or merged (see
+//! [`Settings::address_book`]).
+//!
+//! Only mainnet has a complete built-in book. On devnet and localnet the
+//! built-in entries are limited to what exists at the same address on every
+//! cluster: the pump.fun, pump.fun fee and Metaplex metadata program ids
+//! (pump.fun deploys the same ids on devnet; a localnet is expected to clone
+//! them with `solana-test-validator --clone-upgradeable-program`) and the dev
+//! wallet, a plain system account. The fallback fee recipient and the Helius
+//! Sender and Jito tip accounts are mainnet-only and have no default there:
+//! set them through `[cluster_addresses]` when needed. The program ids come
+//! from the `pump_fun_program` and `metadata_program` settings; every other
+//! entry can be replaced through `[cluster_addresses]`.
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Clusters with a built-in address book.
+pub const CLUSTERS: &[&str] = &["mainnet", "devnet", "localnet"];
+
+/// pump.fun fee program (same program id on mainnet and devnet).
+const FEE_PROGRAM: &str = "pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ";
+
+/// Known authorized fee recipient for standard (non-mayhem) pump.fun tokens
+/// on mainnet, used when the Global account cannot be read.
+const FEE_RECIPIENT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
+
+/// Dev wallet receiving the 1% dev fee.
+const DEV_WALLET: &str = "BEAST1kZRXbU2FQs3SDa38t5jb1gxiVvfoQ3Vos2i8QE";
+
+/// Helius Sender tip accounts (mainnet-beta)
+const HELIUS_TIP_ACCOUNTS: &[&str] = &[
+    "4ACfpUFoaSD9bfPdeu6DBt89gB6ENTeHBXCAi87NhDEE",
+    "D2L6yPZ2FmmmTKPgzaMKdhu6EWZcTpLy1Vhx8uvZe7NZ",
+    "9bnz4RShgq1hAnLnZbP8kbgBg1kEmcJBYQq3gQbmnSta",
+    "5VY91ws6B2hMmBFRsXkoAAdsPHBJwRfBht4DXox3xkwn",
+    "2nyhqdwKcJZR2vcqCyrYsaPVdAnFoJjiksCXJ7hfEYgD",
+    "2q5pghRs6arqVjRvT5gfgWfWcHWmw1ZuCzphgd5KfWGJ",
+    "wyvPkWjVZz1M8fHQnMMCDTQDbkManefNNhweYk5WkcF",
+    "3KCKozbAaF75qEU33jtzozcJ29yJuaLJTy2jFdzUY8bT",
+    "4vieeGHPYPG2MmyPRcYjdiDmmhN3ww7hsFNap8pVN3Ey",
+    "4TQLFNWK8AovT1gFvda5jfw2oJeRMKEmw7aH6MGBJ3or",
+];
+
+/// Jito block-engine tip accounts (mainnet-beta)
+const JITO_TIP_ACCOUNTS: &[&str] = &[
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// `[cluster_addresses]` overrides; unset entries come from the cluster's book.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct ClusterAddresses {
+    pub fee_program: Option<String>,
+    /// Second seed of the fee_config PDA (`[b"fee_config", seed]` under
+    /// `fee_program`). Defaults to `pump_fun_program`.
+    pub fee_config_seed: Option<String>,
+    pub fee_recipient: Option<String>,
+    pub dev_wallet: Option<String>,
+    pub helius_tip_accounts: Option<Vec<String>>,
+    pub jito_tip_accounts: Option<Vec<String>>,
+}
+
+/// Resolved addresses for the configured cluster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressBook {
+    pub pump_program: Pubkey,
+    pub metadata_program: Pubkey,
+    pub fee_program: Pubkey,
+    pub fee_config_seed: Pubkey,
+    /// Fallback when the Global account cannot be read; none off mainnet
+    /// unless overridden
+    pub fee_recipient: Option<Pubkey>,
+    pub dev_wallet: Pubkey,
+    /// Empty off mainnet unless overridden
+    pub helius_tip_accounts: Vec<Pubkey>,
+    pub jito_tip_accounts: Vec<Pubkey>,
+}
+
+fn parse(field: &str, value: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(value).map_err(|e| format!("cluster_addresses.{}: invalid pubkey '{}': {}", field, value, e))
+}
+
+fn parse_list(field: &str, values: &[String]) -> Result<Vec<Pubkey>, String> {
+    values.iter().map(|v| parse(field, v)).collect()
+}
+
+fn defaults(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+impl AddressBook {
+    /// Build the address book for `settings.cluster`, applying overrides.
+    pub fn from_settings(settings: &Settings) -> Result<Self, String> {
+        if !CLUSTERS.contains(&settings.cluster.as_str()) {
+            return Err(format!("cluster must be one of {} (got '{}')", CLUSTERS.join(", "), settings.cluster));
+        }
+        let mainnet = settings.cluster == "mainnet";
+        let o = &settings.cluster_addresses;
+        let builtin = |list: &[&str]| if mainnet { defaults(list) } else { Vec::new() };
+        let helius = o.helius_tip_accounts.clone().unwrap_or_else(|| builtin(HELIUS_TIP_ACCOUNTS));
+        let jito = o.jito_tip_accounts.clone().unwrap_or_else(|| builtin(JITO_TIP_ACCOUNTS));
+        let fee_recipient = o.fee_recipient.as_deref().or(mainnet.then_some(FEE_RECIPIENT));
+        let book = AddressBook {
+            pump_program: Pubkey::from_str(&settings.pump_fun_program)
+                .map_err(|e| format!("pump_fun_program: invalid pubkey '{}': {}", settings.pump_fun_program, e))?,
+            metadata_program: Pubkey::from_str(&settings.metadata_program)
+                .map_err(|e| format!("metadata_program: invalid pubkey '{}': {}", settings.metadata_program, e))?,
+            fee_program: parse("fee_program", o.fee_program.as_deref().unwrap_or(FEE_PROGRAM))?,
+            fee_config_seed: parse(
+                "fee_config_seed",
+                o.fee_config_seed.as_deref().unwrap_or(&settings.pump_fun_program),
+            )?,
+            fee_recipient: fee_recipient.map(|v| parse("fee_recipient", v)).transpose()?,
+            dev_wallet: parse("dev_wallet", o.dev_wallet.as_deref().unwrap_or(DEV_WALLET))?,
+            helius_tip_accounts: parse_list("helius_tip_accounts", &helius)?,
+            jito_tip_accounts: parse_list("jito_tip_accounts", &jito)?,
+        };
+        // An override list that is empty is a mistake on any cluster
+        if (mainnet || o.helius_tip_accounts.is_some()) && book.helius_tip_accounts.is_empty()
+            || (mainnet || o.jito_tip_accounts.is_some()) && book.jito_tip_accounts.is_empty()
+        {
+            return Err("cluster_addresses tip account lists must not be empty".to_string());
+        }
+        Ok(book)
+    }
+
+    /// The fee_config PDA required by pump.fun buy and sell instructions.
+    pub fn fee_config_pda(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"fee_config", self.fee_config_seed.as_ref()], &self.fee_program).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mainnet_book_matches_pump_fee_config_seed() {
+        let settings = Settings::from_file("config.example.toml").unwrap();
+        let book = AddressBook::from_settings(&settings).unwrap();
+        // The fee_config seed is the pump.fun program id itself.
+        assert_eq!(
+            &book.fee_config_seed.to_bytes()[..8],
+            &[1, 86, 224, 246, 147, 102, 90, 207]
+        );
+        assert_eq!(book.jito_tip_accounts.len(), JITO_TIP_ACCOUNTS.len());
+        assert!(book.fee_recipient.is_some());
+    }
+
+    #[test]
+    fn mainnet_only_entries_have_no_default_elsewhere() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.cluster = "devnet".to_string();
+        let book = AddressBook::from_settings(&settings).unwrap();
+        assert_eq!(book.pump_program.to_string(), settings.pump_fun_program);
+        assert_eq!(book.fee_recipient, None);
+        assert!(book.helius_tip_accounts.is_empty() && book.jito_tip_accounts.is_empty());
+
+        settings.cluster_addresses.jito_tip_accounts = Some(Vec::new());
+        assert!(AddressBook::from_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn settings_resolve_the_book_on_load_and_merge() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        let book = settings.address_book().unwrap();
+        assert!(std::sync::Arc::ptr_eq(&book, &settings.address_book().unwrap()));
+
+        let mut reloaded = settings.clone();
+        let wallet = Pubkey::new_unique();
+        reloaded.cluster_addresses.dev_wallet = Some(wallet.to_string());
+        settings.merge(&reloaded);
+        assert_eq!(settings.address_book().unwrap().dev_wallet, wallet);
+    }
+
+    #[test]
+    fn overrides_replace_entries_and_are_validated() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.cluster = "localnet".to_string();
+        let wallet = Pubkey::new_unique();
+        settings.cluster_addresses.dev_wallet = Some(wallet.to_string());
+        settings.cluster_addresses.jito_tip_accounts = Some(vec![wallet.to_string()]);
+        let book = AddressBook::from_settings(&settings).unwrap();
+        assert_eq!(book.dev_wallet, wallet);
+        assert_eq!(book.jito_tip_accounts, vec![wallet]);
+
+        settings.cluster_addresses.fee_program = Some("not-a-key".to_string());
+        assert!(AddressBook::from_settings(&settings).is_err());
+        settings.cluster_addresses.fee_program = None;
+        settings.cluster = "testnet".to_string();
+        assert!(AddressBook::from_settings(&settings).is_err());
+    }
+}
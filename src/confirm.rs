@@ -0,0 +1,202 @@
+//! Signature confirmation tracker.
+//!
+//! A sent signature is watched with `signatureSubscribe` on one of the live
+//! WSS connections and, in parallel, polled with `getSignatureStatuses` as a
+//! fallback (WSS down, subscription refused, or a notification missed). The
+//! first definitive answer wins: landed, or failed with the on-chain error.
+//! Past the timeout the poll keeps going until the cluster's block height
+//! passes the signed blockhash's `lastValidBlockHeight`; only then can the
+//! transaction no longer land, and it is reported expired.
+
+use crate::ws::WsRequest;
+use log::{debug, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+/// How often the RPC fallback polls `getSignatureStatuses`.
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+/// Blocks a blockhash stays valid for after the block it was taken at.
+pub const BLOCKHASH_VALID_BLOCKS: u64 = 150;
+/// How long past the timeout to keep polling when the block height can't be read.
+const MAX_EXPIRY_WAIT: Duration = Duration::from_secs(180);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum ConfirmationOutcome {
+    /// Confirmed without error.
+    Landed { slot: u64 },
+    /// Included in a block but the transaction failed.
+    Failed { slot: u64, err: String },
+    /// Neither landed nor failed before the blockhash expired.
+    Expired,
+}
+
+impl ConfirmationOutcome {
+    pub fn is_landed(&self) -> bool {
+        matches!(self, ConfirmationOutcome::Landed { .. })
+    }
+}
+
+/// Result delivered by the WSS loop for a `signatureNotification`:
+/// Ok((slot, err)) where `err` is the notification's `value.err` (null on success).
+pub type SignatureNotification = Result<(u64, Value), String>;
+
+static WS_SENDERS: RwLock<Option<Arc<Vec<mpsc::Sender<WsRequest>>>>> = RwLock::new(None);
+
+/// Make the running engine's WSS control channels available to the tracker
+/// (None when it stops).
+pub fn register_ws_senders(senders: Option<Arc<Vec<mpsc::Sender<WsRequest>>>>) {
+    *WS_SENDERS.write().unwrap() = senders;
+}
+
+fn outcome_from_err(slot: u64, err: &Value) -> ConfirmationOutcome {
+    if err.is_null() {
+        ConfirmationOutcome::Landed { slot }
+    } else {
+        ConfirmationOutcome::Failed { slot, err: err.to_string() }
+    }
+}
+
+/// Ask the first WSS connection that accepts to notify us about `signature`.
+async fn subscribe(signature: &str) -> Option<oneshot::Receiver<SignatureNotification>> {
+    let senders = WS_SENDERS.read().unwrap().clone()?;
+    for sender in senders.iter() {
+        let (notify_tx, notify_rx) = oneshot::channel();
+        let req = WsRequest::SignatureSubscribe {
+            signature: signature.to_string(),
+            notify: notify_tx,
+        };
+        if sender.try_send(req).is_ok() {
+            return Some(notify_rx);
+        }
+    }
+    None
+}
+
+/// One `getSignatureStatuses` poll. Some(outcome) once the status is definitive.
+async fn poll_status(http: &reqwest::Client, rpc_url: &str, signature: &str) -> Option<ConfirmationOutcome> {
+    let result = crate::execution::json_rpc(
+        http,
+        rpc_url,
+        "getSignatureStatuses",
+        json!([[signature], { "searchTransactionHistory": false }]),
+    )
+    .await;
+    let result = match result {
+        Ok(r) => r,
+        Err(e) => {
+            debug!("getSignatureStatuses for {} failed: {}", signature, e);
+            return None;
+        }
+    };
+    let status = result.get("value").and_then(|v| v.get(0)).filter(|s| !s.is_null())?;
+    let slot = status.get("slot").and_then(|s| s.as_u64()).unwrap_or(0);
+    let err = status.get("err").cloned().unwrap_or(Value::Null);
+    if !err.is_null() {
+        return Some(outcome_from_err(slot, &err));
+    }
+    match status.get("confirmationStatus").and_then(|s| s.as_str()) {
+        Some("confirmed") | Some("finalized") => Some(ConfirmationOutcome::Landed { slot }),
+        _ => None,
+    }
+}
+
+/// Current block height (`getBlockHeight` at confirmed).
+pub(crate) async fn block_height(http: &reqwest::Client, rpc_url: &str) -> Option<u64> {
+    match crate::execution::json_rpc(http, rpc_url, "getBlockHeight", json!([{ "commitment": "confirmed" }])).await {
+        Ok(height) => height.as_u64(),
+        Err(e) => {
+            debug!("getBlockHeight failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Wait for `signature` to land or fail. Past `timeout` it keeps polling until
+/// the block height passes `last_valid_block_height`, the expiry of the
+/// blockhash it was signed with, and then reports it expired. Without a known
+/// expiry it is reported expired at `timeout`.
+pub async fn confirm_signature(
+    http: &reqwest::Client,
+    rpc_url: &str,
+    signature: &str,
+    timeout: Duration,
+    last_valid_block_height: Option<u64>,
+) -> ConfirmationOutcome {
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut past_deadline = false;
+    let mut ws_notify = subscribe(signature).await;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    let outcome = loop {
+        tokio::select! {
+            notification = async { ws_notify.as_mut().unwrap().await }, if ws_notify.is_some() => {
+                match notification {
+                    Ok(Ok((slot, err))) => break outcome_from_err(slot, &err),
+                    Ok(Err(e)) => debug!("signatureSubscribe for {} unavailable: {}", signature, e),
+                    Err(_) => debug!("WSS dropped signature subscription for {}", signature),
+                }
+                // Keep going on the RPC poll alone
+                ws_notify = None;
+            }
+            _ = poll.tick() => {
+                if let Some(outcome) = poll_status(http, rpc_url, signature).await {
+                    break outcome;
+                }
+                if past_deadline {
+                    let Some(last_valid) = last_valid_block_height else { break ConfirmationOutcome::Expired };
+                    match block_height(http, rpc_url).await {
+                        // One last look: it may have landed in the final valid blocks
+                        Some(height) if height > last_valid => {
+                            break poll_status(http, rpc_url, signature).await.unwrap_or(ConfirmationOutcome::Expired);
+                        }
+                        _ if started.elapsed() > timeout + MAX_EXPIRY_WAIT => break ConfirmationOutcome::Expired,
+                        _ => {}
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(deadline), if !past_deadline => {
+                past_deadline = true;
+                if let Some(last_valid) = last_valid_block_height {
+                    debug!("{} not confirmed within {}s; polling until block height {} passes", signature, timeout.as_secs(), last_valid);
+                }
+            }
+        }
+    };
+
+    match &outcome {
+        ConfirmationOutcome::Landed { slot } => {
+            debug!("{} landed in slot {} after {} ms", signature, slot, started.elapsed().as_millis())
+        }
+        ConfirmationOutcome::Failed { slot, err } => {
+            warn!("{} failed on-chain in slot {}: {}", signature, slot, err)
+        }
+        ConfirmationOutcome::Expired => {
+            warn!("{} not confirmed before its blockhash expired ({} ms)", signature, started.elapsed().as_millis())
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_error_maps_to_failed() {
+        assert_eq!(outcome_from_err(7, &Value::Null), ConfirmationOutcome::Landed { slot: 7 });
+        let err = json!({ "InstructionError": [2, { "Custom": 6002 }] });
+        match outcome_from_err(8, &err) {
+            ConfirmationOutcome::Failed { slot, err } => {
+                assert_eq!(slot, 8);
+                assert!(err.contains("6002"));
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+}
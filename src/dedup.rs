@@ -0,0 +1,183 @@
+//! Mint-keyed detection dedup across feeds, with per-source timing.
+//!
+//! PumpPortal, the pump.fun logs subscription and the signature poller can
+//! all report the same launch, and not every feed carries a signature. The
+//! first feed to report a mint wins; later reports are recorded but not
+//! processed again. Each feed's first-seen time is kept per mint, so
+//! `/api/health` and `/api/stats` can show which feed is actually faster
+//! and which one has gone quiet.
+
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// Mints whose sightings are remembered.
+const TRACKED_MINTS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionSource {
+    PumpPortal,
+    Logs,
+    Poll,
+}
+
+impl DetectionSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DetectionSource::PumpPortal => "pumpportal",
+            DetectionSource::Logs => "logs",
+            DetectionSource::Poll => "poll",
+        }
+    }
+}
+
+impl std::fmt::Display for DetectionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Result of reporting a mint from one feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sighting {
+    /// No feed had reported this mint: process it.
+    First,
+    /// Another feed got there first; `behind_ms` is how much later this one was.
+    Duplicate { first: DetectionSource, behind_ms: i64 },
+    /// The same feed reported the mint again.
+    Repeat,
+}
+
+struct Sightings {
+    first: DetectionSource,
+    at: BTreeMap<DetectionSource, DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct SourceCounters {
+    seen: u64,
+    first: u64,
+    behind: u64,
+    behind_ms_total: i64,
+    last_seen: Option<Instant>,
+}
+
+struct DedupState {
+    mints: LruCache<String, Sightings>,
+    sources: BTreeMap<DetectionSource, SourceCounters>,
+}
+
+static STATE: Lazy<Mutex<DedupState>> = Lazy::new(|| {
+    Mutex::new(DedupState {
+        mints: LruCache::new(NonZeroUsize::new(TRACKED_MINTS).unwrap()),
+        sources: BTreeMap::new(),
+    })
+});
+
+/// Record that `source` reported `mint` at `at`.
+pub async fn observe(mint: &str, source: DetectionSource, at: DateTime<Utc>) -> Sighting {
+    let mut state = STATE.lock().await;
+    let sighting = match state.mints.get_mut(mint) {
+        None => {
+            state.mints.put(
+                mint.to_string(),
+                Sightings { first: source, at: BTreeMap::from([(source, at)]) },
+            );
+            Sighting::First
+        }
+        Some(s) if s.at.contains_key(&source) => return Sighting::Repeat,
+        Some(s) => {
+            let first_at = s.at[&s.first];
+            s.at.insert(source, at);
+            Sighting::Duplicate { first: s.first, behind_ms: (at - first_at).num_milliseconds() }
+        }
+    };
+    let counters = state.sources.entry(source).or_default();
+    counters.seen += 1;
+    counters.last_seen = Some(Instant::now());
+    match sighting {
+        Sighting::First => counters.first += 1,
+        Sighting::Duplicate { behind_ms, .. } => {
+            counters.behind += 1;
+            counters.behind_ms_total += behind_ms;
+        }
+        Sighting::Repeat => {}
+    }
+    sighting
+}
+
+/// First-seen time per source for `mint` (RFC 3339), for `DetectedCoin`.
+pub async fn first_seen(mint: &str) -> BTreeMap<String, String> {
+    STATE
+        .lock()
+        .await
+        .mints
+        .peek(mint)
+        .map(|s| s.at.iter().map(|(src, at)| (src.to_string(), at.to_rfc3339())).collect())
+        .unwrap_or_default()
+}
+
+/// Per-source detection counters, for `/api/health` and `/api/stats`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStats {
+    pub source: DetectionSource,
+    /// Mints this feed reported
+    pub seen: u64,
+    /// Mints this feed reported before any other
+    pub first: u64,
+    /// Average lag behind the winning feed when it was not first
+    pub avg_behind_ms: Option<f64>,
+    /// Seconds since this feed last reported a mint
+    pub last_seen_age_secs: Option<u64>,
+}
+
+/// Forget every sighting and feed counter when the engine stops.
+pub(crate) async fn reset() {
+    let mut state = STATE.lock().await;
+    state.mints.clear();
+    state.sources.clear();
+}
+
+pub async fn stats() -> Vec<SourceStats> {
+    let state = STATE.lock().await;
+    state
+        .sources
+        .iter()
+        .map(|(source, c)| SourceStats {
+            source: *source,
+            seen: c.seen,
+            first: c.first,
+            avg_behind_ms: (c.behind > 0)
+                .then(|| (c.behind_ms_total as f64 / c.behind as f64 * 10.0).round() / 10.0),
+            last_seen_age_secs: c.last_seen.map(|t| t.elapsed().as_secs()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn first_feed_wins_and_lag_is_recorded() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        let mint = "DedupTestMint1111111111111111111111111111111";
+        let t0 = Utc::now();
+        assert_eq!(observe(mint, DetectionSource::PumpPortal, t0).await, Sighting::First);
+        assert_eq!(
+            observe(mint, DetectionSource::Logs, t0 + chrono::Duration::milliseconds(350)).await,
+            Sighting::Duplicate { first: DetectionSource::PumpPortal, behind_ms: 350 }
+        );
+        assert_eq!(observe(mint, DetectionSource::PumpPortal, t0).await, Sighting::Repeat);
+
+        let seen = first_seen(mint).await;
+        assert_eq!(seen.len(), 2);
+        assert!(seen.contains_key("pumpportal") && seen.contains_key("logs"));
+    }
+}
@@ -3,17 +3,18 @@
 /// This fee can be enabled or disabled via config.toml (default: enabled).
 #[allow(deprecated)]
 use solana_program::{pubkey::Pubkey, instruction::Instruction, system_instruction};
-use std::str::FromStr;
+use crate::settings::Settings;
 
-// HARDCODED: Dev wallet address and fee percentage
-// The wallet address and percentage are fixed in the code.
+// HARDCODED: Dev fee percentage
+// The wallet address comes from the cluster address book (`cluster.rs`).
 // Whether the fee is applied can be configured via settings.
-const DEV_WALLET: &str = "BEAST1kZRXbU2FQs3SDa38t5jb1gxiVvfoQ3Vos2i8QE";
 const DEV_FEE_PERCENT: f64 = 1.0;
 
-/// Get the dev wallet public key (hardcoded)
-pub fn get_dev_wallet() -> Result<Pubkey, Box<dyn std::error::Error + Send + Sync>> {
-    Pubkey::from_str(DEV_WALLET)
+/// Get the dev wallet public key for the configured cluster
+pub fn get_dev_wallet(settings: &Settings) -> Result<Pubkey, Box<dyn std::error::Error + Send + Sync>> {
+    settings
+        .address_book()
+        .map(|book| book.dev_wallet)
         .map_err(|e| format!("Invalid dev wallet: {}", e).into())
 }
 
@@ -25,19 +26,19 @@ pub fn calculate_dev_fee(amount_lamports: u64) -> u64 {
 
 /// Add dev fee instruction to a list of instructions if enabled.
 /// Creates a system transfer of 1% of the transaction amount to the dev wallet.
-/// Only adds the fee if `enabled` is true.
+/// Only adds the fee if `dev_fee_enabled` is set.
 pub fn add_dev_fee_to_instructions(
     instructions: &mut Vec<Instruction>,
     payer: &Pubkey,
     transaction_amount_lamports: u64,
-    enabled: bool,
+    settings: &Settings,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    if !enabled {
+    if !settings.dev_fee_enabled {
         log::debug!("Dev fee is disabled, skipping fee instruction");
         return Ok(());
     }
 
-    let dev_wallet = get_dev_wallet()?;
+    let dev_wallet = get_dev_wallet(settings)?;
     let fee_amount = calculate_dev_fee(transaction_amount_lamports);
 
     let transfer_instruction = system_instruction::transfer(
@@ -0,0 +1,635 @@
+//! Embedding API. [`EngineBuilder`] wires the detection feeds, the message
+//! pipeline, position actors, the TP/SL monitor and (optionally) the HTTP API
+//! the same way the `sol_beast` binary does; [`Engine`] is the running instance.
+//!
+//! The pipeline keeps some process-wide state (bot control, cross-feed dedup,
+//! the paper ledger, the recorder, RPC endpoint health), so only one engine
+//! runs at a time: starting a second one while the first is running fails.
+//! [`Engine::stop`] drains every task the engine spawned and resets that
+//! state, after which another engine can start in the same process.
+use crate::api::{self, ApiState, BotControl, BotMode, BotRunningState, BotStats, DetectedCoin, TradeRecord};
+use crate::error::AppError;
+use crate::execution::{self, ExecutionBackend};
+use crate::models::Holding;
+use crate::position::PositionRegistry;
+use crate::recorder::{self, Record};
+use crate::settings::Settings;
+use crate::ws::WsRequest;
+use crate::{
+    confirm, dedup, fanout, hot_cache, missed, monitor, paper, pipeline, poller, pumpportal, rate_limit, rpc_pool, shadow,
+    tasks, ws_supervisor, TOTAL_DETECTED_COINS,
+};
+use log::{error, info, warn};
+use lru::LruCache;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+/// Receives every event pushed to dashboard WebSocket clients (`detected-coin`,
+/// `new-trade`, position updates, ...) as its JSON object.
+pub type EventSink = Arc<dyn Fn(serde_json::Value) + Send + Sync>;
+
+/// Feeds the engine connects to. Each one still honours its settings
+/// (`pumpportal_enabled`, `poll_fallback_enabled`); turning one off here skips
+/// it regardless. Messages can also be pushed directly with [`Engine::feed`].
+#[derive(Debug, Clone)]
+pub struct DataSources {
+    /// A supervised connection per `solana_ws_urls` entry: logs detection,
+    /// curve price updates and signature confirmations.
+    pub solana_ws: bool,
+    /// The PumpPortal new-token stream per `pumpportal_wss` entry, which also
+    /// follows trades of held mints for pricing.
+    pub pumpportal: bool,
+    /// The `getSignaturesForAddress` poller used while streaming feeds are down.
+    pub poller: bool,
+}
+
+impl Default for DataSources {
+    fn default() -> Self {
+        DataSources { solana_ws: true, pumpportal: true, poller: true }
+    }
+}
+
+pub struct EngineBuilder {
+    settings: Settings,
+    keypair: Option<Arc<Keypair>>,
+    simulate_keypair: Option<Arc<Keypair>>,
+    real: bool,
+    sources: DataSources,
+    replay: Option<(Vec<Record>, f64)>,
+    rpc_client: Option<Arc<RpcClient>>,
+    backend: Option<Arc<dyn ExecutionBackend>>,
+    event_sink: Option<EventSink>,
+    api_addr: Option<String>,
+    auto_start: bool,
+}
+
+impl EngineBuilder {
+    pub fn new(settings: Settings) -> Self {
+        EngineBuilder {
+            settings,
+            keypair: None,
+            simulate_keypair: None,
+            real: false,
+            sources: DataSources::default(),
+            replay: None,
+            rpc_client: None,
+            backend: None,
+            event_sink: None,
+            api_addr: None,
+            auto_start: false,
+        }
+    }
+
+    /// Trading wallet. Required for real mode, and lets the dashboard switch
+    /// a dry-run engine to real mode later.
+    pub fn keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(Arc::new(keypair));
+        self
+    }
+
+    /// Keypair used to sign dry-run simulations (an ephemeral one otherwise).
+    pub fn simulate_keypair(mut self, keypair: Keypair) -> Self {
+        self.simulate_keypair = Some(Arc::new(keypair));
+        self
+    }
+
+    /// Start in real mode (default: dry-run with the paper wallet).
+    pub fn real(mut self, real: bool) -> Self {
+        self.real = real;
+        self
+    }
+
+    pub fn data_sources(mut self, sources: DataSources) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Feed a recording through the pipeline at `speed`x (0 = as fast as
+    /// possible), answering off-chain metadata fetches from it.
+    pub fn replay(mut self, records: Vec<Record>, speed: f64) -> Self {
+        self.replay = Some((records, speed));
+        self
+    }
+
+    /// RPC client for every task (default: the shared pooled client).
+    pub fn rpc_client(mut self, rpc_client: Arc<RpcClient>) -> Self {
+        self.rpc_client = Some(rpc_client);
+        self
+    }
+
+    /// Send every buy and sell through `backend` instead of the configured
+    /// `buy_execution_backend` / `sell_execution_backend`.
+    pub fn execution_backend(mut self, backend: impl ExecutionBackend + 'static) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    pub fn event_sink(mut self, sink: impl Fn(serde_json::Value) + Send + Sync + 'static) -> Self {
+        self.event_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Serve the dashboard HTTP/WebSocket API on `addr` (e.g. "127.0.0.1:8080").
+    pub fn api(mut self, addr: impl Into<String>) -> Self {
+        self.api_addr = Some(addr.into());
+        self
+    }
+
+    /// Start trading right away instead of waiting for a start from the API.
+    pub fn auto_start(mut self, auto_start: bool) -> Self {
+        self.auto_start = auto_start;
+        self
+    }
+
+    /// Validate the settings, spawn every task and return the running engine.
+    /// Fails while another engine is running in this process.
+    pub async fn start(self) -> Result<Engine, AppError> {
+        self.settings.validate()?;
+        if self.real && self.keypair.is_none() {
+            return Err(AppError::InvalidKeypair("No wallet keypair configured! Set wallet_keypair_path, wallet_private_key_string, wallet_keypair_json, or SOL_BEAST_KEYPAIR_B64 env var".to_string()));
+        }
+        tasks::open().map_err(AppError::Init)?;
+        // Whatever an engine dropped without `stop` left behind
+        reset_process_state().await;
+        match self.launch().await {
+            Ok(engine) => Ok(engine),
+            Err(e) => {
+                shut_down().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn launch(self) -> Result<Engine, AppError> {
+        if let Some(backend) = self.backend {
+            execution::register_backend(backend).map_err(|e| AppError::Init(e.to_string()))?;
+        }
+        if !self.settings.record_path.is_empty() {
+            recorder::start(&self.settings.record_path)?;
+        }
+        let settings = Arc::new(self.settings);
+        let keypair = self.keypair;
+        let simulate_keypair = self.simulate_keypair;
+
+        // Shared settings mutex: the API writes to this, and running tasks read from it
+        // each tick so config changes from the frontend take effect immediately.
+        let shared_settings: Arc<Mutex<Settings>> = Arc::new(Mutex::new(settings.as_ref().clone()));
+
+        // One nonblocking client shared by every task
+        let rpc_client = match self.rpc_client {
+            Some(client) => client,
+            None => rpc_pool::shared_client(&settings)?,
+        };
+        // Keep blockhash, Global account and fee config warm for the buy path
+        // (idles while hot_cache_enabled is off)
+        hot_cache::spawn_refresher(rpc_client.clone(), shared_settings.clone());
+        info!(
+            "Configured price_source={} rpc_rotate_interval_secs={}",
+            settings.price_source, settings.rpc_rotate_interval_secs
+        );
+        let seen = Arc::new(Mutex::new(LruCache::new(settings.cache_capacity.try_into()?)));
+        // Read-only view of open positions (written only by the position actors)
+        let holdings = Arc::new(Mutex::new(HashMap::new()));
+        // API data structures for detected coins and trades
+        let detected_coins = Arc::new(Mutex::new(Vec::new()));
+        let trades_list = Arc::new(Mutex::new(Vec::new()));
+        let price_cache = Arc::new(Mutex::new(LruCache::new(settings.cache_capacity.try_into()?)));
+        // Paper-trade a second strategy on the same detections
+        if !settings.shadow_config_path.is_empty() {
+            let strategy = Settings::from_file(&settings.shadow_config_path)?;
+            strategy.validate()?;
+            shadow::start(strategy, settings.clone()).map_err(AppError::Init)?;
+            info!("Shadow strategy from {} is paper trading alongside", settings.shadow_config_path);
+        }
+        let (tx, mut rx) = mpsc::channel::<String>(1000);
+        // Dynamic mode flag — updated by the API when the user toggles mode via
+        // the dashboard. Buy/sell logic reads this each tick instead of the static
+        // start-up bool so the mode switch takes effect immediately.
+        let is_real_flag = Arc::new(AtomicBool::new(self.real));
+
+        // The running engine owns the global bot control used for logging
+        let initial_mode = if self.real { BotMode::Real } else { BotMode::DryRun };
+        let bot_control = Arc::new(BotControl::new_with_mode(initial_mode));
+        crate::set_bot_control(Some(bot_control.clone()));
+
+        // Broadcast channel for dashboard WebSocket updates and the event sink
+        let (ws_tx, _ws_rx) = broadcast::channel::<String>(100);
+        if let Some(sink) = self.event_sink {
+            spawn_event_sink(ws_tx.subscribe(), sink);
+        }
+
+        // Per-mint price ticks pushed by price sources into the monitor so TP/SL
+        // is evaluated as soon as a new price lands.
+        let (price_tick_tx, price_tick_rx) = mpsc::channel::<monitor::PriceTick>(1024);
+
+        // Spawn WSS tasks and keep control senders so we can request subscriptions
+        let mut ws_control_senders: Vec<mpsc::Sender<WsRequest>> = Vec::new();
+        // Supervisors report dropped connections here so subscriptions can migrate
+        let (ws_down_tx, ws_down_rx) = mpsc::unbounded_channel::<usize>();
+        // Solana WSS carries price monitoring (accountSubscribe) even when
+        // PumpPortal handles detection; ws::run_ws skips logsSubscribe then.
+        if self.sources.solana_ws {
+            for wss_url in settings.solana_ws_urls.iter() {
+                let (ctrl_tx, ctrl_rx) = mpsc::channel(256);
+                ws_control_senders.push(ctrl_tx);
+                // The supervisor owns the control receiver across reconnects and
+                // restarts `ws::run_ws` with backoff whenever the connection drops.
+                tasks::spawn(ws_supervisor::supervise_solana_ws(
+                    ws_control_senders.len() - 1,
+                    wss_url.clone(),
+                    tx.clone(),
+                    ws_tx.clone(),
+                    seen.clone(),
+                    holdings.clone(),
+                    price_cache.clone(),
+                    ctrl_rx,
+                    settings.clone(),
+                    rpc_client.clone(),
+                    price_tick_tx.clone(),
+                    ws_down_tx.clone(),
+                ));
+            }
+        }
+        // Polls for new tokens while every streaming detection feed is down
+        if self.sources.poller {
+            tasks::spawn(poller::run(tx.clone(), rpc_client.clone(), settings.clone()));
+        }
+        tasks::spawn(missed::run(settings.clone()));
+        if let Some((records, speed)) = self.replay {
+            recorder::replay_http(&records);
+            tasks::spawn(recorder::replay(records, tx.clone(), speed));
+        }
+        let ws_control_senders = Arc::new(ws_control_senders);
+        // Let the confirmation tracker use signatureSubscribe on these connections
+        confirm::register_ws_senders(Some(ws_control_senders.clone()));
+        // Per-position actors own holding, buy record and subscription state
+        let positions = PositionRegistry::new(holdings.clone(), ws_control_senders.clone(), bot_control.clone(), ws_tx.clone());
+        if self.sources.pumpportal && settings.pumpportal_enabled {
+            // PumpPortal also streams trades of held mints as a second price source
+            let trades = pumpportal::TradeFeed {
+                held: positions.held_mints(),
+                price_cache: price_cache.clone(),
+                price_tick_tx: price_tick_tx.clone(),
+            };
+            for pp_url in settings.pumpportal_wss.iter() {
+                tasks::spawn(ws_supervisor::supervise_pumpportal(
+                    pp_url.clone(),
+                    tx.clone(),
+                    settings.clone(),
+                    Some(trades.clone()),
+                ));
+            }
+        }
+        tasks::spawn(ws_supervisor::run_migrator(
+            ws_down_rx,
+            ws_control_senders.clone(),
+            positions.clone(),
+            settings.clone(),
+        ));
+        // Round-robin index for WSS sender selection (true round-robin)
+        let next_wss_sender = Arc::new(AtomicUsize::new(0usize));
+
+        tasks::spawn(monitor::monitor_holdings(
+            holdings.clone(),
+            price_cache.clone(),
+            rpc_client.clone(),
+            is_real_flag.clone(),
+            keypair.clone(),
+            simulate_keypair.clone(),
+            settings.clone(),
+            shared_settings.clone(),
+            positions.clone(),
+            ws_control_senders.clone(),
+            next_wss_sender.clone(),
+            trades_list.clone(),
+            bot_control.clone(),
+            ws_tx.clone(),
+            price_tick_rx,
+        ));
+
+        if let Some(addr) = self.api_addr {
+            let api_state = ApiState {
+                settings: shared_settings.clone(),
+                stats: Arc::new(Mutex::new(BotStats {
+                    total_buys: 0,
+                    total_sells: 0,
+                    total_profit: 0.0,
+                    current_holdings: vec![],
+                    uptime_secs: 0,
+                    last_activity: chrono::Utc::now().to_rfc3339(),
+                    running_state: Some("stopped".to_string()),
+                    mode: Some(if self.real { "real" } else { "dry-run" }.to_string()),
+                    paper_wallet: None,
+                    detection_sources: Vec::new(),
+                    websockets: Vec::new(),
+                })),
+                bot_control: bot_control.clone(),
+                detected_coins: detected_coins.clone(),
+                trades: trades_list.clone(),
+                ws_tx: ws_tx.clone(),
+                is_real_flag: is_real_flag.clone(),
+                has_keypair: keypair.is_some(),
+                positions: positions.clone(),
+            };
+            spawn_stats_sync(api_state.stats.clone(), holdings.clone(), trades_list.clone(), bot_control.clone());
+            spawn_api_server(addr, api_state);
+        }
+
+        bot_control
+            .add_log(
+                "info",
+                if self.auto_start {
+                    "Bot initialized and running.".to_string()
+                } else {
+                    "Bot initialized in stopped state. Choose a mode and start manually.".to_string()
+                },
+                Some(format!(
+                    "Wallet: {}",
+                    if self.real {
+                        keypair.as_ref().map(|k| k.pubkey().to_string()).unwrap_or_else(|| "None".to_string())
+                    } else {
+                        format!("Simulation mode{}", if keypair.is_some() { " (keypair loaded, can switch to real)" } else { "" })
+                    }
+                )),
+            )
+            .await;
+        if self.auto_start {
+            *bot_control.running_state.lock().await = BotRunningState::Running;
+        }
+
+        // Process messages
+        let feed = tx.clone();
+        drop(tx);
+        let trades_for_loop = trades_list.clone();
+        let detected_for_loop = detected_coins.clone();
+        let settings_for_loop = shared_settings.clone();
+        let is_real_for_loop = is_real_flag.clone();
+        tasks::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                recorder::record_feed(&msg);
+                let seen = seen.clone();
+                let positions = positions.clone();
+                let rpc_client = rpc_client.clone();
+                let price_cache = price_cache.clone();
+                // Hot-reload: take a fresh snapshot of settings from the shared mutex
+                // so config changes from the frontend take effect immediately.
+                let settings = Arc::new(settings_for_loop.lock().await.clone());
+                let ws_control_senders = ws_control_senders.clone();
+                let next_wss_sender = next_wss_sender.clone();
+                let detected_coins = detected_for_loop.clone();
+                let trades_list = trades_for_loop.clone();
+                let ws_tx = ws_tx.clone();
+                let keypair = keypair.clone();
+                let simulate_keypair = simulate_keypair.clone();
+                let is_real_flag = is_real_for_loop.clone();
+
+                tasks::spawn(async move {
+                    let is_real = is_real_flag.load(Ordering::Relaxed);
+                    if let Err(e) = pipeline::process_message(
+                        &msg,
+                        &seen,
+                        &positions,
+                        &rpc_client,
+                        is_real,
+                        keypair.as_deref(),
+                        simulate_keypair.as_deref(),
+                        &price_cache,
+                        &settings,
+                        ws_control_senders,
+                        next_wss_sender,
+                        detected_coins,
+                        trades_list,
+                        ws_tx,
+                    )
+                    .await
+                    {
+                        // Log the error and a truncated preview of the incoming message for debugging.
+                        let preview: String = msg.chars().take(200).collect();
+                        error!(
+                            "process_message failed for incoming message (truncated): {}... error: {}",
+                            preview, e
+                        );
+                        bot_log!("error", "Failed to process WebSocket message", format!("{}", e));
+                    }
+                });
+            }
+            info!("Message processing loop ended");
+        });
+
+        Ok(Engine {
+            settings: shared_settings,
+            control: bot_control,
+            feed: Some(feed),
+            holdings,
+            trades: trades_list,
+            detected_coins,
+            is_real: is_real_flag,
+        })
+    }
+}
+
+/// A running engine. Dropping it aborts its tasks without waiting for them;
+/// [`Engine::stop`] also waits for them and resets the process-wide state.
+pub struct Engine {
+    settings: Arc<Mutex<Settings>>,
+    control: Arc<BotControl>,
+    /// Taken by `wait`, so the message loop ends with the feeds
+    feed: Option<mpsc::Sender<String>>,
+    holdings: Arc<Mutex<HashMap<String, Holding>>>,
+    trades: Arc<Mutex<Vec<TradeRecord>>>,
+    detected_coins: Arc<Mutex<Vec<DetectedCoin>>>,
+    is_real: Arc<AtomicBool>,
+}
+
+impl Engine {
+    /// Live settings; changes apply to the next message and monitor tick.
+    pub fn settings(&self) -> Arc<Mutex<Settings>> {
+        self.settings.clone()
+    }
+
+    pub fn control(&self) -> Arc<BotControl> {
+        self.control.clone()
+    }
+
+    /// Open positions by mint.
+    pub fn holdings(&self) -> Arc<Mutex<HashMap<String, Holding>>> {
+        self.holdings.clone()
+    }
+
+    /// Trade history, newest first (capped at 200).
+    pub fn trades(&self) -> Arc<Mutex<Vec<TradeRecord>>> {
+        self.trades.clone()
+    }
+
+    /// Detected coins, newest first (capped at `detected_coins_max`).
+    pub fn detected_coins(&self) -> Arc<Mutex<Vec<DetectedCoin>>> {
+        self.detected_coins.clone()
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.is_real.load(Ordering::Relaxed)
+    }
+
+    /// Push a raw feed message (the JSON a Solana logs or PumpPortal feed
+    /// delivers) into the pipeline.
+    pub async fn feed(&self, msg: String) -> Result<(), AppError> {
+        let feed = self.feed.as_ref().ok_or_else(|| AppError::Init("engine is stopped".to_string()))?;
+        feed.send(msg).await.map_err(|_| AppError::Init("engine is stopped".to_string()))
+    }
+
+    /// Allow (`true`) or pause (`false`) new buys; open positions are always monitored.
+    /// Pausing drains like the dashboard's stop: TP/SL/timeout exits keep firing
+    /// and the bot settles on Stopped once no positions are left.
+    pub async fn set_running(&self, running: bool) {
+        *self.control.running_state.lock().await =
+            if running { BotRunningState::Running } else { BotRunningState::Stopping };
+    }
+
+    /// Stop buying, abort every task the engine spawned (feeds, pipeline runs,
+    /// position actors, monitor, refreshers, API) and wait until all of them
+    /// have finished, then reset the process-wide state. Positions still open
+    /// on chain are left as they are.
+    pub async fn stop(self) {
+        self.set_running(false).await;
+        let drained = shut_down().await;
+        info!("Engine stopped ({} tasks drained)", drained);
+    }
+
+    /// Run until the message loop and background tasks end.
+    pub async fn wait(mut self) {
+        // Only the feeds keep the message loop alive from here
+        self.feed.take();
+        tasks::wait().await;
+        reset_process_state().await;
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        // No-op after `stop` or `wait`, which already freed the slot
+        tasks::abort();
+    }
+}
+
+/// Drain the engine's tasks and free the process for another engine.
+async fn shut_down() -> usize {
+    let drained = tasks::close().await;
+    reset_process_state().await;
+    drained
+}
+
+/// Put back the process-wide state a fresh process starts with, so the next
+/// engine neither sees this one's detections, paper wallet or endpoints nor
+/// writes into its recording.
+async fn reset_process_state() {
+    crate::set_bot_control(None);
+    confirm::register_ws_senders(None);
+    execution::clear_backend();
+    shadow::stop();
+    recorder::stop();
+    rpc_pool::reset();
+    rate_limit::reset().await;
+    hot_cache::reset().await;
+    dedup::reset().await;
+    missed::reset().await;
+    paper::reset().await;
+    poller::reset().await;
+    fanout::reset().await;
+    ws_supervisor::reset().await;
+    TOTAL_DETECTED_COINS.store(0, Ordering::Relaxed);
+}
+
+fn spawn_event_sink(mut rx: broadcast::Receiver<String>, sink: EventSink) {
+    tasks::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => match serde_json::from_str(&msg) {
+                    Ok(event) => sink(event),
+                    Err(e) => warn!("Dropping non-JSON engine event: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(n)) => warn!("Event sink lagged; {} events dropped", n),
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+/// Periodically sync holdings and trade totals into the API stats.
+fn spawn_stats_sync(
+    stats: Arc<Mutex<BotStats>>,
+    holdings: Arc<Mutex<HashMap<String, Holding>>>,
+    trades: Arc<Mutex<Vec<TradeRecord>>>,
+    bot_control: Arc<BotControl>,
+) {
+    let start_time = Instant::now();
+    tasks::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+
+            let holdings_vec: Vec<api::HoldingWithMint> = holdings
+                .lock()
+                .await
+                .iter()
+                .map(|(mint, holding)| api::HoldingWithMint { mint: mint.clone(), holding: holding.clone() })
+                .collect();
+
+            // Calculate trade stats from trades list
+            let (total_buys, total_sells, total_profit) = {
+                let trades = trades.lock().await;
+                let mut buys = 0u64;
+                let mut sells = 0u64;
+                let mut profit = 0.0f64;
+                for trade in trades.iter() {
+                    if trade.trade_type == "buy" {
+                        buys += 1;
+                    } else if trade.trade_type == "sell" {
+                        sells += 1;
+                        if let Some(pl) = trade.profit_loss {
+                            profit += pl;
+                        }
+                    }
+                }
+                (buys, sells, profit)
+            };
+
+            let mut stats = stats.lock().await;
+            stats.current_holdings = holdings_vec;
+            stats.total_buys = total_buys;
+            stats.total_sells = total_sells;
+            stats.total_profit = total_profit;
+            stats.uptime_secs = start_time.elapsed().as_secs();
+            stats.last_activity = chrono::Utc::now().to_rfc3339();
+
+            // Log holdings count periodically (every 10 seconds)
+            if stats.uptime_secs % 10 == 0 && !stats.current_holdings.is_empty() {
+                bot_control
+                    .add_log("info", format!("Currently holding {} positions", stats.current_holdings.len()), None)
+                    .await;
+            }
+        }
+    })
+}
+
+fn spawn_api_server(bind_addr: String, state: ApiState) {
+    let api_router = api::create_router(state);
+    tasks::spawn(async move {
+        match tokio::net::TcpListener::bind(&bind_addr).await {
+            Ok(l) => {
+                info!("API server listening on {}", bind_addr);
+                if let Err(e) = axum::serve(l, api_router).await {
+                    error!("API server failed: {}", e);
+                    bot_log!("error", "API server failed", format!("{}", e));
+                }
+            }
+            Err(e) => {
+                error!("Failed to bind API server to {}: {}", bind_addr, e);
+                bot_log!("error", "Failed to bind API server", format!("{}", e));
+            }
+        }
+    })
+}
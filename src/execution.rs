@@ -0,0 +1,502 @@
+//! Transaction execution backends.
+//!
+//! Buy and sell paths build their pump.fun instructions and hand them to an
+//! [`ExecutionBackend`], chosen per side by `buy_execution_backend` /
+//! `sell_execution_backend`:
+//!
+//! - `rpc`        – plain `sendTransaction` with compute-budget instructions
+//! - `helius`     – Helius Sender (tip, simulated CU limit, dynamic priority fee)
+//! - `jito`       – Jito block-engine bundle with a separate tip transaction (see `jito.rs`)
+//! - `pumpportal` – PumpPortal local-transaction API (PumpPortal builds, we sign)
+//! - `fanout`     – one signed transaction raced across every send path (see `fanout.rs`)
+//! - `auto`       – `helius` when `helius_sender_enabled`, otherwise `rpc`
+
+use crate::confirm::ConfirmationOutcome;
+use crate::settings::Settings;
+use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
+use futures_util::future::BoxFuture;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub type ExecError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+/// Everything a backend needs to land one trade.
+pub struct ExecutionRequest<'a> {
+    pub side: Side,
+    pub mint: &'a str,
+    pub payer: &'a Keypair,
+    /// Trade instructions (ATA setup, pump.fun buy/sell, ATA close).
+    pub instructions: Vec<Instruction>,
+    /// Instructions paid on top of the trade (dev fee). Appended after the trade
+    /// instructions, or sent separately by backends that build their own.
+    pub fee_instructions: Vec<Instruction>,
+    /// Extra instructions bundled as a second transaction right after the trade,
+    /// e.g. a same-slot sell. Only the `jito` backend supports this.
+    pub followup_instructions: Vec<Instruction>,
+    /// SOL to spend on a buy (used by backends that build the trade themselves).
+    pub sol_amount: f64,
+    /// Token base units to sell (used by backends that build the trade themselves).
+    pub token_amount: u64,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub signature: String,
+    pub backend: &'static str,
+    /// Whether the trade transaction landed, failed on-chain or expired.
+    pub outcome: ConfirmationOutcome,
+    /// Signature of the follow-up transaction, for backends that send one (Jito bundles).
+    pub followup_signature: Option<String>,
+}
+
+pub trait ExecutionBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>>;
+}
+
+impl<T: ExecutionBackend + ?Sized> ExecutionBackend for Arc<T> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>> {
+        (**self).execute(req, settings)
+    }
+}
+
+/// Backend supplied by an embedding application (`EngineBuilder::execution_backend`).
+/// When set it handles both sides instead of the configured backends.
+static CUSTOM_BACKEND: RwLock<Option<Arc<dyn ExecutionBackend>>> = RwLock::new(None);
+
+/// Route every trade through `backend` until [`clear_backend`].
+pub fn register_backend(backend: Arc<dyn ExecutionBackend>) -> Result<(), ExecError> {
+    let mut custom = CUSTOM_BACKEND.write().unwrap();
+    if custom.is_some() {
+        return Err("an execution backend is already registered".into());
+    }
+    *custom = Some(backend);
+    Ok(())
+}
+
+/// Go back to the configured backends (the engine calls this when it stops).
+pub fn clear_backend() {
+    *CUSTOM_BACKEND.write().unwrap() = None;
+}
+
+/// Name of the backend configured for one side of a trade, with `auto` resolved.
+pub fn backend_name(side: Side, settings: &Settings) -> &str {
+    let configured = match side {
+        Side::Buy => settings.buy_execution_backend.as_str(),
+        Side::Sell => settings.sell_execution_backend.as_str(),
+    };
+    match configured {
+        "auto" if settings.helius_sender_enabled => "helius",
+        "auto" => "rpc",
+        other => other,
+    }
+}
+
+/// Resolve the configured backend for one side of a trade.
+pub fn backend_for(side: Side, settings: &Settings) -> Result<Box<dyn ExecutionBackend>, ExecError> {
+    if let Some(custom) = CUSTOM_BACKEND.read().unwrap().clone() {
+        return Ok(Box::new(custom));
+    }
+    let name = backend_name(side, settings);
+    let rpc_url = primary_rpc_url(settings)?;
+    match name {
+        "rpc" => Ok(Box::new(RpcBackend::new(rpc_url))),
+        "helius" => Ok(Box::new(HeliusSenderBackend::new(rpc_url))),
+        "jito" => Ok(Box::new(crate::jito::JitoBackend::new(settings.jito_block_engine_url.clone(), rpc_url))),
+        "pumpportal" => Ok(Box::new(PumpPortalBackend::new(settings.pumpportal_trade_local_url.clone(), rpc_url))),
+        "fanout" => Ok(Box::new(crate::fanout::FanoutBackend::new())),
+        other => Err(format!("unknown execution backend '{}' for {}", other, side.as_str()).into()),
+    }
+}
+
+fn primary_rpc_url(settings: &Settings) -> Result<String, ExecError> {
+    settings
+        .solana_rpc_urls
+        .first()
+        .cloned()
+        .ok_or_else(|| "no solana_rpc_urls configured".into())
+}
+
+pub(crate) fn compute_budget_instructions(settings: &Settings) -> Vec<Instruction> {
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(settings.compute_unit_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(settings.compute_unit_price_micro_lamports),
+    ]
+}
+
+pub(crate) fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default()
+}
+
+/// POST a JSON-RPC request and return its `result`, turning `error` into Err.
+pub(crate) async fn json_rpc(
+    http: &reqwest::Client,
+    url: &str,
+    method: &str,
+    params: Value,
+) -> Result<Value, ExecError> {
+    let resp = http
+        .post(url)
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+        .send()
+        .await?;
+    let body: Value = resp.json().await?;
+    if let Some(err) = body.get("error") {
+        return Err(format!("{} error from {}: {}", method, url, err).into());
+    }
+    body.get("result")
+        .cloned()
+        .ok_or_else(|| format!("{} response from {} has no result", method, url).into())
+}
+
+/// Latest blockhash and its last valid block height, served from the hot
+/// cache when it is warm.
+pub(crate) async fn latest_blockhash(http: &reqwest::Client, rpc_url: &str) -> Result<(Hash, u64), ExecError> {
+    if let Some(latest) = crate::hot_cache::blockhash_with_height().await {
+        return Ok(latest);
+    }
+    let result = json_rpc(http, rpc_url, "getLatestBlockhash", json!([{ "commitment": "confirmed" }])).await?;
+    let value = result.get("value").ok_or("getLatestBlockhash response missing value")?;
+    let hash_str = value
+        .get("blockhash")
+        .and_then(|b| b.as_str())
+        .ok_or("getLatestBlockhash response missing blockhash")?;
+    let last_valid_block_height = value
+        .get("lastValidBlockHeight")
+        .and_then(|h| h.as_u64())
+        .ok_or("getLatestBlockhash response missing lastValidBlockHeight")?;
+    Ok((Hash::from_str(hash_str)?, last_valid_block_height))
+}
+
+/// Sign a legacy transaction and return (signature, base64 wire bytes).
+pub(crate) fn sign_transaction(
+    instructions: &[Instruction],
+    payer: &Keypair,
+    blockhash: Hash,
+) -> Result<(String, String), ExecError> {
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    tx.try_sign(&[payer], blockhash)?;
+    let signature = tx.signatures[0].to_string();
+    Ok((signature, Base64Engine.encode(bincode::serialize(&tx)?)))
+}
+
+pub(crate) async fn send_raw_transaction(
+    http: &reqwest::Client,
+    url: &str,
+    tx_base64: &str,
+    skip_preflight: bool,
+) -> Result<String, ExecError> {
+    let result = json_rpc(
+        http,
+        url,
+        "sendTransaction",
+        json!([tx_base64, { "encoding": "base64", "skipPreflight": skip_preflight, "maxRetries": 0 }]),
+    )
+    .await?;
+    result
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "sendTransaction returned a non-string result".into())
+}
+
+/// Wait for the trade signature with the confirmation tracker: at least
+/// `execution_confirm_timeout_secs`, then until its blockhash expires.
+pub(crate) async fn wait_for_confirmation(
+    http: &reqwest::Client,
+    rpc_url: &str,
+    signature: &str,
+    last_valid_block_height: Option<u64>,
+    settings: &Settings,
+) -> ConfirmationOutcome {
+    crate::confirm::confirm_signature(
+        http,
+        rpc_url,
+        signature,
+        Duration::from_secs(settings.execution_confirm_timeout_secs),
+        last_valid_block_height,
+    )
+    .await
+}
+
+/// Standard RPC `sendTransaction` with compute-budget instructions, confirmed in-line.
+pub struct RpcBackend {
+    rpc_url: String,
+    http: reqwest::Client,
+}
+
+impl RpcBackend {
+    pub fn new(rpc_url: String) -> Self {
+        Self { rpc_url, http: http_client() }
+    }
+}
+
+impl ExecutionBackend for RpcBackend {
+    fn name(&self) -> &'static str {
+        "rpc"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>> {
+        Box::pin(async move {
+            let mut instructions = compute_budget_instructions(settings);
+            instructions.extend(req.instructions);
+            instructions.extend(req.fee_instructions);
+            let (blockhash, last_valid_block_height) = latest_blockhash(&self.http, &self.rpc_url).await?;
+            let (signature, tx_base64) = sign_transaction(&instructions, req.payer, blockhash)?;
+            send_raw_transaction(&self.http, &self.rpc_url, &tx_base64, false).await?;
+            info!("{} {} sent via RPC: {}", req.side.as_str(), req.mint, signature);
+            let outcome = wait_for_confirmation(&self.http, &self.rpc_url, &signature, Some(last_valid_block_height), settings).await;
+            Ok(Submission { signature, backend: self.name(), outcome, followup_signature: None })
+        })
+    }
+}
+
+/// Helius Sender (see `helius_sender.rs` for tip and priority-fee handling).
+pub struct HeliusSenderBackend {
+    rpc_url: String,
+    http: reqwest::Client,
+}
+
+impl HeliusSenderBackend {
+    pub fn new(rpc_url: String) -> Self {
+        Self { rpc_url, http: http_client() }
+    }
+}
+
+impl ExecutionBackend for HeliusSenderBackend {
+    fn name(&self) -> &'static str {
+        "helius"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>> {
+        Box::pin(async move {
+            let mut instructions = req.instructions;
+            instructions.extend(req.fee_instructions);
+            let client = crate::rpc_pool::shared_client(settings)?;
+            let (signature, last_valid_block_height) = crate::helius_sender::send_transaction_with_retry(
+                instructions,
+                req.payer,
+                settings,
+                &client,
+                3, // max retries
+            )
+            .await?;
+            info!("{} {} sent via Helius Sender: {}", req.side.as_str(), req.mint, signature);
+            // Sender skips preflight, so give it the Helius-specific confirmation window
+            let outcome = crate::confirm::confirm_signature(
+                &self.http,
+                &self.rpc_url,
+                &signature,
+                Duration::from_secs(settings.helius_confirm_timeout_secs),
+                Some(last_valid_block_height),
+            )
+            .await;
+            Ok(Submission { signature, backend: self.name(), outcome, followup_signature: None })
+        })
+    }
+}
+
+/// PumpPortal local-transaction API: PumpPortal builds the trade transaction,
+/// we sign it and send it through our own RPC. Our fee instructions cannot be
+/// merged into their message, so they go out as a separate transaction.
+pub struct PumpPortalBackend {
+    trade_local_url: String,
+    rpc_url: String,
+    http: reqwest::Client,
+}
+
+impl PumpPortalBackend {
+    pub fn new(trade_local_url: String, rpc_url: String) -> Self {
+        Self { trade_local_url, rpc_url, http: http_client() }
+    }
+}
+
+impl ExecutionBackend for PumpPortalBackend {
+    fn name(&self) -> &'static str {
+        "pumpportal"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>> {
+        Box::pin(async move {
+            let (amount, denominated_in_sol) = match req.side {
+                Side::Buy => (req.sol_amount, "true"),
+                Side::Sell => (req.token_amount as f64 / 10f64.powi(req.decimals as i32), "false"),
+            };
+            let body = json!({
+                "publicKey": req.payer.pubkey().to_string(),
+                "action": req.side.as_str(),
+                "mint": req.mint,
+                "amount": amount,
+                "denominatedInSol": denominated_in_sol,
+                "slippage": settings.slippage_bps as f64 / 100.0,
+                "priorityFee": settings.pumpportal_priority_fee_sol,
+                "pool": "pump",
+            });
+            let resp = self.http.post(&self.trade_local_url).json(&body).send().await?;
+            let status = resp.status();
+            if !status.is_success() {
+                let text = resp.text().await.unwrap_or_default();
+                return Err(format!("PumpPortal trade-local returned {}: {}", status, text).into());
+            }
+            let bytes = resp.bytes().await?;
+            let unsigned: VersionedTransaction = bincode::deserialize(&bytes)?;
+            let signed = VersionedTransaction::try_new(unsigned.message, &[req.payer])?;
+            let signature = signed.signatures[0].to_string();
+            let tx_base64 = Base64Engine.encode(bincode::serialize(&signed)?);
+            send_raw_transaction(&self.http, &self.rpc_url, &tx_base64, false).await?;
+            info!("{} {} sent via PumpPortal local tx: {}", req.side.as_str(), req.mint, signature);
+            // PumpPortal took its blockhash before now, so it expires no later
+            // than a blockhash taken at the current height would
+            let last_valid_block_height = crate::confirm::block_height(&self.http, &self.rpc_url)
+                .await
+                .map(|height| height + crate::confirm::BLOCKHASH_VALID_BLOCKS);
+
+            if !req.fee_instructions.is_empty() {
+                let fee_result = async {
+                    let (blockhash, _) = latest_blockhash(&self.http, &self.rpc_url).await?;
+                    let (fee_sig, fee_tx) = sign_transaction(&req.fee_instructions, req.payer, blockhash)?;
+                    send_raw_transaction(&self.http, &self.rpc_url, &fee_tx, false).await?;
+                    Ok::<String, ExecError>(fee_sig)
+                }
+                .await;
+                match fee_result {
+                    Ok(fee_sig) => debug!("Fee transaction for {} sent separately: {}", req.mint, fee_sig),
+                    Err(e) => warn!("Failed to send fee transaction for {}: {}", req.mint, e),
+                }
+            }
+
+            let outcome = wait_for_confirmation(&self.http, &self.rpc_url, &signature, last_valid_block_height, settings).await;
+            Ok(Submission { signature, backend: self.name(), outcome, followup_signature: None })
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::mock_cluster::MockCluster;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::{pubkey::Pubkey, system_instruction};
+
+    fn test_settings(cluster: &MockCluster) -> Arc<Settings> {
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.solana_rpc_urls = vec![cluster.rpc_url()];
+        s.pumpportal_trade_local_url = cluster.trade_local_url();
+        s.execution_confirm_timeout_secs = 2;
+        Arc::new(s)
+    }
+
+    fn request<'a>(payer: &'a Keypair, side: Side) -> ExecutionRequest<'a> {
+        ExecutionRequest {
+            side,
+            mint: "So11111111111111111111111111111111111111112",
+            payer,
+            instructions: vec![system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            fee_instructions: vec![],
+            followup_instructions: vec![],
+            sol_amount: 0.01,
+            token_amount: 1_000_000,
+            decimals: 6,
+        }
+    }
+
+    #[tokio::test]
+    async fn rpc_backend_adds_compute_budget_and_confirms() {
+        let cluster = MockCluster::start().await.unwrap();
+        let settings = test_settings(&cluster);
+        let payer = Keypair::new();
+        let fee_to = Pubkey::new_unique();
+        let mut req = request(&payer, Side::Buy);
+        req.fee_instructions = vec![system_instruction::transfer(&payer.pubkey(), &fee_to, 2)];
+        let sub = RpcBackend::new(settings.solana_rpc_urls[0].clone()).execute(req, &settings).await.unwrap();
+        assert!(sub.outcome.is_landed());
+        let txs = cluster.sent_transactions();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(sub.signature, txs[0].signatures[0].to_string());
+        let keys = txs[0].message.static_account_keys();
+        let ixs = txs[0].message.instructions();
+        let programs: Vec<Pubkey> = ixs.iter().map(|ix| keys[ix.program_id_index as usize]).collect();
+        assert_eq!(programs[0], solana_sdk::compute_budget::id());
+        assert_eq!(programs[1], solana_sdk::compute_budget::id());
+        // The dev fee follows the trade, as it did before backends existed
+        assert_eq!(programs.len(), 4);
+        assert_eq!(keys[ixs[3].accounts[1] as usize], fee_to);
+    }
+
+    #[tokio::test]
+    async fn pumpportal_backend_signs_returned_transaction() {
+        let cluster = MockCluster::start().await.unwrap();
+        let payer = Keypair::new();
+        let message = Message::new(
+            &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 5)],
+            Some(&payer.pubkey()),
+        );
+        let unsigned = VersionedTransaction {
+            signatures: vec![solana_sdk::signature::Signature::default()],
+            message: VersionedMessage::Legacy(message),
+        };
+        cluster.set_trade_local(bincode::serialize(&unsigned).unwrap());
+        let settings = test_settings(&cluster);
+        let sub = PumpPortalBackend::new(settings.pumpportal_trade_local_url.clone(), settings.solana_rpc_urls[0].clone())
+            .execute(request(&payer, Side::Buy), &settings)
+            .await
+            .unwrap();
+        assert!(sub.outcome.is_landed());
+        assert_eq!(cluster.calls("sendTransaction"), 1);
+        assert_ne!(sub.signature, solana_sdk::signature::Signature::default().to_string());
+    }
+}
@@ -0,0 +1,381 @@
+//! Fan-out submission: one signed transaction raced across every send path.
+//!
+//! The transaction pays a single tip: to Helius Sender when it is enabled
+//! (its dual routing already forwards to Jito), otherwise to Jito. It is sent
+//! concurrently to each `solana_rpc_urls` entry and the tipped endpoint; the
+//! first acknowledgement starts confirmation while slower sends finish in the
+//! background. All endpoints receive identical bytes, so the one credited with
+//! landing it is the fastest to acknowledge before the signature status
+//! confirmed it. Per-endpoint statistics are kept in-process for
+//! `/api/execution/landing-stats`.
+
+// Allow deprecated system_instruction module until solana_system_interface is available
+#![allow(deprecated)]
+
+use crate::execution::{
+    self, ExecError, ExecutionBackend, ExecutionRequest, Submission,
+};
+use crate::confirm::ConfirmationOutcome;
+use crate::settings::Settings;
+use futures_util::future::BoxFuture;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer, system_instruction};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EndpointLandingStats {
+    pub endpoint: String,
+    pub kind: String,
+    pub sends: u64,
+    pub send_errors: u64,
+    /// Confirmed transactions this endpoint acknowledged fastest, counting only
+    /// acknowledgements that arrived before the confirmation.
+    pub landed_first: u64,
+    pub avg_ack_ms: f64,
+    pub last_error: Option<String>,
+    pub last_used: Option<String>,
+    #[serde(skip)]
+    total_ack_ms: f64,
+    #[serde(skip)]
+    acks: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LastLanding {
+    pub signature: String,
+    pub mint: String,
+    pub side: String,
+    pub endpoint: String,
+    pub confirm_ms: u64,
+    pub at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LandingReport {
+    pub endpoints: Vec<EndpointLandingStats>,
+    pub last_landing: Option<LastLanding>,
+}
+
+#[derive(Default)]
+struct LandingBook {
+    endpoints: HashMap<String, EndpointLandingStats>,
+    last_landing: Option<LastLanding>,
+}
+
+static LANDING_STATS: Lazy<Mutex<LandingBook>> = Lazy::new(|| Mutex::new(LandingBook::default()));
+
+pub(crate) async fn reset() {
+    *LANDING_STATS.lock().await = LandingBook::default();
+}
+
+/// Snapshot of per-endpoint landing statistics, most successful endpoints first.
+pub async fn landing_report() -> LandingReport {
+    let book = LANDING_STATS.lock().await;
+    let mut endpoints: Vec<EndpointLandingStats> = book.endpoints.values().cloned().collect();
+    endpoints.sort_by(|a, b| {
+        b.landed_first
+            .cmp(&a.landed_first)
+            .then(a.avg_ack_ms.partial_cmp(&b.avg_ack_ms).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    LandingReport { endpoints, last_landing: book.last_landing.clone() }
+}
+
+struct Target {
+    kind: &'static str,
+    /// Label used in stats (API keys stripped)
+    label: String,
+    url: String,
+}
+
+/// Ack result for one endpoint: Ok(ack latency) or Err(reason)
+type AckResult = Result<Duration, String>;
+
+fn strip_query(url: &str) -> String {
+    url.split('?').next().unwrap_or(url).to_string()
+}
+
+fn targets(settings: &Settings) -> Vec<Target> {
+    let mut out: Vec<Target> = settings
+        .solana_rpc_urls
+        .iter()
+        .map(|u| Target { kind: "rpc", label: strip_query(u), url: u.clone() })
+        .collect();
+    // Only the endpoint the transaction tips accepts it
+    if settings.helius_sender_enabled {
+        out.push(Target {
+            kind: "helius",
+            label: strip_query(&settings.helius_sender_endpoint),
+            url: crate::helius_sender::sender_endpoint_url(settings),
+        });
+    } else {
+        let jito = format!("{}/api/v1/transactions", settings.jito_block_engine_url.trim_end_matches('/'));
+        out.push(Target { kind: "jito", label: jito.clone(), url: jito });
+    }
+    out
+}
+
+/// The transaction's only tip, paid to the endpoint `targets` includes.
+fn tip_instruction(payer: &Pubkey, settings: &Settings) -> Result<Instruction, ExecError> {
+    if settings.helius_sender_enabled {
+        let tip = (settings.get_effective_min_tip_sol() * 1_000_000_000.0) as u64;
+        Ok(system_instruction::transfer(payer, &crate::helius_sender::get_random_tip_account(settings)?, tip))
+    } else {
+        let tip = (settings.jito_tip_sol * 1_000_000_000.0) as u64;
+        Ok(system_instruction::transfer(payer, &crate::jito::random_tip_account(settings)?, tip))
+    }
+}
+
+/// Endpoint credited with a confirmed landing: the fastest acknowledgement
+/// that had arrived by the time the confirmation did.
+fn credited(acks: &[(usize, AckResult)]) -> Option<usize> {
+    acks.iter()
+        .filter_map(|(i, a)| a.as_ref().ok().map(|latency| (*i, *latency)))
+        .min_by_key(|(_, latency)| *latency)
+        .map(|(i, _)| i)
+}
+
+/// Record the `(target index, ack)` pairs and, if it landed, the credited target.
+async fn record(targets: &[Target], acks: &[(usize, AckResult)], landed: Option<(usize, LastLanding)>) {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut book = LANDING_STATS.lock().await;
+    for (i, ack) in acks {
+        let target = &targets[*i];
+        let entry = book.endpoints.entry(target.label.clone()).or_insert_with(|| EndpointLandingStats {
+            endpoint: target.label.clone(),
+            kind: target.kind.to_string(),
+            ..Default::default()
+        });
+        entry.sends += 1;
+        entry.last_used = Some(now.clone());
+        match ack {
+            Ok(latency) => {
+                entry.acks += 1;
+                entry.total_ack_ms += latency.as_secs_f64() * 1000.0;
+                entry.avg_ack_ms = entry.total_ack_ms / entry.acks as f64;
+            }
+            Err(e) => {
+                entry.send_errors += 1;
+                entry.last_error = Some(e.clone());
+            }
+        }
+    }
+    if let Some((idx, landing)) = landed {
+        if let Some(entry) = book.endpoints.get_mut(&targets[idx].label) {
+            entry.landed_first += 1;
+        }
+        book.last_landing = Some(landing);
+    }
+}
+
+/// Sends one signed transaction to every configured endpoint at once.
+pub struct FanoutBackend {
+    http: reqwest::Client,
+}
+
+impl FanoutBackend {
+    pub fn new() -> Self {
+        Self { http: execution::http_client() }
+    }
+}
+
+impl ExecutionBackend for FanoutBackend {
+    fn name(&self) -> &'static str {
+        "fanout"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>> {
+        Box::pin(async move {
+            let payer_pubkey = req.payer.pubkey();
+            let rpc_url = settings.solana_rpc_urls.first().ok_or("no solana_rpc_urls configured")?;
+
+            let mut instructions = execution::compute_budget_instructions(settings);
+            instructions.extend(req.instructions);
+            instructions.extend(req.fee_instructions);
+            instructions.push(tip_instruction(&payer_pubkey, settings)?);
+
+            let (blockhash, last_valid_block_height) = execution::latest_blockhash(&self.http, rpc_url).await?;
+            let (signature, tx_base64) = execution::sign_transaction(&instructions, req.payer, blockhash)?;
+
+            let targets = Arc::new(targets(settings));
+            let started = Instant::now();
+            let mut sends: FuturesUnordered<_> = targets
+                .iter()
+                .enumerate()
+                .map(|(i, t)| {
+                    let http = self.http.clone();
+                    let url = t.url.clone();
+                    let tx_base64 = tx_base64.clone();
+                    async move {
+                        let ack = execution::send_raw_transaction(&http, &url, &tx_base64, true)
+                            .await
+                            .map(|_| started.elapsed())
+                            .map_err(|e| e.to_string());
+                        (i, ack)
+                    }
+                })
+                .collect();
+            let mut acks: Vec<(usize, AckResult)> = Vec::with_capacity(targets.len());
+            let log_ack = |i: usize, ack: &AckResult| match ack {
+                Ok(latency) => debug!("fan-out {} {} acked by {} in {:?}", req.side.as_str(), req.mint, targets[i].label, latency),
+                Err(e) => warn!("fan-out {} {} rejected by {}: {}", req.side.as_str(), req.mint, targets[i].label, e),
+            };
+
+            // Wait only for the first acknowledgement
+            while let Some((i, ack)) = sends.next().await {
+                log_ack(i, &ack);
+                let accepted = ack.is_ok();
+                acks.push((i, ack));
+                if accepted {
+                    break;
+                }
+            }
+            if !acks.iter().any(|(_, a)| a.is_ok()) {
+                record(&targets, &acks, None).await;
+                return Err(format!("fan-out send failed on all {} endpoints for {}", targets.len(), req.mint).into());
+            }
+            info!("{} {} fanned out to {} endpoints: {}", req.side.as_str(), req.mint, targets.len(), signature);
+
+            // Confirm while the remaining sends are still in flight
+            let outcome = {
+                let confirmation = execution::wait_for_confirmation(&self.http, rpc_url, &signature, Some(last_valid_block_height), settings);
+                tokio::pin!(confirmation);
+                loop {
+                    tokio::select! {
+                        outcome = &mut confirmation => break outcome,
+                        Some((i, ack)) = sends.next() => {
+                            log_ack(i, &ack);
+                            acks.push((i, ack));
+                        }
+                    }
+                }
+            };
+            let landed = match &outcome {
+                ConfirmationOutcome::Landed { .. } => credited(&acks).map(|i| {
+                    let landing = LastLanding {
+                        signature: signature.clone(),
+                        mint: req.mint.to_string(),
+                        side: req.side.as_str().to_string(),
+                        endpoint: targets[i].label.clone(),
+                        confirm_ms: started.elapsed().as_millis() as u64,
+                        at: chrono::Utc::now().to_rfc3339(),
+                    };
+                    info!(
+                        "{} {} landed in {} ms, first ack from {}",
+                        landing.side, landing.mint, landing.confirm_ms, landing.endpoint
+                    );
+                    (i, landing)
+                }),
+                _ => None,
+            };
+            record(&targets, &acks, landed).await;
+            // Stragglers only feed the send statistics
+            let stats_targets = Arc::clone(&targets);
+            crate::tasks::spawn(async move {
+                while let Some(ack) = sends.next().await {
+                    record(&stats_targets, &[ack], None).await;
+                }
+            });
+            Ok(Submission { signature, backend: self.name(), outcome, followup_signature: None })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_cluster::MockCluster;
+    use serde_json::json;
+
+    #[test]
+    fn targets_include_every_send_path_without_api_keys() {
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.solana_rpc_urls = vec!["http://a/".into(), "http://b/".into()];
+        s.helius_sender_enabled = true;
+        s.helius_api_key = Some("secret".into());
+        let t = targets(&s);
+        let kinds: Vec<&str> = t.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec!["rpc", "rpc", "helius"]);
+        assert!(t[2].url.contains("api-key=secret"));
+        assert!(!t[2].label.contains("secret"));
+
+        // Without Sender the transaction tips Jito instead
+        s.helius_sender_enabled = false;
+        let kinds: Vec<&str> = targets(&s).iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec!["rpc", "rpc", "jito"]);
+    }
+
+    #[tokio::test]
+    async fn first_ack_wins_without_waiting_for_slow_endpoints() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        let fast = MockCluster::start().await.unwrap();
+        // Acks sends after 3s
+        let slow = MockCluster::start().await.unwrap();
+        slow.set_latency(Duration::from_secs(3));
+        // Rejects them
+        let sender = MockCluster::start().await.unwrap();
+        sender.respond("sendTransaction", |_| Err(json!({ "code": -32002, "message": "tip too low" })));
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.solana_rpc_urls = vec![fast.rpc_url(), slow.rpc_url()];
+        s.helius_sender_enabled = true;
+        s.helius_sender_endpoint = sender.sender_url();
+        s.helius_api_key = None;
+        s.execution_confirm_timeout_secs = 2;
+        let settings = Arc::new(s);
+        let payer = solana_sdk::signature::Keypair::new();
+        let req = ExecutionRequest {
+            side: execution::Side::Buy,
+            mint: "So11111111111111111111111111111111111111112",
+            payer: &payer,
+            instructions: vec![system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            fee_instructions: vec![],
+            followup_instructions: vec![],
+            sol_amount: 0.01,
+            token_amount: 0,
+            decimals: 6,
+        };
+
+        let started = Instant::now();
+        let sub = FanoutBackend::new().execute(req, &settings).await.unwrap();
+        assert!(sub.outcome.is_landed());
+        assert!(started.elapsed() < Duration::from_secs(2), "waited for the slow endpoint: {:?}", started.elapsed());
+
+        // One tip, to a Helius tip account
+        let tx = &fast.sent_transactions()[0];
+        let keys = tx.message.static_account_keys();
+        let book = crate::cluster::AddressBook::from_settings(&settings).unwrap();
+        let tips: Vec<_> = tx.message.instructions().iter()
+            .map(|ix| keys[*ix.accounts.last().unwrap_or(&0) as usize])
+            .filter(|to| book.helius_tip_accounts.contains(to) || book.jito_tip_accounts.contains(to))
+            .collect();
+        assert_eq!(tips.len(), 1);
+        assert!(book.helius_tip_accounts.contains(&tips[0]));
+
+        // Only the endpoint that acked before confirmation is credited; the
+        // straggler's ack is still counted once it arrives.
+        let stats = |label: String| async move {
+            landing_report().await.endpoints.into_iter().find(|e| e.endpoint == label)
+        };
+        assert_eq!(stats(fast.rpc_url()).await.unwrap().landed_first, 1);
+        let slow_label = slow.rpc_url();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let slow = loop {
+            match stats(slow_label.clone()).await {
+                Some(slow) => break slow,
+                None => assert!(Instant::now() < deadline, "slow ack never recorded"),
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+        assert_eq!((slow.sends, slow.landed_first), (1, 0));
+        assert_eq!(stats(sender.sender_url()).await.unwrap().send_errors, 1);
+    }
+}
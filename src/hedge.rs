@@ -0,0 +1,253 @@
+//! Hedged JSON-RPC reads for latency-critical lookups.
+//!
+//! The same request goes to the best `fanout` endpoints of the RPC pool at
+//! once; while none has produced a populated answer, another endpoint is
+//! added every `delay_ms` (or as soon as one fails or comes back empty). The
+//! first populated result wins and the stragglers are dropped. Policies are
+//! per call site (`rpc_hedge` in the config) and every call is counted in
+//! per-site metrics.
+
+use crate::rate_limit::{self, Priority};
+use crate::rpc_pool;
+use crate::settings::Settings;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use log::debug;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Default)]
+struct SiteStats {
+    calls: u64,
+    hedged: u64,
+    won_by_hedge: u64,
+    empty: u64,
+    requests_fired: u64,
+    total_latency_ms: f64,
+}
+
+static METRICS: Lazy<Mutex<HashMap<&'static str, SiteStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// True when a `getAccountInfo` result carries account data.
+pub fn account_populated(result: &Value) -> bool {
+    result
+        .get("value")
+        .and_then(|v| v.get("data"))
+        .and_then(|d| d.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|d| d.as_str())
+        .is_some_and(|d| !d.is_empty())
+}
+
+/// Send `request` under the hedge policy for `site` and return the first
+/// result that satisfies `populated`. If no endpoint produces one, the last
+/// unpopulated result is returned (so callers can tell "not found" from
+/// "every endpoint failed").
+pub async fn hedged_read(
+    site: &'static str,
+    request: &Value,
+    settings: &Settings,
+    populated: fn(&Value) -> bool,
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let policy = settings.hedge_policy(site);
+    let urls = rpc_pool::ranked_urls(&settings.solana_rpc_urls, settings.rotate_rpc).await;
+    if urls.is_empty() {
+        return Err("No solana_rpc_urls configured".into());
+    }
+    let started = Instant::now();
+    let delay = Duration::from_millis(policy.delay_ms);
+    let mut pending: VecDeque<(usize, String)> = urls.into_iter().enumerate().collect();
+    let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("unknown");
+    // `hedge` marks requests fired after the initial fan-out
+    let launch = |(order, url): (usize, String), hedge: bool| async move {
+        // Hedged reads are on the trading path: they may use the whole budget
+        if rate_limit::try_acquire(&url, method, Priority::Trading, settings).await.is_err() {
+            let err = format!("{} is over its RPC credit budget", rpc_pool::redact_url(&url));
+            return (order, hedge, url, Err(err));
+        }
+        let resp = rpc_pool::post::<Value>(&url, request, settings).await;
+        (order, hedge, url, resp)
+    };
+    let mut in_flight = FuturesUnordered::new();
+    while in_flight.len() < policy.fanout.max(1) {
+        match pending.pop_front() {
+            Some(next) => in_flight.push(launch(next, false)),
+            None => break,
+        }
+    }
+    let mut fired = in_flight.len();
+    let mut last_result: Option<Value> = None;
+    let mut last_err: Option<String> = None;
+    let mut winner: Option<(usize, bool, Value)> = None;
+
+    while !in_flight.is_empty() {
+        let can_hedge = !delay.is_zero() && !pending.is_empty();
+        let hedge_timer = async move {
+            if can_hedge {
+                tokio::time::sleep(delay).await
+            } else {
+                std::future::pending::<()>().await
+            }
+        };
+        tokio::select! {
+            Some((order, hedge, url, resp)) = in_flight.next() => {
+                match resp {
+                    Ok(parsed) => match (parsed.error, parsed.result) {
+                        (Some(err), _) => last_err = Some(format!("RPC error from {}: {}", url, err)),
+                        (None, Some(result)) if populated(&result) => {
+                            winner = Some((order, hedge, result));
+                            break;
+                        }
+                        (None, result) => last_result = result.or(last_result),
+                    },
+                    Err(e) => last_err = Some(e),
+                }
+                // That endpoint gave nothing usable: move on right away
+                if let Some(next) = pending.pop_front() {
+                    in_flight.push(launch(next, true));
+                    fired += 1;
+                }
+            }
+            _ = hedge_timer => {
+                if let Some(next) = pending.pop_front() {
+                    in_flight.push(launch(next, true));
+                    fired += 1;
+                }
+            }
+        }
+    }
+
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+    {
+        let mut metrics = METRICS.lock().await;
+        let stats = metrics.entry(site).or_default();
+        stats.calls += 1;
+        stats.requests_fired += fired as u64;
+        stats.total_latency_ms += elapsed_ms;
+        if fired > policy.fanout.max(1) {
+            stats.hedged += 1;
+        }
+        match &winner {
+            Some((_, true, _)) => stats.won_by_hedge += 1,
+            Some(_) => {}
+            None => stats.empty += 1,
+        }
+    }
+
+    match winner {
+        Some((order, _, result)) => {
+            debug!("Hedged {} read won by endpoint #{} ({} fired) in {:.0} ms", site, order + 1, fired, elapsed_ms);
+            Ok(result)
+        }
+        None => {
+            debug!("Hedged {} read found nothing after {} requests in {:.0} ms", site, fired, elapsed_ms);
+            match (last_result, last_err) {
+                (Some(result), _) => Ok(result),
+                (None, Some(e)) => Err(e.into()),
+                (None, None) => Err(format!("Hedged {} read got no response", site).into()),
+            }
+        }
+    }
+}
+
+/// Per call site hedging metrics, for `/api/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HedgeMetrics {
+    pub site: String,
+    pub calls: u64,
+    /// Calls that needed more requests than the initial fan-out
+    pub hedged: u64,
+    /// Calls won by a request fired after the initial fan-out
+    pub won_by_hedge: u64,
+    /// Calls where no endpoint returned a populated result
+    pub empty: u64,
+    pub avg_requests: f64,
+    pub avg_latency_ms: f64,
+}
+
+pub async fn metrics() -> Vec<HedgeMetrics> {
+    let metrics = METRICS.lock().await;
+    let mut out: Vec<HedgeMetrics> = metrics
+        .iter()
+        .map(|(site, s)| {
+            let calls = s.calls.max(1) as f64;
+            HedgeMetrics {
+                site: site.to_string(),
+                calls: s.calls,
+                hedged: s.hedged,
+                won_by_hedge: s.won_by_hedge,
+                empty: s.empty,
+                avg_requests: (s.requests_fired as f64 / calls * 100.0).round() / 100.0,
+                avg_latency_ms: (s.total_latency_ms / calls * 10.0).round() / 10.0,
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| a.site.cmp(&b.site));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_cluster::{Account, MockCluster};
+    use crate::settings::HedgePolicy;
+    use serde_json::json;
+    use solana_sdk::pubkey::Pubkey;
+
+    /// A cluster answering after `delay_ms`, holding `account` when `with_data`.
+    async fn spawn_endpoint(account: &Pubkey, delay_ms: u64, with_data: bool) -> MockCluster {
+        let cluster = MockCluster::start().await.unwrap();
+        cluster.set_latency(Duration::from_millis(delay_ms));
+        if with_data {
+            cluster.set_account(account, Account { owner: Pubkey::new_unique(), lamports: 1, data: vec![1, 2, 3] });
+        }
+        cluster
+    }
+
+    fn account_request(account: &Pubkey) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo", "params": [account.to_string(), { "encoding": "base64" }] })
+    }
+
+    #[tokio::test]
+    async fn hedge_beats_slow_endpoint() {
+        let account = Pubkey::new_unique();
+        let slow = spawn_endpoint(&account, 2_000, true).await;
+        let empty = spawn_endpoint(&account, 0, false).await;
+        let fast = spawn_endpoint(&account, 20, true).await;
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.solana_rpc_urls = vec![slow.rpc_url(), empty.rpc_url(), fast.rpc_url()];
+        settings.rotate_rpc = false;
+        settings.rpc_hedge.insert("test_site".into(), HedgePolicy { fanout: 1, delay_ms: 50 });
+
+        let started = Instant::now();
+        let result = hedged_read("test_site", &account_request(&account), &settings, account_populated).await.unwrap();
+        assert!(account_populated(&result));
+        assert!(started.elapsed() < Duration::from_millis(1_000));
+
+        let site = metrics().await.into_iter().find(|m| m.site == "test_site").unwrap();
+        assert_eq!(site.calls, 1);
+        assert_eq!(site.won_by_hedge, 1);
+    }
+
+    #[tokio::test]
+    async fn win_inside_the_initial_fanout_is_not_a_hedge() {
+        let account = Pubkey::new_unique();
+        let slow = spawn_endpoint(&account, 2_000, true).await;
+        let fast = spawn_endpoint(&account, 20, true).await;
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.solana_rpc_urls = vec![slow.rpc_url(), fast.rpc_url()];
+        settings.rotate_rpc = false;
+        settings.rpc_hedge.insert("fanout_site".into(), HedgePolicy { fanout: 2, delay_ms: 50 });
+
+        let result = hedged_read("fanout_site", &account_request(&account), &settings, account_populated).await.unwrap();
+        assert!(account_populated(&result));
+
+        // The second endpoint won, but it was part of the first fan-out
+        let site = metrics().await.into_iter().find(|m| m.site == "fanout_site").unwrap();
+        assert_eq!(site.won_by_hedge, 0);
+        assert_eq!(site.hedged, 0);
+    }
+}
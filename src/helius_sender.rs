@@ -6,12 +6,13 @@ use solana_sdk::{
     message::{Message, VersionedMessage},
     signature::{Keypair, Signer},
     instruction::Instruction,
+    hash::Hash,
     pubkey::Pubkey,
     system_instruction,
     compute_budget::ComputeBudgetInstruction,
     commitment_config::CommitmentConfig,
 };
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use std::error::Error;
 use std::sync::Arc;
 use crate::settings::Settings;
@@ -19,23 +20,6 @@ use log::{debug, info, warn};
 use serde_json::{json, Value};
 use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
 
-// Helius Sender tip accounts (mainnet-beta)
-pub const TIP_ACCOUNTS: &[&str] = &[
-    "4ACfpUFoaSD9bfPdeu6DBt89gB6ENTeHBXCAi87NhDEE",
-    "D2L6yPZ2FmmmTKPgzaMKdhu6EWZcTpLy1Vhx8uvZe7NZ",
-    "9bnz4RShgq1hAnLnZbP8kbgBg1kEmcJBYQq3gQbmnSta",
-    "5VY91ws6B2hMmBFRsXkoAAdsPHBJwRfBht4DXox3xkwn",
-    "2nyhqdwKcJZR2vcqCyrYsaPVdAnFoJjiksCXJ7hfEYgD",
-    "2q5pghRs6arqVjRvT5gfgWfWcHWmw1ZuCzphgd5KfWGJ",
-    "wyvPkWjVZz1M8fHQnMMCDTQDbkManefNNhweYk5WkcF",
-    "3KCKozbAaF75qEU33jtzozcJ29yJuaLJTy2jFdzUY8bT",
-    "4vieeGHPYPG2MmyPRcYjdiDmmhN3ww7hsFNap8pVN3Ey",
-    "4TQLFNWK8AovT1gFvda5jfw2oJeRMKEmw7aH6MGBJ3or",
-];
-
-/// Jito tip floor API endpoint
-const JITO_TIP_FLOOR_API: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
-
 /// Fetch dynamic tip amount from Jito API (75th percentile)
 /// Falls back to minimum based on routing mode if API fails or dynamic tips disabled
 pub async fn get_dynamic_tip_amount(settings: &Settings) -> Result<f64, Box<dyn Error + Send + Sync>> {
@@ -55,7 +39,7 @@ pub async fn get_dynamic_tip_amount(settings: &Settings) -> Result<f64, Box<dyn
     }
     
     // Fetch dynamic tip for dual routing
-    match fetch_jito_tip_floor().await {
+    match crate::jito::fetch_tip_floor(settings).await {
         Ok(tip_75th) => {
             // Use 75th percentile but enforce minimum based on routing mode
             let min_tip = settings.get_effective_min_tip_sol();
@@ -77,36 +61,15 @@ pub async fn get_dynamic_tip_amount(settings: &Settings) -> Result<f64, Box<dyn
     }
 }
 
-/// Fetch tip floor data from Jito API
-async fn fetch_jito_tip_floor() -> Result<f64, Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()?;
-    
-    let response = client.get(JITO_TIP_FLOOR_API).send().await?;
-    let data: Value = response.json().await?;
-    
-    // Parse response: array with first element containing landed_tips_75th_percentile
-    if let Some(array) = data.as_array() {
-        if let Some(first) = array.first() {
-            if let Some(tip_75th) = first.get("landed_tips_75th_percentile") {
-                if let Some(tip_value) = tip_75th.as_f64() {
-                    return Ok(tip_value);
-                }
-            }
-        }
-    }
-    
-    Err("Invalid response format from Jito API".into())
-}
-
-/// Get a random tip account from the list
-pub fn get_random_tip_account() -> Result<Pubkey, Box<dyn Error + Send + Sync>> {
+/// Get a random tip account from the cluster address book
+pub fn get_random_tip_account(settings: &Settings) -> Result<Pubkey, Box<dyn Error + Send + Sync>> {
     use rand::seq::SliceRandom;
-    let account_str = TIP_ACCOUNTS
+    let book = settings.address_book()?;
+    let account = book
+        .helius_tip_accounts
         .choose(&mut rand::thread_rng())
         .ok_or("No tip accounts available")?;
-    Ok(std::str::FromStr::from_str(account_str)?)
+    Ok(*account)
 }
 
 /// Fetch priority fee estimate from Helius Priority Fee API
@@ -115,7 +78,7 @@ pub async fn get_priority_fee_estimate(
     transaction_base64: &str,
     settings: &Settings,
 ) -> Result<u64, Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::new();
+    let client = crate::rpc_pool::http();
     let response = client
         .post(rpc_url)
         .json(&json!({
@@ -156,7 +119,7 @@ pub async fn get_priority_fee_estimate(
 /// 3. Fetches dynamic priority fees from Helius API
 /// 4. Adds compute budget instructions (compute unit limit and price)
 /// 5. Adds a tip instruction (SOL transfer to random tip account)
-/// 6. Validates blockhash before sending
+/// 6. Signs with `recent_blockhash`, which the caller validated
 /// 7. Sends the transaction to Helius Sender with skipPreflight=true
 /// 
 /// Note: Dev fee should be added to instructions by the caller before calling this function
@@ -167,6 +130,7 @@ pub async fn send_transaction_via_helius(
     payer: &Keypair,
     settings: &Arc<Settings>,
     rpc_client: &RpcClient,
+    recent_blockhash: Hash,
 ) -> Result<String, Box<dyn Error + Send + Sync>> {
     if !settings.helius_sender_enabled {
         return Err("Helius Sender is not enabled in settings".into());
@@ -194,7 +158,7 @@ pub async fn send_transaction_via_helius(
     all_test_instructions.extend(instructions.clone());
     
     // Add tip instruction
-    let tip_account = get_random_tip_account()?;
+    let tip_account = get_random_tip_account(settings)?;
     let tip_instruction = system_instruction::transfer(
         &payer_pubkey,
         &tip_account,
@@ -210,7 +174,7 @@ pub async fn send_transaction_via_helius(
     )?;
     
     // Simulate to get compute units
-    let sim_result = rpc_client.simulate_transaction(&test_tx)?;
+    let sim_result = rpc_client.simulate_transaction(&test_tx).await?;
     let compute_units = if let Some(units) = sim_result.value.units_consumed {
         // Add 20% margin to units consumed, with minimum of 200,000 for pump.fun transactions
         // (ATA creation + pump.fun buy/sell typically needs 150k-200k CU)
@@ -251,10 +215,9 @@ pub async fn send_transaction_via_helius(
         &[payer],
     )?;
     
-    // Update with fresh blockhash
-    let final_blockhash = rpc_client.get_latest_blockhash()?;
+    // Sign with the blockhash whose expiry the caller is tracking
     if let VersionedMessage::Legacy(ref mut msg) = tx.message {
-        msg.recent_blockhash = final_blockhash;
+        msg.recent_blockhash = recent_blockhash;
     }
     
     // Re-sign with updated blockhash
@@ -265,25 +228,7 @@ pub async fn send_transaction_via_helius(
     let serialized_tx = bincode::serialize(&tx)?;
     let tx_base64 = Base64Engine.encode(&serialized_tx);
     
-    // Build Helius Sender endpoint URL with routing mode and optional API key
-    let mut endpoint = settings.helius_sender_endpoint.clone();
-    let mut params = Vec::new();
-    
-    // Add SWQOS-only parameter if enabled
-    if settings.helius_use_swqos_only {
-        params.push("swqos_only=true".to_string());
-    }
-    
-    // Add API key if provided
-    if let Some(api_key) = &settings.helius_api_key {
-        params.push(format!("api-key={}", api_key));
-    }
-    
-    // Append parameters to endpoint
-    if !params.is_empty() {
-        let separator = if endpoint.contains('?') { "&" } else { "?" };
-        endpoint = format!("{}{}{}", endpoint, separator, params.join("&"));
-    }
+    let endpoint = sender_endpoint_url(settings);
     
     let routing_mode = if settings.helius_use_swqos_only {
         "SWQOS-only"
@@ -293,7 +238,7 @@ pub async fn send_transaction_via_helius(
     info!("Sending transaction via Helius Sender ({}) to: {}", routing_mode, endpoint);
     
     // Send transaction via Helius Sender
-    let client = reqwest::Client::new();
+    let client = crate::rpc_pool::http();
     let response = client
         .post(&endpoint)
         .json(&json!({
@@ -328,13 +273,36 @@ pub async fn send_transaction_via_helius(
     Err("Invalid response from Helius Sender".into())
 }
 
+/// Helius Sender endpoint URL with routing mode and optional API key applied
+pub fn sender_endpoint_url(settings: &Settings) -> String {
+    let mut endpoint = settings.helius_sender_endpoint.clone();
+    let mut params = Vec::new();
+    
+    // Add SWQOS-only parameter if enabled
+    if settings.helius_use_swqos_only {
+        params.push("swqos_only=true".to_string());
+    }
+    
+    // Add API key if provided
+    if let Some(api_key) = &settings.helius_api_key {
+        params.push(format!("api-key={}", api_key));
+    }
+    
+    // Append parameters to endpoint
+    if !params.is_empty() {
+        let separator = if endpoint.contains('?') { "&" } else { "?" };
+        endpoint = format!("{}{}{}", endpoint, separator, params.join("&"));
+    }
+    endpoint
+}
+
 /// Simulate a base64-encoded serialized transaction via standard RPC endpoint (not Sender).
 /// Uses JSON-RPC method `simulateTransaction` against `settings.solana_rpc_urls[0]`.
 pub async fn simulate_transaction_via_helius(
     tx_base64: &str,
     settings: &Settings,
 ) -> Result<Value, Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::new();
+    let client = crate::rpc_pool::http();
     // Use standard RPC for simulation, as Sender endpoint usually doesn't support simulateTransaction
     let endpoint = if !settings.solana_rpc_urls.is_empty() {
         &settings.solana_rpc_urls[0]
@@ -362,61 +330,64 @@ async fn is_blockhash_valid(
     rpc_client: &RpcClient,
     last_valid_block_height: u64,
 ) -> Result<bool, Box<dyn Error + Send + Sync>> {
-    let current_height = rpc_client.get_block_height()?;
+    let current_height = rpc_client.get_block_height().await?;
     Ok(current_height <= last_valid_block_height)
 }
 
 
 
 /// Retry logic for sending transactions via Helius Sender with blockhash validation
-/// Attempts up to max_retries times with exponential backoff
+/// Attempts up to max_retries times with exponential backoff. Returns the
+/// signature and the last valid block height of the blockhash it signed with.
 pub async fn send_transaction_with_retry(
     instructions: Vec<Instruction>,
     payer: &Keypair,
     settings: &Arc<Settings>,
     rpc_client: &RpcClient,
     max_retries: usize,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<(String, u64), Box<dyn Error + Send + Sync>> {
     let mut last_error: Option<Box<dyn Error + Send + Sync>> = None;
     
-    // Get blockhash info once at the start
-    let blockhash_info = rpc_client.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())?;
-    let last_valid_block_height = blockhash_info.1;
+    // Get the blockhash and its expiry once, from the same response, so every
+    // attempt signs with the hash the validity check is about. A warm
+    // hot-cache entry is fresh enough that the first attempt can skip the
+    // validity round trip.
+    let (recent_blockhash, last_valid_block_height, cached) = match crate::hot_cache::blockhash_with_height().await {
+        Some((hash, height)) => (hash, height, true),
+        None => {
+            let (hash, height) = rpc_client.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed()).await?;
+            (hash, height, false)
+        }
+    };
     
     debug!("Starting transaction send with blockhash valid until block height: {}", last_valid_block_height);
     
     for attempt in 0..max_retries {
         // Check if blockhash is still valid before attempting
-        match is_blockhash_valid(rpc_client, last_valid_block_height).await {
-            Ok(true) => {
-                debug!("Blockhash still valid, attempting send (attempt {}/{})", attempt + 1, max_retries);
-            }
-            Ok(false) => {
-                let err = "Blockhash expired before send attempt";
-                warn!("{}", err);
-                return Err(err.into());
-            }
-            Err(e) => {
-                warn!("Failed to check blockhash validity: {}", e);
-                // Continue anyway, let the RPC reject if expired
+        if attempt == 0 && cached {
+            debug!("Using hot-cached blockhash, attempting send (attempt 1/{})", max_retries);
+        } else {
+            match is_blockhash_valid(rpc_client, last_valid_block_height).await {
+                Ok(true) => {
+                    debug!("Blockhash still valid, attempting send (attempt {}/{})", attempt + 1, max_retries);
+                }
+                Ok(false) => {
+                    let err = "Blockhash expired before send attempt";
+                    warn!("{}", err);
+                    return Err(err.into());
+                }
+                Err(e) => {
+                    warn!("Failed to check blockhash validity: {}", e);
+                    // Continue anyway, let the RPC reject if expired
+                }
             }
         }
         
-        match send_transaction_via_helius(instructions.clone(), payer, settings, rpc_client).await {
+        match send_transaction_via_helius(instructions.clone(), payer, settings, rpc_client, recent_blockhash).await {
             Ok(sig) => {
                 info!("Transaction sent successfully: {}", sig);
-                
-                // Optional: Wait for confirmation with timeout
-                // Uncomment to enable confirmation checking
-                // match confirm_transaction(&sig, rpc_client, 15).await {
-                //     Ok(_) => return Ok(sig),
-                //     Err(e) => {
-                //         warn!("Transaction sent but confirmation failed: {}", e);
-                //         return Ok(sig); // Return signature anyway
-                //     }
-                // }
-                
-                return Ok(sig);
+                // Confirmation is tracked by the caller (see confirm.rs)
+                return Ok((sig, last_valid_block_height));
             }
             Err(e) => {
                 warn!("Helius Sender attempt {}/{} failed: {}", attempt + 1, max_retries, e);
@@ -433,3 +404,63 @@ pub async fn send_transaction_with_retry(
     
     Err(last_error.unwrap_or_else(|| "All retry attempts failed".into()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_cluster::MockCluster;
+
+    #[tokio::test]
+    async fn sender_request_wraps_instructions_in_budget_and_tip() {
+        let cluster = MockCluster::start().await.unwrap();
+        cluster.respond("getPriorityFeeEstimate", |_| Ok(json!({ "priorityFeeEstimate": 10_000.0 })));
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.solana_rpc_urls = vec![cluster.rpc_url()];
+        s.helius_sender_endpoint = cluster.sender_url();
+        s.helius_use_dynamic_tips = false;
+        s.helius_use_swqos_only = true;
+        s.helius_api_key = Some("key".to_string());
+        let settings = Arc::new(s);
+        let rpc_client = RpcClient::new(settings.solana_rpc_urls[0].clone());
+        let payer = Keypair::new();
+        let trade_to = Pubkey::new_unique();
+        let fee_to = Pubkey::new_unique();
+        let instructions = vec![
+            system_instruction::transfer(&payer.pubkey(), &trade_to, 1),
+            system_instruction::transfer(&payer.pubkey(), &fee_to, 2),
+        ];
+        let blockhash = cluster.latest_blockhash();
+
+        let (signature, _) = send_transaction_with_retry(instructions, &payer, &settings, &rpc_client, 1).await.unwrap();
+
+        let sends = cluster.requests("sendTransaction");
+        assert_eq!(sends.len(), 1);
+        assert_eq!(sends[0].uri, "/fast?swqos_only=true&api-key=key");
+        assert_eq!(sends[0].params[1], json!({ "encoding": "base64", "skipPreflight": true, "maxRetries": 0 }));
+
+        let tx = &cluster.sent_transactions()[0];
+        assert_eq!(signature, tx.signatures[0].to_string());
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+        // Signed with the hash from the one getLatestBlockhash whose expiry
+        // was checked (or a hot-cached pair when another test warmed it)
+        let fetches = cluster.calls("getLatestBlockhash");
+        assert!(fetches <= 1);
+        if fetches == 1 {
+            assert_eq!(*tx.message.recent_blockhash(), blockhash);
+        }
+        let keys = tx.message.static_account_keys();
+        let ixs = tx.message.instructions();
+        let programs: Vec<Pubkey> = ixs.iter().map(|ix| keys[ix.program_id_index as usize]).collect();
+        let budget = solana_sdk::compute_budget::id();
+        let system = solana_sdk::system_program::id();
+        assert_eq!(programs, vec![budget, budget, system, system, system]);
+        // Caller's instructions (trade, then dev fee) keep their order; the tip goes last
+        let destination = |i: usize| keys[ixs[i].accounts[1] as usize];
+        assert_eq!(destination(2), trade_to);
+        assert_eq!(destination(3), fee_to);
+        let book = crate::cluster::AddressBook::from_settings(&settings).unwrap();
+        assert!(book.helius_tip_accounts.contains(&destination(4)));
+        let tip_lamports = u64::from_le_bytes(ixs[4].data[4..12].try_into().unwrap());
+        assert_eq!(tip_lamports, (settings.get_effective_min_tip_sol() * 1_000_000_000.0) as u64);
+    }
+}
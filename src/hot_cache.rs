@@ -0,0 +1,349 @@
+//! Hot state for the buy path.
+//!
+//! A background task keeps the latest blockhash, the pump.fun Global account
+//! (fee recipients, initial reserves) and the fee config account fresh.
+//! Per-mint facts that never change — decimals and token program — are
+//! memoized after the first lookup, and bonding-curve state that arrives with
+//! a detection is seeded here so a fresh snipe can be built and signed
+//! without any prerequisite RPC round trips. Refreshes go through the RPC
+//! pool and its rate limiter like any other normal-priority read.
+//!
+//! `hot_cache_enabled` is re-read from the shared settings on every tick:
+//! turning it off clears the refreshed state and every accessor below
+//! returns `None`, so callers fall back to their own RPC reads.
+
+use crate::models::{BondingCurveState, GlobalAccount};
+use crate::rate_limit::Priority;
+use crate::settings::Settings;
+use log::{debug, warn};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Blockhashes stay valid for ~150 slots (~60s); stop handing one out well before that.
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(30);
+/// Seeded curve state is only trusted for the snipe right after detection.
+const CURVE_SEED_MAX_AGE: Duration = Duration::from_secs(10);
+/// Per-mint entries kept (decimals, token program, seeded curves).
+const MINT_ENTRIES: usize = 2048;
+
+struct HotState {
+    /// (blockhash, last valid block height, fetched at)
+    blockhash: Option<(Hash, u64, Instant)>,
+    global: Option<(GlobalAccount, Instant)>,
+    decimals: LruCache<String, u8>,
+    token_programs: LruCache<String, Pubkey>,
+    curves: LruCache<String, (BondingCurveState, Instant)>,
+}
+
+static HOT: Lazy<Mutex<HotState>> = Lazy::new(|| {
+    let cap = NonZeroUsize::new(MINT_ENTRIES).unwrap();
+    Mutex::new(HotState {
+        blockhash: None,
+        global: None,
+        decimals: LruCache::new(cap),
+        token_programs: LruCache::new(cap),
+        curves: LruCache::new(cap),
+    })
+});
+
+/// (fee config address, account data, fetched at)
+type FeeConfigEntry = (Pubkey, Vec<u8>, Instant);
+
+/// Kept outside `HOT` so the synchronous instruction builders can read it.
+static FEE_CONFIG: RwLock<Option<FeeConfigEntry>> = RwLock::new(None);
+
+/// Whether the refresher is running with `hot_cache_enabled` set.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Drop everything the refresher or detections put in; memoized per-mint
+/// facts stay because they never change.
+async fn clear() {
+    let mut hot = HOT.lock().await;
+    hot.blockhash = None;
+    hot.global = None;
+    hot.curves.clear();
+    drop(hot);
+    *FEE_CONFIG.write().unwrap() = None;
+}
+
+/// Forget everything, memoized mint data included, when the engine stops.
+pub(crate) async fn reset() {
+    clear().await;
+    let mut hot = HOT.lock().await;
+    hot.decimals.clear();
+    hot.token_programs.clear();
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// What the cache currently holds, for `/api/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotCacheStatus {
+    pub blockhash_age_ms: Option<u64>,
+    pub global_age_secs: Option<u64>,
+    pub fee_config_age_secs: Option<u64>,
+    pub memoized_decimals: usize,
+    pub memoized_token_programs: usize,
+    pub seeded_curves: usize,
+}
+
+pub async fn status() -> HotCacheStatus {
+    let fee_config_age_secs = FEE_CONFIG.read().unwrap().as_ref().map(|(_, _, t)| t.elapsed().as_secs());
+    let hot = HOT.lock().await;
+    HotCacheStatus {
+        blockhash_age_ms: hot.blockhash.as_ref().map(|(_, _, t)| t.elapsed().as_millis() as u64),
+        global_age_secs: hot.global.as_ref().map(|(_, t)| t.elapsed().as_secs()),
+        fee_config_age_secs,
+        memoized_decimals: hot.decimals.len(),
+        memoized_token_programs: hot.token_programs.len(),
+        seeded_curves: hot.curves.len(),
+    }
+}
+
+/// Latest blockhash and its last valid block height, if fresh enough to sign with.
+pub async fn blockhash_with_height() -> Option<(Hash, u64)> {
+    if !enabled() {
+        return None;
+    }
+    let hot = HOT.lock().await;
+    hot.blockhash
+        .as_ref()
+        .filter(|(_, _, t)| t.elapsed() < BLOCKHASH_MAX_AGE)
+        .map(|(h, height, _)| (*h, *height))
+}
+
+pub async fn global() -> Option<GlobalAccount> {
+    if !enabled() {
+        return None;
+    }
+    HOT.lock().await.global.as_ref().map(|(g, _)| g.clone())
+}
+
+/// Fee recipient from the cached Global account (reserved one for mayhem mode).
+pub async fn fee_recipient(is_mayhem_mode: bool) -> Option<Pubkey> {
+    let g = global().await?;
+    if is_mayhem_mode {
+        g.reserved_fee_recipient.or(g.fee_recipient)
+    } else {
+        g.fee_recipient
+    }
+}
+
+/// Address of the fee config account, once the refresher has seen it on chain.
+pub fn fee_config_pda() -> Option<Pubkey> {
+    if !enabled() {
+        return None;
+    }
+    FEE_CONFIG.read().unwrap().as_ref().map(|(pda, _, _)| *pda)
+}
+
+pub async fn decimals(mint: &str) -> Option<u8> {
+    HOT.lock().await.decimals.get(mint).copied()
+}
+
+pub async fn remember_decimals(mint: &str, decimals: u8) {
+    HOT.lock().await.decimals.put(mint.to_string(), decimals);
+}
+
+pub async fn token_program(mint: &str) -> Option<Pubkey> {
+    HOT.lock().await.token_programs.get(mint).copied()
+}
+
+pub async fn remember_token_program(mint: &str, program: Pubkey) {
+    HOT.lock().await.token_programs.put(mint.to_string(), program);
+}
+
+/// Seed curve state from a detection event. Real reserves and supply are
+/// derived from the cached Global account's initial reserves; without it the
+/// seed is skipped because liquidity checks would see zeros.
+pub async fn seed_curve(
+    mint: &str,
+    virtual_token_reserves: u64,
+    virtual_sol_reserves: u64,
+    complete: bool,
+    creator: Option<Pubkey>,
+    is_mayhem_mode: bool,
+) {
+    if !enabled() {
+        return;
+    }
+    let mut hot = HOT.lock().await;
+    let Some((g, _)) = hot.global.as_ref() else {
+        debug!("Not seeding curve for {}: Global account not cached yet", mint);
+        return;
+    };
+    let sold = g.initial_virtual_token_reserves.saturating_sub(virtual_token_reserves);
+    let state = BondingCurveState {
+        virtual_token_reserves,
+        virtual_sol_reserves,
+        real_token_reserves: g.initial_real_token_reserves.saturating_sub(sold),
+        real_sol_reserves: virtual_sol_reserves.saturating_sub(g.initial_virtual_sol_reserves),
+        token_total_supply: g.token_total_supply,
+        complete,
+        creator,
+        is_mayhem_mode,
+    };
+    hot.curves.put(mint.to_string(), (state, Instant::now()));
+}
+
+/// Curve state seeded by the detection, if still fresh. Taken (not copied) so
+/// a retry falls back to the chain instead of re-using a stale snapshot.
+pub async fn take_seeded_curve(mint: &str) -> Option<BondingCurveState> {
+    let mut hot = HOT.lock().await;
+    match hot.curves.pop(mint) {
+        Some((state, at)) if at.elapsed() < CURVE_SEED_MAX_AGE => Some(state),
+        _ => None,
+    }
+}
+
+/// Look up token program and decimals for a freshly detected mint in the
+/// background so they are memoized by the time the buy needs them.
+pub fn prefetch_mint(mint: String, rpc_client: Arc<RpcClient>, settings: Arc<Settings>) {
+    crate::tasks::spawn(async move {
+        let _ = crate::rpc::detect_token_program_for_mint(&mint, &rpc_client, &settings).await;
+        if decimals(&mint).await.is_none() {
+            let _ = crate::rpc::fetch_mint_decimals(&mint, &rpc_client, &settings).await;
+        }
+    });
+}
+
+async fn refresh_blockhash(settings: &Arc<Settings>) {
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "getLatestBlockhash",
+        "params": [ { "commitment": "confirmed" } ]
+    });
+    match crate::rpc::fetch_with_priority::<Value>(request, "getLatestBlockhash", Priority::Normal, settings).await {
+        Ok(resp) => {
+            let value = resp.result.as_ref().and_then(|r| r.get("value"));
+            let hash = value
+                .and_then(|v| v.get("blockhash"))
+                .and_then(|b| b.as_str())
+                .and_then(|s| Hash::from_str(s).ok());
+            let height = value
+                .and_then(|v| v.get("lastValidBlockHeight"))
+                .and_then(|h| h.as_u64());
+            // Both halves come from this one response; a hash without its
+            // height would be checked against the wrong expiry.
+            if let (Some(hash), Some(height)) = (hash, height) {
+                HOT.lock().await.blockhash = Some((hash, height, Instant::now()));
+            }
+        }
+        Err(e) => debug!("Hot cache blockhash refresh failed: {}", e),
+    }
+}
+
+async fn refresh_accounts(rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) {
+    match crate::rpc::fetch_global_account(rpc_client, settings).await {
+        Ok(g) => HOT.lock().await.global = Some((g, Instant::now())),
+        Err(e) => warn!("Hot cache Global refresh failed: {}", e),
+    }
+    let pda = match crate::tx_builder::derive_fee_config_pda(settings) {
+        Ok(pda) => pda,
+        Err(e) => {
+            warn!("Hot cache cannot derive fee config PDA: {}", e);
+            return;
+        }
+    };
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo",
+        "params": [ pda.to_string(), { "encoding": "base64", "commitment": "confirmed" } ]
+    });
+    match crate::rpc::fetch_with_fallback::<Value>(request, "getAccountInfo", rpc_client, settings).await {
+        Ok(resp) => {
+            use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
+            let data = resp
+                .result
+                .as_ref()
+                .and_then(|r| r.get("value"))
+                .and_then(|v| v.get("data"))
+                .and_then(|d| d.get(0))
+                .and_then(|d| d.as_str())
+                .and_then(|b| Base64Engine.decode(b).ok());
+            if let Some(data) = data {
+                *FEE_CONFIG.write().unwrap() = Some((pda, data, Instant::now()));
+            }
+        }
+        Err(e) => debug!("Hot cache fee config refresh failed: {}", e),
+    }
+}
+
+/// Spawn the background refresher. Blockhash every `hot_cache_blockhash_refresh_ms`,
+/// Global and fee config every `hot_cache_accounts_refresh_secs`. The switch
+/// and both periods are re-read from `settings` each tick so reloads apply.
+pub fn spawn_refresher(rpc_client: Arc<RpcClient>, settings: Arc<Mutex<Settings>>) {
+    crate::tasks::spawn(async move {
+        let mut blockhash_every = Duration::ZERO;
+        let mut accounts_every = Duration::ZERO;
+        let mut blockhash_tick = tokio::time::interval(Duration::from_secs(1));
+        let mut accounts_tick = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            let current = Arc::new(settings.lock().await.clone());
+            if !current.hot_cache_enabled {
+                if ENABLED.swap(false, Ordering::Relaxed) {
+                    clear().await;
+                    debug!("Hot cache disabled by settings");
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+            ENABLED.store(true, Ordering::Relaxed);
+            let every = Duration::from_millis(current.hot_cache_blockhash_refresh_ms.max(100));
+            if every != blockhash_every {
+                blockhash_every = every;
+                blockhash_tick = tokio::time::interval(every);
+                blockhash_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            }
+            let every = Duration::from_secs(current.hot_cache_accounts_refresh_secs.max(1));
+            if every != accounts_every {
+                accounts_every = every;
+                accounts_tick = tokio::time::interval(every);
+                accounts_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            }
+            tokio::select! {
+                _ = blockhash_tick.tick() => refresh_blockhash(&current).await,
+                _ = accounts_tick.tick() => refresh_accounts(&rpc_client, &current).await,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn seeded_curve_derives_real_reserves_from_global() {
+        ENABLED.store(true, Ordering::Relaxed);
+        HOT.lock().await.global = Some((
+            GlobalAccount {
+                initial_virtual_token_reserves: 1_073_000_000_000_000,
+                initial_virtual_sol_reserves: 30_000_000_000,
+                initial_real_token_reserves: 793_100_000_000_000,
+                token_total_supply: 1_000_000_000_000_000,
+                fee_recipient: None,
+                reserved_fee_recipient: None,
+            },
+            Instant::now(),
+        ));
+        let mint = "SeedTestMint1111111111111111111111111111111";
+        seed_curve(mint, 1_000_000_000_000_000, 32_000_000_000, false, None, false).await;
+        let state = take_seeded_curve(mint).await.unwrap();
+        assert_eq!(state.real_sol_reserves, 2_000_000_000);
+        assert_eq!(state.real_token_reserves, 720_100_000_000_000);
+        // Taken: a retry goes back to the chain
+        assert!(take_seeded_curve(mint).await.is_none());
+    }
+}
@@ -0,0 +1,407 @@
+//! Jito block-engine bundles.
+//!
+//! A bundle is the trade transaction, an optional same-slot follow-up (e.g. a
+//! sell) and a separate tip transaction, in that order. The block engine runs
+//! them atomically: either all land in one slot or none do. After `sendBundle`
+//! the bundle is polled with `getInflightBundleStatuses` until it lands, fails
+//! or expires, and `getBundleStatuses` supplies the landed slot.
+
+// Allow deprecated system_instruction module until solana_system_interface is available
+#![allow(deprecated)]
+
+use crate::confirm::ConfirmationOutcome;
+use crate::execution::{self, ExecError, ExecutionBackend, ExecutionRequest, Submission};
+use crate::settings::Settings;
+use futures_util::future::BoxFuture;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The block engine rejects bundles with more transactions than this.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// Get a random Jito tip account from the cluster address book
+pub fn random_tip_account(settings: &Settings) -> Result<Pubkey, ExecError> {
+    use rand::seq::SliceRandom;
+    let book = settings.address_book()?;
+    let account = book
+        .jito_tip_accounts
+        .choose(&mut rand::thread_rng())
+        .ok_or("No Jito tip accounts available")?;
+    Ok(*account)
+}
+
+/// Fetch the 75th percentile landed tip (SOL) from `jito_tip_floor_url`
+pub async fn fetch_tip_floor(settings: &Settings) -> Result<f64, ExecError> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+    let data: Value = client.get(&settings.jito_tip_floor_url).send().await?.json().await?;
+
+    // Response: array with first element containing landed_tips_75th_percentile
+    data.as_array()
+        .and_then(|a| a.first())
+        .and_then(|f| f.get("landed_tips_75th_percentile"))
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| "Invalid response format from Jito API".into())
+}
+
+/// Tip for one bundle: `jito_tip_sol`, raised to the tip floor when `jito_use_tip_floor`.
+pub async fn bundle_tip_lamports(settings: &Settings) -> u64 {
+    let mut tip_sol = settings.jito_tip_sol;
+    if settings.jito_use_tip_floor {
+        match fetch_tip_floor(settings).await {
+            Ok(floor) if floor > tip_sol => {
+                debug!("Raising Jito tip from {:.9} to tip floor {:.9} SOL", tip_sol, floor);
+                tip_sol = floor;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to fetch Jito tip floor ({}), using {:.9} SOL", e, tip_sol),
+        }
+    }
+    (tip_sol * 1_000_000_000.0) as u64
+}
+
+/// Why a bundle did not land.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleFailure {
+    /// `sendBundle` was refused (bad encoding, too many txs, rate limit, ...)
+    Rejected(String),
+    /// The block engine dropped the bundle: blockhash expired or it was never accepted
+    Invalid,
+    /// The bundle was simulated or auctioned and lost (tip too low, a tx failed, ...)
+    Failed,
+    /// Landed, but a transaction in it carries an error
+    LandedWithError(String),
+    /// Still pending after the timeout
+    Timeout { bundle_id: String, secs: u64 },
+    /// Could not reach the block engine
+    Transport(String),
+}
+
+impl fmt::Display for BundleFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleFailure::Rejected(e) => write!(f, "Jito rejected bundle: {}", e),
+            BundleFailure::Invalid => write!(f, "Jito bundle invalid: blockhash expired or bundle not accepted"),
+            BundleFailure::Failed => write!(f, "Jito bundle failed: lost the auction (tip too low) or a transaction failed simulation"),
+            BundleFailure::LandedWithError(e) => write!(f, "Jito bundle landed with a failed transaction: {}", e),
+            BundleFailure::Timeout { bundle_id, secs } => write!(f, "Jito bundle {} not landed within {}s", bundle_id, secs),
+            BundleFailure::Transport(e) => write!(f, "Jito block engine unreachable: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BundleFailure {}
+
+/// A signed bundle ready for `sendBundle`.
+pub struct Bundle {
+    /// Signatures in bundle order; the first one is the trade.
+    pub signatures: Vec<String>,
+    encoded: Vec<String>,
+}
+
+impl Bundle {
+    /// Sign `transactions` plus a tip transaction to `tip_account` into one bundle.
+    pub fn build(
+        transactions: &[Vec<Instruction>],
+        payer: &Keypair,
+        blockhash: Hash,
+        tip_account: &Pubkey,
+        tip_lamports: u64,
+    ) -> Result<Self, ExecError> {
+        if transactions.is_empty() || transactions.len() + 1 > MAX_BUNDLE_TRANSACTIONS {
+            return Err(format!(
+                "a bundle holds 1 to {} transactions plus the tip (got {})",
+                MAX_BUNDLE_TRANSACTIONS - 1,
+                transactions.len()
+            )
+            .into());
+        }
+        let tip_ix = system_instruction::transfer(&payer.pubkey(), tip_account, tip_lamports);
+        let mut signatures = Vec::new();
+        let mut encoded = Vec::new();
+        for instructions in transactions.iter().chain(std::iter::once(&vec![tip_ix])) {
+            let (sig, b64) = execution::sign_transaction(instructions, payer, blockhash)?;
+            signatures.push(sig);
+            encoded.push(b64);
+        }
+        Ok(Self { signatures, encoded })
+    }
+}
+
+/// JSON-RPC client for one block engine.
+pub struct JitoClient {
+    block_engine_url: String,
+    http: reqwest::Client,
+}
+
+impl JitoClient {
+    pub fn new(block_engine_url: &str) -> Self {
+        Self {
+            block_engine_url: block_engine_url.trim_end_matches('/').to_string(),
+            http: execution::http_client(),
+        }
+    }
+
+    async fn call(&self, path: &str, method: &str, params: Value) -> Result<Value, BundleFailure> {
+        let url = format!("{}/api/v1/{}", self.block_engine_url, path);
+        let resp = self
+            .http
+            .post(&url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .map_err(|e| BundleFailure::Transport(e.to_string()))?;
+        let body: Value = resp.json().await.map_err(|e| BundleFailure::Transport(e.to_string()))?;
+        if let Some(err) = body.get("error") {
+            let msg = err.get("message").and_then(|m| m.as_str()).map(|m| m.to_string());
+            return Err(BundleFailure::Rejected(msg.unwrap_or_else(|| err.to_string())));
+        }
+        body.get("result")
+            .cloned()
+            .ok_or_else(|| BundleFailure::Transport(format!("{} response has no result", method)))
+    }
+
+    /// Submit a bundle, returning the bundle id.
+    pub async fn send_bundle(&self, bundle: &Bundle) -> Result<String, BundleFailure> {
+        let result = self
+            .call("bundles", "sendBundle", json!([bundle.encoded, { "encoding": "base64" }]))
+            .await?;
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| BundleFailure::Rejected(format!("unexpected sendBundle result {}", result)))
+    }
+
+    /// In-flight status: "Pending", "Landed", "Failed" or "Invalid"; None when unknown.
+    pub async fn inflight_status(&self, bundle_id: &str) -> Result<Option<String>, BundleFailure> {
+        let result = self
+            .call("getInflightBundleStatuses", "getInflightBundleStatuses", json!([[bundle_id]]))
+            .await?;
+        Ok(result
+            .get("value")
+            .and_then(|v| v.get(0))
+            .and_then(|s| s.get("status"))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string()))
+    }
+
+    /// Landed slot from `getBundleStatuses`, or an error if a transaction failed.
+    pub async fn landed_slot(&self, bundle_id: &str) -> Result<Option<u64>, BundleFailure> {
+        let result = self
+            .call("getBundleStatuses", "getBundleStatuses", json!([[bundle_id]]))
+            .await?;
+        let status = match result.get("value").and_then(|v| v.get(0)) {
+            Some(s) if !s.is_null() => s.clone(),
+            _ => return Ok(None),
+        };
+        if let Some(err) = status.get("err").and_then(|e| e.get("Err")).filter(|e| !e.is_null()) {
+            return Err(BundleFailure::LandedWithError(err.to_string()));
+        }
+        Ok(status.get("slot").and_then(|s| s.as_u64()))
+    }
+
+    /// Poll until the bundle lands, fails, or `timeout` elapses. A landed
+    /// bundle whose slot `getBundleStatuses` has not reported yet gives None.
+    pub async fn wait_for_bundle(&self, bundle_id: &str, timeout: Duration) -> Result<Option<u64>, BundleFailure> {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            match self.inflight_status(bundle_id).await {
+                Ok(Some(status)) => match status.as_str() {
+                    "Landed" => {
+                        // getBundleStatuses may lag the in-flight view briefly
+                        for _ in 0..5 {
+                            if let Some(slot) = self.landed_slot(bundle_id).await? {
+                                return Ok(Some(slot));
+                            }
+                            tokio::time::sleep(Duration::from_millis(400)).await;
+                        }
+                        return Ok(None);
+                    }
+                    "Failed" => return Err(BundleFailure::Failed),
+                    "Invalid" => return Err(BundleFailure::Invalid),
+                    _ => {}
+                },
+                Ok(None) => {}
+                Err(BundleFailure::Transport(e)) => debug!("Bundle status poll for {} failed: {}", bundle_id, e),
+                Err(e) => return Err(e),
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        Err(BundleFailure::Timeout { bundle_id: bundle_id.to_string(), secs: timeout.as_secs() })
+    }
+}
+
+/// Jito block-engine: the trade (plus optional follow-up) and a separate tip
+/// transaction, submitted atomically with `sendBundle`.
+pub struct JitoBackend {
+    client: JitoClient,
+    rpc_url: String,
+}
+
+impl JitoBackend {
+    pub fn new(block_engine_url: String, rpc_url: String) -> Self {
+        Self { client: JitoClient::new(&block_engine_url), rpc_url }
+    }
+}
+
+impl ExecutionBackend for JitoBackend {
+    fn name(&self) -> &'static str {
+        "jito"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        req: ExecutionRequest<'a>,
+        settings: &'a Arc<Settings>,
+    ) -> BoxFuture<'a, Result<Submission, ExecError>> {
+        Box::pin(async move {
+            let mut trade = execution::compute_budget_instructions(settings);
+            trade.extend(req.instructions);
+            trade.extend(req.fee_instructions);
+            let mut transactions = vec![trade];
+            let has_followup = !req.followup_instructions.is_empty();
+            if has_followup {
+                transactions.push(req.followup_instructions);
+            }
+
+            let tip_lamports = bundle_tip_lamports(settings).await;
+            let (blockhash, last_valid_block_height) = execution::latest_blockhash(&self.client.http, &self.rpc_url).await?;
+            let bundle = Bundle::build(&transactions, req.payer, blockhash, &random_tip_account(settings)?, tip_lamports)?;
+            let signature = bundle.signatures[0].clone();
+            let followup_signature = has_followup.then(|| bundle.signatures[1].clone());
+
+            let bundle_id = self.client.send_bundle(&bundle).await?;
+            info!(
+                "{} {} sent via Jito bundle {} ({} txs, tip {} lamports): {}",
+                req.side.as_str(), req.mint, bundle_id, bundle.signatures.len(), tip_lamports, signature
+            );
+            let outcome = match self
+                .client
+                .wait_for_bundle(&bundle_id, Duration::from_secs(settings.execution_confirm_timeout_secs))
+                .await
+            {
+                Ok(Some(slot)) => ConfirmationOutcome::Landed { slot },
+                // Landed per the in-flight view: take the slot from the trade's signature status.
+                // Still pending: the bundle may land until its blockhash expires, and
+                // the trade's signature tells whether it did.
+                Ok(None) | Err(BundleFailure::Timeout { .. }) => {
+                    execution::wait_for_confirmation(&self.client.http, &self.rpc_url, &signature, Some(last_valid_block_height), settings).await
+                }
+                Err(e) => return Err(e.into()),
+            };
+            info!("Jito bundle {} for {}: {:?}", bundle_id, req.mint, outcome);
+            Ok(Submission { signature, backend: self.name(), outcome, followup_signature })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_cluster::MockCluster;
+
+    fn settings_for(cluster: &MockCluster) -> Arc<Settings> {
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.solana_rpc_urls = vec![cluster.rpc_url()];
+        s.jito_block_engine_url = cluster.block_engine_url();
+        s.execution_confirm_timeout_secs = 2;
+        Arc::new(s)
+    }
+
+    fn request<'a>(payer: &'a Keypair, followup: Vec<Instruction>) -> ExecutionRequest<'a> {
+        ExecutionRequest {
+            side: execution::Side::Buy,
+            mint: "So11111111111111111111111111111111111111112",
+            payer,
+            instructions: vec![system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            fee_instructions: vec![],
+            followup_instructions: followup,
+            sol_amount: 0.01,
+            token_amount: 0,
+            decimals: 6,
+        }
+    }
+
+    async fn execute(cluster: &MockCluster, payer: &Keypair, followup: Vec<Instruction>) -> Result<Submission, ExecError> {
+        let settings = settings_for(cluster);
+        JitoBackend::new(settings.jito_block_engine_url.clone(), settings.solana_rpc_urls[0].clone())
+            .execute(request(payer, followup), &settings)
+            .await
+    }
+
+    #[tokio::test]
+    async fn bundle_puts_trade_followup_and_tip_in_order() {
+        let cluster = MockCluster::start().await.unwrap();
+        let payer = Keypair::new();
+        let followup = vec![system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 2)];
+        let sub = execute(&cluster, &payer, followup).await.unwrap();
+        assert!(sub.outcome.is_landed());
+
+        assert_eq!(cluster.calls("sendBundle"), 1);
+        let txs = cluster.sent_transactions();
+        assert_eq!(txs.len(), 3);
+        assert_eq!(sub.signature, txs[0].signatures[0].to_string());
+        assert_eq!(sub.followup_signature, Some(txs[1].signatures[0].to_string()));
+        let tips = crate::cluster::AddressBook::from_settings(&settings_for(&cluster)).unwrap().jito_tip_accounts;
+        let tip_tx = &txs[2];
+        assert_eq!(tip_tx.message.instructions().len(), 1);
+        assert!(tip_tx.message.static_account_keys().iter().any(|k| tips.contains(k)));
+        assert!(!txs[0].message.static_account_keys().iter().any(|k| tips.contains(k)));
+    }
+
+    #[tokio::test]
+    async fn landed_bundle_without_slot_falls_back_to_signature_status() {
+        let cluster = MockCluster::start().await.unwrap();
+        cluster.respond("getBundleStatuses", |_| Ok(json!({ "value": [null] })));
+        let sub = execute(&cluster, &Keypair::new(), vec![]).await.unwrap();
+        assert!(sub.outcome.is_landed());
+        assert!(cluster.calls("getSignatureStatuses") > 0);
+    }
+
+    #[tokio::test]
+    async fn failed_bundle_reports_reason() {
+        let cluster = MockCluster::start().await.unwrap();
+        cluster.respond("getInflightBundleStatuses", |params| {
+            Ok(json!({ "value": [{ "bundle_id": params[0][0], "status": "Failed" }] }))
+        });
+        let err = execute(&cluster, &Keypair::new(), vec![]).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<BundleFailure>(), Some(&BundleFailure::Failed));
+    }
+
+    #[tokio::test]
+    async fn bundle_pending_past_the_timeout_is_followed_by_its_signature() {
+        let cluster = Arc::new(MockCluster::start().await.unwrap());
+        cluster.hold_sends(true);
+        let pending = {
+            let cluster = cluster.clone();
+            tokio::spawn(async move { execute(&cluster, &Keypair::new(), vec![]).await.unwrap() })
+        };
+        // Still pending after execution_confirm_timeout_secs, then it lands
+        tokio::time::sleep(Duration::from_millis(2_500)).await;
+        assert!(!pending.is_finished());
+        cluster.land_pending();
+        let sub = pending.await.unwrap();
+        assert!(sub.outcome.is_landed());
+        assert_eq!(sub.signature, cluster.sent_transactions()[0].signatures[0].to_string());
+    }
+
+    #[test]
+    fn bundle_size_is_limited() {
+        let payer = Keypair::new();
+        let tx = vec![system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)];
+        assert!(Bundle::build(&vec![tx.clone(); 4], &payer, Hash::default(), &Pubkey::new_unique(), 1000).is_ok());
+        assert!(Bundle::build(&vec![tx; 5], &payer, Hash::default(), &Pubkey::new_unique(), 1000).is_err());
+        assert!(Bundle::build(&[], &payer, Hash::default(), &Pubkey::new_unique(), 1000).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! sol_beast: a pump.fun sniper that detects new tokens, buys them and
+//! manages the positions with take-profit / stop-loss levels.
+//!
+//! The `sol_beast` binary is a thin CLI over [`EngineBuilder`]; other services
+//! (or tests) can embed the same engine:
+//!
+//! ```no_run
+//! # async fn demo() -> Result<(), sol_beast::AppError> {
+//! let settings = sol_beast::Settings::from_file("config.toml")?;
+//! let engine = sol_beast::EngineBuilder::new(settings)
+//!     .event_sink(|event| println!("{}", event))
+//!     .auto_start(true)
+//!     .start()
+//!     .await?;
+//! // ...
+//! engine.stop().await;
+//! # Ok(())
+//! # }
+//! ```
+
+// Helper macro to log to both console and API
+macro_rules! bot_log {
+    ($level:expr, $msg:expr) => {
+        log::info!("[{}] {}", $level, $msg);
+        if let Some(control_clone) = $crate::bot_control() {
+            let msg_string = $msg.to_string();
+            let level_string = $level.to_string();
+            $crate::tasks::spawn(async move {
+                control_clone.add_log(&level_string, msg_string, None).await;
+            });
+        }
+    };
+    ($level:expr, $msg:expr, $detail:expr) => {
+        log::info!("[{}] {} - {}", $level, $msg, $detail);
+        if let Some(control_clone) = $crate::bot_control() {
+            let msg_string = $msg.to_string();
+            let level_string = $level.to_string();
+            let detail_string = Some($detail.to_string());
+            $crate::tasks::spawn(async move {
+                control_clone
+                    .add_log(&level_string, msg_string, detail_string)
+                    .await;
+            });
+        }
+    };
+}
+
+pub mod api;
+pub mod backtest;
+mod buyer;
+pub mod cluster;
+mod confirm;
+mod dedup;
+mod dev_fee;
+pub mod engine;
+pub mod error;
+pub mod execution;
+mod fanout;
+mod hedge;
+mod helius_sender;
+mod hot_cache;
+mod idl;
+mod jito;
+pub mod missed;
+#[cfg(test)]
+mod mock_cluster;
+pub mod models;
+mod monitor;
+mod onchain_idl;
+pub mod optimizer;
+mod paper;
+mod pipeline;
+mod poller;
+mod position;
+mod pumpportal;
+mod rate_limit;
+pub mod recorder;
+mod rpc;
+mod rpc_pool;
+pub mod settings;
+mod shadow;
+mod state;
+mod tasks;
+mod tx_builder;
+mod ws;
+mod ws_supervisor;
+
+pub use api::{BotControl, BotMode, BotRunningState};
+pub use engine::{DataSources, Engine, EngineBuilder, EventSink};
+pub use error::AppError;
+pub use execution::{ExecutionBackend, ExecutionRequest, Submission};
+pub use settings::Settings;
+
+use once_cell::sync::Lazy;
+use std::sync::atomic::AtomicUsize;
+
+// Bot control of the running engine, for logging
+static BOT_CONTROL: std::sync::RwLock<Option<std::sync::Arc<api::BotControl>>> = std::sync::RwLock::new(None);
+
+pub(crate) fn bot_control() -> Option<std::sync::Arc<api::BotControl>> {
+    BOT_CONTROL.read().unwrap().clone()
+}
+
+pub(crate) fn set_bot_control(control: Option<std::sync::Arc<api::BotControl>>) {
+    *BOT_CONTROL.write().unwrap() = control;
+}
+
+/// Monotonically-increasing total of genuinely detected coins. The Vec<DetectedCoin>
+/// is capped at `detected_coins_max` for memory, but this counter keeps growing.
+pub(crate) static TOTAL_DETECTED_COINS: Lazy<AtomicUsize> =
+    Lazy::new(|| AtomicUsize::new(0));
@@ -0,0 +1,301 @@
+//! Missed-opportunity tracking for skipped detections.
+//!
+//! A detected coin the bot did not buy because of `max_holded_coins` or an
+//! entry filter is watched for `missed_window_secs`. Every
+//! `missed_poll_interval_secs` the curves of all watched coins are read in
+//! one low-priority `getMultipleAccounts` call. The watch records the best
+//! and worst price a hypothetical entry at the skip-time price would have
+//! seen, and whether the first TP or first SL trigger would have been hit
+//! first. Finished watches are summed up per skip reason on `/api/missed`.
+
+use crate::backtest::PricePoint;
+use crate::rate_limit::Priority;
+use crate::{rpc, settings::Settings};
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Finished watches kept for the API, newest first.
+const MAX_RECENT: usize = 200;
+/// Accounts per `getMultipleAccounts` call.
+const BATCH: usize = 100;
+
+/// Why the bot did not buy a detected coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// `max_holded_coins` positions were already live.
+    MaxHoldedCoins,
+    /// An entry filter refused the buy; holds the filter's setting name.
+    EntryFilter(&'static str),
+}
+
+impl SkipReason {
+    /// Key the skip is reported under on `/api/missed`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::MaxHoldedCoins => "max_holded_coins",
+            SkipReason::EntryFilter(filter) => filter,
+        }
+    }
+}
+
+/// Price path of one skipped coin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watch {
+    pub mint: String,
+    /// [`SkipReason::as_str`]: "max_holded_coins" or the entry filter's name
+    pub reason: String,
+    pub detail: String,
+    pub skipped_at: DateTime<Utc>,
+    /// Price a buy at skip time would have paid; the first poll when unknown
+    pub entry_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub max_gain_percent: f64,
+    /// Worst move below the entry price (<= 0)
+    pub max_drawdown_percent: f64,
+    /// Lowest TP and highest SL trigger configured at skip time
+    pub tp_trigger_percent: Option<f64>,
+    pub sl_trigger_percent: Option<f64>,
+    /// "tp" or "sl", whichever trigger the price crossed first
+    pub first_hit: Option<String>,
+    pub samples: u64,
+    /// The curve completed (migrated) while being watched
+    pub migrated: bool,
+    /// Observed prices, `t_ms` counted from the skip; starts with the entry
+    /// price when it was known at skip time
+    #[serde(default)]
+    pub path: Vec<PricePoint>,
+}
+
+impl Watch {
+    pub fn new(mint: &str, reason: SkipReason, detail: String, entry_price: Option<f64>, settings: &Settings) -> Watch {
+        let tp = settings.tp_levels.iter().map(|l| l.trigger_percent).min_by(|a, b| a.total_cmp(b));
+        let sl = settings.sl_levels.iter().map(|l| l.trigger_percent).max_by(|a, b| a.total_cmp(b));
+        let entry_price = entry_price.filter(|p| *p > 0.0 && p.is_finite());
+        Watch {
+            mint: mint.to_string(),
+            reason: reason.as_str().to_string(),
+            detail,
+            skipped_at: Utc::now(),
+            entry_price,
+            last_price: None,
+            max_gain_percent: 0.0,
+            max_drawdown_percent: 0.0,
+            tp_trigger_percent: tp,
+            sl_trigger_percent: sl,
+            first_hit: None,
+            samples: 0,
+            migrated: false,
+            path: entry_price.map(|price| PricePoint { t_ms: 0, price, real_sol_reserves: None }).into_iter().collect(),
+        }
+    }
+
+    /// Fold one price observed at `at` into the watch.
+    pub fn observe(&mut self, price: f64, real_sol_reserves: Option<u64>, at: DateTime<Utc>) {
+        if price <= 0.0 || !price.is_finite() {
+            return;
+        }
+        self.samples += 1;
+        let t_ms = (at - self.skipped_at).num_milliseconds().max(0) as u64;
+        self.path.push(PricePoint { t_ms, price, real_sol_reserves });
+        self.last_price = Some(price);
+        let entry = *self.entry_price.get_or_insert(price);
+        let change = (price - entry) / entry * 100.0;
+        self.max_gain_percent = self.max_gain_percent.max(change);
+        self.max_drawdown_percent = self.max_drawdown_percent.min(change);
+        if self.first_hit.is_none() {
+            if self.tp_trigger_percent.is_some_and(|tp| change >= tp) {
+                self.first_hit = Some("tp".to_string());
+            } else if self.sl_trigger_percent.is_some_and(|sl| change <= sl) {
+                self.first_hit = Some("sl".to_string());
+            }
+        }
+    }
+}
+
+/// Finished watches of one skip reason.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReasonStats {
+    pub skipped: u64,
+    /// Skips not watched because `missed_max_tracked` was reached
+    pub untracked: u64,
+    /// Finished watches that never got a price
+    pub unpriced: u64,
+    pub watched: u64,
+    pub avg_max_gain_percent: f64,
+    pub avg_max_drawdown_percent: f64,
+    pub best_max_gain_percent: f64,
+    /// Watches whose first TP trigger was hit before the first SL trigger
+    pub tp_first: u64,
+    pub sl_first: u64,
+    pub neither: u64,
+}
+
+impl ReasonStats {
+    fn add(&mut self, watch: &Watch) {
+        if watch.samples == 0 {
+            self.unpriced += 1;
+            return;
+        }
+        let n = self.watched as f64;
+        self.avg_max_gain_percent = (self.avg_max_gain_percent * n + watch.max_gain_percent) / (n + 1.0);
+        self.avg_max_drawdown_percent = (self.avg_max_drawdown_percent * n + watch.max_drawdown_percent) / (n + 1.0);
+        self.best_max_gain_percent = self.best_max_gain_percent.max(watch.max_gain_percent);
+        self.watched += 1;
+        match watch.first_hit.as_deref() {
+            Some("tp") => self.tp_first += 1,
+            Some("sl") => self.sl_first += 1,
+            _ => self.neither += 1,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MissedState {
+    watching: HashMap<String, Watch>,
+    recent: VecDeque<Watch>,
+    by_reason: BTreeMap<String, ReasonStats>,
+    polls: u64,
+    poll_errors: u64,
+}
+
+static STATE: Lazy<Mutex<MissedState>> = Lazy::new(|| Mutex::new(MissedState::default()));
+
+/// Start watching `mint`, skipped for `reason`. `entry_price` is the price
+/// the bot saw when it skipped, if any.
+pub async fn track(mint: &str, reason: SkipReason, detail: String, entry_price: Option<f64>, settings: &Settings) {
+    if settings.missed_window_secs == 0 {
+        return;
+    }
+    let mut guard = STATE.lock().await;
+    let state = &mut *guard;
+    if state.watching.contains_key(mint) {
+        return;
+    }
+    let stats = state.by_reason.entry(reason.as_str().to_string()).or_default();
+    stats.skipped += 1;
+    if state.watching.len() >= settings.missed_max_tracked {
+        stats.untracked += 1;
+        return;
+    }
+    debug!("Tracking skipped {} ({}) for {}s", mint, reason.as_str(), settings.missed_window_secs);
+    state.watching.insert(mint.to_string(), Watch::new(mint, reason, detail, entry_price, settings));
+}
+
+/// Poll the watched curves until the process exits.
+pub async fn run(settings: Arc<Settings>) {
+    if settings.missed_window_secs == 0 {
+        return;
+    }
+    info!(
+        "Tracking skipped coins for {}s (poll every {}s, at most {})",
+        settings.missed_window_secs, settings.missed_poll_interval_secs, settings.missed_max_tracked
+    );
+    let window = chrono::Duration::seconds(settings.missed_window_secs as i64);
+    loop {
+        tokio::time::sleep(Duration::from_secs(settings.missed_poll_interval_secs)).await;
+        let mints: Vec<String> = STATE.lock().await.watching.keys().cloned().collect();
+        let mut curves = HashMap::new();
+        let mut errors = 0;
+        for chunk in mints.chunks(BATCH) {
+            match rpc::fetch_curve_states(chunk, Priority::Low, &settings).await {
+                Ok(batch) => curves.extend(batch),
+                Err(e) => {
+                    debug!("Missed-opportunity poll failed: {}", e);
+                    errors += 1;
+                }
+            }
+        }
+
+        let mut state = STATE.lock().await;
+        state.polls += 1;
+        state.poll_errors += errors;
+        let now = Utc::now();
+        let mut finished = Vec::new();
+        for (mint, watch) in state.watching.iter_mut() {
+            if let Some(curve) = curves.get(mint) {
+                if let Some(price) = curve.spot_price_sol_per_token() {
+                    watch.observe(price, Some(curve.real_sol_reserves), now);
+                }
+                watch.migrated |= curve.complete;
+            }
+            // A completed curve stops trading; its path ends here
+            if watch.migrated || now - watch.skipped_at >= window {
+                finished.push(mint.clone());
+            }
+        }
+        for mint in finished {
+            if let Some(watch) = state.watching.remove(&mint) {
+                state.by_reason.entry(watch.reason.clone()).or_default().add(&watch);
+                state.recent.push_front(watch);
+                state.recent.truncate(MAX_RECENT);
+            }
+        }
+    }
+}
+
+/// Missed-opportunity report, for `/api/missed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissedReport {
+    pub window_secs: u64,
+    pub polls: u64,
+    pub poll_errors: u64,
+    pub by_reason: BTreeMap<String, ReasonStats>,
+    pub watching: Vec<Watch>,
+    pub recent: Vec<Watch>,
+}
+
+/// Drop every watch and counter when the engine stops.
+pub(crate) async fn reset() {
+    *STATE.lock().await = MissedState::default();
+}
+
+pub async fn report(settings: &Settings) -> MissedReport {
+    let state = STATE.lock().await;
+    let mut watching: Vec<Watch> = state.watching.values().cloned().collect();
+    watching.sort_by_key(|w| std::cmp::Reverse(w.skipped_at));
+    MissedReport {
+        window_secs: settings.missed_window_secs,
+        polls: state.polls,
+        poll_errors: state.poll_errors,
+        by_reason: state.by_reason.clone(),
+        watching,
+        recent: state.recent.iter().cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watch_records_extremes_and_the_first_trigger_hit() {
+        let settings = Settings::from_file("config.example.toml").unwrap();
+        let tp = settings.tp_levels.iter().map(|l| l.trigger_percent).fold(f64::INFINITY, f64::min);
+        let mut watch = Watch::new("Mint", SkipReason::EntryFilter("min_liquidity_sol"), String::new(), None, &settings);
+        let at = |secs: i64| watch.skipped_at + chrono::Duration::seconds(secs);
+        let (t1, t2, t3, t4) = (at(1), at(2), at(3), at(4));
+
+        watch.observe(1.0, None, t1);
+        assert_eq!(watch.entry_price, Some(1.0));
+        watch.observe(0.95, None, t2);
+        watch.observe(1.0 + (tp + 1.0) / 100.0, None, t3);
+        watch.observe(0.5, None, t4);
+        assert_eq!(watch.samples, 4);
+        assert_eq!(watch.path.iter().map(|p| p.t_ms).collect::<Vec<_>>(), vec![1_000, 2_000, 3_000, 4_000]);
+        assert!((watch.max_gain_percent - (tp + 1.0)).abs() < 1e-9);
+        assert!((watch.max_drawdown_percent + 50.0).abs() < 1e-9);
+        // The -5% dip stays above every SL trigger in the example config
+        assert_eq!(watch.first_hit.as_deref(), Some("tp"));
+
+        let mut stats = ReasonStats::default();
+        stats.add(&watch);
+        stats.add(&Watch::new("Other", SkipReason::EntryFilter("min_liquidity_sol"), String::new(), Some(1.0), &settings));
+        assert_eq!((stats.watched, stats.unpriced, stats.tp_first), (1, 1, 1));
+    }
+}
@@ -0,0 +1,1283 @@
+//! Offline Solana cluster for end-to-end tests.
+//!
+//! `MockCluster` serves JSON-RPC on `http://127.0.0.1:<port>/`, the pubsub
+//! protocol on `/ws` and a PumpPortal-style new-token stream on
+//! `/pumpportal`, all answered from in-memory state the test scripts:
+//! accounts, transactions and signature statuses. Rewriting an account
+//! pushes `accountNotification` to its subscribers, so a test moves a
+//! coin's price by rewriting its bonding curve. Sent transactions land in
+//! the next slot (or fail with the error set by `set_send_error`); they are
+//! not executed, so a test credits the tokens a buy would have minted with
+//! `set_token_account`.
+//!
+//! The same port also stands in for the send paths around the RPC: a Jito
+//! block engine under `/api/v1/`, Helius Sender on `/fast`, PumpPortal's
+//! trade-local API on `/api/trade-local` and off-chain documents under
+//! `/documents/`. A test replaces any JSON-RPC method's answer with
+//! [`MockCluster::respond`] and slows every answer with `set_latency`.
+
+use crate::models::BondingCurveState;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::Uri;
+use axum::{routing::{get, post}, Json, Router};
+use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+/// Slot heartbeat period; well under any `ws_stale_timeout_secs`.
+const SLOT_EVERY: Duration = Duration::from_millis(200);
+
+/// One account as the cluster stores it.
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+impl Account {
+    fn to_json(&self) -> Value {
+        json!({
+            "data": [Base64Engine.encode(&self.data), "base64"],
+            "executable": false,
+            "lamports": self.lamports,
+            "owner": self.owner.to_string(),
+            "rentEpoch": 0,
+            "space": self.data.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SubKind {
+    Account(String),
+    Logs,
+    Slot,
+    Signature(String),
+}
+
+struct Subscription {
+    kind: SubKind,
+    out: mpsc::UnboundedSender<String>,
+}
+
+/// One JSON-RPC request as the cluster received it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Path and query it was posted to
+    pub uri: String,
+    pub method: String,
+    pub params: Value,
+}
+
+/// Scripted answer to a JSON-RPC method: its `result`, or an `error` object.
+type Hook = Box<dyn Fn(&Value) -> Result<Value, Value> + Send>;
+
+#[derive(Default)]
+struct ClusterState {
+    slot: u64,
+    accounts: HashMap<String, Account>,
+    transactions: HashMap<String, Value>,
+    /// Landed signatures: (slot, err), err null on success
+    statuses: HashMap<String, (u64, Value)>,
+    sent: Vec<VersionedTransaction>,
+    send_error: Option<Value>,
+    /// Hold sent transactions in `pending` instead of landing them
+    hold_sends: bool,
+    pending: Vec<String>,
+    /// Bundle id -> its signatures, in bundle order
+    bundles: HashMap<String, Vec<String>>,
+    /// Address -> (signature, block time), oldest first
+    signatures: HashMap<String, Vec<(String, i64)>>,
+    /// Every JSON-RPC request, in order
+    requests: Vec<Request>,
+    hooks: HashMap<String, Hook>,
+    latency: Duration,
+    /// Transaction the trade-local API hands out
+    trade_local: Vec<u8>,
+    documents: HashMap<String, String>,
+    next_sub: u64,
+    subs: HashMap<u64, Subscription>,
+    pumpportal: Vec<mpsc::UnboundedSender<String>>,
+    /// Every request PumpPortal clients sent, in order
+    pumpportal_requests: Vec<Value>,
+    /// Ping new PumpPortal clients this often instead of staying silent
+    pumpportal_pings: Option<Duration>,
+}
+
+impl ClusterState {
+    fn context(&self) -> Value {
+        json!({ "slot": self.slot })
+    }
+
+    /// Blockhash handed out in the current slot.
+    fn blockhash(&self) -> solana_sdk::hash::Hash {
+        solana_sdk::hash::hash(&self.slot.to_le_bytes())
+    }
+
+    fn account_json(&self, pubkey: &str) -> Value {
+        self.accounts.get(pubkey).map(Account::to_json).unwrap_or(Value::Null)
+    }
+
+    fn subscribe(&mut self, kind: SubKind, out: &mpsc::UnboundedSender<String>) -> u64 {
+        self.next_sub += 1;
+        self.subs.insert(self.next_sub, Subscription { kind, out: out.clone() });
+        self.next_sub
+    }
+
+    /// Send `method` with `result` to every subscription `matches` accepts.
+    fn notify(&self, method: &str, matches: impl Fn(&SubKind) -> bool, result: Value) {
+        for (id, sub) in self.subs.iter().filter(|(_, s)| matches(&s.kind)) {
+            let _ = sub.out.send(notification(method, *id, &result));
+        }
+    }
+
+    /// Land `signature` in a new slot with the scripted send error.
+    fn land(&mut self, signature: &str) {
+        self.slot += 1;
+        let landed = (self.slot, self.send_error.clone().unwrap_or(Value::Null));
+        self.statuses.insert(signature.to_string(), landed);
+        self.notify_signature(signature);
+    }
+
+    /// Signature subscriptions fire once, then cancel.
+    fn notify_signature(&mut self, signature: &str) {
+        let Some((slot, err)) = self.statuses.get(signature).cloned() else { return };
+        let kind = SubKind::Signature(signature.to_string());
+        let result = json!({ "context": { "slot": slot }, "value": { "err": err } });
+        self.notify("signatureNotification", |k| *k == kind, result);
+        self.subs.retain(|_, s| s.kind != kind);
+    }
+
+    /// Accept `tx` like `sendTransaction`: land it, or hold it with `hold_sends`.
+    fn accept(&mut self, tx: VersionedTransaction) -> String {
+        let signature = tx.signatures.first().map(|s| s.to_string()).unwrap_or_default();
+        self.sent.push(tx);
+        if self.hold_sends {
+            self.pending.push(signature.clone());
+        } else {
+            self.land(&signature);
+        }
+        signature
+    }
+
+    /// Slot a bundle landed in: that of its first transaction, once all have.
+    fn bundle_slot(&self, bundle_id: &str) -> Option<u64> {
+        let signatures = self.bundles.get(bundle_id)?;
+        let slots: Option<Vec<u64>> = signatures.iter().map(|s| self.statuses.get(s).map(|(slot, _)| *slot)).collect();
+        slots?.first().copied()
+    }
+}
+
+fn decode_transaction(b64: &Value) -> Option<VersionedTransaction> {
+    let bytes = Base64Engine.decode(b64.as_str()?).ok()?;
+    bincode::deserialize(&bytes).ok()
+}
+
+type Shared = Arc<Mutex<ClusterState>>;
+
+fn notification(method: &str, subscription: u64, result: &Value) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": { "subscription": subscription, "result": result }
+    })
+    .to_string()
+}
+
+/// Bonding curve PDA of `mint` under `program`.
+pub fn curve_address(program: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], program).0
+}
+
+/// Account data of a pump.fun bonding curve in the layout `rpc.rs` and `ws.rs` parse.
+pub fn curve_data(curve: &BondingCurveState) -> Vec<u8> {
+    let mut data = CURVE_DISCRIMINATOR.to_vec();
+    for value in [
+        curve.virtual_token_reserves,
+        curve.virtual_sol_reserves,
+        curve.real_token_reserves,
+        curve.real_sol_reserves,
+        curve.token_total_supply,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(curve.complete as u8);
+    data.extend_from_slice(curve.creator.unwrap_or_default().as_ref());
+    data.push(curve.is_mayhem_mode as u8);
+    data
+}
+
+/// Scripted Solana cluster on a local port; the server stops on drop.
+pub struct MockCluster {
+    addr: SocketAddr,
+    state: Shared,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl MockCluster {
+    pub async fn start() -> io::Result<MockCluster> {
+        let state: Shared = Arc::new(Mutex::new(ClusterState { slot: 1, ..Default::default() }));
+        let app = Router::new()
+            .route("/", post(rpc))
+            .route("/fast", post(rpc))
+            .route("/api/v1/:method", post(rpc))
+            .route("/api/trade-local", post(|State(state): State<Shared>| async move {
+                state.lock().unwrap().trade_local.clone()
+            }))
+            .route("/documents/:name", get(document))
+            .route("/ws", get(|ws: WebSocketUpgrade, State(state): State<Shared>| async move {
+                ws.on_upgrade(move |socket| pubsub(socket, state))
+            }))
+            .route("/pumpportal", get(|ws: WebSocketUpgrade, State(state): State<Shared>| async move {
+                ws.on_upgrade(move |socket| pumpportal(socket, state))
+            }))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        Ok(MockCluster { addr, state, server })
+    }
+
+    pub fn rpc_url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}/ws", self.addr)
+    }
+
+    pub fn pumpportal_url(&self) -> String {
+        format!("ws://{}/pumpportal", self.addr)
+    }
+
+    /// Jito block engine base URL (`jito_block_engine_url`).
+    pub fn block_engine_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Helius Sender endpoint (`helius_sender_endpoint`).
+    pub fn sender_url(&self) -> String {
+        format!("http://{}/fast", self.addr)
+    }
+
+    /// PumpPortal trade-local endpoint (`pumpportal_trade_local_url`).
+    pub fn trade_local_url(&self) -> String {
+        format!("http://{}/api/trade-local", self.addr)
+    }
+
+    /// Answer `method` with `hook(params)` instead of the cluster state.
+    pub fn respond(&self, method: &str, hook: impl Fn(&Value) -> Result<Value, Value> + Send + 'static) {
+        self.state.lock().unwrap().hooks.insert(method.to_string(), Box::new(hook));
+    }
+
+    /// Delay every JSON-RPC answer by `latency`.
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// Serialized transaction the trade-local API returns for signing.
+    pub fn set_trade_local(&self, tx: Vec<u8>) {
+        self.state.lock().unwrap().trade_local = tx;
+    }
+
+    /// Serve `body` as an off-chain document; returns its URL.
+    pub fn serve_document(&self, name: &str, body: &str) -> String {
+        self.state.lock().unwrap().documents.insert(name.to_string(), body.to_string());
+        format!("http://{}/documents/{}", self.addr, name)
+    }
+
+    /// Record a successful `signature` for `address` at `block_time`; the
+    /// newest comes first in `getSignaturesForAddress`.
+    pub fn add_signature(&self, address: &str, signature: &str, block_time: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.signatures.entry(address.to_string()).or_default().push((signature.to_string(), block_time));
+    }
+
+    /// Ping PumpPortal clients that connect from now on every `every`, and
+    /// never send them data.
+    pub fn ping_pumpportal(&self, every: Option<Duration>) {
+        self.state.lock().unwrap().pumpportal_pings = every;
+    }
+
+    /// Create or replace an account and notify its subscribers.
+    pub fn set_account(&self, pubkey: &Pubkey, account: Account) {
+        let mut state = self.state.lock().unwrap();
+        state.slot += 1;
+        let key = pubkey.to_string();
+        let result = json!({ "context": state.context(), "value": account.to_json() });
+        state.accounts.insert(key.clone(), account);
+        state.notify("accountNotification", |k| *k == SubKind::Account(key.clone()), result);
+    }
+
+    /// Write the bonding curve of `mint`; returns the curve address.
+    pub fn set_curve(&self, program: &Pubkey, mint: &Pubkey, curve: &BondingCurveState) -> Pubkey {
+        let address = curve_address(program, mint);
+        let account = Account { owner: *program, lamports: 1_000_000_000, data: curve_data(curve) };
+        self.set_account(&address, account);
+        address
+    }
+
+    /// Add an initialized Token-2022 mint with `decimals`.
+    pub fn add_mint(&self, mint: &Pubkey, decimals: u8) {
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: 1_000_000_000_000_000,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        let owner = Pubkey::from_str(crate::rpc::TOKEN_2022_PROGRAM_ID).unwrap();
+        self.set_account(mint, Account { owner, lamports: 1_461_600, data });
+    }
+
+    /// Set `owner`'s Token-2022 associated token account for `mint` to hold
+    /// `amount` base units; returns its address.
+    pub fn set_token_account(&self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let program = Pubkey::from_str(crate::rpc::TOKEN_2022_PROGRAM_ID).unwrap();
+        let address = spl_associated_token_account::get_associated_token_address_with_program_id(owner, mint, &program);
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        self.set_account(&address, Account { owner: program, lamports: 2_039_280, data });
+        address
+    }
+
+    /// Serve `tx` (a `jsonParsed` getTransaction result) for `signature`.
+    pub fn add_transaction(&self, signature: &str, tx: Value) {
+        self.state.lock().unwrap().transactions.insert(signature.to_string(), tx);
+    }
+
+    /// Push a `logsNotification` to every logs subscriber.
+    pub fn emit_logs(&self, signature: &str, logs: &[String]) {
+        let mut state = self.state.lock().unwrap();
+        state.slot += 1;
+        let result = json!({
+            "context": state.context(),
+            "value": { "signature": signature, "err": null, "logs": logs }
+        });
+        state.notify("logsNotification", |k| *k == SubKind::Logs, result);
+    }
+
+    /// Push a PumpPortal event (new token or trade) to every connected PumpPortal client.
+    pub fn emit_pumpportal(&self, event: Value) {
+        let text = event.to_string();
+        self.state.lock().unwrap().pumpportal.retain(|out| out.send(text.clone()).is_ok());
+    }
+
+    /// Make transactions sent from now on land with `err` (None = succeed).
+    pub fn set_send_error(&self, err: Option<Value>) {
+        self.state.lock().unwrap().send_error = err;
+    }
+
+    /// Keep transactions sent from now on pending until [`land_pending`](Self::land_pending).
+    pub fn hold_sends(&self, hold: bool) {
+        self.state.lock().unwrap().hold_sends = hold;
+    }
+
+    /// Land every pending transaction.
+    pub fn land_pending(&self) {
+        let mut state = self.state.lock().unwrap();
+        for signature in std::mem::take(&mut state.pending) {
+            state.land(&signature);
+        }
+    }
+
+    /// Move the slot (and block height) on by `slots`.
+    pub fn advance_slots(&self, slots: u64) {
+        self.state.lock().unwrap().slot += slots;
+    }
+
+    /// The blockhash `getLatestBlockhash` answers with right now.
+    pub fn latest_blockhash(&self) -> solana_sdk::hash::Hash {
+        self.state.lock().unwrap().blockhash()
+    }
+
+    pub fn sent_transactions(&self) -> Vec<VersionedTransaction> {
+        self.state.lock().unwrap().sent.clone()
+    }
+
+    /// How many times `method` was called over JSON-RPC.
+    pub fn calls(&self, method: &str) -> usize {
+        self.requests(method).len()
+    }
+
+    /// Every `method` request, in order.
+    pub fn requests(&self, method: &str) -> Vec<Request> {
+        self.state.lock().unwrap().requests.iter().filter(|r| r.method == method).cloned().collect()
+    }
+
+    pub fn account_subscribers(&self, pubkey: &Pubkey) -> usize {
+        let kind = SubKind::Account(pubkey.to_string());
+        self.state.lock().unwrap().subs.values().filter(|s| s.kind == kind && !s.out.is_closed()).count()
+    }
+
+    pub fn logs_subscribers(&self) -> usize {
+        self.state.lock().unwrap().subs.values().filter(|s| s.kind == SubKind::Logs && !s.out.is_closed()).count()
+    }
+
+    pub fn pumpportal_clients(&self) -> usize {
+        self.state.lock().unwrap().pumpportal.iter().filter(|out| !out.is_closed()).count()
+    }
+
+    /// Mints currently subscribed with `subscribeTokenTrade` on any PumpPortal client.
+    pub fn pumpportal_trade_subscriptions(&self) -> Vec<String> {
+        let mut subscribed: Vec<String> = Vec::new();
+        for request in self.state.lock().unwrap().pumpportal_requests.iter() {
+            let keys = request.get("keys").and_then(|k| k.as_array()).cloned().unwrap_or_default();
+            let keys = keys.iter().filter_map(|k| k.as_str().map(str::to_string));
+            match request.get("method").and_then(|m| m.as_str()) {
+                Some("subscribeTokenTrade") => subscribed.extend(keys),
+                Some("unsubscribeTokenTrade") => {
+                    let removed: Vec<String> = keys.collect();
+                    subscribed.retain(|mint| !removed.contains(mint));
+                }
+                _ => {}
+            }
+        }
+        subscribed
+    }
+}
+
+impl Drop for MockCluster {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn rpc(State(state): State<Shared>, uri: Uri, Json(req): Json<Value>) -> Json<Value> {
+    let id = req.get("id").cloned().unwrap_or(json!(1));
+    let method = req.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+    let params = req.get("params").cloned().unwrap_or(Value::Null);
+    let latency = state.lock().unwrap().latency;
+    tokio::time::sleep(latency).await;
+    let mut state = state.lock().unwrap();
+    let uri = uri.path_and_query().map_or_else(|| uri.to_string(), |p| p.to_string());
+    state.requests.push(Request { uri, method: method.to_string(), params: params.clone() });
+    if let Some(hook) = state.hooks.get(method) {
+        return Json(match hook(&params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        });
+    }
+    let result = match method {
+        "getAccountInfo" => {
+            let pubkey = params[0].as_str().unwrap_or_default();
+            json!({ "context": state.context(), "value": state.account_json(pubkey) })
+        }
+        "getMultipleAccounts" => {
+            let keys = params[0].as_array().cloned().unwrap_or_default();
+            let values: Vec<Value> = keys.iter().map(|k| state.account_json(k.as_str().unwrap_or_default())).collect();
+            json!({ "context": state.context(), "value": values })
+        }
+        "getBalance" => {
+            let lamports = params[0].as_str().and_then(|k| state.accounts.get(k)).map_or(0, |a| a.lamports);
+            json!({ "context": state.context(), "value": lamports })
+        }
+        "getLatestBlockhash" => json!({
+            "context": state.context(),
+            "value": {
+                "blockhash": state.blockhash().to_string(),
+                "lastValidBlockHeight": state.slot + 150
+            }
+        }),
+        "getSlot" | "getBlockHeight" => json!(state.slot),
+        "getVersion" => json!({ "solana-core": "2.1.1", "feature-set": 0 }),
+        "getProgramAccounts" | "getProgramAccountsV2" => {
+            let program = params[0].as_str().unwrap_or_default();
+            let filters = params[1]["filters"].as_array().cloned().unwrap_or_default();
+            let matches = |account: &Account| {
+                filters.iter().all(|f| {
+                    let offset = f["memcmp"]["offset"].as_u64().unwrap_or_default() as usize;
+                    let bytes = bs58::decode(f["memcmp"]["bytes"].as_str().unwrap_or_default()).into_vec().unwrap_or_default();
+                    account.data.get(offset..offset + bytes.len()) == Some(bytes.as_slice())
+                })
+            };
+            let found: Vec<Value> = state
+                .accounts
+                .iter()
+                .filter(|(_, a)| a.owner.to_string() == program && matches(a))
+                .map(|(k, a)| json!({ "pubkey": k, "account": a.to_json() }))
+                .collect();
+            json!(found)
+        }
+        "getTokenAccountBalance" => {
+            let account = params[0].as_str().and_then(|k| state.accounts.get(k));
+            let Some(token) = account.and_then(|a| spl_token::state::Account::unpack_from_slice(&a.data).ok()) else {
+                return Json(rpc_error(id, -32602, "could not find account"));
+            };
+            let decimals = state
+                .accounts
+                .get(&token.mint.to_string())
+                .and_then(|m| spl_token::state::Mint::unpack_from_slice(&m.data).ok())
+                .map_or(0, |m| m.decimals);
+            let ui_amount = token.amount as f64 / 10f64.powi(decimals as i32);
+            json!({
+                "context": state.context(),
+                "value": {
+                    "amount": token.amount.to_string(),
+                    "decimals": decimals,
+                    "uiAmount": ui_amount,
+                    "uiAmountString": ui_amount.to_string()
+                }
+            })
+        }
+        "getTransaction" => {
+            let signature = params[0].as_str().unwrap_or_default();
+            state.transactions.get(signature).cloned().unwrap_or(Value::Null)
+        }
+        "simulateTransaction" => json!({
+            "context": state.context(),
+            "value": { "err": null, "logs": [], "accounts": null, "unitsConsumed": 0, "returnData": null }
+        }),
+        "sendTransaction" => {
+            let Some(tx) = decode_transaction(&params[0]) else {
+                return Json(rpc_error(id, -32602, "failed to deserialize transaction"));
+            };
+            json!(state.accept(tx))
+        }
+        "getSignaturesForAddress" => {
+            let config = &params[1];
+            let limit = config["limit"].as_u64().unwrap_or(1_000) as usize;
+            let all = state.signatures.get(params[0].as_str().unwrap_or_default()).cloned().unwrap_or_default();
+            let newest_first: Vec<&(String, i64)> = all.iter().rev().collect();
+            let start = config["before"]
+                .as_str()
+                .and_then(|before| newest_first.iter().position(|(s, _)| s == before))
+                .map_or(0, |i| i + 1);
+            let page: Vec<Value> = newest_first[start..]
+                .iter()
+                .take_while(|(s, _)| config["until"].as_str() != Some(s.as_str()))
+                .take(limit)
+                .map(|(signature, block_time)| json!({ "signature": signature, "err": null, "blockTime": block_time }))
+                .collect();
+            json!(page)
+        }
+        // Block engine: a bundle's transactions are accepted together, in order
+        "sendBundle" => {
+            let encoded = params[0].as_array().cloned().unwrap_or_default();
+            let Some(txs) = encoded.iter().map(decode_transaction).collect::<Option<Vec<_>>>() else {
+                return Json(rpc_error(id, -32602, "failed to deserialize bundle"));
+            };
+            let signatures: Vec<String> = txs.into_iter().map(|tx| state.accept(tx)).collect();
+            let bundle_id = solana_sdk::hash::hash(signatures.join(",").as_bytes()).to_string();
+            state.bundles.insert(bundle_id.clone(), signatures);
+            json!(bundle_id)
+        }
+        "getInflightBundleStatuses" => {
+            let ids = params[0].as_array().cloned().unwrap_or_default();
+            let values: Vec<Value> = ids
+                .iter()
+                .map(|id| {
+                    let id = id.as_str().unwrap_or_default();
+                    let status = match (state.bundles.contains_key(id), state.bundle_slot(id)) {
+                        (false, _) => "Invalid",
+                        (true, Some(_)) => "Landed",
+                        (true, None) => "Pending",
+                    };
+                    json!({ "bundle_id": id, "status": status, "landed_slot": state.bundle_slot(id) })
+                })
+                .collect();
+            json!({ "context": state.context(), "value": values })
+        }
+        "getBundleStatuses" => {
+            let ids = params[0].as_array().cloned().unwrap_or_default();
+            let values: Vec<Value> = ids
+                .iter()
+                .map(|id| {
+                    let id = id.as_str().unwrap_or_default();
+                    match state.bundle_slot(id) {
+                        Some(slot) => json!({
+                            "bundle_id": id,
+                            "transactions": state.bundles[id],
+                            "slot": slot,
+                            "confirmation_status": "confirmed",
+                            "err": { "Ok": null }
+                        }),
+                        None => Value::Null,
+                    }
+                })
+                .collect();
+            json!({ "context": state.context(), "value": values })
+        }
+        "getSignatureStatuses" => {
+            let signatures = params[0].as_array().cloned().unwrap_or_default();
+            let values: Vec<Value> = signatures
+                .iter()
+                .map(|s| match state.statuses.get(s.as_str().unwrap_or_default()) {
+                    Some((slot, err)) => json!({
+                        "slot": slot, "confirmations": null, "err": err, "confirmationStatus": "confirmed"
+                    }),
+                    None => Value::Null,
+                })
+                .collect();
+            json!({ "context": state.context(), "value": values })
+        }
+        other => return Json(rpc_error(id, -32601, &format!("Method not found: {}", other))),
+    };
+    Json(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+async fn document(State(state): State<Shared>, Path(name): Path<String>) -> Result<String, axum::http::StatusCode> {
+    state.lock().unwrap().documents.get(&name).cloned().ok_or(axum::http::StatusCode::NOT_FOUND)
+}
+
+/// Write everything sent on `out` to the socket until either side closes,
+/// pinging every `ping_every` in between.
+fn spawn_writer(
+    mut sink: futures_util::stream::SplitSink<WebSocket, Message>,
+    mut out: mpsc::UnboundedReceiver<String>,
+    ping_every: Option<Duration>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ping = tokio::time::interval(ping_every.unwrap_or(Duration::from_secs(3600)));
+        ping.tick().await;
+        loop {
+            let msg = tokio::select! {
+                text = out.recv() => match text {
+                    Some(text) => Message::Text(text),
+                    None => break,
+                },
+                _ = ping.tick(), if ping_every.is_some() => Message::Ping(Vec::new()),
+            };
+            if sink.send(msg).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+async fn pubsub(socket: WebSocket, state: Shared) {
+    let (sink, mut stream) = socket.split();
+    let (out_tx, out_rx) = mpsc::unbounded_channel::<String>();
+    let writer = spawn_writer(sink, out_rx, None);
+    let mut heartbeat: Option<tokio::task::JoinHandle<()>> = None;
+
+    while let Some(Ok(msg)) = stream.next().await {
+        let Message::Text(text) = msg else { continue };
+        let Ok(req) = serde_json::from_str::<Value>(&text) else { continue };
+        let id = req.get("id").cloned().unwrap_or(Value::Null);
+        let params = req.get("params").cloned().unwrap_or(Value::Null);
+        let method = req.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+        let mut guard = state.lock().unwrap();
+        let result = match method {
+            "accountSubscribe" => {
+                let account = params[0].as_str().unwrap_or_default().to_string();
+                json!(guard.subscribe(SubKind::Account(account), &out_tx))
+            }
+            "logsSubscribe" => json!(guard.subscribe(SubKind::Logs, &out_tx)),
+            "slotSubscribe" => {
+                let sub_id = guard.subscribe(SubKind::Slot, &out_tx);
+                if heartbeat.is_none() {
+                    heartbeat = Some(spawn_slot_heartbeat(state.clone()));
+                }
+                json!(sub_id)
+            }
+            "signatureSubscribe" => {
+                let signature = params[0].as_str().unwrap_or_default().to_string();
+                let sub_id = guard.subscribe(SubKind::Signature(signature.clone()), &out_tx);
+                // Reply first, then fire at once if it already landed
+                let _ = out_tx.send(json!({ "jsonrpc": "2.0", "id": id, "result": sub_id }).to_string());
+                guard.notify_signature(&signature);
+                continue;
+            }
+            "accountUnsubscribe" | "logsUnsubscribe" | "slotUnsubscribe" | "signatureUnsubscribe" => {
+                let sub_id = params[0].as_u64().unwrap_or_default();
+                json!(guard.subs.remove(&sub_id).is_some())
+            }
+            other => {
+                let _ = out_tx.send(rpc_error(id, -32601, &format!("Method not found: {}", other)).to_string());
+                continue;
+            }
+        };
+        let _ = out_tx.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string());
+    }
+
+    if let Some(heartbeat) = heartbeat {
+        heartbeat.abort();
+    }
+    writer.abort();
+    state.lock().unwrap().subs.retain(|_, s| !s.out.same_channel(&out_tx));
+}
+
+/// Advance the slot and notify slot subscribers, like a live validator.
+fn spawn_slot_heartbeat(state: Shared) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(SLOT_EVERY);
+        loop {
+            tick.tick().await;
+            let mut guard = state.lock().unwrap();
+            guard.slot += 1;
+            let slot = guard.slot;
+            let result = json!({ "parent": slot - 1, "root": slot.saturating_sub(32), "slot": slot });
+            guard.notify("slotNotification", |k| *k == SubKind::Slot, result);
+        }
+    })
+}
+
+async fn pumpportal(socket: WebSocket, state: Shared) {
+    let (sink, mut stream) = socket.split();
+    let (out_tx, out_rx) = mpsc::unbounded_channel::<String>();
+    let pings = state.lock().unwrap().pumpportal_pings;
+    let writer = spawn_writer(sink, out_rx, pings);
+    state.lock().unwrap().pumpportal.push(out_tx.clone());
+    // Events are pushed by `emit_pumpportal`; requests need no reply, only a record
+    while let Some(Ok(msg)) = stream.next().await {
+        if let Ok(Ok(request)) = msg.to_text().map(serde_json::from_str::<Value>) {
+            state.lock().unwrap().pumpportal_requests.push(request);
+        }
+    }
+    writer.abort();
+    state.lock().unwrap().pumpportal.retain(|out| !out.same_channel(&out_tx));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::TradeRecord;
+    use crate::confirm::ConfirmationOutcome;
+    use crate::engine::{Engine, EngineBuilder};
+    use crate::execution::{ExecutionBackend, ExecutionRequest, RpcBackend, Side};
+    use crate::models::Holding;
+    use crate::settings::Settings;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use std::future::Future;
+    use tokio::sync::{Mutex as AsyncMutex, MutexGuard};
+
+    /// The engine the binary runs (feeds, message loop, positions and
+    /// monitor), pointed at a mock cluster. Dry-run unless started with `start_real`.
+    struct Bot {
+        engine: Engine,
+        holdings: Arc<AsyncMutex<HashMap<String, Holding>>>,
+        trades: Arc<AsyncMutex<Vec<TradeRecord>>>,
+        /// Every dashboard event the engine broadcast
+        events: Arc<Mutex<Vec<Value>>>,
+        _turn: MutexGuard<'static, ()>,
+    }
+
+    impl Bot {
+        async fn start(settings: Settings) -> Bot {
+            Bot::launch(settings, None).await
+        }
+
+        /// Trade for real with `wallet`, sending to the mock cluster.
+        async fn start_real(settings: Settings, wallet: Keypair) -> Bot {
+            Bot::launch(settings, Some(wallet)).await
+        }
+
+        async fn launch(settings: Settings, wallet: Option<Keypair>) -> Bot {
+            // Only one engine runs per process at a time
+            let turn = crate::tasks::TEST_TURN.lock().await;
+            // The pooled client is process-wide; each test talks to its own cluster
+            let rpc_client = Arc::new(RpcClient::new(settings.solana_rpc_urls[0].clone()));
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink = events.clone();
+            let mut builder = EngineBuilder::new(settings).rpc_client(rpc_client);
+            if let Some(wallet) = wallet {
+                builder = builder.real(true).keypair(wallet);
+            }
+            let engine = builder
+                .event_sink(move |event| sink.lock().unwrap().push(event))
+                .auto_start(true)
+                .start()
+                .await
+                .unwrap();
+            Bot { holdings: engine.holdings(), trades: engine.trades(), events, engine, _turn: turn }
+        }
+
+        async fn stop(self) {
+            self.engine.stop().await;
+        }
+
+        /// Whether `mint` started an exit whose reason starts with `reason`.
+        fn began_exit(&self, mint: &Pubkey, reason: &str) -> bool {
+            let mint = mint.to_string();
+            self.events.lock().unwrap().iter().any(|e| {
+                e["type"] == "position-state"
+                    && e["mint"] == mint.as_str()
+                    && e["to"] == "exiting"
+                    && e["detail"].as_str().unwrap_or_default().starts_with(reason)
+            })
+        }
+
+        async fn holds(&self, mint: &Pubkey) -> bool {
+            self.holdings.lock().await.contains_key(&mint.to_string())
+        }
+
+        async fn sells(&self, mint: &Pubkey) -> Vec<TradeRecord> {
+            let mint = mint.to_string();
+            self.trades.lock().await.iter().filter(|t| t.mint == mint && t.trade_type == "sell").cloned().collect()
+        }
+    }
+
+    fn test_settings(cluster: &MockCluster, pumpportal: bool) -> Settings {
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.solana_rpc_urls = vec![cluster.rpc_url()];
+        s.solana_ws_urls = vec![cluster.ws_url()];
+        s.pumpportal_enabled = pumpportal;
+        s.pumpportal_wss = vec![cluster.pumpportal_url()];
+        s.hot_cache_enabled = false;
+        s.poll_fallback_enabled = false;
+        s.missed_window_secs = 0;
+        s.record_path.clear();
+        s
+    }
+
+    /// A curve as pump.fun creates it.
+    fn fresh_curve(creator: Pubkey) -> BondingCurveState {
+        BondingCurveState {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: Some(creator),
+            is_mayhem_mode: false,
+        }
+    }
+
+    async fn eventually<F, Fut>(what: &str, mut check: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+        while !check().await {
+            assert!(tokio::time::Instant::now() < deadline, "timed out waiting for {}", what);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Create `mint` with a fresh curve and announce it on the PumpPortal
+    /// feed. Returns the coin's creator and its curve address.
+    async fn launch_coin(cluster: &MockCluster, program: &Pubkey, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        let creator = Pubkey::new_unique();
+        cluster.add_mint(mint, 6);
+        let curve = cluster.set_curve(program, mint, &fresh_curve(creator));
+        eventually("the PumpPortal feed to connect", || async { cluster.pumpportal_clients() > 0 }).await;
+        cluster.emit_pumpportal(json!({
+            "signature": Signature::new_unique().to_string(),
+            "mint": mint.to_string(),
+            "traderPublicKey": creator.to_string(),
+            "txType": "create",
+            "bondingCurveKey": curve.to_string(),
+            "vTokensInBondingCurve": 1_073_000_000.0,
+            "vSolInBondingCurve": 30.0,
+            "name": "Mock",
+            "symbol": "MOCK",
+            "image": "https://example.invalid/mock.png",
+            "pool": "pump"
+        }));
+        (creator, curve)
+    }
+
+    /// Detect `mint` on the PumpPortal feed and wait for the dry-run buy and
+    /// its curve subscription. Returns the coin's creator.
+    async fn snipe(cluster: &MockCluster, bot: &Bot, program: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let (creator, curve) = launch_coin(cluster, program, mint).await;
+        eventually("the dry-run buy", || bot.holds(mint)).await;
+        eventually("the curve subscription", || async { cluster.account_subscribers(&curve) > 0 }).await;
+        creator
+    }
+
+    /// Data of the first `program` instruction in `tx`.
+    fn program_instruction(tx: &VersionedTransaction, program: &Pubkey) -> Option<Vec<u8>> {
+        let keys = tx.message.static_account_keys();
+        tx.message
+            .instructions()
+            .iter()
+            .find(|ix| keys.get(ix.program_id_index as usize) == Some(program))
+            .map(|ix| ix.data.clone())
+    }
+
+    #[tokio::test]
+    async fn detection_buys_then_takes_profit_on_curve_update() {
+        let cluster = MockCluster::start().await.unwrap();
+        let settings = test_settings(&cluster, true);
+        let program = Pubkey::from_str(&settings.pump_fun_program).unwrap();
+        let bot = Bot::start(settings).await;
+        let mint = Pubkey::new_unique();
+        let creator = snipe(&cluster, &bot, &program, &mint).await;
+        assert!(cluster.calls("simulateTransaction") > 0);
+
+        // 2.5x the SOL side: price +150%, past every TP level of the example config
+        let mut pumped = fresh_curve(creator);
+        pumped.virtual_sol_reserves = 75_000_000_000;
+        pumped.real_sol_reserves = 45_000_000_000;
+        cluster.set_curve(&program, &mint, &pumped);
+
+        eventually("the TP sell", || async { !bot.sells(&mint).await.is_empty() }).await;
+        let sell = bot.sells(&mint).await.remove(0);
+        assert!(sell.reason.as_deref().unwrap_or_default().starts_with("TP1"), "{:?}", sell.reason);
+        assert!(sell.profit_loss.unwrap_or_default() > 0.0);
+        eventually("the position to close", || async { !bot.holds(&mint).await }).await;
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    async fn detection_buys_then_stops_out_on_curve_update() {
+        let cluster = MockCluster::start().await.unwrap();
+        let settings = test_settings(&cluster, true);
+        let program = Pubkey::from_str(&settings.pump_fun_program).unwrap();
+        let bot = Bot::start(settings).await;
+        let mint = Pubkey::new_unique();
+        let creator = snipe(&cluster, &bot, &program, &mint).await;
+
+        let mut dumped = fresh_curve(creator);
+        dumped.virtual_sol_reserves = 15_000_000_000;
+        cluster.set_curve(&program, &mint, &dumped);
+
+        eventually("the SL sell", || async { !bot.sells(&mint).await.is_empty() }).await;
+        let sell = bot.sells(&mint).await.remove(0);
+        assert!(sell.reason.as_deref().unwrap_or_default().starts_with("SL1"), "{:?}", sell.reason);
+        assert!(sell.profit_loss.unwrap_or_default() < 0.0);
+        eventually("the position to close", || async { !bot.holds(&mint).await }).await;
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    async fn paused_bot_still_takes_profit_on_held_positions() {
+        let cluster = MockCluster::start().await.unwrap();
+        let settings = test_settings(&cluster, true);
+        let program = Pubkey::from_str(&settings.pump_fun_program).unwrap();
+        let bot = Bot::start(settings).await;
+        let mint = Pubkey::new_unique();
+        let creator = snipe(&cluster, &bot, &program, &mint).await;
+        bot.engine.set_running(false).await;
+
+        let mut pumped = fresh_curve(creator);
+        pumped.virtual_sol_reserves = 75_000_000_000;
+        pumped.real_sol_reserves = 45_000_000_000;
+        cluster.set_curve(&program, &mint, &pumped);
+
+        eventually("the TP sell", || async { !bot.sells(&mint).await.is_empty() }).await;
+        let sell = bot.sells(&mint).await.remove(0);
+        assert!(sell.reason.as_deref().unwrap_or_default().starts_with("TP1"), "{:?}", sell.reason);
+        eventually("the position to close", || async { !bot.holds(&mint).await }).await;
+        let control = crate::bot_control().unwrap();
+        eventually("the drain to finish", || async {
+            *control.running_state.lock().await == crate::api::BotRunningState::Stopped
+        })
+        .await;
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    async fn pumpportal_trade_tick_wakes_the_monitor() {
+        let cluster = MockCluster::start().await.unwrap();
+        let mut settings = test_settings(&cluster, true);
+        // Cached prices expire at once, so the sweep only ever sees the
+        // unchanged on-chain curve; a TP can only come from the trade tick.
+        settings.price_cache_ttl_secs = 0;
+        let program = Pubkey::from_str(&settings.pump_fun_program).unwrap();
+        let bot = Bot::start(settings).await;
+        let mint = Pubkey::new_unique();
+        let creator = snipe(&cluster, &bot, &program, &mint).await;
+        eventually("the trade subscription", || async {
+            cluster.pumpportal_trade_subscriptions().contains(&mint.to_string())
+        })
+        .await;
+
+        // A trade at 2.5x the SOL side, +150%, seen on PumpPortal only
+        cluster.emit_pumpportal(json!({
+            "signature": Signature::new_unique().to_string(),
+            "mint": mint.to_string(),
+            "traderPublicKey": Pubkey::new_unique().to_string(),
+            "txType": "buy",
+            "vTokensInBondingCurve": 1_073_000_000.0,
+            "vSolInBondingCurve": 75.0,
+            "pool": "pump"
+        }));
+        eventually("the TP exit", || async { bot.began_exit(&mint, "TP1") }).await;
+
+        // The paper sell prices off the chain, so it goes through once the curve catches up
+        let mut pumped = fresh_curve(creator);
+        pumped.virtual_sol_reserves = 75_000_000_000;
+        pumped.real_sol_reserves = 45_000_000_000;
+        cluster.set_curve(&program, &mint, &pumped);
+        eventually("the TP sell", || async { !bot.sells(&mint).await.is_empty() }).await;
+        eventually("the trade unsubscription", || async {
+            !cluster.pumpportal_trade_subscriptions().contains(&mint.to_string())
+        })
+        .await;
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    async fn real_buy_then_takes_profit_through_send_transaction() {
+        let cluster = MockCluster::start().await.unwrap();
+        let mut settings = test_settings(&cluster, true);
+        settings.buy_execution_backend = "rpc".to_string();
+        settings.sell_execution_backend = "rpc".to_string();
+        let program = Pubkey::from_str(&settings.pump_fun_program).unwrap();
+        let wallet = Keypair::new();
+        let owner = wallet.pubkey();
+        // A funded system account (the system program id is all zeroes)
+        cluster.set_account(&owner, Account { owner: Pubkey::default(), lamports: 10_000_000_000, data: vec![] });
+        let bot = Bot::start_real(settings, wallet).await;
+        let mint = Pubkey::new_unique();
+        let (creator, curve) = launch_coin(&cluster, &program, &mint).await;
+
+        // The buy goes out through sendTransaction; credit its tokens like the program would
+        eventually("the buy transaction", || async { !cluster.sent_transactions().is_empty() }).await;
+        let buy = cluster.sent_transactions().remove(0);
+        assert!(buy.signatures[0].verify(owner.as_ref(), &buy.message.serialize()));
+        let data = program_instruction(&buy, &program).expect("a pump.fun instruction in the buy");
+        assert_eq!(data[..8], crate::onchain_idl::compute_anchor_discriminator("buy"));
+        let amount = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        cluster.set_token_account(&owner, &mint, amount);
+        eventually("the confirmed buy", || bot.holds(&mint)).await;
+        assert_eq!(bot.holdings.lock().await[&mint.to_string()].amount, amount);
+        eventually("the curve subscription", || async { cluster.account_subscribers(&curve) > 0 }).await;
+
+        let mut pumped = fresh_curve(creator);
+        pumped.virtual_sol_reserves = 75_000_000_000;
+        pumped.real_sol_reserves = 45_000_000_000;
+        cluster.set_curve(&program, &mint, &pumped);
+
+        eventually("the TP sell", || async { !bot.sells(&mint).await.is_empty() }).await;
+        let sell = bot.sells(&mint).await.remove(0);
+        assert!(sell.reason.as_deref().unwrap_or_default().starts_with("TP1"), "{:?}", sell.reason);
+        let sent = cluster.sent_transactions();
+        assert_eq!(sent.len(), 2);
+        let data = program_instruction(&sent[1], &program).expect("a pump.fun instruction in the sell");
+        assert_eq!(data[..8], crate::onchain_idl::compute_anchor_discriminator("sell"));
+        bot.stop().await;
+    }
+
+    /// Script a pump.fun create of `mint` for `getTransaction` and return its signature.
+    fn add_create_transaction(cluster: &MockCluster, program: &str, mint: &Pubkey) -> String {
+        let creator = Pubkey::new_unique();
+        let curve = curve_address(&Pubkey::from_str(program).unwrap(), mint);
+        let signature = Signature::new_unique().to_string();
+        let mut data = crate::rpc::PUMP_CREATE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[0u8; 16]);
+        // Accounts per the pump.fun IDL: mint, mint_authority, curve, ..., user
+        let accounts: Vec<String> = [*mint, Pubkey::new_unique(), curve, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), creator]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let mut account_keys = accounts.clone();
+        account_keys.push(program.to_string());
+        cluster.add_transaction(&signature, json!({
+            "slot": 2,
+            "transaction": {
+                "signatures": [signature],
+                "message": {
+                    "accountKeys": account_keys,
+                    "instructions": [{ "programId": program, "accounts": accounts, "data": bs58::encode(&data).into_string() }]
+                }
+            },
+            "meta": { "err": null, "innerInstructions": [] }
+        }));
+        signature
+    }
+
+    fn create_logs(program: &str) -> Vec<String> {
+        vec![format!("Program {} invoke [1]", program), "Program log: Instruction: Create".to_string()]
+    }
+
+    #[tokio::test]
+    async fn logs_notification_is_resolved_through_get_transaction() {
+        let cluster = MockCluster::start().await.unwrap();
+        let settings = test_settings(&cluster, false);
+        let program = settings.pump_fun_program.clone();
+        let bot = Bot::start(settings).await;
+
+        let mint = Pubkey::new_unique();
+        let signature = add_create_transaction(&cluster, &program, &mint);
+        eventually("the logs subscription", || async { cluster.logs_subscribers() > 0 }).await;
+        cluster.emit_logs(&signature, &create_logs(&program));
+        eventually("the logs detection", || async { !crate::dedup::first_seen(&mint.to_string()).await.is_empty() }).await;
+        assert_eq!(cluster.calls("getTransaction"), 1);
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    async fn logs_detection_at_capacity_is_tracked_as_missed() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        crate::missed::reset().await;
+        let cluster = MockCluster::start().await.unwrap();
+        let mut settings = test_settings(&cluster, false);
+        settings.missed_window_secs = 600;
+        settings.max_holded_coins = 1;
+        let program = settings.pump_fun_program.clone();
+        let settings = Arc::new(settings);
+
+        let (ws_tx, _) = tokio::sync::broadcast::channel(16);
+        let positions = crate::position::PositionRegistry::new(
+            Arc::new(AsyncMutex::new(HashMap::new())),
+            Arc::new(Vec::new()),
+            Arc::new(crate::api::BotControl::new_with_mode(crate::api::BotMode::DryRun)),
+            ws_tx.clone(),
+        );
+        positions.begin("Held", 1).await.unwrap();
+
+        let mint = Pubkey::new_unique();
+        let signature = add_create_transaction(&cluster, &program, &mint);
+        let notification = json!({
+            "method": "logsNotification",
+            "params": { "result": { "value": { "signature": signature, "err": null, "logs": create_logs(&program) } } }
+        });
+        crate::pipeline::process_message(
+            &notification.to_string(),
+            &Arc::new(AsyncMutex::new(lru::LruCache::new(std::num::NonZeroUsize::new(16).unwrap()))),
+            &positions,
+            &Arc::new(RpcClient::new(cluster.rpc_url())),
+            false,
+            None,
+            None,
+            &Arc::new(AsyncMutex::new(lru::LruCache::new(std::num::NonZeroUsize::new(16).unwrap()))),
+            &settings,
+            Arc::new(Vec::new()),
+            Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            Arc::new(AsyncMutex::new(Vec::new())),
+            Arc::new(AsyncMutex::new(Vec::new())),
+            ws_tx,
+        )
+        .await
+        .unwrap();
+
+        let report = crate::missed::report(&settings).await;
+        assert_eq!(report.by_reason["max_holded_coins"].skipped, 1);
+        assert_eq!(report.watching[0].mint, mint.to_string());
+        // Only the create was resolved; no metadata was fetched for a coin that can't be bought
+        assert_eq!(cluster.calls("getAccountInfo"), 0);
+        crate::missed::reset().await;
+        crate::dedup::reset().await;
+    }
+
+    #[tokio::test]
+    async fn stop_drains_the_engine_before_another_may_start() {
+        let cluster = MockCluster::start().await.unwrap();
+        let bot = Bot::start(test_settings(&cluster, false)).await;
+        eventually("the logs feed to connect", || async { cluster.logs_subscribers() > 0 }).await;
+        let second = EngineBuilder::new(test_settings(&cluster, false)).start().await;
+        assert!(matches!(second, Err(crate::error::AppError::Init(_))));
+
+        bot.stop().await;
+        // Every feed task has ended, so the connection is closed
+        eventually("the logs feed to disconnect", || async { cluster.logs_subscribers() == 0 }).await;
+        let bot = Bot::start(test_settings(&cluster, false)).await;
+        bot.stop().await;
+    }
+
+    fn transfer_request(payer: &Keypair) -> ExecutionRequest<'_> {
+        ExecutionRequest {
+            side: Side::Buy,
+            mint: "So11111111111111111111111111111111111111112",
+            payer,
+            instructions: vec![solana_sdk::system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            fee_instructions: vec![],
+            followup_instructions: vec![],
+            sol_amount: 0.01,
+            token_amount: 0,
+            decimals: 6,
+        }
+    }
+
+    #[tokio::test]
+    async fn sent_transactions_land_or_fail_as_scripted() {
+        let cluster = MockCluster::start().await.unwrap();
+        let mut settings = test_settings(&cluster, false);
+        settings.execution_confirm_timeout_secs = 5;
+        let settings = Arc::new(settings);
+        let payer = Keypair::new();
+        let backend = RpcBackend::new(cluster.rpc_url());
+
+        let landed = backend.execute(transfer_request(&payer), &settings).await.unwrap();
+        assert!(matches!(landed.outcome, ConfirmationOutcome::Landed { .. }));
+        assert_eq!(cluster.sent_transactions()[0].signatures[0].to_string(), landed.signature);
+
+        cluster.set_send_error(Some(json!({ "InstructionError": [2, { "Custom": 6002 }] })));
+        let failed = backend.execute(transfer_request(&payer), &settings).await.unwrap();
+        assert!(matches!(failed.outcome, ConfirmationOutcome::Failed { .. }));
+        assert_eq!(cluster.sent_transactions().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_transactions_are_watched_until_their_blockhash_expires() {
+        let cluster = Arc::new(MockCluster::start().await.unwrap());
+        let mut settings = test_settings(&cluster, false);
+        settings.execution_confirm_timeout_secs = 1;
+        let settings = Arc::new(settings);
+        let payer = Arc::new(Keypair::new());
+        let execute = || {
+            let (cluster, settings, payer) = (cluster.clone(), settings.clone(), payer.clone());
+            tokio::spawn(async move { RpcBackend::new(cluster.rpc_url()).execute(transfer_request(&payer), &settings).await.unwrap() })
+        };
+        cluster.hold_sends(true);
+
+        // Lands after the timeout, while its blockhash is still valid
+        let late = execute();
+        eventually("the first send", || async { cluster.sent_transactions().len() == 1 }).await;
+        tokio::time::sleep(Duration::from_millis(1_500)).await;
+        assert!(!late.is_finished());
+        cluster.land_pending();
+        assert!(matches!(late.await.unwrap().outcome, ConfirmationOutcome::Landed { .. }));
+
+        // Never lands: expired once the block height passes the blockhash's
+        let dropped = execute();
+        eventually("the second send", || async { cluster.sent_transactions().len() == 2 }).await;
+        tokio::time::sleep(Duration::from_millis(1_500)).await;
+        assert!(!dropped.is_finished());
+        cluster.advance_slots(151);
+        assert_eq!(dropped.await.unwrap().outcome, ConfirmationOutcome::Expired);
+    }
+
+    #[tokio::test]
+    async fn shared_client_calls_draw_from_the_rate_limit_budget() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        let cluster = MockCluster::start().await.unwrap();
+        let mut settings = test_settings(&cluster, false);
+        settings.rpc_rate_limits.insert(cluster.rpc_url(), 2.0);
+        settings.rpc_rate_limit_burst = 2.0;
+        settings.rpc_method_weights.clear();
+        let client = crate::rpc_pool::shared_client(&settings).unwrap();
+        let wallet = Keypair::new().pubkey();
+
+        // Two credits are spent at once, the third call waits for a refill
+        let started = std::time::Instant::now();
+        for _ in 0..3 {
+            client.get_balance(&wallet).await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(400));
+        assert_eq!(cluster.calls("getBalance"), 3);
+        let usage = crate::rate_limit::usage(&settings).await;
+        assert_eq!(usage.endpoints[0].by_method["getBalance"], 3);
+        assert!(usage.endpoints[0].denied >= 1);
+
+        crate::rpc_pool::reset();
+        crate::rate_limit::reset().await;
+        assert_eq!(crate::rate_limit::usage(&settings).await.endpoints[0].calls, 0);
+    }
+}
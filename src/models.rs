@@ -49,6 +49,19 @@ impl BondingCurveState {
     }
 }
 
+/// Parts of the pump.fun Global account the buy path needs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GlobalAccount {
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    /// First non-zero entry of `fee_recipients[7]` (standard tokens)
+    pub fee_recipient: Option<Pubkey>,
+    /// `reserved_fee_recipient` (mayhem mode tokens)
+    pub reserved_fee_recipient: Option<Pubkey>,
+}
+
 // Holdings and Price Cache
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Holding {
@@ -100,10 +113,9 @@ impl OffchainTokenMetadata {
     /// `name` and `symbol` from common alternative fields in `extras`.
     pub fn normalize(&mut self) {
         fn norm_opt(mut s: Option<String>) -> Option<String> {
-            s.as_mut().map(|v| {
-                let trimmed = v.trim().trim_end_matches('\u{0}').to_string();
-                *v = trimmed;
-            });
+            if let Some(v) = s.as_mut() {
+                *v = v.trim().trim_end_matches('\u{0}').to_string();
+            }
             s.and_then(|v| {
                 let trimmed = v.trim().trim_end_matches('\u{0}').to_string();
                 if trimmed.is_empty() {
@@ -141,7 +153,7 @@ impl OffchainTokenMetadata {
                         None
                     }
                     serde_json::Value::Array(arr) => {
-                        if let Some(serde_json::Value::String(s4)) = arr.get(0) {
+                        if let Some(serde_json::Value::String(s4)) = arr.first() {
                             return Some(s4.clone());
                         }
                         None
@@ -3,9 +3,9 @@ use crate::{
     settings::Settings,
     rpc,
     api::{TradeRecord, BotControl},
-    state::BuyRecord,
+    position::{EvaluationGuard, PartialExit, PositionRegistry},
 };
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use std::{collections::HashMap, sync::Arc, time::Instant};
 use tokio::sync::{Mutex, mpsc};
 use solana_sdk::{
@@ -15,10 +15,117 @@ use crate::ws::WsRequest;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use once_cell::sync::Lazy;
 use log::error;
-use chrono::Utc;
 use std::str::FromStr;
 use solana_sdk::pubkey::Pubkey;
 
+/// A fresh price observation for a single mint, pushed by a price source
+/// (WSS `accountNotification`, PumpPortal, ...) so the monitor can evaluate
+/// TP/SL for that mint immediately instead of waiting for the next sweep.
+#[derive(Debug, Clone)]
+pub struct PriceTick {
+    pub mint: String,
+    pub price: f64,
+}
+
+pub type PriceTickSender = mpsc::Sender<PriceTick>;
+
+/// Publish a price tick without ever blocking the price source. If the monitor
+/// is backed up the tick is dropped; the periodic sweep still evaluates the
+/// holding from the price cache.
+pub fn publish_price_tick(tx: &PriceTickSender, mint: &str, price: f64) {
+    if let Err(e) = tx.try_send(PriceTick { mint: mint.to_string(), price }) {
+        log::debug!("Dropped price tick for {}: {}", mint, e);
+    }
+}
+
+/// A claimed evaluation of one mint. Releases the claim, then tells the
+/// monitor so a tick that arrived meanwhile is evaluated right away.
+struct Evaluation {
+    mint: String,
+    guard: Option<EvaluationGuard>,
+    released: mpsc::UnboundedSender<String>,
+}
+
+impl Drop for Evaluation {
+    fn drop(&mut self) {
+        self.guard.take();
+        let _ = self.released.send(std::mem::take(&mut self.mint));
+    }
+}
+
+/// What the TP/SL/timeout rules say to do with a holding at one price.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExitDecision {
+    /// Base units to sell now (0 = hold), clamped to the remaining amount.
+    pub sell_amount: u64,
+    pub reason: String,
+    /// TP/SL level indices that fire with this sell.
+    pub triggered_tp: Vec<usize>,
+    pub triggered_sl: Vec<usize>,
+}
+
+/// Evaluate the exit rules for `holding` at `profit_percent`. Pure, so the
+/// backtester runs exactly the rules the live monitor does.
+pub fn evaluate_exit(holding: &Holding, profit_percent: f64, is_timed_out: bool, settings: &Settings) -> ExitDecision {
+    let mut decision = ExitDecision::default();
+    if is_timed_out {
+        // Timeout: sell ALL remaining tokens
+        decision.sell_amount = holding.amount;
+        decision.reason = "TIMEOUT".to_string();
+        return decision;
+    }
+
+    // Check TP levels (sorted ascending by trigger_percent)
+    let mut tp_levels: Vec<(usize, &crate::settings::TpLevel)> = settings.tp_levels.iter().enumerate().collect();
+    tp_levels.sort_by(|a, b| a.1.trigger_percent.partial_cmp(&b.1.trigger_percent).unwrap_or(std::cmp::Ordering::Equal));
+    for (idx, level) in &tp_levels {
+        if holding.triggered_tp_levels.contains(idx) { continue; }
+        if profit_percent >= level.trigger_percent {
+            let partial = ((level.sell_percent / 100.0) * holding.original_amount as f64).round() as u64;
+            decision.sell_amount += partial;
+            decision.triggered_tp.push(*idx);
+            if decision.reason.is_empty() {
+                decision.reason = format!("TP{} ({:.0}% @ +{:.1}%)", idx + 1, level.sell_percent, level.trigger_percent);
+            } else {
+                decision.reason.push_str(&format!(" + TP{}", idx + 1));
+            }
+        }
+    }
+
+    // Check SL levels (sorted descending by trigger_percent, i.e. -10% before -20%)
+    let mut sl_levels: Vec<(usize, &crate::settings::SlLevel)> = settings.sl_levels.iter().enumerate().collect();
+    sl_levels.sort_by(|a, b| b.1.trigger_percent.partial_cmp(&a.1.trigger_percent).unwrap_or(std::cmp::Ordering::Equal));
+    for (idx, level) in &sl_levels {
+        if holding.triggered_sl_levels.contains(idx) { continue; }
+        if profit_percent <= level.trigger_percent {
+            let partial = ((level.sell_percent / 100.0) * holding.original_amount as f64).round() as u64;
+            decision.sell_amount += partial;
+            decision.triggered_sl.push(*idx);
+            if decision.reason.is_empty() {
+                decision.reason = format!("SL{} ({:.0}% @ {:.1}%)", idx + 1, level.sell_percent, level.trigger_percent);
+            } else {
+                decision.reason.push_str(&format!(" + SL{}", idx + 1));
+            }
+        }
+    }
+
+    // Clamp sell_amount to remaining tokens
+    decision.sell_amount = decision.sell_amount.min(holding.amount);
+    decision
+}
+
+/// Exit an operator asked for through the API/CLI: `percent` of the remaining
+/// amount, ignoring the TP/SL levels.
+pub fn manual_exit(holding: &Holding, percent: f64) -> ExitDecision {
+    let sell_amount = ((percent / 100.0) * holding.amount as f64).round() as u64;
+    ExitDecision {
+        sell_amount: sell_amount.max(1).min(holding.amount),
+        reason: format!("MANUAL ({:.0}%)", percent),
+        ..Default::default()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn monitor_holdings(
     holdings: Arc<Mutex<HashMap<String, Holding>>>,
     price_cache: Arc<Mutex<PriceCache>>,
@@ -28,57 +135,40 @@ pub async fn monitor_holdings(
     simulate_keypair: Option<Arc<Keypair>>,
     _initial_settings: Arc<Settings>,
     shared_settings: Arc<tokio::sync::Mutex<Settings>>,
-    trades_map: Arc<Mutex<HashMap<String, BuyRecord>>>,
+    positions: PositionRegistry,
     ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
-    sub_map: Arc<Mutex<HashMap<String, (usize, u64)>>>,
     _next_wss_sender: Arc<AtomicUsize>,
     trades_list: Arc<tokio::sync::Mutex<Vec<TradeRecord>>>,
     bot_control: Arc<BotControl>,
     ws_tx: tokio::sync::broadcast::Sender<String>,
+    mut price_rx: mpsc::Receiver<PriceTick>,
 ) {
     static SUBSCRIBE_ATTEMPT_TIMES: Lazy<tokio::sync::Mutex<HashMap<String, Instant>>> = Lazy::new(|| tokio::sync::Mutex::new(HashMap::new()));
     const SUBSCRIBE_ATTEMPT_DEBOUNCE_SECS: u64 = 30;
-    
-    let (remove_tx, mut remove_rx) = tokio::sync::mpsc::channel::<String>(100);
-    let mut processing = std::collections::HashSet::new();
+
+    // The sweep still runs every second for timeouts, stale prices and the
+    // RPC price source; price ticks trigger an immediate per-mint evaluation.
+    let mut sweep = tokio::time::interval(std::time::Duration::from_secs(1));
+    sweep.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // Latest tick per mint that arrived while the mint was being evaluated;
+    // re-evaluated as soon as that evaluation ends
+    let mut deferred: HashMap<String, f64> = HashMap::new();
+    let (released_tx, mut released_rx) = mpsc::unbounded_channel::<String>();
 
     loop {
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let tick: Option<PriceTick> = tokio::select! {
+            _ = sweep.tick() => None,
+            Some(tick) = price_rx.recv() => Some(tick),
+            Some(mint) = released_rx.recv() => match deferred.remove(&mint) {
+                Some(price) => Some(PriceTick { mint, price }),
+                None => continue,
+            },
+        };
         
         // Hot-reload: take a fresh snapshot of settings from the shared mutex
         // so config changes from the frontend take effect immediately.
         let settings = Arc::new(shared_settings.lock().await.clone());
         
-        while let Ok(msg) = remove_rx.try_recv() {
-            let (is_done_only, mint_to_rem) = if msg.starts_with("DONE:") {
-                (true, msg.replace("DONE:", ""))
-            } else {
-                (false, msg)
-            };
-            
-            processing.remove(&mint_to_rem);
-            
-            if !is_done_only {
-                {
-                    let mut submap = sub_map.lock().await;
-                    if let Some((idx, sub_id)) = submap.remove(&mint_to_rem) {
-                        if idx < ws_control_senders.len() {
-                            let sender = &ws_control_senders[idx];
-                            let (u_tx, u_rx) = tokio::sync::oneshot::channel();
-                            let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
-                            let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
-                        }
-                    }
-                }
-                {
-                    let mut guard = holdings.lock().await;
-                    if guard.remove(&mint_to_rem).is_some() {
-                        let _ = bot_control.add_log("info", format!("Removed {} from monitor", mint_to_rem), None).await;
-                    }
-                }
-            }
-        }
-
         let running_state = bot_control.running_state.lock().await;
         let is_stopping = matches!(*running_state, crate::api::BotRunningState::Stopping);
         if !matches!(*running_state, crate::api::BotRunningState::Running) && !is_stopping { 
@@ -87,10 +177,23 @@ pub async fn monitor_holdings(
         }
         drop(running_state);
         
-        let holdings_snapshot = { holdings.lock().await.clone() };
+        let holdings_snapshot: HashMap<String, Holding> = {
+            let guard = holdings.lock().await;
+            match &tick {
+                Some(t) => guard
+                    .get(&t.mint)
+                    .map(|h| HashMap::from([(t.mint.clone(), h.clone())]))
+                    .unwrap_or_default(),
+                None => guard.clone(),
+            }
+        };
+        if tick.is_some() && holdings_snapshot.is_empty() {
+            // Tick for a mint we don't hold (e.g. pre-buy subscription)
+            continue;
+        }
 
         // Graceful drain: if bot is Stopping and no holdings remain, transition to Stopped
-        if is_stopping && holdings_snapshot.is_empty() {
+        if is_stopping && tick.is_none() && holdings_snapshot.is_empty() {
             let mut rs = bot_control.running_state.lock().await;
             if matches!(*rs, crate::api::BotRunningState::Stopping) {
                 *rs = crate::api::BotRunningState::Stopped;
@@ -106,47 +209,62 @@ pub async fn monitor_holdings(
         }
 
         for (mint, holding) in holdings_snapshot {
-            if holding.amount == 0 { 
-                let _ = remove_tx.send(mint).await;
-                continue; 
+            if holding.amount == 0 {
+                let _ = positions.close(&mint, "empty position").await;
+                continue;
             }
-            if processing.contains(&mint) { continue; }
-            processing.insert(mint.clone());
+            // One evaluation per mint at a time; the guard is released when the task ends
+            let Some(eval_guard) = positions.try_claim_evaluation(&mint).await else {
+                if let Some(tick) = &tick {
+                    deferred.insert(mint, tick.price);
+                }
+                continue;
+            };
+            let evaluation = Evaluation { mint: mint.clone(), guard: Some(eval_guard), released: released_tx.clone() };
 
             let rpc_client = Arc::clone(&rpc_client);
             let price_cache = Arc::clone(&price_cache);
             let settings = Arc::clone(&settings);
             let ws_control_senders = Arc::clone(&ws_control_senders);
             let trades_list = Arc::clone(&trades_list);
-            let trades_map = Arc::clone(&trades_map);
             let ws_tx = ws_tx.clone();
-            let sub_map = Arc::clone(&sub_map);
+            let positions = positions.clone();
             let next_wss_sender = Arc::clone(&_next_wss_sender);
-            let remove_tx = remove_tx.clone();
             let bot_control = Arc::clone(&bot_control);
-            let holdings = Arc::clone(&holdings);
             let kp = keypair.clone();
             let sim_kp = simulate_keypair.clone();
             let mint_c = mint.clone();
             let is_real_flag = Arc::clone(&is_real_flag);
+            let tick_price = tick.as_ref().map(|t| t.price);
 
-            tokio::spawn(async move {
+            crate::tasks::spawn(async move {
+                let _evaluation = evaluation;
                 let is_real = is_real_flag.load(Ordering::Relaxed);
                 // Calculate elapsed FIRST — timeout must be checked before the
                 // potentially slow price fetch to avoid coins stuck past timeout.
-                let elapsed = Utc::now().signed_duration_since(holding.buy_time).num_seconds();
+                let elapsed = crate::recorder::now().signed_duration_since(holding.buy_time).num_seconds();
                 let is_timed_out = elapsed >= settings.timeout_secs;
+                // Taken before pricing so a failing price fetch can't skip it
+                let manual = positions.take_manual_exit(&mint_c).await;
 
-                let current_price: f64 = if is_timed_out {
-                    // Timeout: use any available cached price (even stale) or buy_price.
-                    // Don't block on slow RPC — we already know we want to sell.
+                let current_price: f64 = if is_timed_out || manual.is_some() {
+                    // Timeout or queued manual sell: use the tick, any cached price
+                    // (even stale) or buy_price. Don't block on slow RPC — we
+                    // already know we want to sell.
                     let cached = {
                         let mut cache_guard = price_cache.lock().await;
                         cache_guard.get(&mint_c).map(|(_, p)| *p)
                     };
-                    let p = cached.unwrap_or(holding.buy_price);
-                    log::info!("Timeout for {} ({}s >= {}s), using price {:.18}",
-                        mint_c, elapsed, settings.timeout_secs, p);
+                    let p = tick_price.or(cached).unwrap_or(holding.buy_price);
+                    if is_timed_out {
+                        log::info!("Timeout for {} ({}s >= {}s), using price {:.18}",
+                            mint_c, elapsed, settings.timeout_secs, p);
+                    } else {
+                        log::info!("Manual sell queued for {}, using price {:.18}", mint_c, p);
+                    }
+                    p
+                } else if let Some(p) = tick_price {
+                    // Event-driven evaluation: the tick carries the freshest price
                     p
                 } else {
                     // Normal TP/SL evaluation — need fresh price
@@ -169,7 +287,7 @@ pub async fn monitor_holdings(
                         Ok(p) => p,
                         Err(_) => {
                             if settings.price_source == "wss" && !ws_control_senders.is_empty() {
-                                let has_sub = { sub_map.lock().await.get(&mint_c).is_some() };
+                                let has_sub = positions.subscription(&mint_c).await.is_some();
                                 if !has_sub {
                                     let mut attempts = SUBSCRIBE_ATTEMPT_TIMES.lock().await;
                                     if !matches!(attempts.get(&mint_c), Some(last) if Instant::now().duration_since(*last).as_secs() < SUBSCRIBE_ATTEMPT_DEBOUNCE_SECS) {
@@ -179,8 +297,12 @@ pub async fn monitor_holdings(
                                             let pump_prog = Pubkey::from_str(&settings.pump_fun_program).unwrap_or_default();
                                             let (curve_pda, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_prog);
                                             let idx = next_wss_sender.fetch_add(1, Ordering::Relaxed) % ws_control_senders.len();
-                                            let (otx, _) = tokio::sync::oneshot::channel();
-                                            let _ = ws_control_senders[idx].send(WsRequest::Subscribe { account: curve_pda.to_string(), mint: mint_c.clone(), resp: otx }).await;
+                                            let (otx, orx) = tokio::sync::oneshot::channel();
+                                            if ws_control_senders[idx].send(WsRequest::Subscribe { account: curve_pda.to_string(), mint: mint_c.clone(), resp: otx }).await.is_ok() {
+                                                if let Ok(Ok(Ok(sub_id))) = tokio::time::timeout(std::time::Duration::from_secs(5), orx).await {
+                                                    positions.set_subscription(&mint_c, Some((idx, sub_id))).await;
+                                                }
+                                            }
                                         }
                                     }
                                 }
@@ -193,17 +315,15 @@ pub async fn monitor_holdings(
                                 Ok(Ok(p)) => p,
                                 Ok(Err(e2)) => {
                                     let err_msg = e2.to_string();
-                                    if err_msg.contains("migrated") { 
-                                        let _ = remove_tx.send(mint_c).await; 
+                                    if err_msg.contains("migrated") {
+                                        let _ = positions.close(&mint_c, "bonding curve migrated").await;
                                     } else {
                                         log::warn!("Price fetch failed for {} (will retry): {}", mint_c, err_msg);
-                                        let _ = remove_tx.send(format!("DONE:{}", mint_c)).await;
                                     }
                                     return;
                                 }
                                 Err(_timeout) => {
                                     log::warn!("Price fetch timed out (15s) for {} (will retry)", mint_c);
-                                    let _ = remove_tx.send(format!("DONE:{}", mint_c)).await;
                                     return;
                                 }
                             }
@@ -229,59 +349,23 @@ pub async fn monitor_holdings(
                     "triggered_sl": holding.triggered_sl_levels
                 }).to_string());
 
-                // --- Multi-level TP/SL evaluation ---
-                // Check which TP/SL levels should trigger (that haven't already)
-                let mut sell_amount: u64 = 0;
-                let mut reason_str = String::new();
-                let mut newly_triggered_tp: Vec<usize> = Vec::new();
-                let mut newly_triggered_sl: Vec<usize> = Vec::new();
-
-                if is_timed_out {
-                    // Timeout: sell ALL remaining tokens
-                    sell_amount = holding.amount;
-                    reason_str = "TIMEOUT".to_string();
-                } else {
-                    // Check TP levels (sorted ascending by trigger_percent)
-                    let mut tp_levels: Vec<(usize, &crate::settings::TpLevel)> = settings.tp_levels.iter().enumerate().collect();
-                    tp_levels.sort_by(|a, b| a.1.trigger_percent.partial_cmp(&b.1.trigger_percent).unwrap_or(std::cmp::Ordering::Equal));
-                    for (idx, level) in &tp_levels {
-                        if holding.triggered_tp_levels.contains(idx) { continue; }
-                        if profit_percent >= level.trigger_percent {
-                            let partial = ((level.sell_percent / 100.0) * holding.original_amount as f64).round() as u64;
-                            sell_amount += partial;
-                            newly_triggered_tp.push(*idx);
-                            if reason_str.is_empty() {
-                                reason_str = format!("TP{} ({:.0}% @ +{:.1}%)", idx + 1, level.sell_percent, level.trigger_percent);
-                            } else {
-                                reason_str.push_str(&format!(" + TP{}", idx + 1));
-                            }
-                        }
-                    }
+                // --- Multi-level TP/SL evaluation (a queued manual sell wins, timeout aside) ---
+                let ExitDecision { sell_amount, reason: reason_str, triggered_tp: newly_triggered_tp, triggered_sl: newly_triggered_sl } =
+                    match manual {
+                        Some(percent) if !is_timed_out => manual_exit(&holding, percent),
+                        _ => evaluate_exit(&holding, profit_percent, is_timed_out, &settings),
+                    };
 
-                    // Check SL levels (sorted descending by trigger_percent, i.e. -10% before -20%)
-                    let mut sl_levels: Vec<(usize, &crate::settings::SlLevel)> = settings.sl_levels.iter().enumerate().collect();
-                    sl_levels.sort_by(|a, b| b.1.trigger_percent.partial_cmp(&a.1.trigger_percent).unwrap_or(std::cmp::Ordering::Equal));
-                    for (idx, level) in &sl_levels {
-                        if holding.triggered_sl_levels.contains(idx) { continue; }
-                        if profit_percent <= level.trigger_percent {
-                            let partial = ((level.sell_percent / 100.0) * holding.original_amount as f64).round() as u64;
-                            sell_amount += partial;
-                            newly_triggered_sl.push(*idx);
-                            if reason_str.is_empty() {
-                                reason_str = format!("SL{} ({:.0}% @ {:.1}%)", idx + 1, level.sell_percent, level.trigger_percent);
-                            } else {
-                                reason_str.push_str(&format!(" + SL{}", idx + 1));
-                            }
+                if sell_amount > 0 {
+                    // Open -> Exiting; refuses if an exit is already in flight
+                    if !positions.begin_exit(&mint_c, &reason_str).await {
+                        log::debug!("Exit for {} already in progress; skipping {}", mint_c, reason_str);
+                        if let Some(percent) = manual.filter(|_| !is_timed_out) {
+                            // Keep the operator's request for the next evaluation
+                            let _ = positions.request_manual_exit(&mint_c, percent).await;
                         }
+                        return;
                     }
-
-                    // Clamp sell_amount to remaining tokens
-                    if sell_amount > holding.amount {
-                        sell_amount = holding.amount;
-                    }
-                }
-
-                if sell_amount > 0 {
                     let is_final_sell = sell_amount >= holding.amount;
                     let kp_ref = kp.as_ref().map(|k| k.as_ref());
                     let sim_kp_ref = sim_kp.as_ref().map(|k| k.as_ref());
@@ -297,7 +381,7 @@ pub async fn monitor_holdings(
                                 name: holding.metadata.as_ref().and_then(|m| m.name.clone()),
                                 image: holding.metadata.as_ref().and_then(|m| m.image.clone()),
                                 trade_type: "sell".to_string(),
-                                timestamp: Utc::now().to_rfc3339(),
+                                timestamp: crate::recorder::now().to_rfc3339(),
                                 tx_signature: None,
                                 amount_sol: sell_sol,
                                 amount_tokens: sell_amount as f64 / token_divisor,
@@ -318,21 +402,19 @@ pub async fn monitor_holdings(
                             drop(trades);
 
                             if is_final_sell {
-                                // Full exit: remove holding entirely
-                                let _ = remove_tx.send(mint_c.clone()).await;
-                                trades_map.lock().await.remove(&mint_c);
+                                // Full exit: closing the position drops the holding and subscription
+                                let _ = positions.close(&mint_c, reason_str.clone()).await;
                                 let _ = bot_control.add_log("info", format!("Sold 100% of {} ({}) at {:.18} (profit: {:.2}%)", mint_c, reason_str, current_price, profit_percent), None).await;
                             } else {
-                                // Partial sell: update holding in-place
-                                {
-                                    let mut guard = holdings.lock().await;
-                                    if let Some(h) = guard.get_mut(&mint_c) {
-                                        h.amount = h.amount.saturating_sub(sell_amount);
-                                        for idx in &newly_triggered_tp { h.triggered_tp_levels.push(*idx); }
-                                        for idx in &newly_triggered_sl { h.triggered_sl_levels.push(*idx); }
-                                    }
+                                // Partial sell: Exiting -> Open with the reduced amount
+                                let partial = PartialExit {
+                                    sold_amount: sell_amount,
+                                    triggered_tp: newly_triggered_tp,
+                                    triggered_sl: newly_triggered_sl,
+                                };
+                                if let Err(e) = positions.complete_partial_exit(&mint_c, partial, reason_str.clone()).await {
+                                    error!("Failed to record partial exit for {}: {}", mint_c, e);
                                 }
-                                let _ = remove_tx.send(format!("DONE:{}", mint_c)).await;
                                 let pct_sold = (sell_amount as f64 / holding.original_amount as f64) * 100.0;
                                 let _ = bot_control.add_log("info", format!("Partial sell {:.0}% of {} ({}) at {:.18} (profit: {:.2}%)", pct_sold, mint_c, reason_str, current_price, profit_percent), None).await;
                             }
@@ -344,7 +426,7 @@ pub async fn monitor_holdings(
                                 // Force-remove timed-out coins after sell failure to prevent
                                 // infinite retry loops. Record as forced timeout sell.
                                 log::warn!("Force-removing timed-out {} after sell failure", mint_c);
-                                let _ = remove_tx.send(mint_c.clone()).await;
+                                let _ = positions.fail(&mint_c, format!("TIMEOUT_FORCED: {}", e)).await;
                                 let mut trades = trades_list.lock().await;
                                 trades.insert(0, TradeRecord {
                                     mint: mint_c.clone(),
@@ -352,7 +434,7 @@ pub async fn monitor_holdings(
                                     name: holding.metadata.as_ref().and_then(|m| m.name.clone()),
                                     image: holding.metadata.as_ref().and_then(|m| m.image.clone()),
                                     trade_type: "sell".to_string(),
-                                    timestamp: Utc::now().to_rfc3339(),
+                                    timestamp: crate::recorder::now().to_rfc3339(),
                                     tx_signature: None,
                                     amount_sol: (holding.amount as f64 / token_divisor) * current_price,
                                     amount_tokens: holding.amount as f64 / token_divisor,
@@ -370,16 +452,45 @@ pub async fn monitor_holdings(
                                 if let Ok(json) = serde_json::to_value(&trades[0]) {
                                     let _ = ws_tx.send(serde_json::json!({"type": "new-trade", "trade": json}).to_string());
                                 }
-                                trades_map.lock().await.remove(&mint_c);
                             } else {
-                                let _ = remove_tx.send(format!("DONE:{}", mint_c)).await;
+                                // Back to Open so the next evaluation can retry
+                                let _ = positions
+                                    .transition(&mint_c, crate::position::PositionState::Open, Some(format!("sell failed: {}", e)))
+                                    .await;
                             }
                         }
                     }
-                } else {
-                    let _ = remove_tx.send(format!("DONE:{}", mint_c)).await;
                 }
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_exit_sells_a_share_of_the_remaining_amount() {
+        let holding = Holding {
+            amount: 1_000,
+            original_amount: 4_000,
+            buy_price: 1e-7,
+            buy_time: crate::recorder::now(),
+            decimals: 6,
+            buy_cost_sol: None,
+            triggered_tp_levels: vec![],
+            triggered_sl_levels: vec![],
+            metadata: None,
+            onchain_raw: None,
+            onchain: None,
+        };
+        let half = manual_exit(&holding, 50.0);
+        assert_eq!(half.sell_amount, 500);
+        assert_eq!(half.reason, "MANUAL (50%)");
+        assert!(half.triggered_tp.is_empty() && half.triggered_sl.is_empty());
+        assert_eq!(manual_exit(&holding, 100.0).sell_amount, 1_000);
+        // Never rounds down to a no-op
+        assert_eq!(manual_exit(&holding, 0.01).sell_amount, 1);
+    }
+}
@@ -1,12 +1,16 @@
+//! Load program IDLs from the chain's Anchor IDL account, with a disk cache.
+//! Only the discriminator helpers are wired in so far; the loaders are kept
+//! for programs without a bundled IDL.
+#![allow(dead_code)]
+
 use crate::{idl::SimpleIdl, settings::Settings};
 use log::{debug, info, warn};
 use serde_json::Value;
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
 use std::collections::HashMap;
 use std::io::Read;
 use std::path::Path;
-use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 /// In-memory cache for fetched IDLs (program_id -> SimpleIdl)
@@ -29,7 +33,7 @@ pub async fn fetch_idl_account_data(
 ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
     debug!("Fetching IDL account data for {}", idl_account);
     
-    match rpc_client.get_account_data(idl_account) {
+    match rpc_client.get_account_data(idl_account).await {
         Ok(data) => {
             debug!("Fetched {} bytes from IDL account {}", data.len(), idl_account);
             Ok(data)
@@ -262,7 +266,7 @@ pub async fn load_pumpfun_idl(
     settings: &Settings,
     cache: &IdlCache,
 ) -> Result<SimpleIdl, Box<dyn std::error::Error + Send + Sync>> {
-    let program_id = Pubkey::from_str(&settings.pump_fun_program)?;
+    let program_id = settings.address_book()?.pump_program;
     
     // Try local file first (legacy support)
     if let Ok(idl) = SimpleIdl::load_from("pumpfun.json") {
@@ -277,6 +281,7 @@ pub async fn load_pumpfun_idl(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
 
     #[test]
     fn test_anchor_discriminator_computation() {
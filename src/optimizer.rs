@@ -0,0 +1,431 @@
+//! Parameter sweep on top of the backtester.
+//!
+//! `sol_beast optimize <dataset> <sweep.toml> [--out best.toml]` grid- or
+//! random-searches the parameter ranges in the sweep file, backtests every
+//! candidate against the dataset, ranks them by the chosen objective and
+//! prints the winner as a `Settings` TOML fragment. Every candidate starts
+//! from the loaded config and must pass `Settings::validate`.
+//!
+//! ```toml
+//! mode = "grid"          # or "random" (with `samples` and optional `seed`)
+//! objective = "pnl"      # "pnl", "sharpe" or "drawdown"
+//! fill_delay_ms = 400
+//!
+//! [params]
+//! tp1_trigger = [20, 50, 100]
+//! tp1_sell = { min = 25, max = 100, step = 25 }
+//! sl1_trigger = { min = -40, max = -10, step = 10 }
+//! timeout_secs = [60, 120, 300]
+//! ```
+
+use crate::backtest::{self, BacktestReport, CoinPath};
+use crate::settings::Settings;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Largest grid evaluated before asking for random search instead.
+const MAX_GRID_RUNS: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ParamRange {
+    Values(Vec<f64>),
+    Span { min: f64, max: f64, #[serde(default)] step: Option<f64> },
+}
+
+impl ParamRange {
+    /// Grid points; a span needs a `step`.
+    fn grid(&self) -> Result<Vec<f64>, String> {
+        match self {
+            ParamRange::Values(values) if !values.is_empty() => Ok(values.clone()),
+            ParamRange::Values(_) => Err("empty value list".to_string()),
+            ParamRange::Span { step: None, .. } => Err("a grid range needs a `step`".to_string()),
+            ParamRange::Span { min, max, step: Some(step) } => {
+                if *step <= 0.0 || max < min {
+                    return Err("range needs min <= max and step > 0".to_string());
+                }
+                let count = ((max - min) / step + 1e-9).floor() as usize + 1;
+                Ok((0..count).map(|i| min + step * i as f64).collect())
+            }
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        match self {
+            ParamRange::Values(values) => values[rng.gen_range(0..values.len())],
+            ParamRange::Span { min, max, step } => {
+                let value = if max > min { rng.gen_range(*min..=*max) } else { *min };
+                match step {
+                    Some(step) if *step > 0.0 => (min + ((value - min) / step).round() * step).min(*max),
+                    _ => value,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Grid,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    /// Highest total PnL
+    #[default]
+    Pnl,
+    /// Highest mean/stddev of per-trade PnL
+    Sharpe,
+    /// Lowest max drawdown, ties broken by PnL
+    Drawdown,
+}
+
+impl Objective {
+    /// Higher is better.
+    fn score(self, report: &BacktestReport) -> f64 {
+        match self {
+            Objective::Pnl => report.total_pnl_sol,
+            Objective::Sharpe => report.sharpe,
+            Objective::Drawdown => -report.max_drawdown_sol,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepSpec {
+    #[serde(default)]
+    pub mode: SearchMode,
+    #[serde(default)]
+    pub objective: Objective,
+    /// Candidates drawn in random mode
+    #[serde(default = "default_samples")]
+    pub samples: usize,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Candidates with fewer trades are not ranked
+    #[serde(default = "default_min_trades")]
+    pub min_trades: usize,
+    #[serde(default)]
+    pub fill_delay_ms: u64,
+    /// Ranked candidates kept in the result
+    #[serde(default = "default_top")]
+    pub top: usize,
+    pub params: BTreeMap<String, ParamRange>,
+}
+
+fn default_samples() -> usize { 200 }
+fn default_min_trades() -> usize { 1 }
+fn default_top() -> usize { 10 }
+
+/// A sweepable setting. TP/SL indices are 0-based; names are 1-based
+/// (`tp1_trigger` is `tp_levels[0].trigger_percent`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    TpTrigger(usize),
+    TpSell(usize),
+    SlTrigger(usize),
+    SlSell(usize),
+    TimeoutSecs,
+    MinLiquiditySol,
+    MaxLiquiditySol,
+    SlippageBps,
+}
+
+impl Param {
+    fn parse(name: &str) -> Option<Param> {
+        match name {
+            "timeout_secs" => return Some(Param::TimeoutSecs),
+            "min_liquidity_sol" => return Some(Param::MinLiquiditySol),
+            "max_liquidity_sol" => return Some(Param::MaxLiquiditySol),
+            "slippage_bps" => return Some(Param::SlippageBps),
+            _ => {}
+        }
+        let (level, field) = name.split_once('_')?;
+        let (kind, n) = (level.get(..2)?, level.get(2..)?);
+        let idx = n.parse::<usize>().ok()?.checked_sub(1)?;
+        match (kind, field) {
+            ("tp", "trigger") => Some(Param::TpTrigger(idx)),
+            ("tp", "sell") => Some(Param::TpSell(idx)),
+            ("sl", "trigger") => Some(Param::SlTrigger(idx)),
+            ("sl", "sell") => Some(Param::SlSell(idx)),
+            _ => None,
+        }
+    }
+
+    /// Whether the base config can take this parameter.
+    fn check(self, name: &str, base: &Settings) -> Result<(), String> {
+        match self {
+            Param::TpTrigger(i) | Param::TpSell(i) if i >= base.tp_levels.len() => {
+                Err(format!("{}: the config has {} TP level(s)", name, base.tp_levels.len()))
+            }
+            Param::SlTrigger(i) | Param::SlSell(i) if i >= base.sl_levels.len() => {
+                Err(format!("{}: the config has {} SL level(s)", name, base.sl_levels.len()))
+            }
+            Param::MinLiquiditySol | Param::MaxLiquiditySol if !base.enable_safer_sniping => {
+                Err(format!("{} has no effect unless enable_safer_sniping is set in the config", name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(self, settings: &mut Settings, value: f64) {
+        match self {
+            Param::TpTrigger(i) => settings.tp_levels[i].trigger_percent = value,
+            Param::TpSell(i) => settings.tp_levels[i].sell_percent = value,
+            Param::SlTrigger(i) => settings.sl_levels[i].trigger_percent = value,
+            Param::SlSell(i) => settings.sl_levels[i].sell_percent = value,
+            Param::TimeoutSecs => settings.timeout_secs = value.round() as i64,
+            Param::MinLiquiditySol => settings.min_liquidity_sol = value,
+            Param::MaxLiquiditySol => settings.max_liquidity_sol = value,
+            Param::SlippageBps => settings.slippage_bps = value.max(0.0).round() as u64,
+        }
+    }
+
+    /// Top-level `Settings` key this parameter lives under.
+    fn settings_key(self) -> &'static str {
+        match self {
+            Param::TpTrigger(_) | Param::TpSell(_) => "tp_levels",
+            Param::SlTrigger(_) | Param::SlSell(_) => "sl_levels",
+            Param::TimeoutSecs => "timeout_secs",
+            Param::MinLiquiditySol => "min_liquidity_sol",
+            Param::MaxLiquiditySol => "max_liquidity_sol",
+            Param::SlippageBps => "slippage_bps",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub params: BTreeMap<String, f64>,
+    pub score: f64,
+    pub report: BacktestReport,
+    #[serde(skip)]
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepResult {
+    pub objective: Objective,
+    pub seed: Option<u64>,
+    pub evaluated: usize,
+    /// Candidates rejected by `Settings::validate`
+    pub invalid: usize,
+    /// Candidates with fewer than `min_trades` trades
+    pub too_few_trades: usize,
+    /// Best first, at most `top`
+    pub ranked: Vec<Candidate>,
+    /// Settings keys touched by the sweep, for the TOML fragment
+    pub keys: Vec<&'static str>,
+}
+
+pub fn load_spec(path: &str) -> Result<SweepSpec, Box<dyn std::error::Error + Send + Sync>> {
+    let text = std::fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|e| format!("{}: {}", path, e).into())
+}
+
+/// Backtest every candidate the spec describes and rank them.
+pub fn sweep(spec: &SweepSpec, base: &Settings, coins: &[CoinPath]) -> Result<SweepResult, String> {
+    if spec.params.is_empty() {
+        return Err("the sweep has no [params]".to_string());
+    }
+    let mut params = Vec::new();
+    for (name, range) in &spec.params {
+        let param = Param::parse(name).ok_or_else(|| format!("unknown sweep parameter `{}`", name))?;
+        param.check(name, base)?;
+        params.push((name.clone(), param, range));
+    }
+
+    let mut seed = None;
+    let assignments: Vec<Vec<f64>> = match spec.mode {
+        SearchMode::Grid => {
+            let axes = params
+                .iter()
+                .map(|(name, _, range)| range.grid().map_err(|e| format!("{}: {}", name, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let runs = axes.iter().try_fold(1usize, |acc, axis| acc.checked_mul(axis.len())).unwrap_or(usize::MAX);
+            if runs > MAX_GRID_RUNS {
+                return Err(format!("grid has {} combinations (max {}); use mode = \"random\"", runs, MAX_GRID_RUNS));
+            }
+            cartesian(&axes)
+        }
+        SearchMode::Random => {
+            let s = spec.seed.unwrap_or_else(rand::random);
+            seed = Some(s);
+            let mut rng = StdRng::seed_from_u64(s);
+            (0..spec.samples).map(|_| params.iter().map(|(_, _, range)| range.sample(&mut rng)).collect()).collect()
+        }
+    };
+
+    let mut result = SweepResult {
+        objective: spec.objective,
+        seed,
+        evaluated: 0,
+        invalid: 0,
+        too_few_trades: 0,
+        ranked: Vec::new(),
+        keys: Vec::new(),
+    };
+    for (_, param, _) in &params {
+        if !result.keys.contains(&param.settings_key()) {
+            result.keys.push(param.settings_key());
+        }
+    }
+    for values in assignments {
+        let mut settings = base.clone();
+        for ((_, param, _), value) in params.iter().zip(&values) {
+            param.apply(&mut settings, *value);
+        }
+        result.evaluated += 1;
+        if settings.validate().is_err() {
+            result.invalid += 1;
+            continue;
+        }
+        let report = backtest::run(coins, &settings, spec.fill_delay_ms);
+        if report.trades < spec.min_trades.max(1) {
+            result.too_few_trades += 1;
+            continue;
+        }
+        result.ranked.push(Candidate {
+            params: params.iter().zip(&values).map(|((name, _, _), v)| (name.clone(), *v)).collect(),
+            score: spec.objective.score(&report),
+            report,
+            settings,
+        });
+    }
+    // Scores within float noise tie; ties fall back to PnL, then to the
+    // parameter values (lowest first) so the ranking never depends on order.
+    result.ranked.sort_by(|a, b| {
+        cmp_approx(b.score, a.score)
+            .then_with(|| cmp_approx(b.report.total_pnl_sol, a.report.total_pnl_sol))
+            .then_with(|| {
+                a.params
+                    .values()
+                    .zip(b.params.values())
+                    .map(|(x, y)| x.total_cmp(y))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    });
+    result.ranked.truncate(spec.top.max(1));
+    Ok(result)
+}
+
+/// `total_cmp` that treats values within a relative 1e-9 as equal.
+fn cmp_approx(a: f64, b: f64) -> std::cmp::Ordering {
+    if (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0) {
+        std::cmp::Ordering::Equal
+    } else {
+        a.total_cmp(&b)
+    }
+}
+
+fn cartesian(axes: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    axes.iter().fold(vec![Vec::new()], |acc, axis| {
+        acc.into_iter()
+            .flat_map(|prefix| {
+                axis.iter().map(move |v| {
+                    let mut next = prefix.clone();
+                    next.push(*v);
+                    next
+                })
+            })
+            .collect()
+    })
+}
+
+/// The settings keys `keys` of `settings` as TOML, ready to paste into
+/// `config.toml`.
+pub fn settings_fragment(settings: &Settings, keys: &[&str]) -> Result<String, toml::ser::Error> {
+    let all = toml::Value::try_from(settings)?;
+    let mut fragment = toml::Table::new();
+    for key in keys {
+        if let Some(value) = all.get(*key) {
+            fragment.insert(key.to_string(), value.clone());
+        }
+    }
+    toml::to_string(&fragment)
+}
+
+/// Ranked table for the terminal.
+pub fn format_result(result: &SweepResult) -> String {
+    let mut out = format!(
+        "Evaluated {} candidates ({} invalid, {} with too few trades), objective {:?}",
+        result.evaluated, result.invalid, result.too_few_trades, result.objective
+    );
+    if let Some(seed) = result.seed {
+        out.push_str(&format!(", seed {}", seed));
+    }
+    out.push('\n');
+    for (rank, candidate) in result.ranked.iter().enumerate() {
+        let params: Vec<String> = candidate.params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        out.push_str(&format!(
+            "#{:<3} pnl {:+.6} SOL  sharpe {:.3}  dd {:.6} SOL  win {:.1}%  trades {}  {}\n",
+            rank + 1,
+            candidate.report.total_pnl_sol,
+            candidate.report.sharpe,
+            candidate.report.max_drawdown_sol,
+            candidate.report.win_rate * 100.0,
+            candidate.report.trades,
+            params.join(" "),
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::PricePoint;
+    use crate::settings::{SlLevel, TpLevel};
+
+    #[test]
+    fn grid_sweep_picks_the_best_take_profit_and_emits_loadable_toml() {
+        let mut base = Settings::from_file("config.example.toml").unwrap();
+        base.buy_amount = 1.0;
+        base.timeout_secs = 60;
+        base.enable_safer_sniping = false;
+        base.tp_levels = vec![TpLevel { trigger_percent: 10.0, sell_percent: 100.0 }];
+        base.sl_levels = vec![SlLevel { trigger_percent: -50.0, sell_percent: 100.0 }];
+        // Climbs in steps to +90% before collapsing, so each higher target
+        // (up to 80%) fills at a strictly better price
+        let path = [(0, 1.0), (1_000, 1.3), (2_000, 1.5), (3_000, 1.7), (4_000, 1.9), (5_000, 0.4)]
+            .iter()
+            .map(|(t, p)| PricePoint { t_ms: *t, price: p * 1e-6, real_sol_reserves: None })
+            .collect();
+        let coins = vec![CoinPath { mint: "a".into(), decimals: Some(6), path }];
+
+        let spec: SweepSpec = toml::from_str("[params]\ntp1_trigger = { min = 20, max = 100, step = 20 }\n").unwrap();
+        let result = sweep(&spec, &base, &coins).unwrap();
+        assert_eq!(result.evaluated, 5);
+        let tp = |c: &Candidate| c.params["tp1_trigger"];
+        assert!((tp(&result.ranked[0]) - 80.0).abs() < 1e-9);
+        assert!((tp(&result.ranked[1]) - 60.0).abs() < 1e-9);
+
+        let fragment = settings_fragment(&result.ranked[0].settings, &result.keys).unwrap();
+        let parsed: toml::Table = toml::from_str(&fragment).unwrap();
+        assert!((parsed["tp_levels"][0]["trigger_percent"].as_float().unwrap() - 80.0).abs() < 1e-9);
+        assert!(!parsed.contains_key("timeout_secs"));
+
+        // Identical outcomes rank by parameter value, not by evaluation order
+        let flat = vec![CoinPath {
+            mint: "b".into(),
+            decimals: Some(6),
+            path: [(0, 1.0), (1_000, 2.0)]
+                .iter()
+                .map(|(t, p)| PricePoint { t_ms: *t, price: p * 1e-6, real_sol_reserves: None })
+                .collect(),
+        }];
+        let tied: SweepSpec = toml::from_str("[params]\ntp1_trigger = [60, 20, 40]\n").unwrap();
+        let result = sweep(&tied, &base, &flat).unwrap();
+        let order: Vec<f64> = result.ranked.iter().map(tp).collect();
+        assert_eq!(order, vec![20.0, 40.0, 60.0]);
+
+        let bad: SweepSpec = toml::from_str("[params]\ntp2_trigger = [10]\n").unwrap();
+        assert!(sweep(&bad, &base, &coins).is_err());
+    }
+}
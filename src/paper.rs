@@ -0,0 +1,419 @@
+//! Paper wallet for dry-run trading.
+//!
+//! Dry-run buys and sells are filled against a virtual SOL balance that
+//! starts at `paper_starting_balance_sol`. Fills use the pump.fun
+//! constant-product curve on the virtual reserves (falling back to the spot
+//! price when the curve is unknown), the pump.fun trade fee, the dev fee,
+//! the estimated network fee of the configured execution backend, ATA rent
+//! and an adverse `paper_latency_slippage_bps` move between quote and
+//! landing. A buy the wallet cannot afford, or whose cost breaks
+//! `slippage_bps`, is rejected like a failed transaction would be.
+//!
+//! The ledger lives in memory and starts over when the bot restarts.
+
+use crate::execution::{self, Side};
+use crate::models::BondingCurveState;
+use crate::settings::Settings;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// Signature fee of a single-signer transaction.
+const BASE_FEE_LAMPORTS: u64 = 5_000;
+/// Rent-exempt minimum of a token account, paid on buy and reclaimed when
+/// the final sell closes the ATA.
+const ATA_RENT_LAMPORTS: u64 = 2_039_280;
+
+/// Wallet effect of one paper trade.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PaperFill {
+    pub tokens: u64,
+    /// SOL paid into (buy) or out of (sell) the curve, before fees
+    pub curve_lamports: u64,
+    pub pump_fee_lamports: u64,
+    pub dev_fee_lamports: u64,
+    pub network_fee_lamports: u64,
+    /// ATA rent paid on a buy or reclaimed on a final sell
+    pub rent_lamports: u64,
+    /// Net change of the wallet balance
+    pub net_lamports: i64,
+}
+
+impl PaperFill {
+    pub fn net_sol(&self) -> f64 {
+        self.net_lamports as f64 / LAMPORTS_PER_SOL
+    }
+
+    pub fn network_fee_sol(&self) -> f64 {
+        self.network_fee_lamports as f64 / LAMPORTS_PER_SOL
+    }
+}
+
+/// Estimated network cost of one transaction on the configured backend:
+/// signature fee, compute-unit priority fee and the backend's tip.
+pub fn estimated_network_fee_lamports(side: Side, settings: &Settings) -> u64 {
+    let priority = settings.compute_unit_limit as u64 * settings.compute_unit_price_micro_lamports / 1_000_000;
+    let sol = |v: f64| (v * LAMPORTS_PER_SOL) as u64;
+    match execution::backend_name(side, settings) {
+        // PumpPortal builds the transaction and charges its own priority fee
+        "pumpportal" => BASE_FEE_LAMPORTS + sol(settings.pumpportal_priority_fee_sol),
+        "helius" => BASE_FEE_LAMPORTS + priority + sol(settings.get_effective_min_tip_sol()),
+        "jito" => BASE_FEE_LAMPORTS + priority + sol(settings.jito_tip_sol),
+        // Only one fanout copy lands; assume the dearer tip
+        "fanout" => BASE_FEE_LAMPORTS + priority + sol(settings.get_effective_min_tip_sol().max(settings.jito_tip_sol)),
+        _ => BASE_FEE_LAMPORTS + priority,
+    }
+}
+
+fn bps(amount: u64, bps: u64) -> u64 {
+    (amount as u128 * bps as u128 / 10_000) as u64
+}
+
+fn spot_lamports(tokens: u64, spot_price: f64, decimals: u8) -> u64 {
+    (tokens as f64 / 10f64.powi(decimals as i32) * spot_price * LAMPORTS_PER_SOL) as u64
+}
+
+/// Fill for buying `token_amount` with `sol_amount` as the intended spend,
+/// as the buy instruction does (`max_sol_cost` = spend + `slippage_bps`).
+pub fn buy_fill(
+    sol_amount: f64,
+    token_amount: u64,
+    curve: Option<&BondingCurveState>,
+    spot_price: f64,
+    decimals: u8,
+    settings: &Settings,
+) -> Result<PaperFill, String> {
+    let quoted = match curve {
+        Some(c) if c.virtual_token_reserves > token_amount => {
+            let vsol = c.virtual_sol_reserves as u128;
+            let vtok = c.virtual_token_reserves as u128;
+            let tok = token_amount as u128;
+            // Constant product, rounded up in the curve's favour
+            ((vsol * tok).div_ceil(vtok - tok)) as u64
+        }
+        _ => spot_lamports(token_amount, spot_price, decimals),
+    };
+    let curve_lamports = quoted + bps(quoted, settings.paper_latency_slippage_bps);
+    let pump_fee_lamports = bps(curve_lamports, settings.paper_pump_fee_bps);
+    let spend_lamports = (sol_amount * LAMPORTS_PER_SOL) as u64;
+    let max_sol_cost = spend_lamports + bps(spend_lamports, settings.slippage_bps);
+    if curve_lamports + pump_fee_lamports > max_sol_cost {
+        return Err(format!(
+            "Paper buy would cost {:.9} SOL, above max_sol_cost {:.9} SOL (slippage_bps {})",
+            (curve_lamports + pump_fee_lamports) as f64 / LAMPORTS_PER_SOL,
+            max_sol_cost as f64 / LAMPORTS_PER_SOL,
+            settings.slippage_bps
+        ));
+    }
+    let dev_fee_lamports = if settings.dev_fee_enabled { crate::dev_fee::calculate_dev_fee(spend_lamports) } else { 0 };
+    let network_fee_lamports = estimated_network_fee_lamports(Side::Buy, settings);
+    let total = curve_lamports + pump_fee_lamports + dev_fee_lamports + network_fee_lamports + ATA_RENT_LAMPORTS;
+    Ok(PaperFill {
+        tokens: token_amount,
+        curve_lamports,
+        pump_fee_lamports,
+        dev_fee_lamports,
+        network_fee_lamports,
+        rent_lamports: ATA_RENT_LAMPORTS,
+        net_lamports: -(total as i64),
+    })
+}
+
+/// Fill for selling `token_amount`, quoted by the monitor at `spot_price`
+/// (`min_sol_output` = quote - `slippage_bps`). A final sell closes the ATA.
+pub fn sell_fill(
+    token_amount: u64,
+    curve: Option<&BondingCurveState>,
+    spot_price: f64,
+    decimals: u8,
+    is_final_sell: bool,
+    settings: &Settings,
+) -> Result<PaperFill, String> {
+    let quoted = match curve {
+        Some(c) if c.virtual_token_reserves > 0 => {
+            let vsol = c.virtual_sol_reserves as u128;
+            let vtok = c.virtual_token_reserves as u128;
+            let tok = token_amount as u128;
+            (vsol * tok / (vtok + tok)) as u64
+        }
+        _ => spot_lamports(token_amount, spot_price, decimals),
+    };
+    let curve_lamports = quoted.saturating_sub(bps(quoted, settings.paper_latency_slippage_bps));
+    let pump_fee_lamports = bps(curve_lamports, settings.paper_pump_fee_bps);
+    let expected = spot_lamports(token_amount, spot_price, decimals);
+    let min_sol_output = expected.saturating_sub(bps(expected, settings.slippage_bps));
+    if curve_lamports - pump_fee_lamports < min_sol_output {
+        return Err(format!(
+            "Paper sell would return {:.9} SOL, below min_sol_output {:.9} SOL (slippage_bps {})",
+            (curve_lamports - pump_fee_lamports) as f64 / LAMPORTS_PER_SOL,
+            min_sol_output as f64 / LAMPORTS_PER_SOL,
+            settings.slippage_bps
+        ));
+    }
+    let dev_fee_lamports = if settings.dev_fee_enabled { crate::dev_fee::calculate_dev_fee(curve_lamports) } else { 0 };
+    let network_fee_lamports = estimated_network_fee_lamports(Side::Sell, settings);
+    let rent_lamports = if is_final_sell { ATA_RENT_LAMPORTS } else { 0 };
+    let net = curve_lamports as i64 - (pump_fee_lamports + dev_fee_lamports + network_fee_lamports) as i64 + rent_lamports as i64;
+    Ok(PaperFill {
+        tokens: token_amount,
+        curve_lamports,
+        pump_fee_lamports,
+        dev_fee_lamports,
+        network_fee_lamports,
+        rent_lamports,
+        net_lamports: net,
+    })
+}
+
+/// Lamports a sell of `sell_tokens` returns, pump.fun fee deducted, when it
+/// lands right after a buy of `bought_tokens` on `curve` (a same-slot sell).
+pub fn sell_after_buy_lamports(curve: &BondingCurveState, bought_tokens: u64, sell_tokens: u64, settings: &Settings) -> Option<u64> {
+    if curve.virtual_token_reserves <= bought_tokens {
+        return None;
+    }
+    let vsol = curve.virtual_sol_reserves as u128;
+    let vtok = curve.virtual_token_reserves as u128;
+    let bought = bought_tokens as u128;
+    let paid = (vsol * bought).div_ceil(vtok - bought);
+    let (vsol, vtok, sold) = (vsol + paid, vtok - bought, sell_tokens as u128);
+    let out = (vsol * sold / (vtok + sold)) as u64;
+    Some(out - bps(out, settings.paper_pump_fee_bps))
+}
+
+/// A virtual SOL balance with its open positions and fee totals.
+#[derive(Debug, Default)]
+pub struct PaperLedger {
+    starting_lamports: i64,
+    balance_lamports: i64,
+    /// Cost basis (lamports) and tokens held of each open position
+    open: HashMap<String, (i64, u64)>,
+    pump_fees: u64,
+    dev_fees: u64,
+    network_fees: u64,
+    buys: u64,
+    sells: u64,
+    rejected_buys: u64,
+}
+
+impl PaperLedger {
+    pub fn new(starting_balance_sol: f64) -> PaperLedger {
+        let start = (starting_balance_sol * LAMPORTS_PER_SOL) as i64;
+        PaperLedger { starting_lamports: start, balance_lamports: start, ..Default::default() }
+    }
+
+    /// Settle a buy fill, refusing it when the balance cannot cover it.
+    pub fn buy(&mut self, mint: &str, fill: Result<PaperFill, String>) -> Result<PaperFill, String> {
+        let fill = match fill {
+            Ok(fill) => fill,
+            Err(e) => {
+                self.rejected_buys += 1;
+                return Err(e);
+            }
+        };
+        if self.balance_lamports + fill.net_lamports < 0 {
+            self.rejected_buys += 1;
+            return Err(format!(
+                "Insufficient paper balance for {}: buy needs {:.9} SOL, wallet has {:.9} SOL",
+                mint,
+                -fill.net_sol(),
+                self.balance_lamports as f64 / LAMPORTS_PER_SOL
+            ));
+        }
+        self.balance_lamports += fill.net_lamports;
+        let position = self.open.entry(mint.to_string()).or_default();
+        position.0 -= fill.net_lamports;
+        position.1 += fill.tokens;
+        self.pump_fees += fill.pump_fee_lamports;
+        self.dev_fees += fill.dev_fee_lamports;
+        self.network_fees += fill.network_fee_lamports;
+        self.buys += 1;
+        Ok(fill)
+    }
+
+    pub fn sell(&mut self, mint: &str, fill: &PaperFill, is_final_sell: bool) {
+        self.balance_lamports += fill.net_lamports;
+        if is_final_sell {
+            self.open.remove(mint);
+        } else if let Some((cost, tokens)) = self.open.get_mut(mint) {
+            // Release the sold share of the cost basis
+            let sold = fill.tokens.min(*tokens);
+            if *tokens > 0 {
+                *cost -= (*cost as i128 * sold as i128 / *tokens as i128) as i64;
+            }
+            *tokens -= sold;
+        }
+        self.pump_fees += fill.pump_fee_lamports;
+        self.dev_fees += fill.dev_fee_lamports;
+        self.network_fees += fill.network_fee_lamports;
+        self.sells += 1;
+    }
+
+    pub fn balance_sol(&self) -> f64 {
+        self.balance_lamports as f64 / LAMPORTS_PER_SOL
+    }
+
+    pub fn status(&self) -> PaperStatus {
+        let sol = |lamports: i64| lamports as f64 / LAMPORTS_PER_SOL;
+        let open_cost: i64 = self.open.values().map(|(cost, _)| cost).sum();
+        PaperStatus {
+            starting_balance_sol: sol(self.starting_lamports),
+            balance_sol: sol(self.balance_lamports),
+            open_positions_cost_sol: sol(open_cost),
+            realized_pnl_sol: sol(self.balance_lamports + open_cost - self.starting_lamports),
+            pump_fees_sol: sol(self.pump_fees as i64),
+            dev_fees_sol: sol(self.dev_fees as i64),
+            network_fees_sol: sol(self.network_fees as i64),
+            buys: self.buys,
+            sells: self.sells,
+            rejected_buys: self.rejected_buys,
+        }
+    }
+}
+
+/// The dry-run wallet, created on first use.
+static LEDGER: Lazy<Mutex<Option<PaperLedger>>> = Lazy::new(|| Mutex::new(None));
+
+/// Fill a dry-run buy against the paper wallet, or refuse it.
+pub async fn buy(
+    mint: &str,
+    sol_amount: f64,
+    token_amount: u64,
+    curve: Option<&BondingCurveState>,
+    spot_price: f64,
+    decimals: u8,
+    settings: &Settings,
+) -> Result<PaperFill, String> {
+    let mut guard = LEDGER.lock().await;
+    let ledger = guard.get_or_insert_with(|| PaperLedger::new(settings.paper_starting_balance_sol));
+    let fill = ledger.buy(mint, buy_fill(sol_amount, token_amount, curve, spot_price, decimals, settings))?;
+    log::info!(
+        "Paper buy {}: {} base units for {:.9} SOL (fees {:.9}), balance {:.9} SOL",
+        mint,
+        fill.tokens,
+        -fill.net_sol(),
+        (fill.pump_fee_lamports + fill.dev_fee_lamports + fill.network_fee_lamports) as f64 / LAMPORTS_PER_SOL,
+        ledger.balance_sol()
+    );
+    Ok(fill)
+}
+
+/// Fill a dry-run sell against the paper wallet.
+pub async fn sell(
+    mint: &str,
+    token_amount: u64,
+    curve: Option<&BondingCurveState>,
+    spot_price: f64,
+    decimals: u8,
+    is_final_sell: bool,
+    settings: &Settings,
+) -> Result<PaperFill, String> {
+    let fill = sell_fill(token_amount, curve, spot_price, decimals, is_final_sell, settings)?;
+    let mut guard = LEDGER.lock().await;
+    let ledger = guard.get_or_insert_with(|| PaperLedger::new(settings.paper_starting_balance_sol));
+    ledger.sell(mint, &fill, is_final_sell);
+    log::info!(
+        "Paper sell {}: {} base units for {:.9} SOL net, balance {:.9} SOL",
+        mint,
+        fill.tokens,
+        fill.net_sol(),
+        ledger.balance_sol()
+    );
+    Ok(fill)
+}
+
+/// Paper wallet summary, for `/api/stats` in dry-run.
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct PaperStatus {
+    pub starting_balance_sol: f64,
+    pub balance_sol: f64,
+    /// SOL spent on positions still open
+    pub open_positions_cost_sol: f64,
+    /// Realized PnL: balance plus open cost minus the starting balance
+    pub realized_pnl_sol: f64,
+    pub pump_fees_sol: f64,
+    pub dev_fees_sol: f64,
+    pub network_fees_sol: f64,
+    pub buys: u64,
+    pub sells: u64,
+    pub rejected_buys: u64,
+}
+
+/// Close the paper wallet when the engine stops; the next one starts from
+/// `paper_starting_balance_sol`.
+pub(crate) async fn reset() {
+    *LEDGER.lock().await = None;
+}
+
+pub async fn status(settings: &Settings) -> PaperStatus {
+    LEDGER
+        .lock()
+        .await
+        .get_or_insert_with(|| PaperLedger::new(settings.paper_starting_balance_sol))
+        .status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh_curve() -> BondingCurveState {
+        BondingCurveState {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: None,
+            is_mayhem_mode: false,
+        }
+    }
+
+    #[test]
+    fn curve_fills_charge_fees_rent_and_price_impact() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.dev_fee_enabled = true;
+        settings.slippage_bps = 1_000;
+        let curve = fresh_curve();
+        let spot = curve.spot_price_sol_per_token().unwrap();
+        let tokens = ((1.0 / spot) * 1e6) as u64;
+
+        let buy = buy_fill(1.0, tokens, Some(&curve), spot, 6, &settings).unwrap();
+        // Price impact and latency make the curve leg dearer than the spot quote
+        assert!(buy.curve_lamports > 1_000_000_000);
+        assert_eq!(buy.pump_fee_lamports, bps(buy.curve_lamports, settings.paper_pump_fee_bps));
+        assert_eq!(buy.dev_fee_lamports, 10_000_000);
+        assert_eq!(buy.rent_lamports, ATA_RENT_LAMPORTS);
+        assert!(buy.net_lamports < -1_040_000_000);
+
+        // Selling straight back into the moved curve loses the fees twice
+        let moved = BondingCurveState {
+            virtual_sol_reserves: curve.virtual_sol_reserves + buy.curve_lamports,
+            virtual_token_reserves: curve.virtual_token_reserves - tokens,
+            ..curve.clone()
+        };
+        let sell = sell_fill(tokens, Some(&moved), moved.spot_price_sol_per_token().unwrap(), 6, true, &settings).unwrap();
+        assert_eq!(sell.rent_lamports, ATA_RENT_LAMPORTS);
+        assert!(sell.net_lamports + buy.net_lamports < 0);
+
+        settings.slippage_bps = 10;
+        assert!(buy_fill(1.0, tokens, Some(&curve), spot, 6, &settings).is_err());
+    }
+
+    #[test]
+    fn same_slot_sell_is_quoted_on_the_curve_after_the_buy() {
+        let settings = Settings::from_file("config.example.toml").unwrap();
+        let curve = fresh_curve();
+        let tokens = 30_000_000_000_000;
+        let paid = buy_fill(1.0, tokens, Some(&curve), 0.0, 6, &settings).unwrap().curve_lamports;
+        let all = sell_after_buy_lamports(&curve, tokens, tokens, &settings).unwrap();
+        let half = sell_after_buy_lamports(&curve, tokens, tokens / 2, &settings).unwrap();
+        // Selling everything back returns the curve leg minus the pump.fun fee
+        assert!(all < paid && all > paid - 2 * bps(paid, settings.paper_pump_fee_bps));
+        // The first half sells at a better average price than the second
+        assert!(half > all / 2);
+        assert!(sell_after_buy_lamports(&curve, curve.virtual_token_reserves, 1, &settings).is_none());
+    }
+}
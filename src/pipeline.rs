@@ -0,0 +1,1379 @@
+//! Detection pipeline: every feed message (Solana logs, PumpPortal, fallback
+//! poller, replay) goes through [`process_message`], which dedups it, records
+//! the detected coin and, while the bot is running, buys and opens a position.
+use crate::{
+    api, buyer,
+    dedup::{self, DetectionSource, Sighting},
+    hot_cache, missed::{self, SkipReason},
+    models::PriceCache,
+    position::{BeginRefused, PositionRegistry, PositionState},
+    rpc,
+    settings::Settings,
+    shadow,
+    state::BuyRecord,
+    ws::WsRequest,
+    ws_supervisor::select_healthy_wss,
+    bot_control, TOTAL_DETECTED_COINS,
+};
+use chrono::Utc;
+use log::{debug, error, info, warn};
+use lru::LruCache;
+use mpl_token_metadata::accounts::Metadata as OnchainMetadataRaw;
+use once_cell::sync::Lazy;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{sync::Arc, time::Instant};
+use tokio::sync::{mpsc, Mutex};
+
+// Debounce for repetitive "max held coins" logs so we don't spam the logs.
+static LAST_MAX_HELD_LOG: Lazy<tokio::sync::Mutex<Option<Instant>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(None));
+const MAX_HELD_LOG_DEBOUNCE_SECS: u64 = 60;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn process_message(
+    text: &str,
+    seen: &Arc<Mutex<LruCache<String, ()>>>,
+    positions: &PositionRegistry,
+    rpc_client: &Arc<RpcClient>,
+    is_real: bool,
+    keypair: Option<&Keypair>,
+    simulate_keypair: Option<&Keypair>,
+    price_cache: &Arc<Mutex<PriceCache>>,
+    settings: &Arc<Settings>,
+    ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    next_wss_sender: Arc<AtomicUsize>,
+    detected_coins: Arc<tokio::sync::Mutex<Vec<api::DetectedCoin>>>,
+    trades_list: Arc<tokio::sync::Mutex<Vec<api::TradeRecord>>>,
+    ws_tx: tokio::sync::broadcast::Sender<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // NOTE: don't short-circuit all incoming messages when max held coins is
+    // reached — that would also skip websocket account notifications which we
+    // rely on to update cached prices. Only skip handling of new token
+    // detection (InitializeMint2) when at capacity. We'll perform a debounced
+    // debug log where appropriate.
+    let value: serde_json::Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(
+                "Failed to parse incoming websocket message as JSON: {}. message (truncated)={}",
+                e,
+                text.chars().take(200).collect::<String>()
+            );
+            return Err(Box::new(e));
+        }
+    };
+    if let Some(params) = value
+        .get("params")
+        .and_then(|p| p.get("result"))
+        .and_then(|r| r.get("value"))
+    {
+        // Deduplicate by signature early to avoid double-processing if the same
+        // event is received from both PumpPortal and Solana logs.
+        if let Some(signature) = params.get("signature").and_then(|s| s.as_str()) {
+            if !signature.is_empty() && signature != "0" && seen.lock().await.put(signature.to_string(), ()).is_some() {
+                return Ok(());
+            }
+        }
+
+        // If this message is a normalized PumpPortal payload (or a create the
+        // fallback poller already decoded), handle it using a fast path that
+        // avoids RPC `getTransaction` when the mint/creator/curve and optional
+        // metadata are provided.
+        if let Some(pp) = params.get("pumpportal").or_else(|| params.get("decoded")) {
+            let source = match params.get("source").and_then(|s| s.as_str()) {
+                Some("poll") => DetectionSource::Poll,
+                _ => DetectionSource::PumpPortal,
+            };
+            // Extract fields
+            let signature = params.get("signature").and_then(|s| s.as_str()).unwrap_or("").to_string();
+            let mint = pp.get("mint").and_then(|m| m.as_str()).map(|s| s.to_string());
+            let creator = pp.get("creator").and_then(|c| c.as_str()).map(|s| s.to_string());
+            let curve = pp.get("bonding_curve").and_then(|c| c.as_str()).map(|s| s.to_string());
+            let metadata_value = pp.get("metadata").cloned();
+            let bonding_state = pp.get("bonding_state").cloned();
+
+            if let Some(mint) = mint {
+                // We require creator and curve for a confident detection; otherwise fall back to RPC flow
+                let creator = creator.unwrap_or_else(|| "".to_string());
+                let curve = curve.unwrap_or_else(|| "".to_string());
+                let detect_time = crate::recorder::now();
+                // PumpPortal events may lack a signature: dedup across feeds by mint
+                if !first_sighting(&mint, source, detect_time, &detected_coins).await {
+                    return Ok(());
+                }
+                if let Err(e) = handle_new_token_from_pumpportal(
+                    &signature,
+                    source,
+                    &mint,
+                    &creator,
+                    &curve,
+                    metadata_value,
+                    bonding_state,
+                    positions,
+                    rpc_client,
+                    is_real,
+                    keypair,
+                    simulate_keypair,
+                    price_cache,
+                    settings,
+                    ws_control_senders.clone(),
+                    next_wss_sender.clone(),
+                    detect_time,
+                    detected_coins.clone(),
+                    trades_list.clone(),
+                    ws_tx.clone(),
+                )
+                .await
+                {
+                    error!("handle_new_token_from_pumpportal failed for {}: {}", mint, e);
+                    return Err(e);
+                }
+                // Skip the rest of standard processing for this message
+                return Ok(());
+            }
+        }
+        let logs_opt = params.get("logs").and_then(|l| l.as_array());
+        let sig_opt = params.get("signature").and_then(|s| s.as_str());
+        if logs_opt.is_none() {
+            debug!("Incoming message missing logs field: {:?}", params);
+        }
+        if sig_opt.is_none() {
+            debug!("Incoming message missing signature field: {:?}", params);
+        }
+
+        if let (Some(logs), Some(signature)) = (logs_opt, sig_opt) {
+            // Only process if logs mention the pump.fun program id
+            // This ensures we only process transactions that actually interact with pump.fun
+            let pump_prog_id = &settings.pump_fun_program;
+            let logs_mention_pump = logs.iter().any(|log| {
+                log.as_str()
+                    .map(|s| s.contains(pump_prog_id))
+                    .unwrap_or(false)
+            });
+            
+            if logs_mention_pump 
+            {
+                // If we're already at max holdings, skip detection work
+                // but do not block processing of other websocket messages
+                // (like account notifications). Debounce the debug log
+                // so it doesn't spam the logs. When skipped coins are
+                // tracked, the mint is still resolved so handle_new_token
+                // can report the skip.
+                let total_active = positions.active_count().await;
+                if total_active >= settings.max_holded_coins && settings.missed_window_secs == 0 {
+                    let mut last_lock = LAST_MAX_HELD_LOG.lock().await;
+                    let now = Instant::now();
+                    let should_log = match *last_lock {
+                        Some(ts) => now.duration_since(ts).as_secs() > MAX_HELD_LOG_DEBOUNCE_SECS,
+                        None => true,
+                    };
+                    if should_log {
+                        *last_lock = Some(now);
+                        debug!(
+                            "Max held coins reached ({} active positions >= {}); skipping incoming message processing",
+                            total_active,
+                            settings.max_holded_coins
+                        );
+                    }
+                    // Do not attempt handle_new_token when at capacity.
+                    return Ok(());
+                }
+
+                let detect_time = crate::recorder::now();
+                // Validate signature before attempting expensive RPC fetch to skip obvious invalid signatures.
+                let signature_valid = solana_sdk::signature::Signature::from_str(signature).is_ok();
+                if !signature_valid {
+                    debug!("Skipping invalid signature for pump.fun notification: {}", signature);
+                    return Ok(());
+                }
+                if let Err(e) = handle_new_token(
+                    signature,
+                    DetectionSource::Logs,
+                    positions,
+                    rpc_client,
+                    is_real,
+                    keypair,
+                    simulate_keypair,
+                    price_cache,
+                    settings,
+                    ws_control_senders.clone(),
+                    next_wss_sender.clone(),
+                    detect_time,
+                    detected_coins.clone(),
+                    trades_list.clone(),
+                    ws_tx.clone(),
+                )
+                .await
+                {
+                    error!("handle_new_token failed for {}: {}", signature, e);
+                    return Err(e);
+                }
+            }
+        }
+    } else {
+        debug!("Websocket message missing params/result/value: {:?}", value);
+    }
+    Ok(())
+}
+
+/// Close reason of a position whose Jito same-slot sell took every token.
+const SOLD_IN_SAME_SLOT: &str = "sold in the same slot";
+
+/// Record a landed buy in the trade history, followed by the sell that a
+/// Jito bundle landed with it. The buy shows every token bought at its full
+/// cost; the sell carries its share of that cost.
+#[allow(clippy::too_many_arguments)]
+async fn record_buy_trades(
+    mint: &str,
+    holding: &crate::models::Holding,
+    same_slot_sell: Option<&buyer::SameSlotSell>,
+    offchain_meta: Option<&crate::models::OffchainTokenMetadata>,
+    settings: &Settings,
+    is_real: bool,
+    trades_list: &Arc<tokio::sync::Mutex<Vec<api::TradeRecord>>>,
+    ws_tx: &tokio::sync::broadcast::Sender<String>,
+) {
+    let token_divisor = 10f64.powi(holding.decimals as i32);
+    let sold_tokens = same_slot_sell.map(|sell| sell.tokens).unwrap_or(0);
+    let buy_cost_sol = holding.buy_cost_sol.map(|c| c + same_slot_sell.map(|sell| sell.cost_sol).unwrap_or(0.0));
+    let trade = |trade_type: &str| api::TradeRecord {
+        mint: mint.to_string(),
+        symbol: offchain_meta.and_then(|o| o.symbol.clone()),
+        name: offchain_meta.and_then(|o| o.name.clone()),
+        image: offchain_meta.and_then(|o| o.image.clone()),
+        trade_type: trade_type.to_string(),
+        timestamp: holding.buy_time.to_rfc3339(),
+        tx_signature: None,
+        amount_sol: buy_cost_sol.unwrap_or(settings.buy_amount),
+        amount_tokens: (holding.amount + sold_tokens) as f64 / token_divisor,
+        price_per_token: holding.buy_price,
+        profit_loss: None,
+        profit_loss_percent: None,
+        reason: None,
+        decimals: holding.decimals,
+        actual_sol_change: buy_cost_sol.map(|c| -c),
+        tx_fee_sol: None,
+        simulated: !is_real,
+    };
+    let mut records = vec![trade("buy")];
+    if let Some(sell) = same_slot_sell {
+        let tokens = sell.tokens as f64 / token_divisor;
+        let profit_loss = sell.proceeds_sol - sell.cost_sol;
+        records.push(api::TradeRecord {
+            tx_signature: sell.signature.clone(),
+            amount_sol: sell.proceeds_sol,
+            amount_tokens: tokens,
+            price_per_token: sell.proceeds_sol / tokens,
+            profit_loss: Some(profit_loss),
+            profit_loss_percent: (sell.cost_sol > 0.0).then(|| profit_loss / sell.cost_sol * 100.0),
+            reason: Some("JITO_SAME_SLOT".to_string()),
+            actual_sol_change: Some(sell.proceeds_sol),
+            ..trade("sell")
+        });
+    }
+    let mut trades = trades_list.lock().await;
+    for record in records {
+        trades.insert(0, record);
+        // Broadcast new trade over WebSocket for real-time frontend updates
+        if let Ok(json) = serde_json::to_value(&trades[0]) {
+            let _ = ws_tx.send(serde_json::json!({"type": "new-trade", "trade": json}).to_string());
+        }
+    }
+    if trades.len() > 200 { trades.truncate(200); }
+}
+
+/// Report `mint` from `source` to the cross-feed dedup. Returns true when this
+/// is the first feed to see it; otherwise the existing detected-coin entry just
+/// gets the later feed's first-seen time.
+async fn first_sighting(
+    mint: &str,
+    source: DetectionSource,
+    at: chrono::DateTime<Utc>,
+    detected_coins: &Arc<tokio::sync::Mutex<Vec<api::DetectedCoin>>>,
+) -> bool {
+    match dedup::observe(mint, source, at).await {
+        Sighting::First => {
+            crate::recorder::record_detection(mint, at);
+            true
+        }
+        Sighting::Duplicate { first, behind_ms } => {
+            debug!("{} reported {} {} ms after {}; already handled", source, mint, behind_ms, first);
+            let first_seen = dedup::first_seen(mint).await;
+            if let Some(coin) = detected_coins.lock().await.iter_mut().find(|c| c.mint == mint) {
+                coin.first_seen = first_seen;
+            }
+            false
+        }
+        Sighting::Repeat => false,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_new_token(
+    signature: &str,
+    source: DetectionSource,
+    positions: &PositionRegistry,
+    rpc_client: &Arc<RpcClient>,
+    is_real: bool,
+    keypair: Option<&Keypair>,
+    simulate_keypair: Option<&Keypair>,
+    price_cache: &Arc<Mutex<PriceCache>>,
+    settings: &Arc<Settings>,
+    ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    _next_wss_sender: Arc<AtomicUsize>,
+    detect_time: chrono::DateTime<Utc>,
+    detected_coins: Arc<tokio::sync::Mutex<Vec<api::DetectedCoin>>>,
+    trades_list: Arc<tokio::sync::Mutex<Vec<api::TradeRecord>>>,
+    ws_tx: tokio::sync::broadcast::Sender<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (creator, mint, curve_pda, holder_addr, is_initialization) =
+        rpc::fetch_transaction_details(signature, rpc_client, settings).await?;
+
+    if !is_initialization {
+        // Not a pump.fun create instruction; skip detection.
+        debug!("Transaction {} is not a pump.fun CREATE instruction; skipping detection", signature);
+        return Ok(());
+    }
+    if !first_sighting(&mint, source, detect_time, &detected_coins).await {
+        return Ok(());
+    }
+    // At capacity there is no buy to prepare: track the skip, priced by the
+    // first poll, without fetching metadata or subscribing.
+    let active = positions.active_count().await;
+    if active >= settings.max_holded_coins {
+        let refused = BeginRefused::MaxHeld { active, max: settings.max_holded_coins };
+        debug!("Skipping {}: {}", mint, refused);
+        missed::track(&mint, SkipReason::MaxHoldedCoins, refused.to_string(), None, settings).await;
+        return Ok(());
+    }
+    let (onchain_meta, offchain_meta, onchain_raw) =
+        rpc::fetch_token_metadata(&mint, rpc_client, settings).await?;
+    // Attempt to fetch the bonding curve creator so we can validate pump.fun token
+    // and use the creator for additional verification, but do not require it for detection
+    // because some RPCs may not have the PDA available immediately.
+    let bonding_creator_opt = rpc::fetch_bonding_curve_creator(&mint, rpc_client, settings).await.ok().flatten();
+    if bonding_creator_opt.is_none() {
+        // Not fatal: the bonding curve PDA may not be indexed yet by the RPC node.
+        // Log at debug and continue detection using transaction-parsed creator and curve.
+        debug!("Bonding curve creator not found for mint {} (tx sig={}) — continuing detection using transaction parsed values", mint, signature);
+    }
+
+    // We treat lack of on-chain metadata as expected in some cases — continue
+    // detection even when `onchain_meta` is None. Use offchain meta or onchain
+    // raw to fill in display fields when available.
+    // NOTE: we want to continue regardless of name and URI being present.
+    if onchain_meta.is_some() || offchain_meta.is_some() || onchain_raw.is_some() {
+        // Prepare display fields using available on-chain or off-chain metadata
+        let token_name = offchain_meta
+            .as_ref()
+            .and_then(|off| off.name.clone())
+            .or_else(|| onchain_meta.as_ref().map(|m| m.name.trim_end_matches('\u{0}').to_string()))
+            .unwrap_or_else(|| "Unknown".to_string());
+        let token_symbol = offchain_meta
+            .as_ref()
+            .and_then(|off| off.symbol.clone())
+            .or_else(|| onchain_meta.as_ref().map(|m| m.symbol.trim_end_matches('\u{0}').to_string()));
+        let metadata_uri_opt = if let Some(m) = onchain_meta.as_ref() {
+            let uri = m.uri.trim_end_matches('\u{0}').to_string();
+            if uri.is_empty() { None } else { Some(uri) }
+        } else if let Some(off) = offchain_meta.as_ref() { off.image.clone() } else { None };
+
+        // Log token detection using the available fields
+        info!(
+            "New pump.fun token detected: mint={} signature={} creator={} curve={} holder={} URI={}",
+            mint,
+            signature,
+            creator,
+            curve_pda,
+            holder_addr,
+            metadata_uri_opt.clone().unwrap_or_else(|| "<none>".to_string())
+        );
+        // (already logged above using `metadata_uri_opt`)
+
+        // Log new token detection to API
+        bot_log!(
+            "info",
+            format!("New token detected: {}", token_name),
+            format!("Mint: {}, Creator: {}", mint, creator)
+        );
+
+        // Add to detected coins list. Use offchain metadata if present, else fallback to on-chain
+        // parsed metadata; otherwise mark name as Unknown and symbol None.
+        {
+            let mut coins = detected_coins.lock().await;
+            if let Some(existing) = coins.iter_mut().find(|c| c.mint == mint) {
+                existing.name = Some(token_name.clone());
+                existing.symbol = token_symbol.clone();
+                existing.image = offchain_meta.as_ref().and_then(|o| o.image.clone());
+                existing.creator = creator.clone();
+                existing.bonding_curve = curve_pda.clone();
+                existing.detected_at = detect_time.to_rfc3339();
+                existing.metadata_uri = metadata_uri_opt.clone();
+                existing.buy_price = None;
+                existing.status = "detected".to_string();
+            } else {
+                let new_coin = api::DetectedCoin {
+                    mint: mint.clone(),
+                    name: Some(token_name.clone()),
+                    symbol: token_symbol.clone(),
+                    image: offchain_meta.as_ref().and_then(|o| o.image.clone()),
+                    creator: creator.clone(),
+                    bonding_curve: curve_pda.clone(),
+                    detected_at: detect_time.to_rfc3339(),
+                    metadata_uri: metadata_uri_opt.clone(),
+                    buy_price: None,
+                    status: "detected".to_string(),
+                    source: source.to_string(),
+                    first_seen: dedup::first_seen(&mint).await,
+                };
+                coins.insert(0, new_coin.clone());
+                TOTAL_DETECTED_COINS.fetch_add(1, Ordering::Relaxed);
+                
+                // Broadcast to WebSocket clients
+                let ws_message = serde_json::json!({
+                    "type": "detected-coin",
+                    "coin": new_coin,
+                    "total_detected_coins": TOTAL_DETECTED_COINS.load(Ordering::Relaxed)
+                });
+                let _ = ws_tx.send(ws_message.to_string());
+            }
+            // Keep only last `detected_coins_max` detected coins (configurable)
+            if coins.len() > settings.detected_coins_max {
+                coins.truncate(settings.detected_coins_max);
+            }
+        }
+
+        if let Some(off) = &offchain_meta {
+            info!(
+                "Off-chain metadata for {}: name={:?}, symbol={:?}, image={:?}",
+                mint, off.name, off.symbol, off.image
+            );
+        }
+        if let Some(m) = onchain_meta.as_ref() {
+            if !m.uri.trim_end_matches('\u{0}').is_empty() && m.seller_fee_basis_points < 500 {
+            // Try to get a fast WSS-provided price first depending on price_source.
+            // REFACTORED BUY LOGIC:
+            // 1. Attempt WSS subscription if enabled.
+            // 2. Fall back to RPC if WSS fails or is disabled (to ensure 'dry run' works).
+            // 3. Buy if price available.
+            
+            // Track active subscription for cleanup: (ws_index, sub_id, sender_clone)
+            let mut active_sub_details: Option<(usize, u64, tokio::sync::mpsc::Sender<WsRequest>)> = None;
+            let price_source = settings.price_source.clone();
+
+            // 1. Attempt WSS Subscription
+            if price_source != "rpc" && !ws_control_senders.is_empty() {
+                if let Some(idx) = select_healthy_wss(&ws_control_senders, settings).await {
+                    let sender = ws_control_senders[idx].clone();
+                    let (resp_tx, resp_rx) = tokio::sync::oneshot::channel::<Result<u64, String>>();
+                    // Use error handling for pubkey parsing (though likely valid if we got here)
+                    if let (Ok(pump_prog_key), Ok(mint_pk)) = (
+                        solana_sdk::pubkey::Pubkey::from_str(&settings.pump_fun_program),
+                        solana_sdk::pubkey::Pubkey::from_str(&mint)
+                    ) {
+                        let (curve_pda, _) = solana_sdk::pubkey::Pubkey::find_program_address(
+                            &[b"bonding-curve", mint_pk.as_ref()],
+                            &pump_prog_key,
+                        );
+
+                        let subscribe_req = WsRequest::Subscribe {
+                            account: curve_pda.to_string(),
+                            mint: mint.clone(),
+                            resp: resp_tx,
+                        };
+
+                        if let Err(e) = sender.send(subscribe_req).await {
+                             log::warn!("Failed to send subscribe request for {}: {}", mint, e);
+                        } else {
+                            // Use aggressive 5s timeout for fast failover
+                            match tokio::time::timeout(std::time::Duration::from_secs(5), resp_rx).await {
+                                 Ok(Ok(Ok(sub_id))) => {
+                                     debug!("Subscribed to {} on sub {}", mint, sub_id);
+                                     active_sub_details = Some((idx, sub_id, sender.clone()));
+                                 },
+                                 Ok(Ok(Err(err_msg))) => {
+                                     if err_msg.contains("max subscriptions") {
+                                         debug!("Subscribe rejected for {} (WSS idx={} full)", mint, idx);
+                                     } else if err_msg.contains("degraded") {
+                                         debug!("WSS idx={} degraded, skipped {}", idx, mint);
+                                     } else {
+                                         log::warn!("Subscribe request rejected for {}: {}", mint, err_msg);
+                                     }
+                                 },
+                                 Ok(Err(_)) => log::warn!("Subscribe channel closed for {} (WSS idx={})", mint, idx),
+                                 Err(_) => log::warn!("Subscribe timed out for {} (WSS idx={}, 5s)", mint, idx),
+                            }
+                        }
+                    }
+                } else {
+                     debug!("No healthy WSS available for {} (all degraded/full)", mint);
+                }
+            }
+
+            // 2. Fetch or Prime Price (Hybrid RPC + WSS wait)
+            let mut price_opt: Option<f64> = None;
+            
+            // Always try RPC prime first - it's fast enough and provides immediate fallback
+             match rpc::fetch_current_price(
+                &mint,
+                price_cache,
+                rpc_client,
+                settings,
+            ).await {
+                Ok(p) => {
+                    debug!("Primed price cache for {} via RPC: {:.18} SOL/token", mint, p);
+                    price_opt = Some(p);
+                }
+                Err(e) => {
+                     // Debug instead of warn because we might get it from WSS soon
+                     debug!("RPC prime failed for {}: {}.", mint, e);
+                }
+            }
+
+            // If we have a WSS subscription but no RPC price, wait for WSS update
+            if price_opt.is_none() && active_sub_details.is_some() {
+                 debug!("Waiting for WSS price update for {}...", mint);
+                 let start = std::time::Instant::now();
+                 while start.elapsed().as_secs() < settings.wss_subscribe_timeout_secs {
+                     if let Some((_, price)) = price_cache.lock().await.get(&mint).cloned() {
+                         price_opt = Some(price);
+                         break;
+                     }
+                     tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                 }
+            }
+
+            // 3. Buy Token
+            let mut keep_sub = false;
+            
+            if let Some(price) = price_opt {
+                 // Refresh price cache so the buy quote uses the cached value
+                 // and skips the slow multi-commitment RPC re-fetch sequence.
+                 price_cache.lock().await.put(mint.clone(), (Instant::now(), price));
+                 let quote = buyer::quote(&mint, price_cache, rpc_client, settings).await;
+                 if let Ok(quote) = &quote {
+                     shadow::on_quote(&mint, quote);
+                 }
+
+                 // Reserve a Pending position — this is what enforces max_holded_coins
+                 // and rejects duplicate buys of the same mint.
+                 let skip_buy = {
+                     // Check if bot is running before attempting to buy
+                     if let Some(control) = bot_control() {
+                         let rs = control.running_state.lock().await;
+                         if !matches!(*rs, api::BotRunningState::Running) {
+                             debug!("Bot not running; skipping buy for {}", mint);
+                             true
+                         } else {
+                             false
+                         }
+                     } else {
+                         true
+                     }
+                 } || match positions.begin(&mint, settings.max_holded_coins).await {
+                     Ok(()) => false,
+                     Err(refused) => {
+                         info!("Skipping buy for {}: {}", mint, refused);
+                         if let BeginRefused::MaxHeld { .. } = refused {
+                             missed::track(&mint, SkipReason::MaxHoldedCoins, refused.to_string(), Some(price), settings).await;
+                         }
+                         true
+                     }
+                 };
+
+                 if skip_buy {
+                      // fall through to subscription cleanup
+                 } else {
+                      let _ = positions.transition(&mint, PositionState::Buying, None).await;
+                      let bought = match quote {
+                          Ok(quote) => buyer::buy_token(
+                              &mint,
+                              settings.buy_amount,
+                              quote,
+                              is_real,
+                              keypair,
+                              simulate_keypair,
+                              rpc_client,
+                              settings,
+                          ).await,
+                          Err(e) => Err(e),
+                      };
+                      match bought {
+                          Ok(buyer::Bought { mut holding, same_slot_sell }) => {
+                               // --- SUCCESSFUL BUY LOGIC ---
+                               holding.metadata = offchain_meta.clone();
+                               holding.onchain_raw = onchain_raw.clone();
+                               // Build compact onchain_struct
+                               let mut onchain_struct: Option<crate::models::OnchainFullMetadata> = None;
+                               if let Some(meta) = onchain_meta.as_ref() {
+                                    let name = meta.name.trim_end_matches('\u{0}').to_string();
+                                    let symbol = meta.symbol.trim_end_matches('\u{0}').to_string();
+                                    let uri = meta.uri.trim_end_matches('\u{0}').to_string();
+                                    onchain_struct = Some(crate::models::OnchainFullMetadata {
+                                        name: if name.is_empty() { None } else { Some(name) },
+                                        symbol: if symbol.is_empty() { None } else { Some(symbol) },
+                                        uri: if uri.is_empty() { None } else { Some(uri) },
+                                        seller_fee_basis_points: Some(meta.seller_fee_basis_points),
+                                        raw: onchain_raw.clone(),
+                                    });
+                               } else if let Some(raw_bytes) = onchain_raw.as_ref() {
+                                    if let Ok(parsed) = OnchainMetadataRaw::safe_deserialize(raw_bytes) {
+                                         let name = parsed.name.trim_end_matches('\u{0}').to_string();
+                                         let symbol = parsed.symbol.trim_end_matches('\u{0}').to_string();
+                                         let uri = parsed.uri.trim_end_matches('\u{0}').to_string();
+                                         onchain_struct = Some(crate::models::OnchainFullMetadata {
+                                            name: if name.is_empty() { None } else { Some(name) },
+                                            symbol: if symbol.is_empty() { None } else { Some(symbol) },
+                                            uri: if uri.is_empty() { None } else { Some(uri) },
+                                            seller_fee_basis_points: Some(parsed.seller_fee_basis_points),
+                                            raw: Some(raw_bytes.clone()),
+                                        });
+                                    }
+                               }
+                               holding.onchain = onchain_struct.clone();
+                               if let Some(off) = &holding.metadata {
+                                    info!("Persisting off-chain metadata for {} into holdings: name={:?}, symbol={:?}, image={:?}", mint, off.name, off.symbol, off.image);
+                               }
+                               
+                               let buy_record = BuyRecord {
+                                   mint: mint.clone(),
+                                   symbol: offchain_meta.as_ref().and_then(|o| o.symbol.clone()),
+                                   name: offchain_meta.as_ref().and_then(|o| o.name.clone()),
+                                   uri: offchain_meta.as_ref().and_then(|o| o.image.clone()).or_else(|| onchain_struct.as_ref().and_then(|on| on.uri.clone())),
+                                   image: offchain_meta.as_ref().and_then(|o| o.image.clone()),
+                                   creator: creator.clone(),
+                                   detect_time,
+                                   buy_time: holding.buy_time,
+                                   buy_amount_sol: settings.buy_amount,
+                                   buy_amount_tokens: holding.amount,
+                                   buy_price: holding.buy_price,
+                               };
+                               // Log successful buy to API
+                               bot_log!("info", format!("Successfully bought token {}", mint), format!("Amount: {} SOL, Price: {} SOL per token", settings.buy_amount, holding.buy_price));
+                               
+                               // Update detected coin status
+                               {
+                                   let mut coins = detected_coins.lock().await;
+                                   if let Some(coin) = coins.iter_mut().find(|c| c.mint == mint) {
+                                       coin.status = "bought".to_string();
+                                       coin.buy_price = Some(holding.buy_price);
+                                   }
+                               }
+
+                               // Add buy trade record (and the same-slot sell's)
+                               record_buy_trades(&mint, &holding, same_slot_sell.as_ref(), offchain_meta.as_ref(), settings, is_real, &trades_list, &ws_tx).await;
+
+                               let sold_out = holding.amount == 0;
+                               match positions.open(&mint, holding, buy_record).await {
+                                   Ok(_) if sold_out => {
+                                       let _ = positions.close(&mint, SOLD_IN_SAME_SLOT).await;
+                                   }
+                                   Ok(_) => keep_sub = true,
+                                   Err(e) => error!("Failed to open position for {}: {}", mint, e),
+                               }
+                          },
+                          Err(e) => {
+                               let _ = positions.fail(&mint, format!("buy failed: {}", e)).await;
+                               log::warn!("Failed to buy {}: {}", mint, e);
+                               bot_log!("warn", format!("Failed to buy token {}", mint), format!("{}", e));
+                               if let Some(rejected) = e.downcast_ref::<buyer::EntryRejected>() {
+                                   missed::track(&mint, SkipReason::EntryFilter(rejected.filter), rejected.reason.clone(), Some(price), settings).await;
+                               }
+
+                               // Record failed buy attempt so it appears in Trading History
+                               {
+                                   let mut trades = trades_list.lock().await;
+                                   trades.insert(0, api::TradeRecord {
+                                       mint: mint.clone(),
+                                       symbol: offchain_meta.as_ref().and_then(|o| o.symbol.clone()),
+                                       name: offchain_meta.as_ref().and_then(|o| o.name.clone()),
+                                       image: offchain_meta.as_ref().and_then(|o| o.image.clone()),
+                                       trade_type: "buy".to_string(),
+                                       timestamp: crate::recorder::now().to_rfc3339(),
+                                       tx_signature: None,
+                                       amount_sol: settings.buy_amount,
+                                       amount_tokens: 0.0,
+                                       price_per_token: 0.0,
+                                       profit_loss: None,
+                                       profit_loss_percent: None,
+                                       reason: Some(format!("FAILED: {}", e)),
+                                       decimals: settings.default_token_decimals,
+                                       actual_sol_change: None,
+                                       tx_fee_sol: None,
+                                       simulated: !is_real,
+                                   });
+                                   if trades.len() > 200 { trades.truncate(200); }
+                                   // Broadcast new trade over WebSocket for real-time frontend updates
+                                   if let Ok(json) = serde_json::to_value(&trades[0]) {
+                                       let _ = ws_tx.send(serde_json::json!({"type": "new-trade", "trade": json}).to_string());
+                                   }
+                               }
+
+                               // Update detected coin status to buy_failed
+                               {
+                                   let mut coins = detected_coins.lock().await;
+                                   if let Some(coin) = coins.iter_mut().find(|c| c.mint == mint) {
+                                       coin.status = "buy_failed".to_string();
+                                   }
+                               }
+                          }
+                      }
+                 }
+            } else {
+                  if active_sub_details.is_some() {
+                       log::warn!("No price update received for {} (WSS timed out) and RPC failed. Skipping buy.", mint);
+                  } else {
+                       // dry run or RPC failed
+                       debug!("No price available for {}, skipping buy (RPC failed)", mint);
+                  }
+            }
+
+            // 4. Cleanup Subscription
+            if let Some((idx, sub_id, sender)) = active_sub_details {
+                if keep_sub {
+                    positions.set_subscription(&mint, Some((idx, sub_id))).await;
+                } else {
+                    let (u_tx, u_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+                    let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
+                    let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
+                }
+            }
+        } else {
+            // No on-chain metadata to validate or price-seller checks; skip buy logic
+            debug!("No on-chain metadata to validate buy for mint {} (sig={}) — detected only", mint, signature);
+        }
+            // We do not fall back to RPC here; WSS-only mode requires a streamed
+            // price update from the bonding_curve PDA. If no WSS price was used,
+            // skip buying.
+        }
+    }
+    Ok(())
+}
+
+/// Process a detected token using provided details. If onchain/offchain metadata
+/// are None, this function will attempt RPC fetches as a fallback.
+#[allow(clippy::too_many_arguments)]
+async fn process_detected_token(
+    signature: &str,
+    source: DetectionSource,
+    mint: &str,
+    creator: &str,
+    curve_pda: &str,
+    holder_addr: &str,
+    detect_time: chrono::DateTime<Utc>,
+    rpc_client: &Arc<RpcClient>,
+    _is_real: bool,
+    _keypair: Option<&Keypair>,
+    _simulate_keypair: Option<&Keypair>,
+    _price_cache: &Arc<Mutex<PriceCache>>,
+    settings: &Arc<Settings>,
+    _ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    _next_wss_sender: Arc<AtomicUsize>,
+    detected_coins: Arc<tokio::sync::Mutex<Vec<api::DetectedCoin>>>,
+    _trades_list: Arc<tokio::sync::Mutex<Vec<api::TradeRecord>>>,
+    onchain_meta_opt: Option<mpl_token_metadata::accounts::Metadata>,
+    offchain_meta_opt: Option<crate::models::OffchainTokenMetadata>,
+    onchain_raw_opt: Option<Vec<u8>>,
+    ws_tx: tokio::sync::broadcast::Sender<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // -------------------------------------------------------------------------
+    // 1. EARLIEST POSSIBLE UI UPDATE (Before slow RPC calls)
+    // -------------------------------------------------------------------------
+    {
+        // Calculate placeholder or preliminary info
+        let pre_name = offchain_meta_opt.as_ref().and_then(|o| o.name.clone())
+            .or_else(|| onchain_meta_opt.as_ref().map(|m| m.name.trim_end_matches('\u{0}').to_string()))
+            .unwrap_or_else(|| "Searching metadata...".to_string());
+        let pre_symbol = offchain_meta_opt.as_ref().and_then(|o| o.symbol.clone())
+            .or_else(|| onchain_meta_opt.as_ref().map(|m| m.symbol.trim_end_matches('\u{0}').to_string()));
+        let pre_image = offchain_meta_opt.as_ref().and_then(|o| o.image.clone());
+
+        let mut coins = detected_coins.lock().await;
+        if coins.iter().find(|c| c.mint == mint).is_none() {
+            let new_coin = api::DetectedCoin {
+                mint: mint.to_string(),
+                name: Some(pre_name),
+                symbol: pre_symbol,
+                image: pre_image,
+                creator: creator.to_string(),
+                bonding_curve: curve_pda.to_string(),
+                detected_at: detect_time.to_rfc3339(),
+                metadata_uri: onchain_meta_opt.as_ref().map(|m| m.uri.trim_end_matches('\u{0}').to_string()),
+                buy_price: None,
+                status: "detected".to_string(),
+                source: source.to_string(),
+                first_seen: dedup::first_seen(mint).await,
+            };
+            coins.insert(0, new_coin.clone());
+            TOTAL_DETECTED_COINS.fetch_add(1, Ordering::Relaxed);
+            
+            // Broadcast to WebSocket clients
+            let ws_message = serde_json::json!({
+                "type": "detected-coin",
+                "coin": new_coin,
+                "total_detected_coins": TOTAL_DETECTED_COINS.load(Ordering::Relaxed)
+            });
+            let _ = ws_tx.send(ws_message.to_string());
+            
+            if coins.len() > settings.detected_coins_max {
+                coins.truncate(settings.detected_coins_max);
+            }
+        }
+    }
+
+    // Attempt to fetch bonding curve creator for additional verification
+    let bonding_creator_opt = rpc::fetch_bonding_curve_creator(mint, rpc_client, settings).await.ok().flatten();
+    if bonding_creator_opt.is_none() {
+        debug!("Bonding curve creator not found for mint {} (sig={}) — continuing detection using provided values", mint, signature);
+    }
+
+    // Use provided metadata if present, otherwise fallback to RPC-fetched values
+    let (onchain_meta, offchain_meta, _onchain_raw) = if onchain_meta_opt.is_some() || offchain_meta_opt.is_some() || onchain_raw_opt.is_some() {
+        (onchain_meta_opt, offchain_meta_opt, onchain_raw_opt)
+    } else {
+        rpc::fetch_token_metadata(mint, rpc_client, settings).await?
+    };
+
+    // If we have offchain metadata but no `image`, try to extract it from `extras` or by
+    // fetching a metadata URI if provided (PumpPortal commonly supplies `uri` in extras).
+    let mut offchain_meta = offchain_meta;
+    if let Some(mut off) = offchain_meta.take() {
+        if off.image.is_none() {
+            // Try common image keys in extras
+            if let Some(ref extras) = off.extras {
+                if let Some(img) = extract_first_string(extras, &["image", "image_url", "imageUri", "imageUrl"]) {
+                    off.image = Some(img);
+                }
+            }
+            // If still none, try to find a metadata URI and fetch it
+            if off.image.is_none() {
+                // Look for uri variants in extras
+                if let Some(ref extras) = off.extras {
+                    if let Some(uri) = extract_first_string(extras, &["uri", "metadataUri", "tokenUri", "uriStr"]) {
+                        // Display-only: skipped when the off-chain budget is under pressure
+                        if (uri.starts_with("http://") || uri.starts_with("https://"))
+                            && crate::rate_limit::allow_offchain("image_metadata", settings).await
+                        {
+                            match crate::recorder::http_get(&uri).await {
+                                Ok(body) => if let Ok(body_val) = serde_json::from_str::<serde_json::Value>(&body) {
+                                    if let Some(img2) = extract_first_string(&body_val, &["image", "image_url", "imageUri", "imageUrl"]) {
+                                        off.image = Some(img2);
+                                    } else {
+                                        debug!("Fetched metadata {} but no image field found for mint {}", uri, mint);
+                                    }
+                                } else { debug!("Failed to parse fetched metadata JSON {}", uri); },
+                                Err(e) => debug!("Failed to fetch metadata {}: {}", uri, e),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        // normalize back into option
+        offchain_meta = Some(off);
+    }
+
+    // Helper used above to extract common string fields from a JSON value
+    fn extract_first_string(v: &serde_json::Value, keys: &[&str]) -> Option<String> {
+        for key in keys {
+            if let Some(field) = v.get(*key) {
+                match field {
+                    serde_json::Value::String(s) => return Some(s.clone()),
+                    serde_json::Value::Object(map) => {
+                        if let Some(serde_json::Value::String(s2)) = map.get("en") { return Some(s2.clone()); }
+                        for (_k, val) in map.iter() {
+                            if let serde_json::Value::String(s3) = val { return Some(s3.clone()); }
+                        }
+                    }
+                    serde_json::Value::Array(arr) => {
+                        if let Some(serde_json::Value::String(s4)) = arr.first() { return Some(s4.clone()); }
+                    }
+                    other => return Some(other.to_string()),
+                }
+            }
+        }
+        None
+    }
+
+    // Prepare display fields using available on-chain or off-chain metadata
+    let token_name = offchain_meta
+        .as_ref()
+        .and_then(|off| off.name.clone())
+        .or_else(|| onchain_meta.as_ref().map(|m| m.name.trim_end_matches('\u{0}').to_string()))
+        .unwrap_or_else(|| "Unknown".to_string());
+    let token_symbol = offchain_meta
+        .as_ref()
+        .and_then(|off| off.symbol.clone())
+        .or_else(|| onchain_meta.as_ref().map(|m| m.symbol.trim_end_matches('\u{0}').to_string()));
+    let metadata_uri_opt = if let Some(m) = onchain_meta.as_ref() {
+        Some(m.uri.trim_end_matches('\u{0}').to_string())
+    } else if let Some(off) = offchain_meta.as_ref() { off.image.clone() } else { None };
+
+    info!(
+        "New pump.fun token detected: mint={} signature={} creator={} curve={} holder={} URI={}",
+        mint,
+        signature,
+        creator,
+        curve_pda,
+        holder_addr,
+        metadata_uri_opt.clone().unwrap_or_else(|| "<none>".to_string())
+    );
+
+    bot_log!(
+        "info",
+        format!("New token detected: {}", token_name),
+        format!("Mint: {}, Creator: {}", mint, creator)
+    );
+
+    {
+        let mut coins = detected_coins.lock().await;
+        if let Some(existing) = coins.iter_mut().find(|c| c.mint == mint) {
+            existing.name = Some(token_name.clone());
+            existing.symbol = token_symbol.clone();
+            existing.image = offchain_meta.as_ref().and_then(|o| o.image.clone());
+            existing.creator = creator.to_string();
+            existing.bonding_curve = curve_pda.to_string();
+            existing.detected_at = detect_time.to_rfc3339();
+            existing.metadata_uri = metadata_uri_opt.clone();
+            existing.buy_price = None;
+            existing.status = "detected".to_string();
+        } else {
+            let new_coin = api::DetectedCoin {
+                mint: mint.to_string(),
+                name: Some(token_name.clone()),
+                symbol: token_symbol.clone(),
+                image: offchain_meta.as_ref().and_then(|o| o.image.clone()),
+                creator: creator.to_string(),
+                bonding_curve: curve_pda.to_string(),
+                detected_at: detect_time.to_rfc3339(),
+                metadata_uri: metadata_uri_opt.clone(),
+                buy_price: None,
+                status: "detected".to_string(),
+                source: source.to_string(),
+                first_seen: dedup::first_seen(mint).await,
+            };
+            coins.insert(0, new_coin.clone());
+            TOTAL_DETECTED_COINS.fetch_add(1, Ordering::Relaxed);
+            
+            // Broadcast to WebSocket clients
+            let ws_message = serde_json::json!({
+                "type": "detected-coin",
+                "coin": new_coin,
+                "total_detected_coins": TOTAL_DETECTED_COINS.load(Ordering::Relaxed)
+            });
+            let _ = ws_tx.send(ws_message.to_string());
+            
+            // Keep only last `detected_coins_max` detected coins (configurable)
+            if coins.len() > settings.detected_coins_max {
+                coins.truncate(settings.detected_coins_max);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle an enriched PumpPortal event (or a create decoded by the fallback
+/// poller) without doing full RPC `getTransaction` when possible
+#[allow(clippy::too_many_arguments)]
+async fn handle_new_token_from_pumpportal(
+    signature: &str,
+    source: DetectionSource,
+    mint: &str,
+    creator: &str,
+    curve_pda: &str,
+    metadata_value: Option<serde_json::Value>,
+    bonding_state: Option<serde_json::Value>,
+    positions: &PositionRegistry,
+    rpc_client: &Arc<RpcClient>,
+    is_real: bool,
+    keypair: Option<&Keypair>,
+    simulate_keypair: Option<&Keypair>,
+    price_cache: &Arc<Mutex<PriceCache>>,
+    settings: &Arc<Settings>,
+    ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    next_wss_sender: Arc<AtomicUsize>,
+    detect_time: chrono::DateTime<Utc>,
+    detected_coins: Arc<tokio::sync::Mutex<Vec<api::DetectedCoin>>>,
+    trades_list: Arc<tokio::sync::Mutex<Vec<api::TradeRecord>>>,
+    ws_tx: tokio::sync::broadcast::Sender<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let is_mayhem_mode = metadata_value
+        .as_ref()
+        .and_then(|m| m.get("is_mayhem_mode"))
+        .and_then(|b| b.as_bool())
+        .unwrap_or(false);
+    // Parse metadata_value into OffchainTokenMetadata if present
+    let offchain_meta_opt: Option<crate::models::OffchainTokenMetadata> = if let Some(val) = metadata_value {
+        match serde_json::from_value(val) {
+            Ok(m) => Some(m),
+            Err(e) => {
+                debug!("Failed to parse PumpPortal metadata into OffchainTokenMetadata: {}", e);
+                None
+            }
+        }
+    } else { None };
+
+    // If PumpPortal provided bonding_state/reserves, compute price and update price_cache
+    if let Some(bstate) = bonding_state {
+        // Try to extract common numeric fields used by bonding curve
+        let get_u64 = |obj: &serde_json::Value, key: &str| -> Option<u64> {
+            match obj.get(key) {
+                Some(v) => {
+                    if let Some(n) = v.as_u64() { Some(n) }
+                    else if let Some(s) = v.as_str() { s.parse::<u64>().ok() }
+                    else { None }
+                }
+                None => None,
+            }
+        };
+
+        let vtok_opt = get_u64(&bstate, "virtual_token_reserves");
+        let vsol_opt = get_u64(&bstate, "virtual_sol_reserves");
+        let complete_flag = bstate.get("complete").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if complete_flag {
+            debug!("Bonding state from PumpPortal reports migrated/completed for mint {}", mint);
+        } else if let (Some(vtok), Some(vsol)) = (vtok_opt, vsol_opt) {
+            // Compute an initial price from PumpPortal reserve data and seed the
+            // price cache so the buy path below has an immediate price available
+            // instead of waiting for a WSS update that may never arrive for a
+            // brand-new token.
+            if vtok > 0 {
+                // pump.fun tokens always use 6 decimals.  Use the proven formula
+                // from BondingCurveState::spot_price_sol_per_token():
+                // price = (vsol_lamports / vtok_base_units) * 1e-3
+                // which is equivalent to (vsol/1e9) / (vtok/1e6)
+                let price = (vsol as f64 / vtok as f64) * 1e-3;
+                info!("Computed initial price from PumpPortal reserves for {}: {:.18} SOL/token (vtok={}, vsol={})", mint, price, vtok, vsol);
+                price_cache.lock().await.put(mint.to_string(), (Instant::now(), price));
+                if settings.hot_cache_enabled {
+                    // Seed the curve so the buy needs no prerequisite curve reads
+                    let creator_pk = Pubkey::from_str(creator).ok();
+                    hot_cache::seed_curve(mint, vtok, vsol, complete_flag, creator_pk, is_mayhem_mode).await;
+                }
+            } else {
+                debug!("PumpPortal provided zero token reserves for {} — cannot compute price", mint);
+            }
+        }
+        if settings.hot_cache_enabled {
+            if let Some(dec) = get_u64(&bstate, "decimals").and_then(|d| u8::try_from(d).ok()) {
+                hot_cache::remember_decimals(mint, dec).await;
+            }
+        }
+    }
+    if settings.hot_cache_enabled {
+        // Token program (and decimals if not provided) resolve in the background
+        // while the detection is processed, so the buy finds them memoized.
+        hot_cache::prefetch_mint(mint.to_string(), rpc_client.clone(), settings.clone());
+    }
+    // No onchain_raw or onchain_meta provided; process_detected_token will fallback to RPC if needed
+    // If PumpPortal did not provide a bonding_curve PDA string, compute the canonical
+    // pump.fun bonding-curve PDA here and pass it through so downstream code and the
+    // API always see a populated `bonding_curve` field.
+    let curve_string = if curve_pda.trim().is_empty() {
+        match Pubkey::from_str(&settings.pump_fun_program) {
+            Ok(pump_program) => match Pubkey::from_str(mint) {
+                Ok(mint_pk) => {
+                    let (pda, _b) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_program);
+                    pda.to_string()
+                }
+                Err(_) => "".to_string(),
+            },
+            Err(_) => "".to_string(),
+        }
+    } else {
+        curve_pda.to_string()
+    };
+
+    // First, perform the usual detected-token processing which will populate the API list
+    process_detected_token(
+        signature,
+        source,
+        mint,
+        creator,
+        &curve_string,
+        "",
+        detect_time,
+        rpc_client,
+        is_real,
+        keypair,
+        simulate_keypair,
+        price_cache,
+        settings,
+        ws_control_senders.clone(),
+        next_wss_sender.clone(),
+        detected_coins.clone(),
+        trades_list.clone(),
+        None,
+        offchain_meta_opt.clone(),
+        None,
+        ws_tx.clone(),
+    )
+    .await?;
+
+    // Attempt a buy on PumpPortal detections when a price is available.
+    // Prefer WSS live price for the buy decision by subscribing just before buy
+    // (latency-sensitive). If WSS subscription or price retrieval fails, fall
+    // back to cached or RPC price as before.
+    let mut price_opt: Option<f64> = None;
+    let mut subscribed_idx: Option<usize> = None;
+    let mut subscribed_sub_id: Option<u64> = None;
+    let mut sub_was_created = false;
+
+    // Try to create a short-lived WSS subscription to get a live price update
+    if !ws_control_senders.is_empty() {
+        if let Some(idx) = select_healthy_wss(&ws_control_senders, settings).await {
+            let sender = &ws_control_senders[idx];
+            let (resp_tx, resp_rx) = tokio::sync::oneshot::channel::<Result<u64, String>>();
+            let pump_prog = settings.address_book()?.pump_program;
+            if let Ok(mint_pk) = Pubkey::from_str(mint) {
+                let (curve_pda, _b) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_prog);
+                let subscribe_req = WsRequest::Subscribe { account: curve_pda.to_string(), mint: mint.to_string(), resp: resp_tx };
+                if let Err(e) = sender.send(subscribe_req).await {
+                    log::warn!("Failed to send subscribe request for {}: {}", mint, e);
+                } else {
+                    match tokio::time::timeout(std::time::Duration::from_secs(5), resp_rx).await {
+                        Ok(Ok(Ok(sub_id))) => {
+                            debug!("Subscribed to {} on sub {} (pre-buy)", mint, sub_id);
+                            sub_was_created = true;
+                            subscribed_idx = Some(idx);
+                            subscribed_sub_id = Some(sub_id);
+                        }
+                        Ok(Ok(Err(err_msg))) => {
+                            if err_msg.contains("max subscriptions") {
+                                debug!("Subscribe rejected for {} (WSS idx={} full)", mint, idx);
+                            } else if err_msg.contains("degraded") {
+                                debug!("WSS idx={} degraded, skipped {}", idx, mint);
+                            } else {
+                                log::warn!("Subscribe request rejected for {}: {}", mint, err_msg);
+                            }
+                        }
+                        Ok(Err(_)) => log::warn!("Subscribe channel closed for {} (WSS idx={})", mint, idx),
+                        Err(_) => warn!("Subscribe timed out for {} (WSS idx={}, 5s)", mint, idx),
+                    }
+                }
+            }
+        } else {
+            debug!("No healthy WSS available for {} (all degraded/full)", mint);
+        }
+    }
+
+    // If subscription was established, wait briefly for a price to appear in the cache
+    if sub_was_created {
+        // First check quickly
+        if let Some((_, price)) = price_cache.lock().await.get(mint).cloned() {
+            price_opt = Some(price);
+        } else {
+            // Wait up to settings.wss_subscribe_timeout_secs for WSS to push initial price
+            let start = Instant::now();
+            while start.elapsed().as_secs() < settings.wss_subscribe_timeout_secs {
+                if let Some((_, price)) = price_cache.lock().await.get(mint).cloned() {
+                    price_opt = Some(price);
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            }
+        }
+    }
+
+    // If we still don't have a WSS price, fall back to cached price then RPC as before
+    if price_opt.is_none() {
+        if let Some((_, price)) = price_cache.lock().await.get(mint).cloned() {
+            price_opt = Some(price);
+        }
+    }
+    if price_opt.is_none() {
+        if let Ok(p) = rpc::fetch_current_price(mint, price_cache, rpc_client, settings).await {
+            price_opt = Some(p);
+        }
+    }
+
+    if let Some(price) = price_opt {
+        // Refresh price cache timestamp so the buy quote finds a fresh entry
+        // and skips the slow multi-commitment RPC re-fetch sequence.
+        price_cache.lock().await.put(mint.to_string(), (Instant::now(), price));
+        let quote = buyer::quote(mint, price_cache, rpc_client, settings).await;
+        if let Ok(quote) = &quote {
+            shadow::on_quote(mint, quote);
+        }
+
+        // Check if bot is running before attempting to buy
+        {
+            if let Some(control) = bot_control() {
+                let rs = control.running_state.lock().await;
+                if !matches!(*rs, crate::api::BotRunningState::Running) {
+                    debug!("Bot not running; skipping PumpPortal buy for {}", mint);
+                    if let (true, Some(sub_idx), Some(sub_id)) = (sub_was_created, subscribed_idx, subscribed_sub_id) {
+                        let sender = &ws_control_senders[sub_idx];
+                        let (u_tx, u_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+                        let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
+                        let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+
+        // Reserve a Pending position — enforces max_holded_coins and rejects
+        // duplicate buys of the same mint.
+        if let Err(refused) = positions.begin(mint, settings.max_holded_coins).await {
+            info!("Skipping PumpPortal buy for {}: {}", mint, refused);
+            if let BeginRefused::MaxHeld { .. } = refused {
+                missed::track(mint, SkipReason::MaxHoldedCoins, refused.to_string(), Some(price), settings).await;
+            }
+            if let (true, Some(sub_idx), Some(sub_id)) = (sub_was_created, subscribed_idx, subscribed_sub_id) {
+                let sender = &ws_control_senders[sub_idx];
+                let (u_tx, u_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+                let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
+                let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
+            }
+            return Ok(());
+        }
+        let _ = positions.transition(mint, PositionState::Buying, None).await;
+
+        let bought = match quote {
+            Ok(quote) => buyer::buy_token(mint, settings.buy_amount, quote, is_real, keypair, simulate_keypair, rpc_client, settings).await,
+            Err(e) => Err(e),
+        };
+        match bought {
+            Ok(buyer::Bought { mut holding, same_slot_sell }) => {
+                // Persist metadata into the created holding
+                holding.metadata = offchain_meta_opt.clone();
+                // Update holdings and trades similar to RPC path
+                let buy_record = BuyRecord {
+                    mint: mint.to_string(),
+                    symbol: offchain_meta_opt.as_ref().and_then(|o| o.symbol.clone()),
+                    name: offchain_meta_opt.as_ref().and_then(|o| o.name.clone()),
+                    uri: offchain_meta_opt.as_ref().and_then(|o| o.image.clone()),
+                    image: offchain_meta_opt.as_ref().and_then(|o| o.image.clone()),
+                    creator: creator.to_string(),
+                    detect_time,
+                    buy_time: holding.buy_time,
+                    buy_amount_sol: settings.buy_amount,
+                    buy_amount_tokens: holding.amount,
+                    buy_price: holding.buy_price,
+                };
+
+                // Update detected coin status
+                {
+                    let mut coins = detected_coins.lock().await;
+                    if let Some(coin) = coins.iter_mut().find(|c| c.mint == mint) {
+                        coin.status = "bought".to_string();
+                        coin.buy_price = Some(holding.buy_price);
+                    }
+                }
+
+                // Emit trade records (and the same-slot sell's)
+                record_buy_trades(mint, &holding, same_slot_sell.as_ref(), offchain_meta_opt.as_ref(), settings, is_real, &trades_list, &ws_tx).await;
+
+                let sold_out = holding.amount == 0;
+                if let Err(e) = positions.open(mint, holding, buy_record).await {
+                    error!("Failed to open position for {}: {}", mint, e);
+                } else if sold_out {
+                    // Closing releases any subscription handed to the position below
+                    let _ = positions.close(mint, SOLD_IN_SAME_SLOT).await;
+                }
+
+                // If we created a subscription pre-buy, hand it to the position
+                if let (true, Some(sub_idx), Some(sub_id)) = (sub_was_created, subscribed_idx, subscribed_sub_id) {
+                    positions.set_subscription(mint, Some((sub_idx, sub_id))).await;
+                } else {
+                    // Otherwise, try to subscribe now (best effort) to keep monitoring
+                    if !ws_control_senders.is_empty() {
+                        if let Some(idx) = select_healthy_wss(&ws_control_senders, settings).await {
+                            let sender = &ws_control_senders[idx];
+                            let (resp_tx, resp_rx) = tokio::sync::oneshot::channel::<Result<u64, String>>();
+                            let pump_prog = settings.address_book()?.pump_program;
+                            if let Ok(mint_pk) = Pubkey::from_str(mint) {
+                                let (curve_pda, _) = Pubkey::find_program_address(
+                                    &[b"bonding-curve", mint_pk.as_ref()],
+                                    &pump_prog,
+                                );
+                                let subscribe_req = WsRequest::Subscribe { account: curve_pda.to_string(), mint: mint.to_string(), resp: resp_tx };
+                                if let Err(e) = sender.send(subscribe_req).await {
+                                    log::warn!("Failed to send subscribe request for {}: {}", mint, e);
+                                } else {
+                                    if let Ok(Ok(Ok(sub_id))) = tokio::time::timeout(std::time::Duration::from_secs(5), resp_rx).await {
+                                        debug!("Subscribed to {} on sub {}", mint, sub_id);
+                                        positions.set_subscription(mint, Some((idx, sub_id))).await;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                info!("PumpPortal fast-path buy succeeded for {}", mint);
+            }
+            Err(e) => {
+                let _ = positions.fail(mint, format!("buy failed: {}", e)).await;
+                log::warn!("Failed to buy {} (pumpportal fast-path): {}", mint, e);
+                bot_log!("warn", format!("Failed to buy token {}", mint), format!("{}", e));
+                if let Some(rejected) = e.downcast_ref::<buyer::EntryRejected>() {
+                    missed::track(mint, SkipReason::EntryFilter(rejected.filter), rejected.reason.clone(), Some(price), settings).await;
+                }
+
+                // Record failed buy attempt so it appears in Trading History
+                {
+                    let mut trades = trades_list.lock().await;
+                    trades.insert(0, api::TradeRecord {
+                        mint: mint.to_string(),
+                        symbol: offchain_meta_opt.as_ref().and_then(|o| o.symbol.clone()),
+                        name: offchain_meta_opt.as_ref().and_then(|o| o.name.clone()),
+                        image: offchain_meta_opt.as_ref().and_then(|o| o.image.clone()),
+                        trade_type: "buy".to_string(),
+                        timestamp: crate::recorder::now().to_rfc3339(),
+                        tx_signature: None,
+                        amount_sol: settings.buy_amount,
+                        amount_tokens: 0.0,
+                        price_per_token: 0.0,
+                        profit_loss: None,
+                        profit_loss_percent: None,
+                        reason: Some(format!("FAILED: {}", e)),
+                        decimals: settings.default_token_decimals,
+                        actual_sol_change: None,
+                        tx_fee_sol: None,
+                        simulated: !is_real,
+                    });
+                    if trades.len() > 200 { trades.truncate(200); }
+                    // Broadcast new trade over WebSocket for real-time frontend updates
+                    if let Ok(json) = serde_json::to_value(&trades[0]) {
+                        let _ = ws_tx.send(serde_json::json!({"type": "new-trade", "trade": json}).to_string());
+                    }
+                }
+
+                // Update detected coin status to buy_failed
+                {
+                    let mut coins = detected_coins.lock().await;
+                    if let Some(coin) = coins.iter_mut().find(|c| c.mint == mint) {
+                        coin.status = "buy_failed".to_string();
+                    }
+                }
+
+                // If we created a subscription and didn't buy, unsubscribe to free slot
+                if let (true, Some(sub_idx), Some(sub_id)) = (sub_was_created, subscribed_idx, subscribed_sub_id) {
+                    let sender = &ws_control_senders[sub_idx];
+                    let (u_tx, u_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+                    let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
+                    let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
+                }
+            }
+        }
+    } else {
+        warn!("No price available for {} yet; skipping pumpportal fast-path buy", mint);
+        // Unsubscribe if we created a subscription but got no price and won't buy
+        if let (true, Some(sub_idx), Some(sub_id)) = (sub_was_created, subscribed_idx, subscribed_sub_id) {
+            let sender = &ws_control_senders[sub_idx];
+            let (u_tx, u_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+            let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
+            let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,317 @@
+//! Polling fallback detector.
+//!
+//! When every streaming detection feed (PumpPortal, logs WSS) has been down
+//! for `poll_fallback_after_secs`, this task polls `getSignaturesForAddress`
+//! on the pump.fun mint authority (or `poll_detector_address`), paging back
+//! with `before` until it reaches the `until` cursor, decodes the create
+//! transactions among the new signatures and feeds them into
+//! `process_message` as normalized events tagged `"source": "poll"`. The
+//! mint authority only signs creates, so trades never cost a
+//! `getTransaction`. It stands down as soon as a streaming feed reconnects.
+
+use crate::rate_limit::Priority;
+use crate::{rpc, settings::Settings, ws_supervisor};
+use futures_util::stream::{self, StreamExt};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+
+/// Transactions decoded concurrently per poll.
+const DECODE_CONCURRENCY: usize = 4;
+/// Pages of `poll_signature_limit` signatures fetched per poll while catching up.
+const MAX_PAGES: usize = 10;
+
+#[derive(Debug, Default)]
+struct PollerState {
+    active: bool,
+    activations: u64,
+    polls: u64,
+    signatures: u64,
+    creates: u64,
+    last_error: Option<String>,
+}
+
+static STATE: Lazy<Mutex<PollerState>> = Lazy::new(|| Mutex::new(PollerState::default()));
+
+/// One signature from `getSignaturesForAddress`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub failed: bool,
+    pub block_time: Option<i64>,
+}
+
+/// Parse a `getSignaturesForAddress` result (newest first).
+pub fn parse_signatures(result: &Value) -> Vec<SignatureInfo> {
+    result
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|entry| {
+                    Some(SignatureInfo {
+                        signature: entry.get("signature")?.as_str()?.to_string(),
+                        failed: entry.get("err").is_some_and(|e| !e.is_null()),
+                        block_time: entry.get("blockTime").and_then(|t| t.as_i64()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Signatures worth decoding, oldest first. Without a cursor (first poll
+/// after activation) only transactions young enough to still be bought are
+/// kept, so activation does not replay history.
+pub fn select_new(mut sigs: Vec<SignatureInfo>, has_cursor: bool, now_unix: i64, max_age_secs: u64) -> Vec<SignatureInfo> {
+    sigs.retain(|s| !s.failed);
+    if !has_cursor {
+        sigs.retain(|s| s.block_time.is_some_and(|t| now_unix - t <= max_age_secs as i64));
+    }
+    sigs.reverse();
+    sigs
+}
+
+/// Normalized event for `process_message`, same shape as PumpPortal's.
+fn normalized_event(signature: &str, mint: &str, creator: &str, curve: &str) -> String {
+    json!({
+        "params": {
+            "result": {
+                "value": {
+                    "signature": signature,
+                    "source": "poll",
+                    "decoded": {
+                        "mint": mint,
+                        "creator": creator,
+                        "bonding_curve": curve,
+                    }
+                }
+            }
+        }
+    })
+    .to_string()
+}
+
+/// The address polled for creates: `poll_detector_address`, or the pump.fun
+/// mint authority PDA (`["mint-authority"]`), which appears only in creates.
+pub fn detector_address(settings: &Settings) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if !settings.poll_detector_address.is_empty() {
+        return Ok(settings.poll_detector_address.clone());
+    }
+    let program = settings.address_book()?.pump_program;
+    Ok(Pubkey::find_program_address(&[b"mint-authority"], &program).0.to_string())
+}
+
+pub async fn run(tx: mpsc::Sender<String>, rpc_client: Arc<RpcClient>, settings: Arc<Settings>) {
+    if !settings.poll_fallback_enabled {
+        return;
+    }
+    let address = match detector_address(&settings) {
+        Ok(address) => address,
+        Err(e) => {
+            warn!("Polling fallback disabled: {}", e);
+            return;
+        }
+    };
+    let mut down_since: Option<Instant> = None;
+    let mut cursor: Option<String> = None;
+    loop {
+        tokio::time::sleep(Duration::from_millis(settings.poll_interval_ms)).await;
+        if tx.is_closed() {
+            return;
+        }
+
+        if ws_supervisor::detection_feeds_up(&settings).await {
+            down_since = None;
+            let mut state = STATE.lock().await;
+            if state.active {
+                state.active = false;
+                cursor = None;
+                info!("Streaming detection feed is back; polling fallback off");
+            }
+            continue;
+        }
+        let since = *down_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < Duration::from_secs(settings.poll_fallback_after_secs) {
+            continue;
+        }
+        {
+            let mut state = STATE.lock().await;
+            if !state.active {
+                state.active = true;
+                state.activations += 1;
+                warn!("All streaming detection feeds down for {}s; polling {} for new tokens", since.elapsed().as_secs(), address);
+            }
+        }
+
+        if let Err(e) = poll_once(&address, &mut cursor, &tx, &rpc_client, &settings).await {
+            debug!("Fallback poll failed: {}", e);
+            STATE.lock().await.last_error = Some(e.to_string());
+        }
+    }
+}
+
+async fn poll_once(
+    address: &str,
+    cursor: &mut Option<String>,
+    tx: &mpsc::Sender<String>,
+    rpc_client: &Arc<RpcClient>,
+    settings: &Arc<Settings>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let sigs = fetch_signatures(address, cursor.as_deref(), settings).await?;
+    let newest = sigs.first().map(|s| s.signature.clone());
+    let now_unix = chrono::Utc::now().timestamp();
+    let fresh = select_new(sigs, cursor.is_some(), now_unix, settings.max_create_to_buy_secs);
+    if let Some(newest) = newest {
+        *cursor = Some(newest);
+    }
+
+    let signature_count = fresh.len() as u64;
+    let decoded: Vec<Option<String>> = stream::iter(fresh)
+        .map(|info| {
+            let rpc_client = Arc::clone(rpc_client);
+            let settings = Arc::clone(settings);
+            async move {
+                match rpc::fetch_transaction_details(&info.signature, &rpc_client, &settings).await {
+                    Ok((creator, mint, curve, _holder, true)) => {
+                        Some(normalized_event(&info.signature, &mint, &creator, &curve))
+                    }
+                    Ok(_) => None,
+                    Err(e) => {
+                        debug!("Fallback poll could not decode {}: {}", info.signature, e);
+                        None
+                    }
+                }
+            }
+        })
+        .buffered(DECODE_CONCURRENCY)
+        .collect()
+        .await;
+    let mut creates = 0u64;
+    for event in decoded.into_iter().flatten() {
+        creates += 1;
+        if tx.send(event).await.is_err() {
+            break;
+        }
+    }
+
+    let mut state = STATE.lock().await;
+    state.polls += 1;
+    state.signatures += signature_count;
+    state.creates += creates;
+    state.last_error = None;
+    Ok(())
+}
+
+/// Signatures for `address` newer than `until`, newest first. With a cursor,
+/// full pages are followed with `before` until the cursor is reached (at
+/// most `MAX_PAGES`); without one only the newest page is read.
+pub async fn fetch_signatures(
+    address: &str,
+    until: Option<&str>,
+    settings: &Arc<Settings>,
+) -> Result<Vec<SignatureInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut all: Vec<SignatureInfo> = Vec::new();
+    for _ in 0..MAX_PAGES {
+        let mut config = json!({ "limit": settings.poll_signature_limit, "commitment": "confirmed" });
+        if let Some(until) = until {
+            config["until"] = json!(until);
+        }
+        if let Some(before) = all.last() {
+            config["before"] = json!(before.signature);
+        }
+        let request = json!({
+            "jsonrpc": "2.0", "id": 1, "method": "getSignaturesForAddress",
+            "params": [ address, config ]
+        });
+        let resp = rpc::fetch_with_priority::<Value>(request, "getSignaturesForAddress", Priority::Normal, settings).await?;
+        let page = parse_signatures(&resp.result.unwrap_or(Value::Null));
+        let full = page.len() >= settings.poll_signature_limit;
+        all.extend(page);
+        if !full || until.is_none() {
+            return Ok(all);
+        }
+    }
+    debug!("Fallback poll stopped after {} pages; older signatures since the last poll are skipped", MAX_PAGES);
+    Ok(all)
+}
+
+/// Fallback detector state, for `/api/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PollerStatus {
+    pub active: bool,
+    pub activations: u64,
+    pub polls: u64,
+    pub signatures: u64,
+    pub creates: u64,
+    pub last_error: Option<String>,
+}
+
+pub(crate) async fn reset() {
+    *STATE.lock().await = PollerState::default();
+}
+
+pub async fn status() -> PollerStatus {
+    let state = STATE.lock().await;
+    PollerStatus {
+        active: state.active,
+        activations: state.activations,
+        polls: state.polls,
+        signatures: state.signatures,
+        creates: state.creates,
+        last_error: state.last_error.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_poll_only_keeps_young_successful_signatures_oldest_first() {
+        let result = json!([
+            { "signature": "c", "err": null, "blockTime": 1_000 },
+            { "signature": "b", "err": { "InstructionError": [0, "Custom"] }, "blockTime": 999 },
+            { "signature": "a", "err": null, "blockTime": 995 },
+            { "signature": "old", "err": null, "blockTime": 900 }
+        ]);
+        let sigs = parse_signatures(&result);
+        assert_eq!(sigs.len(), 4);
+        assert!(sigs[1].failed);
+
+        let first: Vec<String> = select_new(sigs.clone(), false, 1_002, 10).into_iter().map(|s| s.signature).collect();
+        assert_eq!(first, vec!["a", "c"]);
+        let later: Vec<String> = select_new(sigs, true, 1_002, 10).into_iter().map(|s| s.signature).collect();
+        assert_eq!(later, vec!["old", "a", "c"]);
+    }
+
+    #[test]
+    fn default_detector_is_the_mint_authority() {
+        let settings = Settings::from_file("config.example.toml").unwrap();
+        assert_eq!(detector_address(&settings).unwrap(), "TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM");
+    }
+
+    #[tokio::test]
+    async fn pages_back_to_the_cursor() {
+        let cluster = crate::mock_cluster::MockCluster::start().await.unwrap();
+        // s0 (oldest) .. s9 (newest)
+        for i in 0..10 {
+            cluster.add_signature("addr", &format!("s{}", i), 1_000);
+        }
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.solana_rpc_urls = vec![cluster.rpc_url()];
+        settings.poll_signature_limit = 3;
+        let settings = Arc::new(settings);
+
+        let caught_up: Vec<String> =
+            fetch_signatures("addr", Some("s2"), &settings).await.unwrap().into_iter().map(|s| s.signature).collect();
+        assert_eq!(caught_up, vec!["s9", "s8", "s7", "s6", "s5", "s4", "s3"]);
+        // No cursor: newest page only
+        assert_eq!(fetch_signatures("addr", None, &settings).await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,558 @@
+//! Per-position lifecycle state machine.
+//!
+//! Every mint we trade is owned by a single actor task that holds the
+//! position's state (holding, buy record, WSS subscription) and only allows
+//! legal transitions:
+//!
+//! ```text
+//! Pending ──► Buying ──► Open ◄──► Exiting ──► Closed
+//!    │           │         │          │
+//!    └► Closed   └► Failed └► Closed  └► Failed
+//! ```
+//!
+//! The rest of the bot talks to positions exclusively through
+//! [`PositionRegistry`]. The shared `holdings` map is kept as a read-only view
+//! (WSS price PnL, API stats) and is only written by the actors.
+
+use crate::{api::BotControl, models::Holding, state::BuyRecord, ws::WsRequest};
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex};
+
+/// How many terminal positions are kept around for the API.
+const RECENT_POSITIONS_MAX: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionState {
+    /// Slot reserved, price/filters still being checked.
+    Pending,
+    /// Buy transaction in flight.
+    Buying,
+    /// Tokens held and monitored for TP/SL/timeout.
+    Open,
+    /// A sell is in flight; no other sell may start.
+    Exiting,
+    /// Position fully exited (or abandoned before buying).
+    Closed,
+    /// Buy or forced exit failed.
+    Failed,
+}
+
+impl PositionState {
+    pub fn is_terminal(self) -> bool {
+        matches!(self, PositionState::Closed | PositionState::Failed)
+    }
+
+    pub fn can_transition_to(self, next: PositionState) -> bool {
+        use PositionState::*;
+        matches!(
+            (self, next),
+            (Pending, Buying)
+                | (Pending, Closed)
+                | (Pending, Failed)
+                | (Buying, Open)
+                | (Buying, Failed)
+                | (Open, Exiting)
+                | (Open, Closed)
+                | (Exiting, Open)
+                | (Exiting, Closed)
+                | (Exiting, Failed)
+        )
+    }
+}
+
+impl std::fmt::Display for PositionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PositionState::Pending => "pending",
+            PositionState::Buying => "buying",
+            PositionState::Open => "open",
+            PositionState::Exiting => "exiting",
+            PositionState::Closed => "closed",
+            PositionState::Failed => "failed",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Why [`PositionRegistry::begin`] refused to reserve a slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeginRefused {
+    /// The mint already has a live position.
+    AlreadyActive(String),
+    /// `max_holded_coins` positions are live.
+    MaxHeld { active: usize, max: usize },
+}
+
+impl std::fmt::Display for BeginRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BeginRefused::AlreadyActive(mint) => write!(f, "position for {} already active", mint),
+            BeginRefused::MaxHeld { active, max } => write!(f, "max held coins reached ({} active >= {})", active, max),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionRecord {
+    pub from: PositionState,
+    pub to: PositionState,
+    pub at: DateTime<Utc>,
+    pub detail: Option<String>,
+}
+
+/// Point-in-time view of a position, returned by every registry query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub mint: String,
+    pub state: PositionState,
+    pub holding: Option<Holding>,
+    pub buy_record: Option<BuyRecord>,
+    /// Active WSS price subscription: (wss_sender_index, sub_id)
+    pub subscription: Option<(usize, u64)>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub history: Vec<TransitionRecord>,
+}
+
+/// Changes applied to the holding when a partial exit completes.
+#[derive(Debug, Clone, Default)]
+pub struct PartialExit {
+    pub sold_amount: u64,
+    pub triggered_tp: Vec<usize>,
+    pub triggered_sl: Vec<usize>,
+}
+
+enum Command {
+    Transition {
+        to: PositionState,
+        detail: Option<String>,
+        open: Option<Box<(Holding, BuyRecord)>>,
+        partial: Option<PartialExit>,
+        resp: oneshot::Sender<Result<PositionSnapshot, String>>,
+    },
+    SetSubscription {
+        sub: Option<(usize, u64)>,
+        resp: oneshot::Sender<Option<(usize, u64)>>,
+    },
+    Snapshot {
+        resp: oneshot::Sender<PositionSnapshot>,
+    },
+}
+
+#[derive(Clone)]
+struct PositionHandle {
+    tx: mpsc::Sender<Command>,
+    evaluating: Arc<AtomicBool>,
+    /// Percent of the remaining amount an operator asked to sell, executed
+    /// by the monitor on its next evaluation of the mint.
+    manual_exit: Arc<Mutex<Option<f64>>>,
+}
+
+/// Held while the monitor evaluates a position; prevents overlapping
+/// evaluations of the same mint. Released on drop.
+pub struct EvaluationGuard {
+    flag: Arc<AtomicBool>,
+}
+
+impl Drop for EvaluationGuard {
+    fn drop(&mut self) {
+        self.flag.store(false, Ordering::Release);
+    }
+}
+
+#[derive(Clone)]
+pub struct PositionRegistry {
+    positions: Arc<Mutex<HashMap<String, PositionHandle>>>,
+    recent: Arc<Mutex<VecDeque<PositionSnapshot>>>,
+    holdings: Arc<Mutex<HashMap<String, Holding>>>,
+    ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    bot_control: Arc<BotControl>,
+    ws_tx: broadcast::Sender<String>,
+    /// Mints with an open holding, for price feeds that subscribe per mint.
+    held: Arc<watch::Sender<BTreeSet<String>>>,
+}
+
+impl PositionRegistry {
+    pub fn new(
+        holdings: Arc<Mutex<HashMap<String, Holding>>>,
+        ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+        bot_control: Arc<BotControl>,
+        ws_tx: broadcast::Sender<String>,
+    ) -> Self {
+        Self {
+            positions: Arc::new(Mutex::new(HashMap::new())),
+            recent: Arc::new(Mutex::new(VecDeque::new())),
+            holdings,
+            ws_control_senders,
+            bot_control,
+            ws_tx,
+            held: Arc::new(watch::Sender::new(BTreeSet::new())),
+        }
+    }
+
+    /// Watch the set of mints with an open holding. Updated whenever a
+    /// position opens or reaches a terminal state.
+    pub fn held_mints(&self) -> watch::Receiver<BTreeSet<String>> {
+        self.held.subscribe()
+    }
+
+    /// Reserve a slot for `mint` in the Pending state. Fails if the mint
+    /// already has a live position or if `max_active` positions are live.
+    pub async fn begin(&self, mint: &str, max_active: usize) -> Result<(), BeginRefused> {
+        let mut positions = self.positions.lock().await;
+        if positions.contains_key(mint) {
+            return Err(BeginRefused::AlreadyActive(mint.to_string()));
+        }
+        if positions.len() >= max_active {
+            return Err(BeginRefused::MaxHeld { active: positions.len(), max: max_active });
+        }
+        let (tx, rx) = mpsc::channel(32);
+        positions.insert(
+            mint.to_string(),
+            PositionHandle {
+                tx,
+                evaluating: Arc::new(AtomicBool::new(false)),
+                manual_exit: Arc::new(Mutex::new(None)),
+            },
+        );
+        drop(positions);
+
+        let now = crate::recorder::now();
+        let snapshot = PositionSnapshot {
+            mint: mint.to_string(),
+            state: PositionState::Pending,
+            holding: None,
+            buy_record: None,
+            subscription: None,
+            created_at: now,
+            updated_at: now,
+            history: Vec::new(),
+        };
+        debug!("Position {} created (pending)", mint);
+        crate::tasks::spawn(run_position(snapshot, rx, self.clone()));
+        Ok(())
+    }
+
+    /// Number of live (non-terminal) positions, used for `max_holded_coins`.
+    pub async fn active_count(&self) -> usize {
+        self.positions.lock().await.len()
+    }
+
+    pub async fn state(&self, mint: &str) -> Option<PositionState> {
+        self.snapshot(mint).await.map(|s| s.state)
+    }
+
+    pub async fn snapshot(&self, mint: &str) -> Option<PositionSnapshot> {
+        let handle = self.positions.lock().await.get(mint).cloned()?;
+        let (resp, rx) = oneshot::channel();
+        handle.tx.send(Command::Snapshot { resp }).await.ok()?;
+        rx.await.ok()
+    }
+
+    /// Live positions followed by the most recent terminal ones.
+    pub async fn list(&self) -> Vec<PositionSnapshot> {
+        let handles: Vec<PositionHandle> =
+            self.positions.lock().await.values().cloned().collect();
+        let mut out = Vec::with_capacity(handles.len());
+        for handle in handles {
+            let (resp, rx) = oneshot::channel();
+            if handle.tx.send(Command::Snapshot { resp }).await.is_ok() {
+                if let Ok(s) = rx.await {
+                    out.push(s);
+                }
+            }
+        }
+        out.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        out.extend(self.recent.lock().await.iter().cloned());
+        out
+    }
+
+    async fn send_transition(
+        &self,
+        mint: &str,
+        to: PositionState,
+        detail: Option<String>,
+        open: Option<(Holding, BuyRecord)>,
+        partial: Option<PartialExit>,
+    ) -> Result<PositionSnapshot, String> {
+        let handle = self
+            .positions
+            .lock()
+            .await
+            .get(mint)
+            .cloned()
+            .ok_or_else(|| format!("no active position for {}", mint))?;
+        let (resp, rx) = oneshot::channel();
+        handle
+            .tx
+            .send(Command::Transition { to, detail, open: open.map(Box::new), partial, resp })
+            .await
+            .map_err(|_| format!("position actor for {} has stopped", mint))?;
+        rx.await
+            .map_err(|_| format!("position actor for {} dropped the request", mint))?
+    }
+
+    pub async fn transition(
+        &self,
+        mint: &str,
+        to: PositionState,
+        detail: Option<String>,
+    ) -> Result<PositionSnapshot, String> {
+        self.send_transition(mint, to, detail, None, None).await
+    }
+
+    /// Buying → Open: the actor takes ownership of the holding and buy record.
+    pub async fn open(
+        &self,
+        mint: &str,
+        holding: Holding,
+        buy_record: BuyRecord,
+    ) -> Result<PositionSnapshot, String> {
+        self.send_transition(mint, PositionState::Open, None, Some((holding, buy_record)), None)
+            .await
+    }
+
+    /// Open → Exiting. Returns false if another exit is already in flight or
+    /// the position is not open, which is what prevents double-sells.
+    pub async fn begin_exit(&self, mint: &str, reason: &str) -> bool {
+        self.transition(mint, PositionState::Exiting, Some(reason.to_string()))
+            .await
+            .is_ok()
+    }
+
+    /// Exiting → Open after a partial sell, recording sold amount and triggered levels.
+    pub async fn complete_partial_exit(
+        &self,
+        mint: &str,
+        partial: PartialExit,
+        detail: String,
+    ) -> Result<PositionSnapshot, String> {
+        self.send_transition(mint, PositionState::Open, Some(detail), None, Some(partial))
+            .await
+    }
+
+    pub async fn close(&self, mint: &str, detail: impl Into<String>) -> Result<PositionSnapshot, String> {
+        self.transition(mint, PositionState::Closed, Some(detail.into())).await
+    }
+
+    pub async fn fail(&self, mint: &str, detail: impl Into<String>) -> Result<PositionSnapshot, String> {
+        self.transition(mint, PositionState::Failed, Some(detail.into())).await
+    }
+
+    /// Attach (or clear) the WSS price subscription. Returns the previous one.
+    /// If the position is gone the subscription is released immediately so it
+    /// cannot be orphaned.
+    pub async fn set_subscription(&self, mint: &str, sub: Option<(usize, u64)>) -> Option<(usize, u64)> {
+        let handle = self.positions.lock().await.get(mint).cloned();
+        if let Some(handle) = handle {
+            let (resp, rx) = oneshot::channel();
+            if handle.tx.send(Command::SetSubscription { sub, resp }).await.is_ok() {
+                if let Ok(prev) = rx.await {
+                    return prev;
+                }
+            }
+        }
+        if let Some((idx, sub_id)) = sub {
+            debug!("Position {} no longer active; releasing sub {}", mint, sub_id);
+            self.unsubscribe(idx, sub_id).await;
+        }
+        None
+    }
+
+    pub async fn subscription(&self, mint: &str) -> Option<(usize, u64)> {
+        self.snapshot(mint).await.and_then(|s| s.subscription)
+    }
+
+    /// Claim the right to evaluate `mint`. Returns None if the position is
+    /// unknown or another evaluation is still running.
+    pub async fn try_claim_evaluation(&self, mint: &str) -> Option<EvaluationGuard> {
+        let handle = self.positions.lock().await.get(mint).cloned()?;
+        if handle
+            .evaluating
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return None;
+        }
+        Some(EvaluationGuard { flag: handle.evaluating })
+    }
+
+    /// Queue a manual sell of `percent` of the remaining amount. Only positions
+    /// holding tokens (Open, or Exiting for a sell already in flight) accept
+    /// one; a newer request replaces a pending one.
+    pub async fn request_manual_exit(&self, mint: &str, percent: f64) -> Result<(), String> {
+        if !(percent > 0.0 && percent <= 100.0) {
+            return Err(format!("percent must be in (0, 100] (got {})", percent));
+        }
+        let handle = self
+            .positions
+            .lock()
+            .await
+            .get(mint)
+            .cloned()
+            .ok_or_else(|| format!("no active position for {}", mint))?;
+        let state = self.state(mint).await.ok_or_else(|| format!("no active position for {}", mint))?;
+        if !matches!(state, PositionState::Open | PositionState::Exiting) {
+            return Err(format!("position for {} is {}, not open", mint, state));
+        }
+        *handle.manual_exit.lock().await = Some(percent);
+        info!("Manual exit of {:.0}% queued for {}", percent, mint);
+        Ok(())
+    }
+
+    /// Take the pending manual sell for `mint`, if any.
+    pub async fn take_manual_exit(&self, mint: &str) -> Option<f64> {
+        let handle = self.positions.lock().await.get(mint).cloned()?;
+        let percent = handle.manual_exit.lock().await.take();
+        percent
+    }
+
+    async fn unsubscribe(&self, idx: usize, sub_id: u64) {
+        if let Some(sender) = self.ws_control_senders.get(idx) {
+            let (u_tx, u_rx) = oneshot::channel();
+            let _ = sender.send(WsRequest::Unsubscribe { sub_id, resp: u_tx }).await;
+            let _ = tokio::time::timeout(std::time::Duration::from_secs(3), u_rx).await;
+        }
+    }
+
+    async fn retire(&self, snapshot: PositionSnapshot) {
+        self.positions.lock().await.remove(&snapshot.mint);
+        let mut recent = self.recent.lock().await;
+        recent.push_front(snapshot);
+        recent.truncate(RECENT_POSITIONS_MAX);
+    }
+}
+
+async fn run_position(
+    mut snapshot: PositionSnapshot,
+    mut rx: mpsc::Receiver<Command>,
+    registry: PositionRegistry,
+) {
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            Command::Snapshot { resp } => {
+                let _ = resp.send(snapshot.clone());
+            }
+            Command::SetSubscription { sub, resp } => {
+                let prev = std::mem::replace(&mut snapshot.subscription, sub);
+                let _ = resp.send(prev);
+            }
+            Command::Transition { to, detail, open, partial, resp } => {
+                let from = snapshot.state;
+                if !from.can_transition_to(to) {
+                    debug!("Rejected transition {} -> {} for {}", from, to, snapshot.mint);
+                    let _ = resp.send(Err(format!(
+                        "illegal transition {} -> {} for {}",
+                        from, to, snapshot.mint
+                    )));
+                    continue;
+                }
+
+                if let Some((holding, buy_record)) = open.map(|o| *o) {
+                    registry.holdings.lock().await.insert(snapshot.mint.clone(), holding.clone());
+                    registry.held.send_if_modified(|held| held.insert(snapshot.mint.clone()));
+                    snapshot.holding = Some(holding);
+                    snapshot.buy_record = Some(buy_record);
+                }
+                if let Some(p) = partial {
+                    if let Some(h) = snapshot.holding.as_mut() {
+                        h.amount = h.amount.saturating_sub(p.sold_amount);
+                        h.triggered_tp_levels.extend(p.triggered_tp);
+                        h.triggered_sl_levels.extend(p.triggered_sl);
+                        registry.holdings.lock().await.insert(snapshot.mint.clone(), h.clone());
+                    }
+                }
+
+                let now = crate::recorder::now();
+                snapshot.state = to;
+                snapshot.updated_at = now;
+                snapshot.history.push(TransitionRecord { from, to, at: now, detail: detail.clone() });
+
+                let detail_str = detail.clone().unwrap_or_default();
+                if to.is_terminal() || to == PositionState::Open {
+                    info!("Position {}: {} -> {} {}", snapshot.mint, from, to, detail_str);
+                } else {
+                    debug!("Position {}: {} -> {} {}", snapshot.mint, from, to, detail_str);
+                }
+                if to.is_terminal() {
+                    let level = if to == PositionState::Failed { "warn" } else { "info" };
+                    registry
+                        .bot_control
+                        .add_log(level, format!("Position {} {}", snapshot.mint, to), detail.clone())
+                        .await;
+                }
+                let _ = registry.ws_tx.send(
+                    serde_json::json!({
+                        "type": "position-state",
+                        "mint": snapshot.mint,
+                        "from": from,
+                        "to": to,
+                        "detail": detail,
+                        "at": now.to_rfc3339(),
+                    })
+                    .to_string(),
+                );
+
+                if to.is_terminal() {
+                    // Tear down everything the position owned before retiring it
+                    if let Some((idx, sub_id)) = snapshot.subscription.take() {
+                        registry.unsubscribe(idx, sub_id).await;
+                    }
+                    if registry.holdings.lock().await.remove(&snapshot.mint).is_some() {
+                        debug!("Removed {} from monitor", snapshot.mint);
+                    }
+                    registry.held.send_if_modified(|held| held.remove(&snapshot.mint));
+                    let _ = resp.send(Ok(snapshot.clone()));
+                    registry.retire(snapshot).await;
+                    return;
+                }
+                let _ = resp.send(Ok(snapshot.clone()));
+            }
+        }
+    }
+    // All senders gone without reaching a terminal state (registry dropped)
+    warn!("Position actor for {} stopped in state {}", snapshot.mint, snapshot.state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_open_positions_can_start_an_exit() {
+        use PositionState::*;
+        assert!(Open.can_transition_to(Exiting));
+        assert!(!Exiting.can_transition_to(Exiting));
+        assert!(!Buying.can_transition_to(Exiting));
+        assert!(!Closed.can_transition_to(Open));
+        assert!(!Failed.can_transition_to(Pending));
+    }
+
+    #[test]
+    fn terminal_states() {
+        assert!(PositionState::Closed.is_terminal());
+        assert!(PositionState::Failed.is_terminal());
+        assert!(!PositionState::Exiting.is_terminal());
+    }
+
+    #[tokio::test]
+    async fn begin_says_why_it_refused() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        let (ws_tx, _) = broadcast::channel(16);
+        let registry = PositionRegistry::new(
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(Vec::new()),
+            Arc::new(BotControl::new_with_mode(crate::api::BotMode::DryRun)),
+            ws_tx,
+        );
+        registry.begin("A", 1).await.unwrap();
+        assert_eq!(registry.begin("A", 2).await, Err(BeginRefused::AlreadyActive("A".into())));
+        assert_eq!(registry.begin("B", 1).await, Err(BeginRefused::MaxHeld { active: 1, max: 1 }));
+    }
+}
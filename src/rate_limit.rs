@@ -0,0 +1,243 @@
+//! Per-endpoint RPC rate limiting and credit budgeting.
+//!
+//! Each RPC URL gets a token bucket refilled at `rpc_rate_limit_per_sec`
+//! credits per second (overridable per URL in `rpc_rate_limits`) holding at
+//! most `rpc_rate_limit_burst` credits. A call costs its method's weight from
+//! `rpc_method_weights` (1 when unlisted), and the bucket holds at least the
+//! heaviest weight so every method can be served. Lower priorities must leave
+//! a share of the bucket untouched, so when the budget runs low background
+//! reads (metadata) are shed first and trading reads keep the remainder.
+//!
+//! Off-chain metadata and image fetches (IPFS gateways and the like) draw
+//! from their own `offchain` bucket at low priority and are shed the same way.
+
+use crate::settings::Settings;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Reads on the buy/sell path; may drain the bucket
+    Trading,
+    /// Everything else that should eventually be served
+    Normal,
+    /// Nice-to-have reads (metadata); shed when the budget is low
+    Low,
+}
+
+impl Priority {
+    /// Share of the bucket that must remain after a call at this priority.
+    fn reserve_fraction(self) -> f64 {
+        match self {
+            Priority::Trading => 0.0,
+            Priority::Normal => 0.1,
+            Priority::Low => 0.5,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    credits: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    calls: u64,
+    credits_used: f64,
+    denied: u64,
+    by_method: BTreeMap<String, u64>,
+    credits_by_method: BTreeMap<String, f64>,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    buckets: HashMap<String, Bucket>,
+    usage: HashMap<String, Usage>,
+    shed: BTreeMap<String, u64>,
+}
+
+static STATE: Lazy<Mutex<LimiterState>> = Lazy::new(|| Mutex::new(LimiterState::default()));
+
+/// Bucket for off-chain metadata and image fetches. Rated like an RPC URL;
+/// `rpc_rate_limits.offchain` overrides the rate.
+pub const OFFCHAIN: &str = "offchain";
+
+fn rate_for(url: &str, settings: &Settings) -> f64 {
+    settings
+        .rpc_rate_limits
+        .get(url)
+        .copied()
+        .unwrap_or(settings.rpc_rate_limit_per_sec)
+}
+
+/// Credits one call of `method` costs.
+pub fn method_weight(method: &str, settings: &Settings) -> f64 {
+    settings.rpc_method_weights.get(method).copied().unwrap_or(1.0)
+}
+
+/// Bucket size: the configured burst, raised to the heaviest method weight.
+fn capacity(settings: &Settings) -> f64 {
+    settings
+        .rpc_method_weights
+        .values()
+        .copied()
+        .fold(settings.rpc_rate_limit_burst.max(1.0), f64::max)
+}
+
+/// Take credits for one `method` call against `url`. Err carries how long
+/// until the call would fit the budget at this priority.
+pub async fn try_acquire(url: &str, method: &str, priority: Priority, settings: &Settings) -> Result<(), Duration> {
+    let rate = rate_for(url, settings);
+    let weight = method_weight(method, settings);
+    let mut state = STATE.lock().await;
+    if rate > 0.0 {
+        let capacity = capacity(settings);
+        let now = Instant::now();
+        let bucket = state
+            .buckets
+            .entry(url.to_string())
+            .or_insert(Bucket { credits: capacity, refilled_at: now });
+        bucket.credits = (bucket.credits + now.duration_since(bucket.refilled_at).as_secs_f64() * rate).min(capacity);
+        bucket.refilled_at = now;
+        let needed = weight + capacity * priority.reserve_fraction();
+        if bucket.credits < needed {
+            let wait = Duration::from_secs_f64((needed - bucket.credits) / rate);
+            state.usage.entry(url.to_string()).or_default().denied += 1;
+            return Err(wait);
+        }
+        bucket.credits -= weight;
+    }
+    let usage = state.usage.entry(url.to_string()).or_default();
+    usage.calls += 1;
+    usage.credits_used += weight;
+    *usage.by_method.entry(method.to_string()).or_default() += 1;
+    *usage.credits_by_method.entry(method.to_string()).or_default() += weight;
+    Ok(())
+}
+
+/// Count a call dropped because every endpoint was over budget.
+pub async fn record_shed(method: &str) {
+    *STATE.lock().await.shed.entry(method.to_string()).or_default() += 1;
+}
+
+/// Take a credit from the `offchain` bucket for a `kind` fetch. False (and
+/// counted as shed) when the budget is too low for a low-priority call.
+pub async fn allow_offchain(kind: &str, settings: &Settings) -> bool {
+    if try_acquire(OFFCHAIN, kind, Priority::Low, settings).await.is_ok() {
+        return true;
+    }
+    record_shed(kind).await;
+    false
+}
+
+/// Refill every bucket and zero the usage counters for the next engine.
+pub(crate) async fn reset() {
+    *STATE.lock().await = LimiterState::default();
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointUsage {
+    pub url: String,
+    pub rate_per_sec: f64,
+    /// None when the endpoint is unlimited
+    pub credits_available: Option<f64>,
+    pub calls: u64,
+    pub credits_used: f64,
+    pub denied: u64,
+    pub by_method: BTreeMap<String, u64>,
+    pub credits_by_method: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub endpoints: Vec<EndpointUsage>,
+    /// Low-priority calls dropped by method
+    pub shed: BTreeMap<String, u64>,
+}
+
+/// Usage counters for `/api/rpc/usage`.
+pub async fn usage(settings: &Settings) -> UsageReport {
+    let state = STATE.lock().await;
+    let now = Instant::now();
+    let capacity = capacity(settings);
+    let offchain = state.usage.contains_key(OFFCHAIN).then(|| OFFCHAIN.to_string());
+    let endpoints = settings
+        .solana_rpc_urls
+        .iter()
+        .chain(offchain.iter())
+        .map(|url| {
+            let rate = rate_for(url, settings);
+            let credits_available = (rate > 0.0).then(|| {
+                state
+                    .buckets
+                    .get(url)
+                    .map(|b| (b.credits + now.duration_since(b.refilled_at).as_secs_f64() * rate).min(capacity))
+                    .unwrap_or(capacity)
+            });
+            let usage = state.usage.get(url);
+            EndpointUsage {
+                url: crate::rpc_pool::redact_url(url),
+                rate_per_sec: rate,
+                credits_available: credits_available.map(|c| (c * 10.0).round() / 10.0),
+                calls: usage.map(|u| u.calls).unwrap_or(0),
+                credits_used: usage.map(|u| u.credits_used).unwrap_or(0.0),
+                denied: usage.map(|u| u.denied).unwrap_or(0),
+                by_method: usage.map(|u| u.by_method.clone()).unwrap_or_default(),
+                credits_by_method: usage.map(|u| u.credits_by_method.clone()).unwrap_or_default(),
+            }
+        })
+        .collect();
+    UsageReport { endpoints, shed: state.shed.clone() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn low_priority_is_denied_before_trading() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        let url = "http://limit-test";
+        settings.rpc_rate_limits.insert(url.to_string(), 1.0);
+        settings.rpc_rate_limit_burst = 10.0;
+        settings.rpc_method_weights.insert("getProgramAccounts".into(), 4.0);
+
+        // 10 credits: one heavy low-priority call leaves 6, the next would dip below the 5 reserve
+        assert!(try_acquire(url, "getProgramAccounts", Priority::Low, &settings).await.is_ok());
+        assert!(try_acquire(url, "getProgramAccounts", Priority::Low, &settings).await.is_err());
+        // Trading may still spend what is left
+        assert!(try_acquire(url, "getAccountInfo", Priority::Trading, &settings).await.is_ok());
+        assert!(try_acquire(url, "getProgramAccounts", Priority::Trading, &settings).await.is_ok());
+        let wait = try_acquire(url, "getProgramAccounts", Priority::Trading, &settings).await.unwrap_err();
+        assert!(wait > Duration::from_secs(2));
+
+        let state = STATE.lock().await;
+        let counted = state.usage.get(url).unwrap();
+        assert_eq!(counted.calls, 3);
+        assert_eq!(counted.denied, 2);
+        assert_eq!(counted.credits_by_method["getProgramAccounts"], 8.0);
+    }
+
+    #[tokio::test]
+    async fn offchain_fetches_are_shed_under_pressure() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.rpc_rate_limits.insert(OFFCHAIN.to_string(), 0.001);
+        settings.rpc_rate_limit_burst = 4.0;
+        settings.rpc_method_weights.clear();
+
+        // Low priority keeps half the bucket: two of four credits are spendable
+        assert!(allow_offchain("image_metadata", &settings).await);
+        assert!(allow_offchain("image_metadata", &settings).await);
+        assert!(!allow_offchain("image_metadata", &settings).await);
+
+        let report = usage(&settings).await;
+        assert_eq!(report.shed["image_metadata"], 1);
+        let offchain = report.endpoints.iter().find(|e| e.url == OFFCHAIN).unwrap();
+        assert_eq!(offchain.calls, 2);
+    }
+}
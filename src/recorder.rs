@@ -0,0 +1,428 @@
+//! Record-and-replay of raw feed messages.
+//!
+//! With `record_path` set, every message that reaches `process_message` is
+//! written with its receive time to a gzip-compressed JSONL file, together
+//! with every raw JSON-RPC response seen by `rpc_pool::post`, every call made
+//! through the shared `RpcClient` (see [`rpc_client`]), every off-chain
+//! metadata document fetched with [`http_get`] and the first sighting of each
+//! coin. Running with `--replay <file>` feeds the recorded messages back
+//! through the same pipeline (dry-run, no websockets) while a local mock RPC
+//! server answers each request with the response recorded for the same
+//! method and params and [`http_get`] answers from the recorded documents,
+//! so detection and filtering bugs can be reproduced offline. During replay
+//! [`now`] follows the recorded receive times instead of the wall clock.
+//! `sol_beast export-dataset <file>` turns the sightings and the curve reads
+//! that followed them into a backtest dataset.
+//!
+//! The file is appended to; each run adds a gzip member, and a file cut off
+//! by a crash is read up to the last complete line.
+
+use axum::{extract::State, routing::post, Json, Router};
+use chrono::{DateTime, Utc};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use futures_util::future::BoxFuture;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::client_error::Result as ClientResult;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::{mpsc as std_mpsc, Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+
+/// Lines written between forced flushes of the gzip stream.
+const FLUSH_EVERY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Record {
+    /// A raw message as received by `process_message`.
+    Feed { t_ms: i64, msg: String },
+    /// A JSON-RPC request and the raw response body it got.
+    Rpc { t_ms: i64, method: String, params: Value, response: String },
+    /// An off-chain document (token metadata JSON) fetched over HTTP.
+    Http { t_ms: i64, url: String, body: String },
+    /// First sighting of a new coin; anchors its price path when the
+    /// recording is exported as a backtest dataset.
+    Detection { t_ms: i64, mint: String },
+}
+
+/// Background writer for one recording file.
+pub struct Recorder {
+    tx: Option<std_mpsc::Sender<String>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn open(path: &str) -> io::Result<Recorder> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        let (tx, rx) = std_mpsc::channel::<String>();
+        let writer = std::thread::Builder::new()
+            .name("feed-recorder".into())
+            .spawn(move || {
+                let mut gz = GzEncoder::new(file, Compression::fast());
+                let mut unflushed = 0usize;
+                loop {
+                    match rx.recv_timeout(Duration::from_secs(1)) {
+                        Ok(line) => {
+                            if let Err(e) = gz.write_all(line.as_bytes()).and_then(|_| gz.write_all(b"\n")) {
+                                warn!("Feed recorder write failed: {}", e);
+                                return;
+                            }
+                            unflushed += 1;
+                            if unflushed >= FLUSH_EVERY {
+                                let _ = gz.flush();
+                                unflushed = 0;
+                            }
+                        }
+                        Err(std_mpsc::RecvTimeoutError::Timeout) => {
+                            if unflushed > 0 {
+                                let _ = gz.flush();
+                                unflushed = 0;
+                            }
+                        }
+                        Err(std_mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                if let Err(e) = gz.finish() {
+                    warn!("Feed recorder could not finish the gzip stream: {}", e);
+                }
+            })?;
+        Ok(Recorder { tx: Some(tx), writer: Some(writer) })
+    }
+
+    pub fn write(&self, record: &Record) {
+        if let (Some(tx), Ok(line)) = (self.tx.as_ref(), serde_json::to_string(record)) {
+            let _ = tx.send(line);
+        }
+    }
+
+    /// Flush and close the file, waiting for the writer thread.
+    pub fn close(mut self) {
+        self.tx.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+static RECORDER: RwLock<Option<Recorder>> = RwLock::new(None);
+
+/// Start recording to `path` until [`stop`].
+pub fn start(path: &str) -> io::Result<()> {
+    let mut current = RECORDER.write().unwrap();
+    if current.is_some() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "feed recorder already started"));
+    }
+    *current = Some(Recorder::open(path)?);
+    info!("Recording feed messages and RPC responses to {}", path);
+    Ok(())
+}
+
+/// Flush and close the recording, and leave replay mode (the engine calls
+/// this when it stops).
+pub fn stop() {
+    let recorder = RECORDER.write().unwrap().take();
+    if let Some(recorder) = recorder {
+        recorder.close();
+    }
+    *REPLAY_HTTP.write().unwrap() = None;
+    *REPLAY_CLOCK.lock().unwrap() = None;
+}
+
+pub fn record_feed(msg: &str) {
+    if let Some(recorder) = RECORDER.read().unwrap().as_ref() {
+        recorder.write(&Record::Feed { t_ms: chrono::Utc::now().timestamp_millis(), msg: msg.to_string() });
+    }
+}
+
+pub fn record_rpc(request: &Value, response: &str) {
+    if let Some(recorder) = RECORDER.read().unwrap().as_ref() {
+        recorder.write(&Record::Rpc {
+            t_ms: chrono::Utc::now().timestamp_millis(),
+            method: request.get("method").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+            params: request.get("params").cloned().unwrap_or(Value::Null),
+            response: response.to_string(),
+        });
+    }
+}
+
+pub fn record_detection(mint: &str, at: DateTime<Utc>) {
+    if let Some(recorder) = RECORDER.read().unwrap().as_ref() {
+        recorder.write(&Record::Detection { t_ms: at.timestamp_millis(), mint: mint.to_string() });
+    }
+}
+
+/// Transport for the shared `RpcClient` that records each call like
+/// `rpc_pool::post` does, so `get_balance`, `get_latest_blockhash` and the
+/// like can be answered during replay.
+struct RecordingSender {
+    inner: RpcClient,
+}
+
+impl RpcSender for RecordingSender {
+    fn send<'a, 'b>(&'a self, request: RpcRequest, params: Value) -> BoxFuture<'b, ClientResult<Value>>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        Box::pin(async move {
+            let result = self.inner.send::<Value>(request, params.clone()).await;
+            if let Ok(value) = &result {
+                let call = json!({ "method": request.to_string(), "params": params });
+                record_rpc(&call, &json!({ "jsonrpc": "2.0", "id": 1, "result": value }).to_string());
+            }
+            result
+        })
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// An `RpcClient` for `url` whose calls go into the recording.
+pub fn rpc_client(url: String) -> RpcClient {
+    RpcClient::new_sender(
+        RecordingSender { inner: RpcClient::new(url) },
+        RpcClientConfig::with_commitment(CommitmentConfig::default()),
+    )
+}
+
+/// Off-chain documents served during replay, by URL.
+static REPLAY_HTTP: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// GET an off-chain document (token metadata JSON). Recorded while recording;
+/// answered from the recording during replay, where unrecorded URLs fail.
+pub async fn http_get(url: &str) -> Result<String, String> {
+    if let Some(recorded) = REPLAY_HTTP.read().unwrap().as_ref() {
+        return recorded.get(url).cloned().ok_or_else(|| format!("{} is not in the recording", url));
+    }
+    let resp = crate::rpc_pool::http().get(url).send().await.map_err(|e| format!("HTTP error: {}", e))?;
+    let body = resp.text().await.map_err(|e| format!("failed to read body: {}", e))?;
+    if let Some(recorder) = RECORDER.read().unwrap().as_ref() {
+        recorder.write(&Record::Http { t_ms: Utc::now().timestamp_millis(), url: url.to_string(), body: body.clone() });
+    }
+    Ok(body)
+}
+
+/// Serve [`http_get`] from the documents in `records` until [`stop`].
+pub fn replay_http(records: &[Record]) {
+    let documents = records
+        .iter()
+        .filter_map(|r| match r {
+            Record::Http { url, body, .. } => Some((url.clone(), body.clone())),
+            _ => None,
+        })
+        .collect();
+    *REPLAY_HTTP.write().unwrap() = Some(documents);
+}
+
+/// (recorded time of the last replayed message, when it was sent, clock rate)
+static REPLAY_CLOCK: Lazy<std::sync::Mutex<Option<(i64, Instant, f64)>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Current time for detection and trade timestamps: the wall clock, or during
+/// replay the recorded time of the last message plus the time since it was fed.
+pub fn now() -> DateTime<Utc> {
+    match *REPLAY_CLOCK.lock().unwrap() {
+        Some((t_ms, sent, rate)) => {
+            let advanced_ms = (sent.elapsed().as_millis() as f64 * rate) as i64;
+            DateTime::from_timestamp_millis(t_ms + advanced_ms).unwrap_or_else(Utc::now)
+        }
+        None => Utc::now(),
+    }
+}
+
+/// Read every complete record from a recording.
+pub fn read_recording(path: &str) -> io::Result<Vec<Record>> {
+    let reader = BufReader::new(MultiGzDecoder::new(std::fs::File::open(path)?));
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            // Writer was killed mid-stream: keep what was flushed
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(r) => records.push(r),
+            Err(e) => warn!("Skipping unreadable recording line: {}", e),
+        }
+    }
+    Ok(records)
+}
+
+fn rpc_key(method: &str, params: &Value) -> String {
+    json!([method, params]).to_string()
+}
+
+type RecordedResponses = Arc<Mutex<HashMap<String, VecDeque<String>>>>;
+
+/// Serve the recorded RPC responses on a local port; returns its URL.
+/// Repeated identical requests get the recorded answers in order, then the
+/// last one again. Unrecorded requests get a `null` result.
+pub async fn start_mock_rpc(records: &[Record]) -> io::Result<String> {
+    let mut responses: HashMap<String, VecDeque<String>> = HashMap::new();
+    for record in records {
+        if let Record::Rpc { method, params, response, .. } = record {
+            responses.entry(rpc_key(method, params)).or_default().push_back(response.clone());
+        }
+    }
+    let state: RecordedResponses = Arc::new(Mutex::new(responses));
+    let app = Router::new()
+        .route(
+            "/",
+            post(|State(state): State<RecordedResponses>, Json(req): Json<Value>| async move {
+                let method = req.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+                let key = rpc_key(method, req.get("params").unwrap_or(&Value::Null));
+                let recorded = {
+                    let mut state = state.lock().await;
+                    state.get_mut(&key).and_then(|queue| {
+                        if queue.len() > 1 { queue.pop_front() } else { queue.front().cloned() }
+                    })
+                };
+                let mut body = recorded
+                    .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+                    .unwrap_or_else(|| json!({ "jsonrpc": "2.0", "result": null }));
+                body["id"] = req.get("id").cloned().unwrap_or(json!(1));
+                Json(body)
+            }),
+        )
+        .with_state(state);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/", listener.local_addr()?);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            warn!("Replay mock RPC server stopped: {}", e);
+        }
+    });
+    Ok(url)
+}
+
+/// Feed the recorded messages into `tx`, keeping their original spacing
+/// divided by `speed` (0 = as fast as the pipeline accepts them).
+pub async fn replay(records: Vec<Record>, tx: mpsc::Sender<String>, speed: f64) {
+    let feed: Vec<(i64, String)> = records
+        .into_iter()
+        .filter_map(|r| match r {
+            Record::Feed { t_ms, msg } => Some((t_ms, msg)),
+            Record::Rpc { .. } | Record::Http { .. } | Record::Detection { .. } => None,
+        })
+        .collect();
+    info!("Replaying {} recorded feed messages (speed {})", feed.len(), speed);
+    let mut prev_ms: Option<i64> = None;
+    for (t_ms, msg) in feed {
+        if let (Some(prev), true) = (prev_ms, speed > 0.0) {
+            let gap_ms = ((t_ms - prev).max(0) as f64 / speed) as u64;
+            if gap_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(gap_ms)).await;
+            }
+        }
+        prev_ms = Some(t_ms);
+        // Between messages the clock runs at replay speed; flat out it runs in real time
+        *REPLAY_CLOCK.lock().unwrap() = Some((t_ms, Instant::now(), if speed > 0.0 { speed } else { 1.0 }));
+        if tx.send(msg).await.is_err() {
+            return;
+        }
+    }
+    info!("Replay finished");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn recording_round_trips_and_mock_rpc_answers_from_it() {
+        let path = std::env::temp_dir().join(format!("sol_beast_rec_{}.jsonl.gz", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "getAccountInfo", "params": ["abc", { "encoding": "base64" }] });
+        let recorder = Recorder::open(&path).unwrap();
+        recorder.write(&Record::Feed { t_ms: 1, msg: "{\"params\":{}}".into() });
+        recorder.write(&Record::Rpc {
+            t_ms: 2,
+            method: "getAccountInfo".into(),
+            params: request["params"].clone(),
+            response: r#"{"jsonrpc":"2.0","id":1,"result":{"value":{"lamports":5}}}"#.into(),
+        });
+        recorder.close();
+
+        let records = read_recording(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(matches!(&records[0], Record::Feed { msg, .. } if msg == "{\"params\":{}}"));
+
+        let url = start_mock_rpc(&records).await.unwrap();
+        let resp: Value = reqwest::Client::new().post(&url).json(&request).send().await.unwrap().json().await.unwrap();
+        assert_eq!(resp["id"], 7);
+        assert_eq!(resp["result"]["value"]["lamports"], 5);
+        let miss = json!({ "jsonrpc": "2.0", "id": 8, "method": "getBalance", "params": ["abc"] });
+        let resp: Value = reqwest::Client::new().post(&url).json(&miss).send().await.unwrap().json().await.unwrap();
+        assert!(resp["result"].is_null());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn rpc_client_calls_and_offchain_fetches_are_recorded() {
+        use crate::mock_cluster::{Account, MockCluster};
+        let cluster = MockCluster::start().await.unwrap();
+        let owner = solana_sdk::pubkey::Pubkey::new_unique();
+        cluster.set_account(&owner, Account { owner: solana_sdk::system_program::id(), lamports: 4242, data: vec![] });
+        let url = cluster.serve_document("meta.json", r#"{"image":"ipfs://x"}"#);
+
+        let path = std::env::temp_dir().join(format!("sol_beast_rec_global_{}.jsonl.gz", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+        start(&path).unwrap();
+
+        assert_eq!(rpc_client(cluster.rpc_url()).get_balance(&owner).await.unwrap(), 4242);
+        assert_eq!(http_get(&url).await.unwrap(), r#"{"image":"ipfs://x"}"#);
+
+        // The writer flushes after a second of quiet
+        tokio::time::sleep(Duration::from_millis(1_500)).await;
+        let records = read_recording(&path).unwrap();
+        let balance = records.iter().find_map(|r| match r {
+            Record::Rpc { method, params, response, .. } if method == "getBalance" && params[0] == owner.to_string() => {
+                Some(response.clone())
+            }
+            _ => None,
+        });
+        assert_eq!(serde_json::from_str::<Value>(&balance.unwrap()).unwrap()["result"]["value"], 4242);
+        assert!(records.iter().any(|r| matches!(r, Record::Http { url: u, body, .. } if *u == url && body.contains("ipfs://x"))));
+
+        // Replay answers the document from the recording
+        replay_http(&records);
+        assert_eq!(http_get(&url).await.unwrap(), r#"{"image":"ipfs://x"}"#);
+        assert!(http_get("http://not-recorded.invalid/x").await.is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn replay_clock_follows_recorded_times() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        let recorded_ms = Utc::now().timestamp_millis() - 2_000;
+        let (tx, mut rx) = mpsc::channel(4);
+        replay(vec![Record::Feed { t_ms: recorded_ms, msg: "{}".into() }], tx, 0.0).await;
+        assert_eq!(rx.recv().await.as_deref(), Some("{}"));
+        let clock = now().timestamp_millis();
+        *REPLAY_CLOCK.lock().unwrap() = None;
+        // Recorded time plus the few ms since it was fed, not the wall clock
+        assert!(clock >= recorded_ms && clock < recorded_ms + 500);
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
     models::{
     
     BondingCurveState,
+    GlobalAccount,
     PriceCache,
     RpcResponse,
     OffchainTokenMetadata,
@@ -134,14 +135,13 @@ fn process_pump_create_data(
 // a rotating sequential probe we no longer need it.
 use log::{info, warn, error, debug};
 use mpl_token_metadata::accounts::Metadata;
-use reqwest::Client;
 use serde::Deserialize;
 use serde_json::{json, Value};
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use crate::tx_builder::{build_sell_instruction};
 use crate::idl::load_all_idls;
 use crate::onchain_idl::get_instruction_discriminator;
-use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent};
+use spl_associated_token_account::{get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent};
 use solana_program::pubkey::Pubkey;
 use solana_program::instruction::AccountMeta;
 use spl_token::{self};
@@ -155,12 +155,12 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use std::{str::FromStr, sync::Arc, time::Instant};
-use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::Mutex;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::time::Duration;
 use crate::idl::SimpleIdl;
+use crate::rate_limit::Priority;
 
 /// Bonding curve layout constants (offsets after 8-byte discriminator)
 const BONDING_CURVE_CREATOR_OFFSET: usize = 41; // 5×u64(40) + bool(1)
@@ -169,13 +169,40 @@ const BONDING_CURVE_MAYHEM_OFFSET: usize = 73;  // creator_offset + pubkey(32)
 /// Parse the `is_mayhem_mode` flag from bonding curve account data (after discriminator).
 /// The flag is at offset 73 (after 5×u64 + bool + pubkey).
 fn parse_is_mayhem_mode(slice: &[u8]) -> bool {
-    if slice.len() >= BONDING_CURVE_MAYHEM_OFFSET + 1 {
+    if slice.len() > BONDING_CURVE_MAYHEM_OFFSET {
         slice[BONDING_CURVE_MAYHEM_OFFSET] != 0
     } else {
         false
     }
 }
 
+/// Decode a bonding curve account (discriminator included). None when the
+/// data is not a pump.fun bonding curve.
+pub fn parse_bonding_curve(decoded: &[u8]) -> Option<BondingCurveState> {
+    const PUMP_CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+    if decoded.len() < 49 || decoded[..8] != PUMP_CURVE_DISCRIMINATOR {
+        return None;
+    }
+    // Layout: 5*u64 (40 bytes) + bool (1 byte) + creator (32 bytes) + is_mayhem_mode (1 byte)
+    let slice = &decoded[8..];
+    let read_u64 = |at: usize| slice[at..at + 8].try_into().map(u64::from_le_bytes).unwrap_or(0);
+    let creator = if slice.len() >= BONDING_CURVE_CREATOR_OFFSET + PUBKEY_SIZE {
+        Pubkey::try_from(&slice[BONDING_CURVE_CREATOR_OFFSET..BONDING_CURVE_CREATOR_OFFSET + PUBKEY_SIZE]).ok()
+    } else {
+        None
+    };
+    Some(BondingCurveState {
+        virtual_token_reserves: read_u64(0),
+        virtual_sol_reserves: read_u64(8),
+        real_token_reserves: read_u64(16),
+        real_sol_reserves: read_u64(24),
+        token_total_supply: read_u64(32),
+        complete: slice[40] != 0,
+        creator,
+        is_mayhem_mode: parse_is_mayhem_mode(slice),
+    })
+}
+
 
 /// Detect which token program (SPL Token or Token-2022) owns the given mint.
 /// Queries the mint account and inspects the `owner` field.
@@ -185,31 +212,46 @@ pub async fn detect_token_program_for_mint(
     rpc_client: &Arc<RpcClient>,
     settings: &Arc<Settings>,
 ) -> Pubkey {
+    if let Some(program) = crate::hot_cache::token_program(mint).await {
+        return program;
+    }
+    match detect_token_program_uncached(mint, rpc_client, settings).await {
+        Some(program) => {
+            crate::hot_cache::remember_token_program(mint, program).await;
+            program
+        }
+        // Default to Token-2022 for pump.fun (all new tokens use it)
+        None => Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap(),
+    }
+}
+
+async fn detect_token_program_uncached(
+    mint: &str,
+    _rpc_client: &Arc<RpcClient>,
+    settings: &Arc<Settings>,
+) -> Option<Pubkey> {
     let request = json!({
         "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo",
         "params": [ mint, { "encoding": "base64", "commitment": "confirmed" } ]
     });
-    match fetch_with_fallback::<Value>(request, "getAccountInfo", rpc_client, settings).await {
-        Ok(resp) => {
-            if let Some(result_val) = resp.result {
-                let account = if let Some(v) = result_val.get("value") { v.clone() } else { result_val };
-                if let Some(owner) = account.get("owner").and_then(|o| o.as_str()) {
-                    if owner == TOKEN_2022_PROGRAM_ID {
-                        debug!("Mint {} is owned by Token-2022", mint);
-                        return Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap();
-                    } else if owner == TOKEN_PROGRAM_ID {
-                        debug!("Mint {} is owned by SPL Token", mint);
-                        return Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap();
-                    } else {
-                        debug!("Mint {} has unknown owner {}, defaulting to Token-2022", mint, owner);
-                    }
+    match crate::hedge::hedged_read("mint_account", &request, settings, crate::hedge::account_populated).await {
+        Ok(result_val) => {
+            let account = if let Some(v) = result_val.get("value") { v.clone() } else { result_val };
+            if let Some(owner) = account.get("owner").and_then(|o| o.as_str()) {
+                if owner == TOKEN_2022_PROGRAM_ID {
+                    debug!("Mint {} is owned by Token-2022", mint);
+                    return Pubkey::from_str(TOKEN_2022_PROGRAM_ID).ok();
+                } else if owner == TOKEN_PROGRAM_ID {
+                    debug!("Mint {} is owned by SPL Token", mint);
+                    return Pubkey::from_str(TOKEN_PROGRAM_ID).ok();
+                } else {
+                    debug!("Mint {} has unknown owner {}, defaulting to Token-2022", mint, owner);
                 }
             }
         }
         Err(e) => debug!("Failed to detect token program for {}: {}", mint, e),
     }
-    // Default to Token-2022 for pump.fun (all new tokens use it)
-    Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap()
+    None
 }
 
 /// Fetches transaction details and extracts pump.fun token creation information.
@@ -341,10 +383,10 @@ pub async fn fetch_transaction_details(
 
 pub async fn fetch_token_metadata(
     mint: &str,
-    rpc_client: &Arc<RpcClient>,
+    _rpc_client: &Arc<RpcClient>,
     settings: &Arc<Settings>,
 ) -> Result<(Option<Metadata>, Option<OffchainTokenMetadata>, Option<Vec<u8>>), Box<dyn std::error::Error + Send + Sync>> {
-    let metadata_program_pk = Pubkey::from_str(&settings.metadata_program)?;
+    let metadata_program_pk = settings.address_book()?.metadata_program;
     let mint_pk = Pubkey::from_str(mint)?;
     let metadata_pda = Pubkey::find_program_address(
         &[b"metadata", metadata_program_pk.as_ref(), mint_pk.as_ref()],
@@ -352,13 +394,14 @@ pub async fn fetch_token_metadata(
     )
     .0;
     debug!("Fetching token metadata for mint {} -> metadata PDA {}", mint, metadata_pda);
-    let data: RpcResponse<Value> = fetch_with_fallback::<Value>(
+    // Metadata is display-only: shed it first when the RPC budget runs low
+    let data: RpcResponse<Value> = fetch_with_priority::<Value>(
         json!({
             "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo",
             "params": [ metadata_pda.to_string(), { "encoding": "base64", "commitment": "confirmed" } ]
         }),
         "getAccountInfo",
-        rpc_client,
+        Priority::Low,
         settings,
     )
     .await?;
@@ -371,68 +414,64 @@ pub async fn fetch_token_metadata(
                     Ok(meta) => {
                         // Try to fetch off-chain metadata JSON from the URI in on-chain metadata
                         let uri = meta.uri.trim_end_matches('\u{0}').to_string();
-                        if !uri.is_empty() && (uri.starts_with("http://") || uri.starts_with("https://")) {
-                            let client = Client::new();
-                            match client.get(&uri).send().await {
-                                Ok(resp) => match resp.text().await {
-                                    Ok(body) => match serde_json::from_str::<serde_json::Value>(&body) {
-                                            Ok(body_val) => {
-                                                // Try to pull common top-level fields from the JSON using flexible heuristics
-                                                fn extract_first_string(v: &serde_json::Value, keys: &[&str]) -> Option<String> {
-                                                    for key in keys {
-                                                        if let Some(field) = v.get(*key) {
-                                                            match field {
-                                                                serde_json::Value::String(s) => return Some(s.clone()),
-                                                                serde_json::Value::Object(map) => {
-                                                                    // Try `en` locale or first string value
-                                                                    if let Some(serde_json::Value::String(s2)) = map.get("en") {
-                                                                        return Some(s2.clone());
-                                                                    }
-                                                                    for (_k, val) in map.iter() {
-                                                                        if let serde_json::Value::String(s3) = val {
-                                                                            return Some(s3.clone());
-                                                                        }
-                                                                    }
+                        if !uri.is_empty()
+                            && (uri.starts_with("http://") || uri.starts_with("https://"))
+                            && crate::rate_limit::allow_offchain("offchain_metadata", settings).await
+                        {
+                            match crate::recorder::http_get(&uri).await {
+                                Ok(body) => match serde_json::from_str::<serde_json::Value>(&body) {
+                                        Ok(body_val) => {
+                                            // Try to pull common top-level fields from the JSON using flexible heuristics
+                                            fn extract_first_string(v: &serde_json::Value, keys: &[&str]) -> Option<String> {
+                                                for key in keys {
+                                                    if let Some(field) = v.get(*key) {
+                                                        match field {
+                                                            serde_json::Value::String(s) => return Some(s.clone()),
+                                                            serde_json::Value::Object(map) => {
+                                                                // Try `en` locale or first string value
+                                                                if let Some(serde_json::Value::String(s2)) = map.get("en") {
+                                                                    return Some(s2.clone());
                                                                 }
-                                                                serde_json::Value::Array(arr) => {
-                                                                    if let Some(serde_json::Value::String(s4)) = arr.get(0) {
-                                                                        return Some(s4.clone());
+                                                                for (_k, val) in map.iter() {
+                                                                    if let serde_json::Value::String(s3) = val {
+                                                                        return Some(s3.clone());
                                                                     }
                                                                 }
-                                                                other => {
-                                                                    // Fallback: use string representation for numbers or bools
-                                                                    return Some(other.to_string());
+                                                            }
+                                                            serde_json::Value::Array(arr) => {
+                                                                if let Some(serde_json::Value::String(s4)) = arr.first() {
+                                                                    return Some(s4.clone());
                                                                 }
                                                             }
+                                                            other => {
+                                                                // Fallback: use string representation for numbers or bools
+                                                                return Some(other.to_string());
+                                                            }
                                                         }
                                                     }
-                                                    None
                                                 }
-
-                                                let mut off = OffchainTokenMetadata {
-                                                    name: extract_first_string(&body_val, &["name", "title", "token_name"]),
-                                                    symbol: extract_first_string(&body_val, &["symbol", "ticker"]),
-                                                    description: body_val.get("description").and_then(|d| d.as_str().map(|s| s.to_string())),
-                                                    image: extract_first_string(&body_val, &["image", "image_url", "imageUri"]),
-                                                    extras: Some(body_val.clone()),
-                                                };
-                                                // Normalize and extract fields from extras
-                                                off.normalize();
-                                                debug!("Fetched off-chain metadata for {}: {:?}", mint, off);
-                                                Ok((Some(meta), Some(off), Some(decoded)))
+                                                None
                                             }
-                                        Err(e) => {
-                                            warn!("Failed to parse off-chain metadata JSON for {}: {}", uri, e);
-                                            Ok((Some(meta), None, Some(decoded)))
+
+                                            let mut off = OffchainTokenMetadata {
+                                                name: extract_first_string(&body_val, &["name", "title", "token_name"]),
+                                                symbol: extract_first_string(&body_val, &["symbol", "ticker"]),
+                                                description: body_val.get("description").and_then(|d| d.as_str().map(|s| s.to_string())),
+                                                image: extract_first_string(&body_val, &["image", "image_url", "imageUri"]),
+                                                extras: Some(body_val.clone()),
+                                            };
+                                            // Normalize and extract fields from extras
+                                            off.normalize();
+                                            debug!("Fetched off-chain metadata for {}: {:?}", mint, off);
+                                            Ok((Some(meta), Some(off), Some(decoded)))
                                         }
-                                    },
                                     Err(e) => {
-                                        warn!("Failed to read off-chain metadata body for {}: {}", uri, e);
+                                        warn!("Failed to parse off-chain metadata JSON for {}: {}", uri, e);
                                         Ok((Some(meta), None, Some(decoded)))
                                     }
                                 },
                                 Err(e) => {
-                                    warn!("HTTP error fetching off-chain metadata {}: {}", uri, e);
+                                    warn!("Failed to fetch off-chain metadata {}: {}", uri, e);
                                     Ok((Some(meta), None, Some(decoded)))
                                 }
                             }
@@ -465,58 +504,87 @@ pub async fn fetch_token_metadata(
 
 pub async fn fetch_with_fallback<T: for<'de> Deserialize<'de> + Send + 'static>(
     request: Value,
-    _method: &str,
+    method: &str,
     _rpc_client: &Arc<RpcClient>,
     settings: &Arc<Settings>,
 ) -> Result<RpcResponse<T>, Box<dyn std::error::Error + Send + Sync>> {
-    // Rotate starting index for RPC URLs to spread load when `rotate_rpc` is enabled.
-    static RPC_ROUND_ROBIN: Lazy<AtomicUsize> = Lazy::new(|| AtomicUsize::new(0));
+    fetch_with_priority(request, method, Priority::Normal, settings).await
+}
+
+/// `fetch_with_fallback` with an explicit rate-limit priority. When every
+/// endpoint is over its credit budget, low-priority calls are shed and the
+/// others wait for the soonest refill.
+pub async fn fetch_with_priority<T: for<'de> Deserialize<'de> + Send + 'static>(
+    request: Value,
+    method: &str,
+    priority: Priority,
+    settings: &Arc<Settings>,
+) -> Result<RpcResponse<T>, Box<dyn std::error::Error + Send + Sync>> {
+    const MAX_BUDGET_WAITS: usize = 3;
     let urls = &settings.solana_rpc_urls;
     if urls.is_empty() {
         return Err("No solana_rpc_urls configured".into());
     }
-    let client = reqwest::Client::new();
-    // Determine start index
-    let start = if settings.rotate_rpc {
-        RPC_ROUND_ROBIN.fetch_add(1, Ordering::Relaxed) % urls.len()
-    } else {
-        0
-    };
-    // Try each endpoint in round-robin order, return first successful parse
-    for i in 0..urls.len() {
-        let idx = (start + i) % urls.len();
-        let http = &urls[idx];
-        let request_body = request.clone();
-        match client.post(http).json(&request_body).send().await {
-            Ok(resp) => {
-                let status = resp.status();
-                let text = resp.text().await.map_err(|e| Box::<dyn std::error::Error + Send + Sync>::from(e.to_string()))?;
-                if !status.is_success() {
-                    debug!("HTTP {} from {}: {}", status, http, text);
-                    continue;
-                }
-                match serde_json::from_str::<RpcResponse<T>>(&text) {
-                    Ok(parsed) => {
-                        if parsed.error.is_some() {
-                            return Err(format!("RPC error from {}: {:?}", http, parsed.error).into());
-                        }
-                        return Ok(parsed);
-                    }
-                    Err(e) => {
-                        debug!("JSON parse error from {}: {} -- body: {}", http, e, text);
-                        continue;
+    for _ in 0..=MAX_BUDGET_WAITS {
+        let mut soonest: Option<Duration> = None;
+        let mut attempted = false;
+        // Try endpoints best health score first (rotating among equals when
+        // `rotate_rpc` is enabled), return first successful parse
+        for http in crate::rpc_pool::ranked_urls(urls, settings.rotate_rpc).await {
+            if let Err(wait) = crate::rate_limit::try_acquire(&http, method, priority, settings).await {
+                soonest = Some(soonest.map_or(wait, |s| s.min(wait)));
+                continue;
+            }
+            attempted = true;
+            match crate::rpc_pool::post::<T>(&http, &request, settings).await {
+                Ok(parsed) => {
+                    if parsed.error.is_some() {
+                        return Err(format!("RPC error from {}: {:?}", http, parsed.error).into());
                     }
+                    return Ok(parsed);
+                }
+                Err(e) => {
+                    debug!("{}", e);
+                    continue;
                 }
             }
-            Err(e) => {
-                debug!("HTTP error contacting {}: {}", http, e);
-                continue;
-            }
         }
+        if attempted {
+            break;
+        }
+        // Every endpoint is over budget for this priority
+        if priority == Priority::Low {
+            crate::rate_limit::record_shed(method).await;
+            return Err(format!("{} shed: RPC credit budget exhausted", method).into());
+        }
+        let wait = soonest.unwrap_or(Duration::from_millis(100)).min(Duration::from_secs(1));
+        debug!("RPC budget exhausted for {}, waiting {} ms", method, wait.as_millis());
+        tokio::time::sleep(wait).await;
     }
     Err("All RPC endpoints failed to respond successfully".into())
 }
 
+/// Lamports the fee payer (account 0) spent in a landed transaction, from
+/// `getTransaction` meta. Retries briefly since the RPC may trail confirmation.
+pub async fn fetch_fee_payer_delta_lamports(
+    signature: &str,
+    rpc_client: &Arc<RpcClient>,
+    settings: &Arc<Settings>,
+) -> Option<i128> {
+    for _ in 0..4 {
+        let req = json!({ "jsonrpc": "2.0", "id": 1, "method": "getTransaction", "params": [ signature, { "encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0 } ] });
+        if let Ok(resp) = fetch_with_fallback::<Value>(req, "getTransaction", rpc_client, settings).await {
+            if let Some(meta) = resp.result.as_ref().and_then(|r| r.get("meta")) {
+                let pre = meta.get("preBalances").and_then(|b| b.get(0)).and_then(|v| v.as_u64())?;
+                let post = meta.get("postBalances").and_then(|b| b.get(0)).and_then(|v| v.as_u64())?;
+                return Some(pre as i128 - post as i128);
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    }
+    None
+}
+
 pub async fn fetch_current_price(
     mint: &str,
     price_cache: &Arc<Mutex<PriceCache>>,
@@ -530,7 +598,7 @@ pub async fn fetch_current_price(
         }
     }
 
-    let pump_program = Pubkey::from_str(&settings.pump_fun_program)?;
+    let pump_program = settings.address_book()?.pump_program;
     let mint_pubkey = Pubkey::from_str(mint)?;
     // PDA seeds per pump.fun IDL: ["bonding-curve", mint]
     let (curve_pda, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pubkey.as_ref()], &pump_program);
@@ -543,33 +611,29 @@ pub async fn fetch_current_price(
     let mut last_err: Option<String> = None;
     let mut decoded_opt: Option<Vec<u8>> = None;
     for c in &commitments {
-        // Try a few attempts per commitment to allow different RPC endpoints to respond
-        // with populated data (fetch_with_fallback picks the first HTTP success which
-        // might still have an empty `value`).
+        // Each attempt is a hedged read: the best endpoints are raced and the
+        // first one returning populated account data wins. A freshly created
+        // curve may not have propagated yet, so an empty answer is retried
+        // with a short backoff before moving to the next commitment.
         for attempt in 0..3 {
             let request = json!({
                 "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo",
                 "params": [ curve_pda.to_string(), { "encoding": "base64", "commitment": c } ]
             });
-            match fetch_with_fallback::<Value>(request, "getAccountInfo", rpc_client, settings).await {
-                Ok(data) => {
-                    if let Some(result_val) = data.result {
-                        let account_obj = if let Some(v) = result_val.get("value") { v.clone() } else { result_val.clone() };
-                        if let Some(base64_str) = account_obj.get("data").and_then(|d| d.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
-                            match Base64Engine.decode(base64_str) {
-                                Ok(decoded) => {
-                                    decoded_opt = Some(decoded);
-                                    break;
-                                }
-                                Err(e) => {
-                                    last_err = Some(format!("Decode error for bonding curve {} mint {}: {}", curve_pda, mint, e));
-                                }
+            match crate::hedge::hedged_read("curve_state", &request, settings, crate::hedge::account_populated).await {
+                Ok(result_val) => {
+                    if let Some(base64_str) = result_val.get("value").and_then(|v| v.get("data")).and_then(|d| d.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
+                        match Base64Engine.decode(base64_str) {
+                            Ok(decoded) => {
+                                decoded_opt = Some(decoded);
+                                break;
+                            }
+                            Err(e) => {
+                                last_err = Some(format!("Decode error for bonding curve {} mint {}: {}", curve_pda, mint, e));
                             }
-                        } else {
-                            last_err = Some(format!("No data field in account object for curve PDA {} at commitment {} (attempt {})", curve_pda, c, attempt));
                         }
                     } else {
-                        last_err = Some(format!("getAccountInfo returned no result for curve PDA {} at commitment {} (attempt {})", curve_pda, c, attempt));
+                        last_err = Some(format!("No data field in account object for curve PDA {} at commitment {} (attempt {})", curve_pda, c, attempt));
                     }
                 }
                 Err(e) => {
@@ -618,7 +682,7 @@ pub async fn fetch_current_price(
         // `value` is null.
         if decoded_opt.is_none() {
             for http in &settings.solana_rpc_urls {
-                let client = reqwest::Client::new();
+                let client = crate::rpc_pool::http();
                 let request = json!({
                     "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo",
                     "params": [ curve_pda.to_string(), { "encoding": "base64", "commitment": "finalized" } ]
@@ -845,6 +909,19 @@ pub async fn fetch_mint_decimals(
     mint: &str,
     rpc_client: &Arc<RpcClient>,
     settings: &Arc<Settings>,
+) -> Result<u8, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(d) = crate::hot_cache::decimals(mint).await {
+        return Ok(d);
+    }
+    let decimals = fetch_mint_decimals_uncached(mint, rpc_client, settings).await?;
+    crate::hot_cache::remember_decimals(mint, decimals).await;
+    Ok(decimals)
+}
+
+async fn fetch_mint_decimals_uncached(
+    mint: &str,
+    rpc_client: &Arc<RpcClient>,
+    settings: &Arc<Settings>,
 ) -> Result<u8, Box<dyn std::error::Error + Send + Sync>> {
     // Try multiple commitments and retries (similar to fetch_current_price) to
     // robustly fetch mint account data and parse the `decimals` field.
@@ -858,41 +935,37 @@ pub async fn fetch_mint_decimals(
                 "method": "getAccountInfo",
                 "params": [ mint_pk.to_string(), { "encoding": "base64", "commitment": c } ]
             });
-            match fetch_with_fallback::<Value>(request, "getAccountInfo", rpc_client, settings).await {
-                Ok(v) => {
-                    if let Some(result_val) = v.result {
-                        let account_obj = if let Some(x) = result_val.get("value") { x.clone() } else { result_val.clone() };
-                        if let Some(base64_str) = account_obj.get("data").and_then(|d| d.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
-                            match Base64Engine.decode(base64_str) {
-                                Ok(decoded) => {
-                                    // Token-2022 mints are larger than 82 bytes; try with
-                                    // truncated data first so spl_token::state::Mint::unpack
-                                    // (which requires exactly 82 bytes) can succeed.
-                                    let unpack_slice = if decoded.len() > spl_token::state::Mint::LEN {
-                                        &decoded[..spl_token::state::Mint::LEN]
-                                    } else {
-                                        &decoded
-                                    };
-                                    if let Ok(mint_state) = spl_token::state::Mint::unpack(unpack_slice) {
-                                        debug!("Fetched mint decimals for {} at commitment {}: {}", mint, c, mint_state.decimals);
-                                        return Ok(mint_state.decimals);
-                                    } else {
-                                        debug!("Failed to parse Mint state for {} (attempt {}, commitment {})", mint, attempt, c);
-                                        // continue retries
-                                    }
-                                }
-                                Err(e) => {
-                                    debug!("Failed to base64-decode mint account for {}: {}", mint, e);
+            match crate::hedge::hedged_read("mint_account", &request, settings, crate::hedge::account_populated).await {
+                Ok(result_val) => {
+                    let account_obj = if let Some(x) = result_val.get("value") { x.clone() } else { result_val.clone() };
+                    if let Some(base64_str) = account_obj.get("data").and_then(|d| d.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
+                        match Base64Engine.decode(base64_str) {
+                            Ok(decoded) => {
+                                // Token-2022 mints are larger than 82 bytes; try with
+                                // truncated data first so spl_token::state::Mint::unpack
+                                // (which requires exactly 82 bytes) can succeed.
+                                let unpack_slice = if decoded.len() > spl_token::state::Mint::LEN {
+                                    &decoded[..spl_token::state::Mint::LEN]
+                                } else {
+                                    &decoded
+                                };
+                                if let Ok(mint_state) = spl_token::state::Mint::unpack(unpack_slice) {
+                                    debug!("Fetched mint decimals for {} at commitment {}: {}", mint, c, mint_state.decimals);
+                                    return Ok(mint_state.decimals);
+                                } else {
+                                    debug!("Failed to parse Mint state for {} (attempt {}, commitment {})", mint, attempt, c);
                                 }
                             }
-                        } else {
-                            debug!("No account data for mint {} (commitment={}, attempt={})", mint, c, attempt);
+                            Err(e) => {
+                                debug!("Failed to base64-decode mint account for {}: {}", mint, e);
+                            }
                         }
+                    } else {
+                        debug!("No account data for mint {} (commitment={}, attempt={})", mint, c, attempt);
                     }
                 }
                 Err(e) => debug!("RPC error fetching mint {} at commitment {} (attempt {}): {}", mint, c, attempt, e),
             }
-            // slight backoff
             tokio::time::sleep(std::time::Duration::from_millis(150 * (attempt as u64 + 1))).await;
         }
     }
@@ -920,28 +993,6 @@ pub async fn fetch_mint_decimals(
     Err(format!("Failed to fetch mint decimals for {} after retries", mint).into())
 }
 
-/// Compute price from raw reserves using the mint decimals (fallback to 6 decimals if
-/// fetching decimals fails). Price = (vsol_lamports / 1e9) / (vtok / 10^decimals)
-pub async fn price_from_reserves(
-    mint: &str,
-    vtok: u64,
-    vsol: u64,
-    rpc_client: &Arc<RpcClient>,
-    settings: &Arc<Settings>,
-) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    // Try to fetch decimals; pump.fun tokens always use 6 decimals so that is the
-    // correct fallback (not the configurable default_token_decimals).
-    let decimals: u8 = match fetch_mint_decimals(mint, rpc_client, settings).await {
-        Ok(d) => d,
-        Err(e) => {
-            warn!("Failed to fetch mint decimals for {}: {} -- falling back to 6 (pump.fun default)", mint, e);
-            6
-        }
-    }; 
-    let price = (vsol as f64 / 1_000_000_000.0) / (vtok as f64 / 10f64.powi(decimals as i32));
-    Ok(price)
-}
-
 pub async fn detect_idl_for_mint(mint: &str, rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Option<SimpleIdl> {
     let idls = load_all_idls();
     if idls.is_empty() { return None; }
@@ -991,8 +1042,8 @@ pub async fn build_missing_ata_preinstructions(
 }
 
 /// Fetch bonding curve state for safety checks (liquidity validation)
-pub async fn fetch_bonding_curve_state(mint: &str, rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Result<BondingCurveState, Box<dyn std::error::Error + Send + Sync>> {
-    let pump_program = Pubkey::from_str(&settings.pump_fun_program)?;
+pub async fn fetch_bonding_curve_state(mint: &str, _rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Result<BondingCurveState, Box<dyn std::error::Error + Send + Sync>> {
+    let pump_program = settings.address_book()?.pump_program;
     let mint_pubkey = Pubkey::from_str(mint)?;
     let (curve_pda, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pubkey.as_ref()], &pump_program);
     
@@ -1001,44 +1052,51 @@ pub async fn fetch_bonding_curve_state(mint: &str, rpc_client: &Arc<RpcClient>,
         "params": [ curve_pda.to_string(), { "encoding": "base64", "commitment": "confirmed" } ]
     });
     
-    let data = fetch_with_fallback::<Value>(request, "getAccountInfo", rpc_client, settings).await?;
-    if let Some(result_val) = data.result {
-        let account_obj = if let Some(v) = result_val.get("value") { v.clone() } else { result_val.clone() };
-        if let Some(base64_str) = account_obj.get("data").and_then(|d| d.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
-            let decoded = Base64Engine.decode(base64_str)?;
-            
-            // Parse bonding curve state
-            const PUMP_CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
-            if decoded.len() >= 49 && decoded[..8] == PUMP_CURVE_DISCRIMINATOR {
-                let slice = &decoded[8..];
-                // Layout: 5*u64 (40 bytes) + bool (1 byte) + creator (32 bytes) + is_mayhem_mode (1 byte)
-                let creator = if slice.len() >= BONDING_CURVE_CREATOR_OFFSET + PUBKEY_SIZE {
-                    Pubkey::try_from(&slice[BONDING_CURVE_CREATOR_OFFSET..BONDING_CURVE_CREATOR_OFFSET + PUBKEY_SIZE]).ok()
-                } else {
-                    None
-                };
-                let is_mayhem_mode = parse_is_mayhem_mode(slice);
-                
-                let state = BondingCurveState {
-                    virtual_token_reserves: u64::from_le_bytes(slice[0..8].try_into().map_err(|e: std::array::TryFromSliceError| Box::new(AppError::Conversion(e.to_string())))?),
-                    virtual_sol_reserves: u64::from_le_bytes(slice[8..16].try_into().map_err(|e: std::array::TryFromSliceError| Box::new(AppError::Conversion(e.to_string())))?),
-                    real_token_reserves: u64::from_le_bytes(slice[16..24].try_into().map_err(|e: std::array::TryFromSliceError| Box::new(AppError::Conversion(e.to_string())))?),
-                    real_sol_reserves: u64::from_le_bytes(slice[24..32].try_into().map_err(|e: std::array::TryFromSliceError| Box::new(AppError::Conversion(e.to_string())))?),
-                    token_total_supply: u64::from_le_bytes(slice[32..40].try_into().map_err(|e: std::array::TryFromSliceError| Box::new(AppError::Conversion(e.to_string())))?),
-                    complete: slice[40] != 0,
-                    creator,
-                    is_mayhem_mode,
-                };
-                return Ok(state);
-            }
+    let result_val = crate::hedge::hedged_read("curve_state", &request, settings, crate::hedge::account_populated).await?;
+    let account_obj = if let Some(v) = result_val.get("value") { v.clone() } else { result_val.clone() };
+    if let Some(base64_str) = account_obj.get("data").and_then(|d| d.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
+        let decoded = Base64Engine.decode(base64_str)?;
+        if let Some(state) = parse_bonding_curve(&decoded) {
+            return Ok(state);
         }
     }
     Err("Failed to fetch bonding curve state".into())
 }
 
-/// Known authorized fee recipient for standard (non-mayhem) pump.fun tokens.
-/// This is the canonical fee recipient that the pump.fun fee program recognizes.
-const PUMP_FEE_RECIPIENT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
+/// State of up to 100 bonding curves in one `getMultipleAccounts` call at
+/// `priority`. Mints whose curve is missing or unreadable are left out.
+pub async fn fetch_curve_states(
+    mints: &[String],
+    priority: Priority,
+    settings: &Arc<Settings>,
+) -> Result<HashMap<String, BondingCurveState>, Box<dyn std::error::Error + Send + Sync>> {
+    let pump_program = settings.address_book()?.pump_program;
+    let mut pdas = Vec::with_capacity(mints.len());
+    for mint in mints {
+        let mint_pubkey = Pubkey::from_str(mint)?;
+        pdas.push(Pubkey::find_program_address(&[b"bonding-curve", mint_pubkey.as_ref()], &pump_program).0.to_string());
+    }
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "getMultipleAccounts",
+        "params": [ pdas, { "encoding": "base64", "commitment": "confirmed" } ]
+    });
+    let resp = fetch_with_priority::<Value>(request, "getMultipleAccounts", priority, settings).await?;
+    let accounts = resp
+        .result
+        .as_ref()
+        .and_then(|r| r.get("value"))
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let mut states = HashMap::new();
+    for (mint, account) in mints.iter().zip(accounts.iter()) {
+        let Some(data) = account.get("data").and_then(|d| d.get(0)).and_then(|v| v.as_str()) else { continue };
+        if let Some(state) = Base64Engine.decode(data).ok().and_then(|d| parse_bonding_curve(&d)) {
+            states.insert(mint.clone(), state);
+        }
+    }
+    Ok(states)
+}
 
 // Global PDA account layout offsets (after 8-byte discriminator)
 const PUBKEY_SIZE: usize = 32;
@@ -1087,13 +1145,11 @@ const GLOBAL_RESERVED_FEE_RECIPIENT_END: usize = GLOBAL_RESERVED_FEE_RECIPIENT_O
 /// If `is_mayhem_mode` is true, the reserved_fee_recipient is returned.
 /// Otherwise, the first non-zero entry from fee_recipients[] is returned.
 /// Falls back to the hardcoded known fee recipient if parsing fails.
-pub async fn fetch_global_fee_recipient(rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Result<Pubkey, Box<dyn std::error::Error + Send + Sync>> {
-    fetch_fee_recipient_for_mint(false, rpc_client, settings).await
-}
-
-/// Fetch the appropriate fee recipient based on whether the token uses mayhem mode.
 pub async fn fetch_fee_recipient_for_mint(is_mayhem_mode: bool, rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Result<Pubkey, Box<dyn std::error::Error + Send + Sync>> {
-    let pump_program = Pubkey::from_str(&settings.pump_fun_program)?;
+    if let Some(recipient) = crate::hot_cache::fee_recipient(is_mayhem_mode).await {
+        return Ok(recipient);
+    }
+    let pump_program = settings.address_book()?.pump_program;
     let (global_pda, _) = Pubkey::find_program_address(&[b"global"], &pump_program);
     
     let request = json!({
@@ -1142,18 +1198,80 @@ pub async fn fetch_fee_recipient_for_mint(is_mayhem_mode: bool, rpc_client: &Arc
         }
     }
     
-    // Fallback to hardcoded known authorized fee recipient
-    let fallback = Pubkey::from_str(PUMP_FEE_RECIPIENT)?;
-    warn!("Using hardcoded fallback fee_recipient: {}", fallback);
+    // Fallback to the cluster's known authorized fee recipient
+    let fallback = settings.address_book()?.fee_recipient.ok_or_else(|| {
+        format!(
+            "Global account unreadable and the {} address book has no fee recipient; set cluster_addresses.fee_recipient",
+            settings.cluster
+        )
+    })?;
+    warn!("Using fallback fee_recipient from the {} address book: {}", settings.cluster, fallback);
     Ok(fallback)
 }
 
+/// Offsets of the initial reserves in the Global PDA (after discriminator):
+/// initialized(1) + authority(32) + fee_recipient(32) = 65
+const GLOBAL_INITIAL_RESERVES_OFFSET: usize = 65;
+
+/// Parse a raw Global account (including discriminator).
+pub fn parse_global_account(decoded: &[u8]) -> Option<GlobalAccount> {
+    const GLOBAL_DISCRIMINATOR: [u8; 8] = [0xa7, 0xe8, 0xe8, 0xb1, 0xc8, 0x6c, 0x72, 0x7f];
+    if decoded.len() < 8 + GLOBAL_INITIAL_RESERVES_OFFSET + 32 || decoded[..8] != GLOBAL_DISCRIMINATOR {
+        return None;
+    }
+    let slice = &decoded[8..];
+    let read_u64 = |off: usize| u64::from_le_bytes(slice[off..off + 8].try_into().unwrap_or([0u8; 8]));
+    let non_zero = |pk: Pubkey| if pk == Pubkey::default() { None } else { Some(pk) };
+    let fee_recipient = if slice.len() >= GLOBAL_FEE_RECIPIENTS_END {
+        (0..GLOBAL_FEE_RECIPIENTS_COUNT).find_map(|i| {
+            let start = GLOBAL_FEE_RECIPIENTS_OFFSET + i * PUBKEY_SIZE;
+            Pubkey::try_from(&slice[start..start + PUBKEY_SIZE]).ok().and_then(non_zero)
+        })
+    } else {
+        None
+    };
+    let reserved_fee_recipient = if slice.len() >= GLOBAL_RESERVED_FEE_RECIPIENT_END {
+        Pubkey::try_from(&slice[GLOBAL_RESERVED_FEE_RECIPIENT_OFFSET..GLOBAL_RESERVED_FEE_RECIPIENT_END]).ok().and_then(non_zero)
+    } else {
+        None
+    };
+    Some(GlobalAccount {
+        initial_virtual_token_reserves: read_u64(GLOBAL_INITIAL_RESERVES_OFFSET),
+        initial_virtual_sol_reserves: read_u64(GLOBAL_INITIAL_RESERVES_OFFSET + 8),
+        initial_real_token_reserves: read_u64(GLOBAL_INITIAL_RESERVES_OFFSET + 16),
+        token_total_supply: read_u64(GLOBAL_INITIAL_RESERVES_OFFSET + 24),
+        fee_recipient,
+        reserved_fee_recipient,
+    })
+}
+
+/// Fetch and parse the pump.fun Global account.
+pub async fn fetch_global_account(rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Result<GlobalAccount, Box<dyn std::error::Error + Send + Sync>> {
+    let pump_program = settings.address_book()?.pump_program;
+    let (global_pda, _) = Pubkey::find_program_address(&[b"global"], &pump_program);
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "getAccountInfo",
+        "params": [ global_pda.to_string(), { "encoding": "base64", "commitment": "confirmed" } ]
+    });
+    let data = fetch_with_fallback::<Value>(request, "getAccountInfo", rpc_client, settings).await?;
+    let result_val = data.result.ok_or("Global account not found")?;
+    let account_obj = if let Some(v) = result_val.get("value") { v.clone() } else { result_val };
+    let base64_str = account_obj
+        .get("data")
+        .and_then(|d| d.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|v| v.as_str())
+        .ok_or("Global account has no data")?;
+    let decoded = Base64Engine.decode(base64_str)?;
+    parse_global_account(&decoded).ok_or_else(|| "Unexpected Global account layout".into())
+}
+
 
 /// Fetch the bonding curve account for `mint` and attempt to read the creator pubkey
 /// from the on-chain `BondingCurve` struct. Returns `None` if the account is missing
 /// or the layout is unexpected.
 pub async fn fetch_bonding_curve_creator(mint: &str, rpc_client: &Arc<RpcClient>, settings: &Arc<Settings>) -> Result<Option<Pubkey>, Box<dyn std::error::Error + Send + Sync>> {
-    let pump_program = Pubkey::from_str(&settings.pump_fun_program)?;
+    let pump_program = settings.address_book()?.pump_program;
     let mint_pk = Pubkey::from_str(mint)?;
     let (curve_pda, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_program);
     let request = serde_json::json!({
@@ -1333,12 +1451,13 @@ async fn find_curve_account_by_mint(
 /// Result of a sell transaction, containing on-chain accounting data.
 #[derive(Debug, Clone)]
 pub struct SellResult {
-    /// Actual SOL balance change (post - pre), includes all fee deductions. Only for real mode.
+    /// Actual SOL balance change (post - pre), includes all fee deductions. Paper wallet change in dry-run.
     pub sol_balance_change: Option<f64>,
-    /// Transaction fee in SOL (base fee + priority fee). Only for real mode.
+    /// Transaction fee in SOL (base fee + priority fee). Estimated in dry-run.
     pub tx_fee_sol: Option<f64>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn sell_token(
     mint: &str,
     amount: u64,
@@ -1363,7 +1482,6 @@ pub async fn sell_token(
         sol_received_estimate,
         current_price
     );
-    let client = RpcClient::new(&settings.solana_rpc_urls[0]);
     let _idls = load_all_idls();
     let creator_opt = fetch_bonding_curve_creator(mint, rpc_client, settings).await.ok().flatten();
     
@@ -1372,10 +1490,8 @@ pub async fn sell_token(
     info!("Sell {}: using token program {}", mint, token_program_id);
     
     // Fetch fee_recipient from Global PDA (mayhem-mode aware)
-    let is_mayhem = match fetch_bonding_curve_state(mint, rpc_client, settings).await {
-        Ok(state) => state.is_mayhem_mode,
-        Err(_) => false,
-    };
+    let curve_state = fetch_bonding_curve_state(mint, rpc_client, settings).await.ok();
+    let is_mayhem = curve_state.as_ref().map(|state| state.is_mayhem_mode).unwrap_or(false);
     let fee_recipient = fetch_fee_recipient_for_mint(is_mayhem, rpc_client, settings).await?;
 
     if is_real {
@@ -1393,7 +1509,7 @@ pub async fn sell_token(
             context.insert("bonding_curve.creator".to_string(), c); 
             context.insert("bondingCurve.creator".to_string(), c); 
         }
-        let pump_program_pk = Pubkey::from_str(&settings.pump_fun_program)?;
+        let pump_program_pk = settings.address_book()?.pump_program;
         let (curve_pda_fallback, _) = Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &pump_program_pk);
         context.insert("bonding_curve".to_string(), curve_pda_fallback);
         if let Some(creator) = context.get("bonding_curve.creator") {
@@ -1403,7 +1519,7 @@ pub async fn sell_token(
         // Use actual fee_recipient from bonding curve
         context.insert("fee_recipient".to_string(), fee_recipient);
         // Add fee_program - for SELL it IS included in the main instruction accounts (unlike buy)
-        let fee_program_pubkey = Pubkey::from_str("pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ")?;
+        let fee_program_pubkey = settings.address_book()?.fee_program;
         context.insert("fee_program".to_string(), fee_program_pubkey);
         // Add token program so IDL resolves the correct one (Token-2022 vs SPL Token)
         context.insert("token_program".to_string(), token_program_id);
@@ -1429,7 +1545,7 @@ pub async fn sell_token(
         }
         let instruction = if let Some(instr) = instruction_opt { instr } else {
             // fallback to legacy builder using configured pump program
-            let program_id = Pubkey::from_str(&settings.pump_fun_program)?;
+            let program_id = settings.address_book()?.pump_program;
             // Calculate min_sol_output with slippage
             let min_sol_output = ((amount as f64 / token_divisor) * current_price * 1_000_000_000.0) as u64;
             let slippage_multiplier = 1.0 - (settings.slippage_bps as f64 / 10000.0);
@@ -1484,7 +1600,7 @@ pub async fn sell_token(
         // Pre-sell validation: check that user actually holds tokens for this mint
         let pre_sell_balance: u64 = if let Ok(Some(acc)) = find_token_account_owned_by_owner(mint, &user_pubkey.to_string(), rpc_client, settings).await {
             if let Ok(pk) = Pubkey::from_str(&acc) {
-                if let Ok(bal) = client.get_token_account_balance(&pk) {
+                if let Ok(bal) = rpc_client.get_token_account_balance(&pk).await {
                     bal.amount.parse::<u64>().unwrap_or(0)
                 } else { 0 }
             } else { 0 }
@@ -1525,9 +1641,10 @@ pub async fn sell_token(
         }
         
         // Add conditional 1% dev fee (if enabled in config)
+        let mut fee_instrs: Vec<solana_program::instruction::Instruction> = Vec::new();
         {
             let sol_received_lamports = (sol_received_estimate * 1_000_000_000.0) as u64;
-            crate::dev_fee::add_dev_fee_to_instructions(&mut all_instrs, &user_pubkey, sol_received_lamports, settings.dev_fee_enabled)?;
+            crate::dev_fee::add_dev_fee_to_instructions(&mut fee_instrs, &user_pubkey, sol_received_lamports, settings)?;
             if settings.dev_fee_enabled {
                 info!("Added 1% dev fee to sell transaction (estimated: {:.9} SOL)", sol_received_estimate);
             } else {
@@ -1536,44 +1653,52 @@ pub async fn sell_token(
         }
         
         // Before sending: record pre-send SOL and token balances so we can compute exact deltas
-        let pre_sol_lamports = client.get_balance(&user_pubkey)?;
+        let pre_sol_lamports = rpc_client.get_balance(&user_pubkey).await?;
         let mut pre_token_amount: u64 = 0;
         if let Ok(Some(acc)) = find_token_account_owned_by_owner(mint, &user_pubkey.to_string(), rpc_client, settings).await {
             if let Ok(pk) = Pubkey::from_str(&acc) {
-                if let Ok(bal) = client.get_token_account_balance(&pk) {
+                if let Ok(bal) = rpc_client.get_token_account_balance(&pk).await {
                     if let Ok(v) = bal.amount.parse::<u64>() { pre_token_amount = v; }
                 }
             }
         }
 
-        // Choose transaction submission method and capture signature
-        let signature: String;
-        if settings.helius_sender_enabled {
-            info!("Using Helius Sender for sell transaction of mint {}", mint);
-            signature = crate::helius_sender::send_transaction_with_retry(
-                all_instrs,
-                payer,
-                settings,
-                &client,
-                3, // max retries
-            ).await?;
-            info!("Sell transaction sent via Helius Sender: {}", signature);
-        } else {
-            let mut tx = Transaction::new_with_payer(&all_instrs, Some(&payer.pubkey()));
-            let blockhash = client.get_latest_blockhash()?;
-            tx.sign(&[payer], blockhash);
-            let sig = client.send_and_confirm_transaction(&tx)?;
-            signature = sig.to_string();
+        // Submit through the configured execution backend and capture signature
+        let backend = crate::execution::backend_for(crate::execution::Side::Sell, settings)?;
+        info!("Using {} execution backend for sell transaction of mint {}", backend.name(), mint);
+        let submission = backend.execute(crate::execution::ExecutionRequest {
+            side: crate::execution::Side::Sell,
+            mint,
+            payer,
+            instructions: all_instrs,
+            fee_instructions: fee_instrs,
+            followup_instructions: Vec::new(),
+            sol_amount: sol_received_estimate,
+            token_amount: amount,
+            decimals,
+        }, settings).await?;
+        let signature = submission.signature;
+        info!("Sell transaction sent via {}: {}", submission.backend, signature);
+        match submission.outcome {
+            crate::confirm::ConfirmationOutcome::Landed { slot } => {
+                info!("Sell {} landed in slot {}", mint, slot);
+            }
+            crate::confirm::ConfirmationOutcome::Failed { err, .. } => {
+                return Err(format!("Sell TX {} for {} failed on-chain: {}", signature, mint, err).into());
+            }
+            crate::confirm::ConfirmationOutcome::Expired => {
+                return Err(format!("Sell TX {} for {} expired: its blockhash is no longer valid", signature, mint).into());
+            }
         }
 
         // After send: fetch post-send balances and transaction fee via getTransaction
         // Give RPC a short moment to index the tx
         tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-        let post_sol_lamports = client.get_balance(&user_pubkey)?;
+        let post_sol_lamports = rpc_client.get_balance(&user_pubkey).await?;
         let mut post_token_amount: u64 = 0;
         if let Ok(Some(acc)) = find_token_account_owned_by_owner(mint, &user_pubkey.to_string(), rpc_client, settings).await {
             if let Ok(pk) = Pubkey::from_str(&acc) {
-                if let Ok(bal) = client.get_token_account_balance(&pk) {
+                if let Ok(bal) = rpc_client.get_token_account_balance(&pk).await {
                     if let Ok(v) = bal.amount.parse::<u64>() { post_token_amount = v; }
                 }
             }
@@ -1639,7 +1764,7 @@ pub async fn sell_token(
                     _maybe_owned_sim.as_ref().ok_or_else(|| Box::<dyn std::error::Error + Send + Sync>::from("Failed to get sim keypair ref"))?
                 };
         let sim_payer_pubkey = sim_payer_ref.pubkey();
-        let program_id = Pubkey::from_str(&settings.pump_fun_program)?;
+        let program_id = settings.address_book()?.pump_program;
         // Calculate min_sol_output with slippage using actual decimals
         let min_sol_output = ((amount as f64 / token_divisor) * current_price * 1_000_000_000.0) as u64;
         let slippage_multiplier = 1.0 - (settings.slippage_bps as f64 / 10000.0);
@@ -1666,7 +1791,7 @@ pub async fn sell_token(
         debug!("  Payer (sim wallet): {}", sim_payer_pubkey);
         
         let mut tx = Transaction::new_with_payer(std::slice::from_ref(&instruction), Some(&sim_payer_pubkey));
-        match client.get_latest_blockhash() {
+        match rpc_client.get_latest_blockhash().await {
             Ok(blockhash) => {
                 // For dry-run simulation with ephemeral keypair:
                 // We build the transaction correctly but cannot fully simulate because
@@ -1696,10 +1821,11 @@ pub async fn sell_token(
         }
     }
 
-    // Complete the function by returning success (dry-run has no on-chain data)
+    // Dry runs settle against the paper wallet
+    let fill = crate::paper::sell(mint, amount, curve_state.as_ref(), current_price, decimals, is_final_sell, settings).await?;
     Ok(SellResult {
-        sol_balance_change: None,
-        tx_fee_sol: None,
+        sol_balance_change: Some(fill.net_sol()),
+        tx_fee_sol: Some(fill.network_fee_sol()),
     })
 }
 
@@ -0,0 +1,342 @@
+//! Shared nonblocking RPC handle and health-scored HTTP endpoint pool.
+//!
+//! Every task uses the same `nonblocking::RpcClient` for a given primary URL,
+//! and raw HTTP calls share one `reqwest::Client`, so calls never park a
+//! runtime worker thread and connections are pooled instead of being
+//! re-established per buy or sell.
+//!
+//! Raw JSON-RPC requests (`rpc::fetch_with_fallback`) go through the endpoint
+//! pool: each URL in `solana_rpc_urls` keeps an EWMA latency, an EWMA error
+//! rate and a count of rate-limit (429) responses. Endpoints are tried best
+//! score first, and one that keeps failing (or answers 429) is ejected for
+//! `rpc_eject_secs` — ejected endpoints are still tried last, so a fully
+//! degraded pool keeps probing instead of refusing every request.
+//!
+//! The shared client's own calls (balances, blockhashes, simulation) take
+//! credits from the same per-URL budget, so `/api/rpc/usage` counts them too.
+
+use crate::error::AppError;
+use crate::models::RpcResponse;
+use crate::rate_limit::Priority;
+use futures_util::future::BoxFuture;
+use crate::settings::Settings;
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Weight of the newest sample in the latency / error-rate averages.
+const EWMA_ALPHA: f64 = 0.2;
+
+/// One client per primary RPC URL, so settings with a different URL never
+/// get a client for someone else's endpoint.
+static SHARED: Lazy<std::sync::Mutex<HashMap<String, Arc<RpcClient>>>> = Lazy::new(Default::default);
+
+/// The process-wide client for the primary RPC URL, created on first use.
+pub fn shared_client(settings: &Settings) -> Result<Arc<RpcClient>, AppError> {
+    let url = settings
+        .solana_rpc_urls
+        .first()
+        .ok_or_else(|| AppError::Validation("solana_rpc_urls is empty".to_string()))?;
+    let mut clients = SHARED.lock().unwrap();
+    Ok(clients
+        .entry(url.clone())
+        .or_insert_with(|| Arc::new(limited_client(url.clone(), settings)))
+        .clone())
+}
+
+/// Transport for the shared `RpcClient` that takes each call's credits from
+/// `url`'s budget like `rpc::fetch_with_priority` does. Its calls sit on the
+/// buy/sell path, so they run at trading priority and wait out an empty bucket.
+struct LimitedSender {
+    inner: RpcClient,
+    settings: Settings,
+}
+
+impl RpcSender for LimitedSender {
+    fn send<'a, 'b>(&'a self, request: RpcRequest, params: Value) -> BoxFuture<'b, ClientResult<Value>>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        const MAX_BUDGET_WAITS: usize = 3;
+        Box::pin(async move {
+            let url = self.inner.url();
+            let method = request.to_string();
+            let mut waits = 0;
+            while let Err(wait) = crate::rate_limit::try_acquire(&url, &method, Priority::Trading, &self.settings).await {
+                if waits == MAX_BUDGET_WAITS {
+                    crate::rate_limit::record_shed(&method).await;
+                    return Err(ClientErrorKind::Custom(format!("{} shed: RPC credit budget exhausted", method)).into());
+                }
+                waits += 1;
+                let wait = wait.min(Duration::from_secs(1));
+                log::debug!("RPC budget exhausted for {}, waiting {} ms", method, wait.as_millis());
+                tokio::time::sleep(wait).await;
+            }
+            self.inner.send::<Value>(request, params).await
+        })
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// An `RpcClient` for `url` whose calls are rate limited, counted and recorded.
+fn limited_client(url: String, settings: &Settings) -> RpcClient {
+    RpcClient::new_sender(
+        LimitedSender { inner: crate::recorder::rpc_client(url), settings: settings.clone() },
+        RpcClientConfig::with_commitment(CommitmentConfig::default()),
+    )
+}
+
+/// Drop the shared clients when the engine stops; they belong to its runtime.
+pub(crate) fn reset() {
+    SHARED.lock().unwrap().clear();
+}
+
+/// Pooled HTTP client for raw JSON-RPC requests.
+static HTTP: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+/// The pooled HTTP client, for requests that do not go through [`post`].
+pub fn http() -> &'static reqwest::Client {
+    &HTTP
+}
+
+/// One JSON-RPC POST to `url`, recording the outcome against the endpoint.
+/// Err means the endpoint failed (transport, HTTP status, unparsable body);
+/// an RPC-level error is returned inside the response for the caller to judge.
+pub async fn post<T: DeserializeOwned>(url: &str, request: &Value, settings: &Settings) -> Result<RpcResponse<T>, String> {
+    let started = Instant::now();
+    let (outcome, result) = match HTTP.post(url).json(request).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            match resp.text().await {
+                Err(e) => (Outcome::Error, Err(format!("failed to read body from {}: {}", redact_url(url), e))),
+                Ok(_) if status == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    (Outcome::RateLimited, Err(format!("HTTP 429 from {}", redact_url(url))))
+                }
+                Ok(text) if !status.is_success() => {
+                    (Outcome::Error, Err(format!("HTTP {} from {}: {}", status, redact_url(url), text)))
+                }
+                Ok(text) => {
+                    crate::recorder::record_rpc(request, &text);
+                    match serde_json::from_str::<RpcResponse<T>>(&text) {
+                        Ok(parsed) => (Outcome::Ok(started.elapsed()), Ok(parsed)),
+                        Err(e) => (Outcome::Error, Err(format!("JSON parse error from {}: {} -- body: {}", redact_url(url), e, text))),
+                    }
+                }
+            }
+        }
+        Err(e) => (Outcome::Error, Err(format!("HTTP error contacting {}: {}", redact_url(url), e))),
+    };
+    record(url, outcome, settings).await;
+    result
+}
+
+/// Result of one request against an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Ok(Duration),
+    RateLimited,
+    Error,
+}
+
+#[derive(Debug, Default)]
+struct EndpointStats {
+    ewma_latency_ms: Option<f64>,
+    ewma_error_rate: f64,
+    requests: u64,
+    errors: u64,
+    rate_limited: u64,
+    consecutive_failures: u32,
+    ejected_until: Option<Instant>,
+}
+
+impl EndpointStats {
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until.is_some_and(|t| t > now)
+    }
+
+    /// Higher is better. Unknown endpoints start at 100 so they get tried.
+    fn score(&self, now: Instant) -> i32 {
+        let mut score = 100;
+        if let Some(latency) = self.ewma_latency_ms {
+            score -= (latency / 20.0) as i32; // -5 per 100ms
+        }
+        score -= (self.ewma_error_rate * 100.0) as i32;
+        score -= (self.consecutive_failures as i32) * 15;
+        if self.is_ejected(now) {
+            score -= 1000;
+        }
+        score
+    }
+}
+
+static POOL: Lazy<Mutex<HashMap<String, EndpointStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// `urls` ordered best score first. With `rotate` the starting offset rotates
+/// between calls so equally healthy endpoints share load.
+pub async fn ranked_urls(urls: &[String], rotate: bool) -> Vec<String> {
+    static ROUND_ROBIN: Lazy<std::sync::atomic::AtomicUsize> = Lazy::new(|| std::sync::atomic::AtomicUsize::new(0));
+    if urls.is_empty() {
+        return Vec::new();
+    }
+    let start = if rotate {
+        ROUND_ROBIN.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % urls.len()
+    } else {
+        0
+    };
+    let now = Instant::now();
+    let pool = POOL.lock().await;
+    let mut ordered: Vec<(String, i32)> = (0..urls.len())
+        .map(|i| {
+            let url = &urls[(start + i) % urls.len()];
+            let score = pool.get(url).map(|s| s.score(now)).unwrap_or(100);
+            (url.clone(), score)
+        })
+        .collect();
+    // Stable sort keeps the rotation order among equal scores
+    ordered.sort_by_key(|o| std::cmp::Reverse(o.1));
+    ordered.into_iter().map(|(url, _)| url).collect()
+}
+
+/// Record the outcome of a request against `url`.
+pub async fn record(url: &str, outcome: Outcome, settings: &Settings) {
+    let now = Instant::now();
+    let mut pool = POOL.lock().await;
+    let stats = pool.entry(url.to_string()).or_default();
+    stats.requests += 1;
+    match outcome {
+        Outcome::Ok(latency) => {
+            let ms = latency.as_secs_f64() * 1000.0;
+            stats.ewma_latency_ms = Some(match stats.ewma_latency_ms {
+                Some(prev) => prev + EWMA_ALPHA * (ms - prev),
+                None => ms,
+            });
+            stats.ewma_error_rate *= 1.0 - EWMA_ALPHA;
+            stats.consecutive_failures = 0;
+            stats.ejected_until = None;
+        }
+        Outcome::RateLimited | Outcome::Error => {
+            stats.errors += 1;
+            stats.ewma_error_rate += EWMA_ALPHA * (1.0 - stats.ewma_error_rate);
+            stats.consecutive_failures += 1;
+            let eject = if outcome == Outcome::RateLimited {
+                stats.rate_limited += 1;
+                true
+            } else {
+                stats.consecutive_failures >= settings.rpc_eject_after_failures
+            };
+            if eject && !stats.is_ejected(now) {
+                log::warn!(
+                    "Ejecting RPC endpoint {} for {}s ({})",
+                    redact_url(url),
+                    settings.rpc_eject_secs,
+                    if outcome == Outcome::RateLimited { "rate limited" } else { "repeated failures" }
+                );
+                stats.ejected_until = Some(now + Duration::from_secs(settings.rpc_eject_secs));
+            }
+        }
+    }
+}
+
+/// Strip the query string (API keys) from a URL before it is logged or served.
+pub(crate) fn redact_url(url: &str) -> String {
+    match url.split_once('?') {
+        Some((base, _)) => format!("{}?…", base),
+        None => url.to_string(),
+    }
+}
+
+/// Per-endpoint health for `/api/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub score: i32,
+    pub latency_ms: Option<f64>,
+    pub error_rate: f64,
+    pub requests: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+    pub ejected_for_secs: Option<u64>,
+}
+
+pub async fn health(urls: &[String]) -> Vec<EndpointHealth> {
+    let now = Instant::now();
+    let pool = POOL.lock().await;
+    let empty = EndpointStats::default();
+    urls.iter()
+        .map(|url| {
+            let stats = pool.get(url).unwrap_or(&empty);
+            EndpointHealth {
+                url: redact_url(url),
+                score: stats.score(now),
+                latency_ms: stats.ewma_latency_ms.map(|l| (l * 10.0).round() / 10.0),
+                error_rate: (stats.ewma_error_rate * 1000.0).round() / 1000.0,
+                requests: stats.requests,
+                errors: stats.errors,
+                rate_limited: stats.rate_limited,
+                ejected_for_secs: stats
+                    .ejected_until
+                    .filter(|t| *t > now)
+                    .map(|t| (t - now).as_secs()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failing_endpoint_is_ejected_and_ranked_last() {
+        let settings = Settings::from_file("config.example.toml").unwrap();
+        let urls = vec!["http://pool-test-a".to_string(), "http://pool-test-b".to_string()];
+        record(&urls[1], Outcome::Ok(Duration::from_millis(80)), &settings).await;
+        for _ in 0..settings.rpc_eject_after_failures {
+            record(&urls[0], Outcome::Error, &settings).await;
+        }
+        assert_eq!(ranked_urls(&urls, false).await, vec![urls[1].clone(), urls[0].clone()]);
+        let report = health(&urls).await;
+        assert!(report[0].ejected_for_secs.is_some());
+        assert!(report[1].ejected_for_secs.is_none());
+
+        // One success closes the circuit again
+        record(&urls[0], Outcome::Ok(Duration::from_millis(50)), &settings).await;
+        assert!(health(&urls).await[0].ejected_for_secs.is_none());
+    }
+
+    #[test]
+    fn shared_client_is_per_url_and_needs_one() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.solana_rpc_urls = vec!["http://shared-test-a".to_string()];
+        let a = shared_client(&settings).unwrap();
+        assert!(Arc::ptr_eq(&a, &shared_client(&settings).unwrap()));
+        settings.solana_rpc_urls = vec!["http://shared-test-b".to_string()];
+        let b = shared_client(&settings).unwrap();
+        assert_eq!(b.url(), "http://shared-test-b");
+        settings.solana_rpc_urls.clear();
+        assert!(shared_client(&settings).is_err());
+    }
+
+    #[test]
+    fn api_keys_are_redacted() {
+        assert_eq!(redact_url("https://rpc.example/?api-key=secret"), "https://rpc.example/?…");
+    }
+}
@@ -2,7 +2,9 @@ use crate::error::AppError;
 use serde::{Deserialize, Serialize};
 use base64::engine::general_purpose::STANDARD as Base64Engine;
 use base64::Engine;
+use solana_sdk::signature::{Keypair, Signer};
 use std::env;
+use std::sync::Arc;
 
 /// A single take-profit level: when profit reaches `trigger_percent`, sell `sell_percent`% of the original position.
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -18,6 +20,25 @@ pub struct SlLevel {
     pub sell_percent: f64,
 }
 
+/// Hedging for one RPC read call site: fire at the best `fanout` endpoints at
+/// once, then one more every `delay_ms` until a populated answer arrives
+/// (`delay_ms = 0` only moves on when an endpoint fails or comes back empty).
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HedgePolicy {
+    #[serde(default = "default_hedge_fanout")]
+    pub fanout: usize,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+fn default_hedge_fanout() -> usize { 1 }
+
+impl Default for HedgePolicy {
+    fn default() -> Self {
+        HedgePolicy { fanout: 1, delay_ms: 0 }
+    }
+}
+
 fn default_tp_levels() -> Vec<TpLevel> {
     vec![TpLevel { trigger_percent: 30.0, sell_percent: 100.0 }]
 }
@@ -119,6 +140,152 @@ pub struct Settings {
     /// Optional: Override IDL account pubkeys for specific programs (program_id -> idl_account)
     #[serde(default)]
     pub idl_account_overrides: std::collections::HashMap<String, String>,
+    /// Transaction backend for buys: "auto", "rpc", "helius", "jito", "pumpportal" or "fanout".
+    /// "auto" uses Helius Sender when `helius_sender_enabled`, otherwise plain RPC.
+    #[serde(default = "default_buy_execution_backend")]
+    pub buy_execution_backend: String,
+    /// Transaction backend for sells (same values as `buy_execution_backend`).
+    #[serde(default = "default_sell_execution_backend")]
+    pub sell_execution_backend: String,
+    /// Compute unit limit added by the RPC and Jito backends.
+    #[serde(default = "default_compute_unit_limit")]
+    pub compute_unit_limit: u32,
+    /// Priority fee (micro-lamports per CU) added by the RPC and Jito backends.
+    #[serde(default = "default_compute_unit_price_micro_lamports")]
+    pub compute_unit_price_micro_lamports: u64,
+    /// How long backends that confirm in-line wait for a landed signature before
+    /// they only keep watching until its blockhash expires.
+    #[serde(default = "default_execution_confirm_timeout_secs")]
+    pub execution_confirm_timeout_secs: u64,
+    #[serde(default = "default_jito_block_engine_url")]
+    pub jito_block_engine_url: String,
+    #[serde(default = "default_jito_tip_sol")]
+    pub jito_tip_sol: f64,
+    #[serde(default = "default_pumpportal_trade_local_url")]
+    pub pumpportal_trade_local_url: String,
+    #[serde(default = "default_pumpportal_priority_fee_sol")]
+    pub pumpportal_priority_fee_sol: f64,
+    /// Jito tip floor API (75th percentile of landed tips).
+    #[serde(default = "default_jito_tip_floor_url")]
+    pub jito_tip_floor_url: String,
+    /// Raise `jito_tip_sol` to the current Jito tip floor for bundles.
+    #[serde(default = "default_jito_use_tip_floor")]
+    pub jito_use_tip_floor: bool,
+    /// Percent of each `jito` buy sold again in the same bundle, right after
+    /// the buy (0 = off).
+    #[serde(default)]
+    pub jito_same_slot_sell_percent: f64,
+    /// Keep blockhash, Global account and per-mint lookups warm in the background
+    /// so the buy path needs no prerequisite RPC calls.
+    #[serde(default = "default_hot_cache_enabled")]
+    pub hot_cache_enabled: bool,
+    /// How often the hot cache refreshes the latest blockhash (ms).
+    #[serde(default = "default_hot_cache_blockhash_refresh_ms")]
+    pub hot_cache_blockhash_refresh_ms: u64,
+    /// How often the hot cache refreshes the Global and fee config accounts (seconds).
+    #[serde(default = "default_hot_cache_accounts_refresh_secs")]
+    pub hot_cache_accounts_refresh_secs: u64,
+    /// Consecutive failures after which an RPC endpoint is ejected from the pool.
+    /// A 429 (rate limited) response ejects immediately.
+    #[serde(default = "default_rpc_eject_after_failures")]
+    pub rpc_eject_after_failures: u32,
+    /// How long an ejected RPC endpoint is tried only as a last resort (seconds).
+    #[serde(default = "default_rpc_eject_secs")]
+    pub rpc_eject_secs: u64,
+    /// Hedged reads per call site (`curve_state`, `mint_account`). Sites not listed
+    /// use one endpoint at a time.
+    #[serde(default = "default_rpc_hedge")]
+    pub rpc_hedge: std::collections::HashMap<String, HedgePolicy>,
+    /// Credits per second each RPC endpoint may spend (0 = unlimited).
+    #[serde(default = "default_rpc_rate_limit_per_sec")]
+    pub rpc_rate_limit_per_sec: f64,
+    /// Maximum credits an RPC endpoint's bucket holds.
+    #[serde(default = "default_rpc_rate_limit_burst")]
+    pub rpc_rate_limit_burst: f64,
+    /// Per-URL override of `rpc_rate_limit_per_sec`.
+    #[serde(default = "default_rpc_rate_limits")]
+    pub rpc_rate_limits: std::collections::HashMap<String, f64>,
+    /// Credits per call by RPC method (1 when unlisted).
+    #[serde(default = "default_rpc_method_weights")]
+    pub rpc_method_weights: std::collections::HashMap<String, f64>,
+    /// First delay before restarting a failed websocket worker; doubles per
+    /// consecutive failure up to `ws_reconnect_max_secs`.
+    #[serde(default = "default_ws_reconnect_base_ms")]
+    pub ws_reconnect_base_ms: u64,
+    /// Upper bound for the websocket restart backoff.
+    #[serde(default = "default_ws_reconnect_max_secs")]
+    pub ws_reconnect_max_secs: u64,
+    /// Websocket ping interval (keeps NAT/proxy state alive and surfaces dead sockets).
+    #[serde(default = "default_ws_ping_interval_secs")]
+    pub ws_ping_interval_secs: u64,
+    /// Reconnect a Solana WSS that delivered nothing for this long. A slotSubscribe
+    /// heartbeat keeps a healthy stream busy several times per second.
+    #[serde(default = "default_ws_stale_timeout_secs")]
+    pub ws_stale_timeout_secs: u64,
+    /// Reconnect a PumpPortal stream that delivered nothing for this long.
+    #[serde(default = "default_pumpportal_stale_timeout_secs")]
+    pub pumpportal_stale_timeout_secs: u64,
+    /// Keep the pump.fun logsSubscribe detection feed on while PumpPortal is
+    /// enabled. Detections are deduplicated by mint and attributed to the
+    /// faster feed.
+    #[serde(default = "default_logs_detection_with_pumpportal")]
+    pub logs_detection_with_pumpportal: bool,
+    /// Poll `getSignaturesForAddress` for new tokens while every streaming
+    /// detection feed (PumpPortal / logs WSS) is down.
+    #[serde(default = "default_poll_fallback_enabled")]
+    pub poll_fallback_enabled: bool,
+    /// How long the streaming feeds must be down before polling starts.
+    #[serde(default = "default_poll_fallback_after_secs")]
+    pub poll_fallback_after_secs: u64,
+    /// Delay between polls while the fallback detector is active.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Signatures requested per poll (newest first).
+    #[serde(default = "default_poll_signature_limit")]
+    pub poll_signature_limit: usize,
+    /// Address whose signatures are polled; empty means the pump.fun mint
+    /// authority PDA, which only appears in creates, so each poll is far
+    /// cheaper than walking every program transaction.
+    #[serde(default = "default_poll_detector_address")]
+    pub poll_detector_address: String,
+    /// Append every incoming feed message and raw RPC response to this gzip
+    /// JSONL file for later `--replay`; empty disables recording.
+    #[serde(default = "default_record_path")]
+    pub record_path: String,
+    /// Starting SOL balance of the dry-run paper wallet.
+    #[serde(default = "default_paper_starting_balance_sol")]
+    pub paper_starting_balance_sol: f64,
+    /// pump.fun trade fee (protocol + creator) applied to paper fills, in bps.
+    #[serde(default = "default_paper_pump_fee_bps")]
+    pub paper_pump_fee_bps: u64,
+    /// Adverse price move between quote and landing applied to paper fills, in bps.
+    #[serde(default = "default_paper_latency_slippage_bps")]
+    pub paper_latency_slippage_bps: u64,
+    /// Config file of a second strategy run on paper against the same detections;
+    /// empty disables shadow mode.
+    #[serde(default = "default_shadow_config_path")]
+    pub shadow_config_path: String,
+    /// How long a coin skipped by `max_holded_coins` or an entry filter is price-tracked
+    /// to measure the missed opportunity; 0 disables tracking.
+    #[serde(default = "default_missed_window_secs")]
+    pub missed_window_secs: u64,
+    /// Seconds between curve price polls of the skipped coins being tracked.
+    #[serde(default = "default_missed_poll_interval_secs")]
+    pub missed_poll_interval_secs: u64,
+    /// Most skipped coins tracked at once; later skips are counted but not tracked.
+    #[serde(default = "default_missed_max_tracked")]
+    pub missed_max_tracked: usize,
+    /// Cluster the bot runs against: "mainnet", "devnet" or "localnet" (solana-test-validator).
+    /// Selects the fee, tip and dev-wallet address book in `cluster.rs`.
+    #[serde(default = "default_cluster")]
+    pub cluster: String,
+    /// Per-entry overrides of the cluster address book (pubkeys as base58 strings).
+    #[serde(default)]
+    pub cluster_addresses: crate::cluster::ClusterAddresses,
+    /// Address book resolved from `cluster`, `cluster_addresses` and the program
+    /// ids; rebuilt by `from_file` and `merge`
+    #[serde(skip)]
+    pub(crate) address_book: Option<Arc<crate::cluster::AddressBook>>,
 }
 
 fn default_token_decimals() -> u8 { 6 }
@@ -130,7 +297,24 @@ impl Settings {
         let builder = config::Config::builder()
             .add_source(config::File::with_name(path));
         let cfg = builder.build()?;
-        Ok(cfg.try_deserialize()?)
+        let mut settings: Settings = cfg.try_deserialize()?;
+        settings.refresh_address_book();
+        Ok(settings)
+    }
+
+    /// The address book for the configured cluster, built once on load and on
+    /// every merge. Settings assembled by hand fall back to building it here.
+    pub fn address_book(&self) -> Result<Arc<crate::cluster::AddressBook>, String> {
+        match &self.address_book {
+            Some(book) => Ok(book.clone()),
+            None => crate::cluster::AddressBook::from_settings(self).map(Arc::new),
+        }
+    }
+
+    /// Rebuild the cached address book. An invalid book is left unset so
+    /// `validate` and `address_book` report the error.
+    pub fn refresh_address_book(&mut self) {
+        self.address_book = crate::cluster::AddressBook::from_settings(self).ok().map(Arc::new);
     }
 
     pub fn save_to_file(&self, path: &str) -> Result<(), AppError> {
@@ -264,10 +448,170 @@ impl Settings {
         if other.idl_account_overrides != self.idl_account_overrides {
             self.idl_account_overrides = other.idl_account_overrides.clone();
         }
+        if other.buy_execution_backend != self.buy_execution_backend {
+            self.buy_execution_backend = other.buy_execution_backend.clone();
+        }
+        if other.sell_execution_backend != self.sell_execution_backend {
+            self.sell_execution_backend = other.sell_execution_backend.clone();
+        }
+        if other.compute_unit_limit != self.compute_unit_limit {
+            self.compute_unit_limit = other.compute_unit_limit;
+        }
+        if other.compute_unit_price_micro_lamports != self.compute_unit_price_micro_lamports {
+            self.compute_unit_price_micro_lamports = other.compute_unit_price_micro_lamports;
+        }
+        if other.execution_confirm_timeout_secs != self.execution_confirm_timeout_secs {
+            self.execution_confirm_timeout_secs = other.execution_confirm_timeout_secs;
+        }
+        if other.jito_block_engine_url != self.jito_block_engine_url {
+            self.jito_block_engine_url = other.jito_block_engine_url.clone();
+        }
+        if other.jito_tip_sol != self.jito_tip_sol {
+            self.jito_tip_sol = other.jito_tip_sol;
+        }
+        if other.pumpportal_trade_local_url != self.pumpportal_trade_local_url {
+            self.pumpportal_trade_local_url = other.pumpportal_trade_local_url.clone();
+        }
+        if other.pumpportal_priority_fee_sol != self.pumpportal_priority_fee_sol {
+            self.pumpportal_priority_fee_sol = other.pumpportal_priority_fee_sol;
+        }
+        if other.jito_tip_floor_url != self.jito_tip_floor_url {
+            self.jito_tip_floor_url = other.jito_tip_floor_url.clone();
+        }
+        if other.jito_use_tip_floor != self.jito_use_tip_floor {
+            self.jito_use_tip_floor = other.jito_use_tip_floor;
+        }
+        if other.jito_same_slot_sell_percent != self.jito_same_slot_sell_percent {
+            self.jito_same_slot_sell_percent = other.jito_same_slot_sell_percent;
+        }
+        if other.hot_cache_enabled != self.hot_cache_enabled {
+            self.hot_cache_enabled = other.hot_cache_enabled;
+        }
+        if other.hot_cache_blockhash_refresh_ms != self.hot_cache_blockhash_refresh_ms {
+            self.hot_cache_blockhash_refresh_ms = other.hot_cache_blockhash_refresh_ms;
+        }
+        if other.hot_cache_accounts_refresh_secs != self.hot_cache_accounts_refresh_secs {
+            self.hot_cache_accounts_refresh_secs = other.hot_cache_accounts_refresh_secs;
+        }
+        if other.rpc_eject_after_failures != self.rpc_eject_after_failures {
+            self.rpc_eject_after_failures = other.rpc_eject_after_failures;
+        }
+        if other.rpc_eject_secs != self.rpc_eject_secs {
+            self.rpc_eject_secs = other.rpc_eject_secs;
+        }
+        if other.rpc_hedge != self.rpc_hedge {
+            self.rpc_hedge = other.rpc_hedge.clone();
+        }
+        if other.rpc_rate_limit_per_sec != self.rpc_rate_limit_per_sec {
+            self.rpc_rate_limit_per_sec = other.rpc_rate_limit_per_sec;
+        }
+        if other.rpc_rate_limit_burst != self.rpc_rate_limit_burst {
+            self.rpc_rate_limit_burst = other.rpc_rate_limit_burst;
+        }
+        if other.rpc_rate_limits != self.rpc_rate_limits {
+            self.rpc_rate_limits = other.rpc_rate_limits.clone();
+        }
+        if other.rpc_method_weights != self.rpc_method_weights {
+            self.rpc_method_weights = other.rpc_method_weights.clone();
+        }
+        if other.ws_reconnect_base_ms != self.ws_reconnect_base_ms {
+            self.ws_reconnect_base_ms = other.ws_reconnect_base_ms;
+        }
+        if other.ws_reconnect_max_secs != self.ws_reconnect_max_secs {
+            self.ws_reconnect_max_secs = other.ws_reconnect_max_secs;
+        }
+        if other.ws_ping_interval_secs != self.ws_ping_interval_secs {
+            self.ws_ping_interval_secs = other.ws_ping_interval_secs;
+        }
+        if other.ws_stale_timeout_secs != self.ws_stale_timeout_secs {
+            self.ws_stale_timeout_secs = other.ws_stale_timeout_secs;
+        }
+        if other.pumpportal_stale_timeout_secs != self.pumpportal_stale_timeout_secs {
+            self.pumpportal_stale_timeout_secs = other.pumpportal_stale_timeout_secs;
+        }
+        if other.logs_detection_with_pumpportal != self.logs_detection_with_pumpportal {
+            self.logs_detection_with_pumpportal = other.logs_detection_with_pumpportal;
+        }
+        if other.poll_fallback_enabled != self.poll_fallback_enabled {
+            self.poll_fallback_enabled = other.poll_fallback_enabled;
+        }
+        if other.poll_fallback_after_secs != self.poll_fallback_after_secs {
+            self.poll_fallback_after_secs = other.poll_fallback_after_secs;
+        }
+        if other.poll_interval_ms != self.poll_interval_ms {
+            self.poll_interval_ms = other.poll_interval_ms;
+        }
+        if other.poll_signature_limit != self.poll_signature_limit {
+            self.poll_signature_limit = other.poll_signature_limit;
+        }
+        if other.poll_detector_address != self.poll_detector_address {
+            self.poll_detector_address = other.poll_detector_address.clone();
+        }
+        if other.record_path != self.record_path {
+            self.record_path = other.record_path.clone();
+        }
+        if other.paper_starting_balance_sol != self.paper_starting_balance_sol {
+            self.paper_starting_balance_sol = other.paper_starting_balance_sol;
+        }
+        if other.paper_pump_fee_bps != self.paper_pump_fee_bps {
+            self.paper_pump_fee_bps = other.paper_pump_fee_bps;
+        }
+        if other.paper_latency_slippage_bps != self.paper_latency_slippage_bps {
+            self.paper_latency_slippage_bps = other.paper_latency_slippage_bps;
+        }
+        if other.shadow_config_path != self.shadow_config_path {
+            self.shadow_config_path = other.shadow_config_path.clone();
+        }
+        if other.missed_window_secs != self.missed_window_secs {
+            self.missed_window_secs = other.missed_window_secs;
+        }
+        if other.missed_poll_interval_secs != self.missed_poll_interval_secs {
+            self.missed_poll_interval_secs = other.missed_poll_interval_secs;
+        }
+        if other.missed_max_tracked != self.missed_max_tracked {
+            self.missed_max_tracked = other.missed_max_tracked;
+        }
+        if other.cluster != self.cluster {
+            self.cluster = other.cluster.clone();
+        }
+        if other.cluster_addresses != self.cluster_addresses {
+            self.cluster_addresses = other.cluster_addresses.clone();
+        }
+        self.refresh_address_book();
     }
 
     /// Validate settings ranges and constraints
     pub fn validate(&self) -> Result<(), AppError> {
+        for (site, policy) in &self.rpc_hedge {
+            if policy.fanout == 0 {
+                return Err(AppError::Validation(format!("rpc_hedge.{}.fanout must be at least 1", site)));
+            }
+        }
+        if self.rpc_rate_limit_per_sec < 0.0 || self.rpc_rate_limits.values().any(|r| *r < 0.0) {
+            return Err(AppError::Validation("RPC rate limits must be >= 0 (0 = unlimited)".to_string()));
+        }
+        if self.rpc_rate_limit_burst < 1.0 {
+            return Err(AppError::Validation("rpc_rate_limit_burst must be at least 1".to_string()));
+        }
+        if self.rpc_eject_after_failures == 0 {
+            return Err(AppError::Validation("rpc_eject_after_failures must be at least 1".to_string()));
+        }
+        if self.ws_reconnect_base_ms == 0 || self.ws_reconnect_base_ms > self.ws_reconnect_max_secs * 1000 {
+            return Err(AppError::Validation(
+                "ws_reconnect_base_ms must be > 0 and not exceed ws_reconnect_max_secs".to_string(),
+            ));
+        }
+        if self.ws_ping_interval_secs == 0 || self.ws_stale_timeout_secs == 0 || self.pumpportal_stale_timeout_secs == 0 {
+            return Err(AppError::Validation(
+                "ws_ping_interval_secs, ws_stale_timeout_secs and pumpportal_stale_timeout_secs must be > 0".to_string(),
+            ));
+        }
+        if self.poll_interval_ms < 100 {
+            return Err(AppError::Validation("poll_interval_ms must be at least 100".to_string()));
+        }
+        if self.poll_signature_limit == 0 || self.poll_signature_limit > 1000 {
+            return Err(AppError::Validation("poll_signature_limit must be between 1 and 1000".to_string()));
+        }
         // Validate TP levels
         if self.tp_levels.is_empty() {
             return Err(AppError::Validation("At least one TP level is required".to_string()));
@@ -322,9 +666,52 @@ impl Settings {
         if self.max_holded_coins == 0 {
             return Err(AppError::Validation("max_holded_coins must be > 0".to_string()));
         }
+        if self.paper_starting_balance_sol < 0.0 {
+            return Err(AppError::Validation("paper_starting_balance_sol must be >= 0".to_string()));
+        }
+        if !(0.0..=100.0).contains(&self.jito_same_slot_sell_percent) {
+            return Err(AppError::Validation("jito_same_slot_sell_percent must be between 0 and 100".to_string()));
+        }
+        if self.slippage_bps > 10_000 {
+            return Err(AppError::Validation("slippage_bps must be <= 10000".to_string()));
+        }
+        if self.paper_pump_fee_bps > 10_000 || self.paper_latency_slippage_bps > 10_000 {
+            return Err(AppError::Validation("paper_pump_fee_bps and paper_latency_slippage_bps must be <= 10000".to_string()));
+        }
+        if self.missed_window_secs > 0 && (self.missed_poll_interval_secs == 0 || self.missed_max_tracked == 0) {
+            return Err(AppError::Validation("missed_poll_interval_secs and missed_max_tracked must be > 0 when missed_window_secs is set".to_string()));
+        }
+        crate::cluster::AddressBook::from_settings(self).map_err(AppError::Validation)?;
         if self.max_liquidity_sol < self.min_liquidity_sol {
             return Err(AppError::Validation("max_liquidity_sol must be >= min_liquidity_sol".to_string()));
         }
+        if self.cluster != "mainnet" && self.pumpportal_enabled {
+            return Err(AppError::Validation(format!(
+                "pumpportal_enabled must be false on cluster \"{}\" (PumpPortal only streams mainnet tokens)",
+                self.cluster
+            )));
+        }
+        for (side, backend) in [("buy", &self.buy_execution_backend), ("sell", &self.sell_execution_backend)] {
+            if !["auto", "rpc", "helius", "jito", "pumpportal", "fanout"].contains(&backend.as_str()) {
+                return Err(AppError::Validation(format!(
+                    "{}_execution_backend must be one of auto, rpc, helius, jito, pumpportal, fanout (got '{}')",
+                    side, backend
+                )));
+            }
+            if backend == "helius" && !self.helius_sender_enabled {
+                return Err(AppError::Validation(format!(
+                    "{}_execution_backend = \"helius\" requires helius_sender_enabled = true",
+                    side
+                )));
+            }
+            let resolved = if backend == "auto" && self.helius_sender_enabled { "helius" } else { backend.as_str() };
+            if self.cluster != "mainnet" && ["helius", "jito", "pumpportal", "fanout"].contains(&resolved) {
+                return Err(AppError::Validation(format!(
+                    "{}_execution_backend resolves to \"{}\", which only exists on mainnet (cluster = \"{}\")",
+                    side, resolved, self.cluster
+                )));
+            }
+        }
         Ok(())
     }
 }
@@ -377,6 +764,45 @@ pub fn parse_private_key_string(s: &str) -> Result<Vec<u8>, String> {
     Err("Unrecognized private key format. Expected: base58, JSON array, or comma-separated bytes".to_string())
 }
 
+fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair, AppError> {
+    Keypair::try_from(bytes).map_err(|e| AppError::InvalidKeypair(e.to_string()))
+}
+
+/// Load the trading wallet from `SOL_BEAST_KEYPAIR_B64`, then
+/// `wallet_private_key_string`, `wallet_keypair_json` or `wallet_keypair_path`.
+pub fn load_wallet_keypair(settings: &Settings) -> Result<Option<Keypair>, AppError> {
+    let bytes = if let Some(bytes) = load_keypair_from_env_var("SOL_BEAST_KEYPAIR_B64") {
+        bytes
+    } else if let Some(pk_string) = settings.wallet_private_key_string.as_deref() {
+        parse_private_key_string(pk_string).map_err(AppError::InvalidKeypair)?
+    } else if let Some(j) = settings.wallet_keypair_json.as_deref() {
+        serde_json::from_str(j)?
+    } else if let Some(path) = settings.wallet_keypair_path.as_deref() {
+        std::fs::read(path)?
+    } else {
+        return Ok(None);
+    };
+    keypair_from_bytes(&bytes).map(Some)
+}
+
+/// Load the optional keypair used to sign dry-run simulations from
+/// `SOL_BEAST_SIMULATE_KEYPAIR_B64`, `simulate_wallet_private_key_string` or
+/// `simulate_wallet_keypair_json`. Without one an ephemeral keypair is used.
+pub fn load_simulate_keypair(settings: &Settings) -> Result<Option<Keypair>, AppError> {
+    let bytes = if let Some(bytes) = load_keypair_from_env_var("SOL_BEAST_SIMULATE_KEYPAIR_B64") {
+        bytes
+    } else if let Some(pk_string) = settings.simulate_wallet_private_key_string.as_deref() {
+        let kp = keypair_from_bytes(&parse_private_key_string(pk_string).map_err(AppError::InvalidKeypair)?)?;
+        log::info!("Loaded simulate keypair, pubkey: {}", kp.pubkey());
+        return Ok(Some(kp));
+    } else if let Some(j) = settings.simulate_wallet_keypair_json.as_deref() {
+        serde_json::from_str(j)?
+    } else {
+        return Ok(None);
+    };
+    keypair_from_bytes(&bytes).map(Some)
+}
+
 fn default_bonding_curve_strict() -> bool { false }
 fn default_bonding_curve_log_debounce_secs() -> u64 { 300 }
 fn default_buy_amount() -> f64 { 0.1 }
@@ -404,7 +830,79 @@ fn default_pumpportal_wss() -> Vec<String> { vec!["wss://pumpportal.fun/api/data
 
 fn default_detected_coins_max() -> usize { 300 }
 
+fn default_buy_execution_backend() -> String { "auto".to_string() }
+fn default_sell_execution_backend() -> String { "auto".to_string() }
+fn default_compute_unit_limit() -> u32 { 200_000 }
+fn default_compute_unit_price_micro_lamports() -> u64 { 100_000 }
+fn default_execution_confirm_timeout_secs() -> u64 { 30 }
+fn default_jito_block_engine_url() -> String { "https://mainnet.block-engine.jito.wtf".to_string() }
+fn default_jito_tip_sol() -> f64 { 0.0001 }
+fn default_pumpportal_trade_local_url() -> String { "https://pumpportal.fun/api/trade-local".to_string() }
+fn default_pumpportal_priority_fee_sol() -> f64 { 0.00005 }
+
+fn default_jito_tip_floor_url() -> String { "https://bundles.jito.wtf/api/v1/bundles/tip_floor".to_string() }
+fn default_jito_use_tip_floor() -> bool { false }
+
+fn default_hot_cache_enabled() -> bool { true }
+fn default_hot_cache_blockhash_refresh_ms() -> u64 { 400 }
+fn default_hot_cache_accounts_refresh_secs() -> u64 { 30 }
+
+fn default_rpc_eject_after_failures() -> u32 { 3 }
+fn default_rpc_eject_secs() -> u64 { 30 }
+
+fn default_rpc_hedge() -> std::collections::HashMap<String, HedgePolicy> {
+    std::collections::HashMap::from([
+        ("curve_state".to_string(), HedgePolicy { fanout: 2, delay_ms: 150 }),
+        ("mint_account".to_string(), HedgePolicy { fanout: 2, delay_ms: 150 }),
+    ])
+}
+
+fn default_rpc_rate_limit_per_sec() -> f64 { 0.0 }
+fn default_rpc_rate_limit_burst() -> f64 { 20.0 }
+fn default_rpc_rate_limits() -> std::collections::HashMap<String, f64> { std::collections::HashMap::new() }
+fn default_rpc_method_weights() -> std::collections::HashMap<String, f64> {
+    std::collections::HashMap::from([
+        ("getProgramAccounts".to_string(), 10.0),
+        ("getProgramAccountsV2".to_string(), 10.0),
+        ("getTransaction".to_string(), 2.0),
+    ])
+}
+
+fn default_ws_reconnect_base_ms() -> u64 { 500 }
+fn default_ws_reconnect_max_secs() -> u64 { 30 }
+
+fn default_ws_ping_interval_secs() -> u64 { 15 }
+fn default_ws_stale_timeout_secs() -> u64 { 10 }
+fn default_pumpportal_stale_timeout_secs() -> u64 { 120 }
+
+fn default_logs_detection_with_pumpportal() -> bool { false }
+
+fn default_poll_fallback_enabled() -> bool { true }
+fn default_poll_fallback_after_secs() -> u64 { 10 }
+fn default_poll_interval_ms() -> u64 { 2000 }
+fn default_poll_signature_limit() -> usize { 50 }
+fn default_poll_detector_address() -> String { String::new() }
+
+fn default_record_path() -> String { String::new() }
+
+fn default_paper_starting_balance_sol() -> f64 { 10.0 }
+fn default_paper_pump_fee_bps() -> u64 { 125 }
+fn default_paper_latency_slippage_bps() -> u64 { 50 }
+
+fn default_shadow_config_path() -> String { String::new() }
+
+fn default_missed_window_secs() -> u64 { 600 }
+fn default_missed_poll_interval_secs() -> u64 { 15 }
+fn default_missed_max_tracked() -> usize { 200 }
+
+fn default_cluster() -> String { "mainnet".to_string() }
+
 impl Settings {
+    /// Hedge policy for an RPC read call site (no hedging when not configured).
+    pub fn hedge_policy(&self, site: &str) -> HedgePolicy {
+        self.rpc_hedge.get(site).cloned().unwrap_or_default()
+    }
+
     /// Get the effective minimum tip amount based on routing mode
     /// - Default dual routing: uses configured helius_min_tip_sol (default 0.001 SOL)
     /// - SWQOS-only: uses minimum 0.000005 SOL unless helius_min_tip_sol is higher
@@ -438,4 +936,13 @@ mod tests {
         assert_eq!(s.sl_levels[0].trigger_percent, -20.0);
         assert_eq!(s.cache_capacity, 1024);
     }
+
+    #[test]
+    fn slippage_above_the_whole_amount_is_rejected() {
+        let mut s = Settings::from_file("config.example.toml").unwrap();
+        s.slippage_bps = 10_000;
+        assert!(s.validate().is_ok());
+        s.slippage_bps = 10_001;
+        assert!(matches!(s.validate(), Err(AppError::Validation(_))));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,489 @@
+//! Shadow mode: a second strategy paper-traded next to the live one.
+//!
+//! With `shadow_config_path` set, the strategy in that file (buy_amount,
+//! entry filters, TP/SL levels, timeout, max_holded_coins, slippage and the
+//! paper wallet settings) is run on every coin the live pipeline detects.
+//! Its buys and sells are always filled against its own paper wallet,
+//! whatever the live mode is, and recorded as `simulated` trades kept apart
+//! from the live trade list. RPC endpoints and price sources come from the
+//! main config. `/api/shadow/compare` diffs the two strategies.
+//!
+//! The shadow strategy enters on the quote the live pipeline already took
+//! (price, curve state, decimals), so it costs no RPC reads at detection;
+//! detections the live pipeline drops before quoting (the logs feed at
+//! `max_holded_coins`, coins without a price) never reach it. Open shadow
+//! positions are priced by one low-priority `getMultipleAccounts` call per
+//! check, which is shed first when the RPC budget runs low.
+
+use crate::api::TradeRecord;
+use crate::buyer::{check_entry_filters, Quote};
+use crate::models::{BondingCurveState, Holding};
+use crate::monitor::{evaluate_exit, ExitDecision};
+use crate::paper::{self, PaperFill, PaperLedger, PaperStatus};
+use crate::rate_limit::Priority;
+use crate::rpc;
+use crate::settings::Settings;
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Shadow trades kept for the API, newest first.
+const MAX_TRADES: usize = 200;
+/// How often open shadow positions are checked against TP/SL/timeout.
+const EXIT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Curves per `getMultipleAccounts` call.
+const BATCH: usize = 100;
+
+struct Shadow {
+    strategy: Settings,
+    /// Live settings, used for RPC calls
+    live: Arc<Settings>,
+    holdings: Mutex<HashMap<String, Holding>>,
+    /// Last price seen per open position; a timed-out position sells at it
+    last_prices: Mutex<HashMap<String, f64>>,
+    trades: Mutex<Vec<TradeRecord>>,
+    ledger: Mutex<PaperLedger>,
+    /// Detections the shadow entry filters skipped, by filter name
+    skipped: Mutex<BTreeMap<String, u64>>,
+}
+
+static SHADOW: RwLock<Option<Arc<Shadow>>> = RwLock::new(None);
+
+fn current() -> Option<Arc<Shadow>> {
+    SHADOW.read().unwrap().clone()
+}
+
+/// Start shadow trading `strategy` on top of the live pipeline.
+pub fn start(strategy: Settings, live: Arc<Settings>) -> Result<(), String> {
+    {
+        let mut shadow = SHADOW.write().unwrap();
+        if shadow.is_some() {
+            return Err("shadow strategy already started".to_string());
+        }
+        *shadow = Some(Arc::new(Shadow::new(strategy, live)));
+    }
+    crate::tasks::spawn(async {
+        let mut interval = tokio::time::interval(EXIT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match current() {
+                Some(shadow) => shadow.check_exits().await,
+                None => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Drop the shadow strategy when the engine stops.
+pub(crate) fn stop() {
+    *SHADOW.write().unwrap() = None;
+}
+
+/// Offer a freshly quoted mint to the shadow strategy. Returns at once; the
+/// entry runs in the background on the live pipeline's quote, without RPC
+/// reads of its own.
+pub fn on_quote(mint: &str, quote: &Quote) {
+    if let Some(shadow) = current() {
+        let (mint, quote) = (mint.to_string(), quote.clone());
+        crate::tasks::spawn(async move {
+            if let Err(e) = shadow.enter(&mint, &quote).await {
+                debug!("Shadow strategy did not buy {}: {}", mint, e);
+            }
+        });
+    }
+}
+
+impl Shadow {
+    fn new(strategy: Settings, live: Arc<Settings>) -> Shadow {
+        Shadow {
+            ledger: Mutex::new(PaperLedger::new(strategy.paper_starting_balance_sol)),
+            strategy,
+            live,
+            holdings: Mutex::new(HashMap::new()),
+            last_prices: Mutex::new(HashMap::new()),
+            trades: Mutex::new(Vec::new()),
+            skipped: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    async fn enter(&self, mint: &str, quote: &Quote) -> Result<(), String> {
+        let strategy = &self.strategy;
+        {
+            let holdings = self.holdings.lock().await;
+            if holdings.contains_key(mint) {
+                return Ok(());
+            }
+            if holdings.len() >= strategy.max_holded_coins {
+                return self.skip("max_holded_coins", format!("{} open shadow positions", holdings.len())).await;
+            }
+        }
+        let Quote { price, curve, decimals, .. } = quote;
+        let (price, decimals) = (*price, *decimals);
+        let token_amount = ((strategy.buy_amount / price) * 10f64.powi(decimals as i32)) as u64;
+        if let Err(rejected) = check_entry_filters(token_amount, price, curve.as_ref().map(|c| c.real_sol_reserves), strategy) {
+            return self.skip(rejected.filter, rejected.reason).await;
+        }
+
+        let mut holdings = self.holdings.lock().await;
+        if holdings.contains_key(mint) || holdings.len() >= strategy.max_holded_coins {
+            return Ok(());
+        }
+        let fill = paper::buy_fill(strategy.buy_amount, token_amount, curve.as_ref(), price, decimals, strategy);
+        let fill = match self.ledger.lock().await.buy(mint, fill) {
+            Ok(fill) => fill,
+            Err(e) => {
+                self.record(TradeRecord {
+                    amount_sol: strategy.buy_amount,
+                    reason: Some(format!("FAILED: {}", e)),
+                    ..trade(mint, "buy", decimals)
+                })
+                .await;
+                return Err(e);
+            }
+        };
+        let holding = Holding {
+            amount: token_amount,
+            original_amount: token_amount,
+            buy_price: price,
+            buy_time: Utc::now(),
+            decimals,
+            buy_cost_sol: Some(-fill.net_sol()),
+            triggered_tp_levels: Vec::new(),
+            triggered_sl_levels: Vec::new(),
+            metadata: None,
+            onchain_raw: None,
+            onchain: None,
+        };
+        self.record(TradeRecord {
+            amount_sol: -fill.net_sol(),
+            amount_tokens: token_amount as f64 / 10f64.powi(decimals as i32),
+            price_per_token: price,
+            actual_sol_change: Some(fill.net_sol()),
+            tx_fee_sol: Some(fill.network_fee_sol()),
+            ..trade(mint, "buy", decimals)
+        })
+        .await;
+        holdings.insert(mint.to_string(), holding);
+        self.last_prices.lock().await.insert(mint.to_string(), price);
+        info!("Shadow buy {}: {} base units at {:.18} SOL/token", mint, token_amount, price);
+        Ok(())
+    }
+
+    async fn skip(&self, filter: &str, reason: String) -> Result<(), String> {
+        *self.skipped.lock().await.entry(filter.to_string()).or_default() += 1;
+        Err(format!("{} ({})", reason, filter))
+    }
+
+    /// Read the curves of all open positions in low-priority batches, shed
+    /// first when the RPC budget runs low, and check them for exits.
+    async fn check_exits(&self) {
+        let mints: Vec<String> = self.holdings.lock().await.keys().cloned().collect();
+        let mut curves = HashMap::new();
+        for chunk in mints.chunks(BATCH) {
+            match rpc::fetch_curve_states(chunk, Priority::Low, &self.live).await {
+                Ok(batch) => curves.extend(batch),
+                Err(e) => debug!("Shadow curve poll failed: {}", e),
+            }
+        }
+        self.check_exits_at(&curves).await;
+    }
+
+    async fn check_exits_at(&self, curves: &HashMap<String, BondingCurveState>) {
+        let open: Vec<(String, Holding)> =
+            self.holdings.lock().await.iter().map(|(m, h)| (m.clone(), h.clone())).collect();
+        for (mint, holding) in open {
+            let elapsed = Utc::now().signed_duration_since(holding.buy_time).num_seconds();
+            let is_timed_out = elapsed >= self.strategy.timeout_secs;
+            let curve = curves.get(&mint);
+            let price = match curve.and_then(|c| c.spot_price_sol_per_token()) {
+                Some(price) => {
+                    self.last_prices.lock().await.insert(mint.clone(), price);
+                    price
+                }
+                // Same as the live monitor: a timed-out position sells at the last known price
+                None if is_timed_out => self.last_prices.lock().await.get(&mint).copied().unwrap_or(holding.buy_price),
+                None => continue,
+            };
+            let profit_percent =
+                if holding.buy_price != 0.0 { (price - holding.buy_price) / holding.buy_price * 100.0 } else { 0.0 };
+            let decision = evaluate_exit(&holding, profit_percent, is_timed_out, &self.strategy);
+            if decision.sell_amount > 0 {
+                self.exit(&mint, &holding, price, curve, profit_percent, is_timed_out, decision).await;
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn exit(
+        &self,
+        mint: &str,
+        holding: &Holding,
+        price: f64,
+        curve: Option<&BondingCurveState>,
+        profit_percent: f64,
+        is_timed_out: bool,
+        decision: ExitDecision,
+    ) {
+        let ExitDecision { sell_amount, mut reason, triggered_tp, triggered_sl } = decision;
+        let is_final_sell = sell_amount >= holding.amount;
+        let (fill, is_final_sell) = match paper::sell_fill(sell_amount, curve, price, holding.decimals, is_final_sell, &self.strategy) {
+            Ok(fill) => (fill, is_final_sell),
+            Err(e) if is_timed_out => {
+                // Like the live monitor, a timed-out position is dropped rather
+                // than retried forever; its cost is written off
+                warn!("Shadow sell failed for timed-out {}: {}", mint, e);
+                reason = "TIMEOUT_FORCED".to_string();
+                (PaperFill { tokens: holding.amount, ..Default::default() }, true)
+            }
+            Err(e) => {
+                debug!("Shadow sell failed for {} ({}): {}", mint, reason, e);
+                return;
+            }
+        };
+        self.ledger.lock().await.sell(mint, &fill, is_final_sell);
+
+        let token_divisor = 10f64.powi(holding.decimals as i32);
+        let sell_sol = fill.tokens as f64 / token_divisor * price;
+        let buy_sol = holding.buy_price * fill.tokens as f64 / token_divisor;
+        {
+            let mut holdings = self.holdings.lock().await;
+            if is_final_sell {
+                holdings.remove(mint);
+                self.last_prices.lock().await.remove(mint);
+            } else if let Some(h) = holdings.get_mut(mint) {
+                h.amount -= sell_amount;
+                h.triggered_tp_levels.extend(triggered_tp);
+                h.triggered_sl_levels.extend(triggered_sl);
+            }
+        }
+        self.record(TradeRecord {
+            amount_sol: sell_sol,
+            amount_tokens: fill.tokens as f64 / token_divisor,
+            price_per_token: price,
+            profit_loss: Some(sell_sol - buy_sol),
+            profit_loss_percent: Some(profit_percent),
+            reason: Some(reason.clone()),
+            actual_sol_change: Some(fill.net_sol()),
+            tx_fee_sol: Some(fill.network_fee_sol()),
+            ..trade(mint, "sell", holding.decimals)
+        })
+        .await;
+        info!("Shadow sell {} ({}) at {:.18} (profit: {:.2}%)", mint, reason, price, profit_percent);
+    }
+
+    async fn record(&self, trade: TradeRecord) {
+        let mut trades = self.trades.lock().await;
+        trades.insert(0, trade);
+        trades.truncate(MAX_TRADES);
+    }
+}
+
+fn trade(mint: &str, trade_type: &str, decimals: u8) -> TradeRecord {
+    TradeRecord {
+        mint: mint.to_string(),
+        symbol: None,
+        name: None,
+        image: None,
+        trade_type: trade_type.to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+        tx_signature: None,
+        amount_sol: 0.0,
+        amount_tokens: 0.0,
+        price_per_token: 0.0,
+        profit_loss: None,
+        profit_loss_percent: None,
+        reason: None,
+        decimals,
+        actual_sol_change: None,
+        tx_fee_sol: None,
+        simulated: true,
+    }
+}
+
+/// Results of one strategy, from its trade records.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StrategySummary {
+    pub buys: u64,
+    pub failed_buys: u64,
+    pub sells: u64,
+    pub wins: u64,
+    pub losses: u64,
+    /// Share of sells with a positive profit, 0..1
+    pub win_rate: f64,
+    /// Sum of `profit_loss` over sells (price-based, before fees)
+    pub realized_pnl_sol: f64,
+    /// Sum of `actual_sol_change` over all trades that have one
+    pub net_sol_change: f64,
+    pub open_positions: usize,
+}
+
+pub fn summarize(trades: &[TradeRecord], open_positions: usize) -> StrategySummary {
+    let mut summary = StrategySummary { open_positions, ..Default::default() };
+    for trade in trades {
+        match trade.trade_type.as_str() {
+            "buy" if trade.reason.as_deref().is_some_and(|r| r.starts_with("FAILED")) => summary.failed_buys += 1,
+            "buy" => summary.buys += 1,
+            "sell" => {
+                summary.sells += 1;
+                let pnl = trade.profit_loss.unwrap_or(0.0);
+                if pnl > 0.0 {
+                    summary.wins += 1;
+                } else {
+                    summary.losses += 1;
+                }
+                summary.realized_pnl_sol += pnl;
+            }
+            _ => {}
+        }
+        summary.net_sol_change += trade.actual_sol_change.unwrap_or(0.0);
+    }
+    if summary.sells > 0 {
+        summary.win_rate = summary.wins as f64 / summary.sells as f64;
+    }
+    summary
+}
+
+/// Mints bought by one strategy, the other, or both.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MintOverlap {
+    pub both: Vec<String>,
+    pub only_live: Vec<String>,
+    pub only_shadow: Vec<String>,
+}
+
+fn bought_mints(trades: &[TradeRecord]) -> BTreeSet<&str> {
+    trades
+        .iter()
+        .filter(|t| t.trade_type == "buy" && !t.reason.as_deref().is_some_and(|r| r.starts_with("FAILED")))
+        .map(|t| t.mint.as_str())
+        .collect()
+}
+
+pub fn overlap(live: &[TradeRecord], shadow: &[TradeRecord]) -> MintOverlap {
+    let live = bought_mints(live);
+    let shadow = bought_mints(shadow);
+    MintOverlap {
+        both: live.intersection(&shadow).map(|m| m.to_string()).collect(),
+        only_live: live.difference(&shadow).map(|m| m.to_string()).collect(),
+        only_shadow: shadow.difference(&live).map(|m| m.to_string()).collect(),
+    }
+}
+
+/// Live vs shadow results, for `/api/shadow/compare`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub shadow_config_path: String,
+    pub live: StrategySummary,
+    pub shadow: StrategySummary,
+    /// Shadow minus live, per headline number
+    pub realized_pnl_diff_sol: f64,
+    pub win_rate_diff: f64,
+    pub mints: MintOverlap,
+    pub shadow_wallet: PaperStatus,
+    /// Detections the shadow entry filters skipped, by filter name
+    pub shadow_skipped: BTreeMap<String, u64>,
+    pub shadow_trades: Vec<TradeRecord>,
+}
+
+/// Compare the shadow strategy with the live trade records; None when
+/// shadow mode is off.
+pub async fn compare(live_trades: &[TradeRecord], live_open_positions: usize) -> Option<Comparison> {
+    let shadow = current()?;
+    let shadow_trades = shadow.trades.lock().await.clone();
+    let live = summarize(live_trades, live_open_positions);
+    let summary = summarize(&shadow_trades, shadow.holdings.lock().await.len());
+    let shadow_wallet = shadow.ledger.lock().await.status();
+    let shadow_skipped = shadow.skipped.lock().await.clone();
+    Some(Comparison {
+        shadow_config_path: shadow.live.shadow_config_path.clone(),
+        realized_pnl_diff_sol: summary.realized_pnl_sol - live.realized_pnl_sol,
+        win_rate_diff: summary.win_rate - live.win_rate,
+        mints: overlap(live_trades, &shadow_trades),
+        live,
+        shadow: summary,
+        shadow_wallet,
+        shadow_skipped,
+        shadow_trades,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_and_overlap_count_only_landed_buys() {
+        let buy = |mint: &str| TradeRecord { amount_sol: 0.1, ..trade(mint, "buy", 6) };
+        let sell = |mint: &str, pnl: f64| TradeRecord { profit_loss: Some(pnl), ..trade(mint, "sell", 6) };
+        let live = vec![
+            buy("A"),
+            buy("B"),
+            TradeRecord { reason: Some("FAILED: slippage".into()), ..trade("C", "buy", 6) },
+            sell("A", 0.05),
+            sell("B", -0.02),
+        ];
+        let shadow = vec![buy("B"), buy("D"), sell("D", 0.01)];
+
+        let summary = summarize(&live, 0);
+        assert_eq!((summary.buys, summary.failed_buys, summary.sells), (2, 1, 2));
+        assert_eq!((summary.wins, summary.losses), (1, 1));
+        assert!((summary.win_rate - 0.5).abs() < 1e-12);
+        assert!((summary.realized_pnl_sol - 0.03).abs() < 1e-12);
+
+        let mints = overlap(&live, &shadow);
+        assert_eq!(mints.both, vec!["B"]);
+        assert_eq!(mints.only_live, vec!["A"]);
+        assert_eq!(mints.only_shadow, vec!["D"]);
+    }
+
+    fn curve(virtual_sol_reserves: u64) -> BondingCurveState {
+        BondingCurveState {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: virtual_sol_reserves - 30_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: None,
+            is_mayhem_mode: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn enters_on_the_pipeline_quote_and_exits_on_the_polled_curve() {
+        let mut strategy = Settings::from_file("config.example.toml").unwrap();
+        strategy.enable_safer_sniping = false;
+        strategy.timeout_secs = 600;
+        strategy.tp_levels = vec![crate::settings::TpLevel { trigger_percent: 50.0, sell_percent: 100.0 }];
+        let shadow = Shadow::new(strategy.clone(), Arc::new(strategy.clone()));
+
+        let entry = curve(31_000_000_000);
+        let price = entry.spot_price_sol_per_token().unwrap();
+        let quote = Quote { price, curve: Some(entry.clone()), decimals: 6, curve_seeded: true };
+        shadow.enter("Mint", &quote).await.unwrap();
+        let tokens = ((strategy.buy_amount / price) * 1e6) as u64;
+        let buy = paper::buy_fill(strategy.buy_amount, tokens, Some(&entry), price, 6, &strategy).unwrap();
+        assert_eq!(shadow.holdings.lock().await["Mint"].amount, tokens);
+        assert_eq!(shadow.trades.lock().await[0].actual_sol_change, Some(buy.net_sol()));
+
+        // No curve read this round: nothing to decide on before the timeout
+        shadow.check_exits_at(&HashMap::new()).await;
+        assert_eq!(shadow.trades.lock().await.len(), 1);
+
+        let up = curve(62_000_000_000);
+        let up_price = up.spot_price_sol_per_token().unwrap();
+        shadow.check_exits_at(&HashMap::from([("Mint".to_string(), up.clone())])).await;
+        let sell = paper::sell_fill(tokens, Some(&up), up_price, 6, true, &strategy).unwrap();
+        let trades = shadow.trades.lock().await;
+        assert_eq!((trades[0].trade_type.as_str(), trades[0].price_per_token), ("sell", up_price));
+        assert_eq!(trades[0].actual_sol_change, Some(sell.net_sol()));
+        assert!(shadow.holdings.lock().await.is_empty());
+        let balance = shadow.ledger.lock().await.balance_sol();
+        assert!((balance - (strategy.paper_starting_balance_sol + buy.net_sol() + sell.net_sol())).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,113 @@
+//! Background tasks of the running engine.
+//!
+//! While an engine is live, [`spawn`] puts every task it starts on the
+//! engine's behalf (feeds, per-message pipeline runs, position actors, the
+//! TP/SL evaluations, refreshers, resubscribes, the shadow loop) in one
+//! `JoinSet`, so [`Engine::stop`](crate::Engine::stop) can abort them and
+//! wait until every one has finished. The set doubles as the process's
+//! engine slot: [`open`] fails while another engine holds it. Outside an
+//! engine (CLI commands, unit tests) `spawn` is a plain `tokio::spawn`.
+
+use log::{debug, error};
+use once_cell::sync::Lazy;
+use std::future::{poll_fn, Future};
+use std::sync::Mutex;
+use std::task::Poll;
+use tokio::task::{JoinError, JoinSet};
+
+enum Scope {
+    Open(JoinSet<()>),
+    /// Stopping: tasks still running are being drained; new ones are dropped
+    Closing(JoinSet<()>),
+}
+
+static SCOPE: Lazy<Mutex<Option<Scope>>> = Lazy::new(|| Mutex::new(None));
+
+/// Engine tests, and tests of the process-wide state an engine resets when
+/// it stops, take turns on this.
+#[cfg(test)]
+pub(crate) static TEST_TURN: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Claim the engine slot. Fails while another engine is running in this process.
+pub(crate) fn open() -> Result<(), String> {
+    let mut scope = SCOPE.lock().unwrap();
+    if scope.is_some() {
+        return Err("an engine is already running in this process; stop it before starting another".to_string());
+    }
+    *scope = Some(Scope::Open(JoinSet::new()));
+    Ok(())
+}
+
+/// Run `task` in the background, owned by the running engine if there is one.
+pub(crate) fn spawn<F>(task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut scope = SCOPE.lock().unwrap();
+    match scope.as_mut() {
+        Some(Scope::Open(set)) => {
+            // Reap finished tasks so per-message runs don't pile up
+            while let Some(done) = set.try_join_next() {
+                log_failure(done);
+            }
+            set.spawn(task);
+        }
+        Some(Scope::Closing(_)) => {
+            debug!("Engine is stopping; not starting a new background task");
+        }
+        None => {
+            tokio::spawn(task);
+        }
+    }
+}
+
+fn log_failure(done: Result<(), JoinError>) {
+    if let Err(e) = done {
+        if e.is_panic() {
+            error!("A background task panicked: {:?}", e);
+        }
+    }
+}
+
+/// Wait for the next engine task to finish; None once none are left.
+async fn join_next() -> Option<Result<(), JoinError>> {
+    poll_fn(|cx| match SCOPE.lock().unwrap().as_mut() {
+        Some(Scope::Open(set) | Scope::Closing(set)) => set.poll_join_next(cx),
+        None => Poll::Ready(None),
+    })
+    .await
+}
+
+/// Wait until every engine task has ended on its own, then free the slot.
+pub(crate) async fn wait() {
+    while let Some(done) = join_next().await {
+        log_failure(done);
+    }
+    *SCOPE.lock().unwrap() = None;
+}
+
+/// Abort every engine task without waiting for them and free the slot.
+pub(crate) fn abort() {
+    // Dropping the set aborts what is left in it
+    let scope = SCOPE.lock().unwrap().take();
+    drop(scope);
+}
+
+/// Abort every engine task, wait for all of them to finish and free the slot.
+/// Returns how many tasks were drained.
+pub(crate) async fn close() -> usize {
+    {
+        let mut scope = SCOPE.lock().unwrap();
+        if let Some(Scope::Open(mut set) | Scope::Closing(mut set)) = scope.take() {
+            set.abort_all();
+            *scope = Some(Scope::Closing(set));
+        }
+    }
+    let mut drained = 0;
+    while let Some(done) = join_next().await {
+        drained += 1;
+        log_failure(done);
+    }
+    *SCOPE.lock().unwrap() = None;
+    drained
+}
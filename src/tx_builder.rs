@@ -9,22 +9,7 @@ use std::collections::HashMap;
 use log::{debug, warn};
 
 const SYSTEM_PROGRAM_PUBKEY: &str = "11111111111111111111111111111111";
-const TOKEN_PROGRAM_PUBKEY: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
 const TOKEN_2022_PROGRAM_PUBKEY: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
-// fee_program address from IDL
-const FEE_PROGRAM_PUBKEY: &str = "pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ";
-
-/// Fee config PDA seed constant from pump.fun program IDL.
-/// This 32-byte value is the second element in the seeds array used for deriving the fee_config PDA
-/// from the fee program (pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ).
-/// 
-/// The fee_config PDA is derived as: find_program_address(&[b"fee_config", &FEE_CONFIG_SEED], fee_program)
-/// where b"fee_config" is the first seed and FEE_CONFIG_SEED is the second seed in the array.
-/// 
-/// DO NOT modify this value without verifying against the pump.fun program's on-chain
-/// implementation, as incorrect seeds will cause transaction failures with AccountNotInitialized errors.
-const FEE_CONFIG_SEED: [u8; 32] = [1, 86, 224, 246, 147, 102, 90, 207, 68, 219, 21, 104, 191, 23, 91, 170, 81, 137, 203, 151, 245, 210, 255, 59, 101, 93, 43, 182, 253, 109, 24, 176];
-
 #[derive(BorshSerialize)]
 pub struct BuyArgs {
     pub amount: u64,
@@ -39,16 +24,6 @@ pub struct SellArgs {
     pub min_sol_output: u64,
 }
 
-// Discriminators from pumpfun IDL (8 bytes) - DEPRECATED: Use IDL-derived discriminators instead
-// Kept as fallback for backward compatibility when IDL is not available
-// Using plain buy instruction (not buy_exact_sol_in)
-pub const BUY_DISCRIMINATOR_FALLBACK: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
-pub const SELL_DISCRIMINATOR_FALLBACK: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
-
-// For backward compatibility exports
-pub const BUY_DISCRIMINATOR: [u8; 8] = BUY_DISCRIMINATOR_FALLBACK;
-pub const SELL_DISCRIMINATOR: [u8; 8] = SELL_DISCRIMINATOR_FALLBACK;
-
 /// Get discriminator for buy instruction, preferring IDL over computed value
 fn get_buy_discriminator(idl_opt: Option<&SimpleIdl>) -> [u8; 8] {
     if let Some(idl) = idl_opt {
@@ -93,18 +68,17 @@ fn get_sell_discriminator(idl_opt: Option<&SimpleIdl>) -> [u8; 8] {
 /// This PDA is required by both buy and sell instructions.
 /// 
 /// # Returns
-/// The derived fee_config PDA public key
+/// The derived fee_config PDA public key for the configured cluster
 /// 
 /// # Errors
-/// Returns an error if the FEE_PROGRAM_PUBKEY cannot be parsed
-fn derive_fee_config_pda() -> Result<Pubkey, Box<dyn std::error::Error + Send + Sync>> {
-    let fee_program_pk = Pubkey::from_str(FEE_PROGRAM_PUBKEY)?;
-    let (fee_config_pda, _) = Pubkey::find_program_address(&[b"fee_config", &FEE_CONFIG_SEED], &fee_program_pk);
-    Ok(fee_config_pda)
+/// Returns an error if the cluster address book is invalid
+pub(crate) fn derive_fee_config_pda(settings: &Settings) -> Result<Pubkey, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(settings.address_book()?.fee_config_pda())
 }
 
 
 
+#[allow(clippy::too_many_arguments)]
 pub fn build_buy_instruction(
     program_id: &Pubkey,
     mint: &str,
@@ -114,7 +88,7 @@ pub fn build_buy_instruction(
     user: &Pubkey,
     fee_recipient: &Pubkey,
     creator_pubkey: Option<Pubkey>,
-    _settings: &Settings,
+    settings: &Settings,
 ) -> Result<Instruction, Box<dyn std::error::Error + Send + Sync>> {
     
     let args = BuyArgs {
@@ -210,8 +184,10 @@ pub fn build_buy_instruction(
     accounts.push(AccountMeta::new_readonly(*program_id, false));            // 11: program
     accounts.push(AccountMeta::new(global_vol_acc, false));                  // 12: global_vol_acc
     accounts.push(AccountMeta::new(user_vol_acc, false));                    // 13: user_vol_acc
-    accounts.push(AccountMeta::new_readonly(derive_fee_config_pda()?, false)); // 14: fee_config
-    accounts.push(AccountMeta::new_readonly(Pubkey::from_str(FEE_PROGRAM_PUBKEY)?, false)); // 15: fee_program
+    let book = settings.address_book()?;
+    let fee_config = crate::hot_cache::fee_config_pda().unwrap_or_else(|| book.fee_config_pda());
+    accounts.push(AccountMeta::new_readonly(fee_config, false)); // 14: fee_config
+    accounts.push(AccountMeta::new_readonly(book.fee_program, false)); // 15: fee_program
 
     // Use fallback discriminator
     let discriminator = get_buy_discriminator(None);
@@ -222,6 +198,7 @@ pub fn build_buy_instruction(
     Ok(Instruction { program_id: *program_id, accounts, data })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn build_sell_instruction(
     program_id: &Pubkey,
     mint: &str,
@@ -230,7 +207,7 @@ pub fn build_sell_instruction(
     user: &Pubkey,
     fee_recipient: &Pubkey,
     creator_pubkey: Option<Pubkey>,
-    _settings: &Settings,
+    settings: &Settings,
 ) -> Result<Instruction, Box<dyn std::error::Error + Send + Sync>> {
     let args = SellArgs {
         amount,
@@ -255,8 +232,7 @@ pub fn build_sell_instruction(
     context.insert("feeRecipient".to_string(), *fee_recipient);
     // NOTE: The pump.fun IDL includes `feeProgram` in both buy and sell instructions.
     // For sell, we add it to the context here so the IDL resolver can use it.
-    let fee_program_pubkey = Pubkey::from_str(FEE_PROGRAM_PUBKEY)
-        .map_err(|e| format!("Invalid fee_program pubkey: {}", e))?;
+    let fee_program_pubkey = settings.address_book()?.fee_program;
     context.insert("fee_program".to_string(), fee_program_pubkey);
     context.insert("feeProgram".to_string(), fee_program_pubkey);
     
@@ -318,8 +294,10 @@ pub fn build_sell_instruction(
     accounts.push(AccountMeta::new_readonly(token_program_pk, false)); // 9: token_program (Token-2022)
     accounts.push(AccountMeta::new_readonly(event_authority, false));        // 10: event_authority
     accounts.push(AccountMeta::new_readonly(*program_id, false));            // 11: program
-    accounts.push(AccountMeta::new_readonly(derive_fee_config_pda()?, false)); // 12: fee_config
-    accounts.push(AccountMeta::new_readonly(Pubkey::from_str(FEE_PROGRAM_PUBKEY)?, false)); // 13: fee_program
+    let book = settings.address_book()?;
+    let fee_config = crate::hot_cache::fee_config_pda().unwrap_or_else(|| book.fee_config_pda());
+    accounts.push(AccountMeta::new_readonly(fee_config, false)); // 12: fee_config
+    accounts.push(AccountMeta::new_readonly(book.fee_program, false)); // 13: fee_program
 
     // Use fallback discriminator
     let discriminator = get_sell_discriminator(None);
@@ -1,14 +1,12 @@
-use crate::{settings::Settings, Holding, PriceCache};
+use crate::{models::{Holding, PriceCache}, settings::Settings};
 use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine};
 use futures_util::{stream::StreamExt, SinkExt};
 use log::{debug, error, info, warn};
 use lru::LruCache;
 use serde_json::{json, Value};
-use solana_client::rpc_client::RpcClient;
-use solana_program::pubkey::Pubkey;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use std::{
     collections::HashMap,
-    str::FromStr,
     sync::Arc,
     time::Instant,
 };
@@ -29,16 +27,27 @@ pub enum WsRequest {
     GetHealth {
         resp: oneshot::Sender<WsHealth>,
     },
+    /// One-shot `signatureSubscribe`; `notify` receives the notification's
+    /// slot and `err` value (see `confirm.rs`).
+    SignatureSubscribe {
+        signature: String,
+        notify: oneshot::Sender<crate::confirm::SignatureNotification>,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct WsHealth {
+    /// False while the supervisor is waiting to reconnect this endpoint
+    pub connected: bool,
     pub active_subs: usize,
     pub pending_subs: usize,
     pub recent_timeouts: usize,
     pub is_healthy: bool,
 }
 
+/// One connection to `wss_url`. Returns when the connection fails or drops;
+/// `ws_supervisor` restarts it and keeps `control_rx` across sessions.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_ws(
     wss_url: &str,
     tx: mpsc::Sender<String>,
@@ -46,455 +55,538 @@ pub async fn run_ws(
     _seen: Arc<Mutex<LruCache<String, ()>>>,
     holdings: Arc<Mutex<HashMap<String, Holding>>>,
     price_cache: Arc<Mutex<PriceCache>>,
-    mut control_rx: mpsc::Receiver<WsRequest>,
+    control_rx: &mut mpsc::Receiver<WsRequest>,
     settings: Arc<Settings>,
-    rpc_client: Arc<RpcClient>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // ---------- outer re-connect loop ----------
-    loop {
-        let (ws_stream, _) = connect_async(wss_url).await?;
-        let (mut write, mut read) = ws_stream.split();
-
-        debug!(
-            "WSS {} connected (max_create_to_buy_secs={})",
-            wss_url,
-            settings.max_create_to_buy_secs
-        );
-
-        // pump.fun program logs
-        // Only subscribe to logs if PumpPortal is NOT enabled (otherwise we get duplicate detections)
-        if !settings.pumpportal_enabled {
-            write
-                .send(Message::Text(
-                    json!({
-                        "jsonrpc": "2.0",
-                        "id": 1,
-                        "method": "logsSubscribe",
-                        "params": [
-                            { "mentions": [ &settings.pump_fun_program ] },
-                            { "commitment": "confirmed" }
-                        ]
-                    })
-                    .to_string(),
-                ))
-                .await?;
-        }
-
-        // bonding-curve accounts for everything we already hold — subscribe to
-        // the bonding_curve PDA (not the token vault). The BondingCurveState is
-        // dynamic and contains both virtual reserves needed for price calculation.
-        let holdings_accounts = {
-            let holdings = holdings.lock().await;
-            let pump_prog = Pubkey::from_str(&settings.pump_fun_program)?;
-            holdings
-                .keys()
-                .filter_map(|mint_str| {
-                    Pubkey::from_str(mint_str).ok().map(|mint_pk| {
-                        let (pda, _) = Pubkey::find_program_address(
-                            &[b"bonding-curve", mint_pk.as_ref()],
-                            &pump_prog,
-                        );
-                        pda.to_string()
-                    })
+    _rpc_client: Arc<RpcClient>,
+    price_tick_tx: crate::monitor::PriceTickSender,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (ws_stream, _) = connect_async(wss_url).await?;
+    let (mut write, mut read) = ws_stream.split();
+    crate::ws_supervisor::mark_connected(wss_url).await;
+
+    debug!(
+        "WSS {} connected (max_create_to_buy_secs={})",
+        wss_url,
+        settings.max_create_to_buy_secs
+    );
+
+    // pump.fun program logs
+    // With PumpPortal on, only subscribe when both feeds are wanted; detections
+    // are then deduplicated by mint (see dedup.rs)
+    if !settings.pumpportal_enabled || settings.logs_detection_with_pumpportal {
+        write
+            .send(Message::Text(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "logsSubscribe",
+                    "params": [
+                        { "mentions": [ &settings.pump_fun_program ] },
+                        { "commitment": "confirmed" }
+                    ]
                 })
-                .collect::<Vec<_>>()
-        };
-
-        for acc in holdings_accounts {
-            write
-                .send(Message::Text(
-                    json!({
-                        "jsonrpc": "2.0",
-                        "id": 2,
-                        "method": "accountSubscribe",
-                        "params": [ acc, { "commitment": "confirmed", "encoding": "base64" } ]
-                    })
-                    .to_string(),
-                ))
-                .await?;
-        }
+                .to_string(),
+            ))
+            .await?;
+    }
 
-        // ---------- runtime state ----------
-        let mut req_id_counter: i64 = 1000;
-        let mut active_sub_count: usize = 0;
-        let mut subid_to_mint: HashMap<u64, (String, Instant, String)> = HashMap::new();
-        let mut pending_sub: HashMap<i64, (oneshot::Sender<Result<u64, String>>, Instant)> = HashMap::new();
-        let mut recent_timeouts: usize = 0;
-        let mut last_successful_sub: Option<Instant> = None;
-
-        const CURVE_DISCRIM: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
-
-        // ---------- inner event loop ----------
-        loop {
-            tokio::select! {
-                // ---------- websocket incoming ----------
-                msg = read.next() => {
-                    let msg = match msg {
-                        Some(Ok(m)) => m,
-                        Some(Err(e)) => { error!("WS read error: {}", e); break; }
-                        None => { error!("WS stream ended"); break; }
-                    };
+    // Slot heartbeat: a live stream delivers slotNotification several times a
+    // second, so silence beyond ws_stale_timeout_secs means it has stalled.
+    write
+        .send(Message::Text(
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "slotSubscribe" }).to_string(),
+        ))
+        .await?;
+
+    // ---------- runtime state ----------
+    let mut req_id_counter: i64 = 1000;
+    let mut active_sub_count: usize = 0;
+    let mut subid_to_mint: HashMap<u64, (String, Instant, String)> = HashMap::new();
+    let mut pending_sub: HashMap<i64, (oneshot::Sender<Result<u64, String>>, Instant)> = HashMap::new();
+    let mut recent_timeouts: usize = 0;
+    let mut last_successful_sub: Option<Instant> = None;
+    // signatureSubscribe: pending by request id, then active by subscription id.
+    // They auto-cancel after the first notification and don't count against max_subs_per_wss.
+    let mut pending_sig: HashMap<i64, (String, oneshot::Sender<crate::confirm::SignatureNotification>)> = HashMap::new();
+    let mut sig_subs: HashMap<u64, (String, oneshot::Sender<crate::confirm::SignatureNotification>)> = HashMap::new();
+
+    // Keepalive: ping on a timer, reconnect when nothing arrives for too long
+    let mut last_message = Instant::now();
+    let stale_after = std::time::Duration::from_secs(settings.ws_stale_timeout_secs);
+    let ping_every = std::time::Duration::from_secs(settings.ws_ping_interval_secs);
+    let mut ping_timer = tokio::time::interval_at(tokio::time::Instant::now() + ping_every, ping_every);
+    let mut watchdog = tokio::time::interval(std::time::Duration::from_secs(1));
+    // An interval, not a per-iteration sleep: the heartbeat keeps the loop busy
+    let cleanup_every = std::time::Duration::from_secs(settings.sub_ttl_secs.clamp(1, 30));
+    let mut cleanup_timer = tokio::time::interval_at(tokio::time::Instant::now() + cleanup_every, cleanup_every);
+
+    const CURVE_DISCRIM: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+
+    // ---------- inner event loop ----------
+    loop {
+        tokio::select! {
+            // ---------- websocket incoming ----------
+            msg = read.next() => {
+                let msg = match msg {
+                    Some(Ok(m)) => m,
+                    Some(Err(e)) => return Err(format!("read error: {}", e).into()),
+                    None => return Err("stream ended".into()),
+                };
+
+                let text = match msg {
+                    Message::Text(t) => t,
+                    Message::Close(_) => return Err("close frame received".into()),
+                    _ => continue,
+                };
+                // Pings and pongs do not prove the subscriptions are streaming
+                last_message = Instant::now();
+
+                let value: Value = match serde_json::from_str(&text) {
+                    Ok(v) => v,
+                    Err(e) => { debug!("JSON parse error: {}", e); continue; }
+                };
+
+                // Heartbeat only; receiving it already refreshed last_message
+                if value.get("method").and_then(|m| m.as_str()) == Some("slotNotification") {
+                    continue;
+                }
 
-                    let text = match msg {
-                        Message::Text(t) => t,
-                        Message::Close(_) => { error!("WS close frame"); break; }
-                        _ => continue,
-                    };
+                // Only forward notifications (which contain `params`) into the
+                // main processing channel. RPC responses that only contain
+                // `id`/`result` are handled locally here and shouldn't be
+                // forwarded (they previously triggered "missing params" logs).
+                if value.get("params").is_some() {
+                    // FILTER: Only forward "logsNotification" (new coins).
+                    // "accountNotification" (price updates) are high volume and handled
+                    // locally in this loop; forwarding them floods the `tx` channel
+                    // and blocks the bot from receiving new coin events from PumpPortal.
+                    if let Some(method) = value.get("method").and_then(|m| m.as_str()) {
+                        if method == "logsNotification" {
+                            let _ = tx.send(text.clone()).await;
+                        }
+                    }
+                }
 
-                    let value: Value = match serde_json::from_str(&text) {
-                        Ok(v) => v,
-                        Err(e) => { debug!("JSON parse error: {}", e); continue; }
-                    };
+                // ---- signature subscription response ----
+                if let Some(id) = value.get("id").and_then(|v| v.as_i64()) {
+                    if let Some((signature, notify)) = pending_sig.remove(&id) {
+                        match value.get("result").and_then(|r| r.as_u64()) {
+                            Some(sub_id) => { sig_subs.insert(sub_id, (signature, notify)); }
+                            None => {
+                                let _ = notify.send(Err(format!("signatureSubscribe refused: {}", text)));
+                            }
+                        }
+                        continue;
+                    }
+                }
 
-                    // Only forward notifications (which contain `params`) into the
-                    // main processing channel. RPC responses that only contain
-                    // `id`/`result` are handled locally here and shouldn't be
-                    // forwarded (they previously triggered "missing params" logs).
-                    if value.get("params").is_some() {
-                        // FILTER: Only forward "logsNotification" (new coins).
-                        // "accountNotification" (price updates) are high volume and handled
-                        // locally in this loop; forwarding them floods the `tx` channel
-                        // and blocks the bot from receiving new coin events from PumpPortal.
-                        if let Some(method) = value.get("method").and_then(|m| m.as_str()) {
-                            if method == "logsNotification" {
-                                let _ = tx.send(text.clone()).await;
+                // ---- subscription response ----
+                if let (Some(id), Some(result)) =
+                    (value.get("id").and_then(|v| v.as_i64()), value.get("result"))
+                {
+                    if let Some((responder, _timestamp)) = pending_sub.remove(&id) {
+                        if let Some(sub_id) = result.as_u64() {
+                            // Move any placeholder mapping keyed by request-id to the
+                            // actual subscription id returned by the RPC. Earlier we
+                            // insert a placeholder entry using the request id so
+                            // that we can preserve mint/account info until the
+                            // RPC responds with the real sub id. If we don't
+                            // remap, account notifications (which carry the real
+                            // subscription id) will not be associated with the
+                            // intended mint.
+                            if let Some(entry) = subid_to_mint.remove(&(id as u64)) {
+                                subid_to_mint.insert(sub_id, entry);
+                                debug!("Subscription confirmed: req_id={} -> sub_id={}", id, sub_id);
                             }
+                            last_successful_sub = Some(Instant::now());
+                            // Reset timeout counter on success
+                            if recent_timeouts > 0 {
+                                recent_timeouts = recent_timeouts.saturating_sub(1);
+                            }
+                            let _ = responder.send(Ok(sub_id));
+                        } else {
+                            // Subscription failed - decrement counter
+                            active_sub_count = active_sub_count.saturating_sub(1);
+                            let _ = responder.send(Err(format!(
+                                "subscribe result missing subscription id: {}",
+                                text
+                            )));
                         }
+                        continue;
                     }
+                }
 
-                    // ---- subscription response ----
-                    if let (Some(id), Some(result)) =
-                        (value.get("id").and_then(|v| v.as_i64()), value.get("result"))
-                    {
-                        if let Some((responder, _timestamp)) = pending_sub.remove(&id) {
-                            if let Some(sub_id) = result.as_u64() {
-                                // Move any placeholder mapping keyed by request-id to the
-                                // actual subscription id returned by the RPC. Earlier we
-                                // insert a placeholder entry using the request id so
-                                // that we can preserve mint/account info until the
-                                // RPC responds with the real sub id. If we don't
-                                // remap, account notifications (which carry the real
-                                // subscription id) will not be associated with the
-                                // intended mint.
-                                if let Some(entry) = subid_to_mint.remove(&(id as u64)) {
-                                    subid_to_mint.insert(sub_id, entry);
-                                    debug!("Subscription confirmed: req_id={} -> sub_id={}", id, sub_id);
-                                }
-                                last_successful_sub = Some(Instant::now());
-                                // Reset timeout counter on success
-                                if recent_timeouts > 0 {
-                                    recent_timeouts = recent_timeouts.saturating_sub(1);
-                                }
-                                let _ = responder.send(Ok(sub_id));
+                // ---- unsubscribe response ----
+                if let Some(id) = value.get("id").and_then(|v| v.as_i64()) {
+                    if id == -1 {
+                        // This is an unsubscribe response (we use id=-1 for unsubscribes)
+                        // The result should be a boolean indicating success
+                        if let Some(result) = value.get("result") {
+                            if result.as_bool() == Some(true) {
+                                debug!("Unsubscribe confirmed");
                             } else {
-                                // Subscription failed - decrement counter
-                                active_sub_count = active_sub_count.saturating_sub(1);
-                                let _ = responder.send(Err(format!(
-                                    "subscribe result missing subscription id: {}",
-                                    text
-                                )));
+                                debug!("Unsubscribe response: {:?}", result);
                             }
-                            continue;
                         }
+                        continue;
                     }
+                }
 
-                    // ---- unsubscribe response ----
-                    if let Some(id) = value.get("id").and_then(|v| v.as_i64()) {
-                        if id == -1 {
-                            // This is an unsubscribe response (we use id=-1 for unsubscribes)
-                            // The result should be a boolean indicating success
-                            if let Some(result) = value.get("result") {
-                                if result.as_bool() == Some(true) {
-                                    debug!("Unsubscribe confirmed");
-                                } else {
-                                    debug!("Unsubscribe response: {:?}", result);
-                                }
-                            }
-                            continue;
+                // ---- account notification ----
+                if let Some(params) = value.get("params") {
+                    let sub_id = match params.get("subscription").and_then(|v| v.as_u64()) {
+                        Some(s) => s,
+                        None => continue,
+                    };
+
+                    // ---- signature notification ----
+                    if value.get("method").and_then(|m| m.as_str()) == Some("signatureNotification") {
+                        if let Some((signature, notify)) = sig_subs.remove(&sub_id) {
+                            let result = params.get("result");
+                            let slot = result
+                                .and_then(|r| r.get("context"))
+                                .and_then(|c| c.get("slot"))
+                                .and_then(|s| s.as_u64())
+                                .unwrap_or(0);
+                            let err = result
+                                .and_then(|r| r.get("value"))
+                                .and_then(|v| v.get("err"))
+                                .cloned()
+                                .unwrap_or(Value::Null);
+                            debug!("signatureNotification for {} (slot {})", signature, slot);
+                            let _ = notify.send(Ok((slot, err)));
                         }
+                        continue;
                     }
 
-                    // ---- account notification ----
-                    if let Some(params) = value.get("params") {
-                        let sub_id = match params.get("subscription").and_then(|v| v.as_u64()) {
-                            Some(s) => s,
-                            None => continue,
-                        };
-                        let (mint, last, _account_pubkey) = match subid_to_mint.get_mut(&sub_id) {
-                            Some(v) => v,
-                            None => continue,
-                        };
-                        *last = Instant::now();
+                    let (mint, last, _account_pubkey) = match subid_to_mint.get_mut(&sub_id) {
+                        Some(v) => v,
+                        None => continue,
+                    };
+                    *last = Instant::now();
 
-                        let data_arr = match params
-                            .get("result")
-                            .and_then(|v| v.get("value"))
-                            .and_then(|v| v.get("data"))
-                            .and_then(|v| v.as_array())
-                        {
-                            Some(a) => a,
-                            None => continue,
-                        };
-                        let encoded = match data_arr.first().and_then(|v| v.as_str()) {
-                            Some(e) => e,
-                            None => continue,
+                    let data_arr = match params
+                        .get("result")
+                        .and_then(|v| v.get("value"))
+                        .and_then(|v| v.get("data"))
+                        .and_then(|v| v.as_array())
+                    {
+                        Some(a) => a,
+                        None => continue,
+                    };
+                    let encoded = match data_arr.first().and_then(|v| v.as_str()) {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    let decoded = match Base64Engine.decode(encoded) {
+                        Ok(d) => d,
+                        Err(e) => { debug!("base64 decode error: {}", e); continue; }
+                    };
+
+                    // ---- bonding-curve account ----
+                    if decoded.len() >= 8 && decoded[..8] == CURVE_DISCRIM[..] {
+                        if decoded.len() < 8 + 41 {
+                            error!("curve account too short for sub {}", sub_id);
+                            continue;
+                        }
+                        let slice2 = &decoded[8..];
+                        let vtok = if let Ok(bytes) = slice2[0..8].try_into() {
+                            u64::from_le_bytes(bytes)
+                        } else {
+                            error!("Failed to convert slice to u64 for vtok");
+                            continue;
                         };
-                        let decoded = match Base64Engine.decode(encoded) {
-                            Ok(d) => d,
-                            Err(e) => { debug!("base64 decode error: {}", e); continue; }
+                        let vsol = if let Ok(bytes) = slice2[8..16].try_into() {
+                            u64::from_le_bytes(bytes)
+                        } else {
+                            error!("Failed to convert slice to u64 for vsol");
+                            continue;
                         };
+                        let complete = slice2[40] != 0;
 
-                        // ---- bonding-curve account ----
-                        if decoded.len() >= 8 && decoded[..8] == CURVE_DISCRIM[..] {
-                            if decoded.len() < 8 + 41 {
-                                error!("curve account too short for sub {}", sub_id);
-                                continue;
-                            }
-                            let slice2 = &decoded[8..];
-                            let vtok = if let Ok(bytes) = slice2[0..8].try_into() {
-                                u64::from_le_bytes(bytes)
-                            } else {
-                                error!("Failed to convert slice to u64 for vtok");
-                                continue;
-                            };
-                            let vsol = if let Ok(bytes) = slice2[8..16].try_into() {
-                                u64::from_le_bytes(bytes)
-                            } else {
-                                error!("Failed to convert slice to u64 for vsol");
-                                continue;
-                            };
-                            let complete = slice2[40] != 0;
-
-                            if complete {
-                                error!("Bonding curve state reports migrated for sub {} mint {}", sub_id, mint);
-                                continue;
-                            }
+                        if complete {
+                            error!("Bonding curve state reports migrated for sub {} mint {}", sub_id, mint);
+                            continue;
+                        }
 
-                            if vtok == 0 {
-                                error!("Error: virtual_token_reserves is zero for mint {} (sub {})", mint, sub_id);
-                                continue;
-                            }
+                        if vtok == 0 {
+                            error!("Error: virtual_token_reserves is zero for mint {} (sub {})", mint, sub_id);
+                            continue;
+                        }
+
+                        // Compute price in SOL per token using the virtual reserves.
+                        // pump.fun tokens always use 6 decimals — no RPC call needed.
+                        // Formula: price = (vsol/1e9) / (vtok/1e6)  ≡  (vsol/vtok) * 1e-3
+                        let price_in_sol_per_token = (vsol as f64 / vtok as f64) * 1e-3;
 
-                            // Compute price in SOL per token using the virtual reserves.
-                            // pump.fun tokens always use 6 decimals — no RPC call needed.
-                            // Formula: price = (vsol/1e9) / (vtok/1e6)  ≡  (vsol/vtok) * 1e-3
-                            let price_in_sol_per_token = (vsol as f64 / vtok as f64) * 1e-3;
-
-                            // Update price cache immediately (before logging or broadcasting)
-                            {
-                                let mut cache = price_cache.lock().await;
-                                let prev_price = cache.get(mint).map(|(_, p)| *p);
-                                cache.put(mint.clone(), (Instant::now(), price_in_sol_per_token));
-
-                                if let Some(prev) = prev_price {
-                                    let denom = if prev.abs() < 1e-18 { 1e-18 } else { prev };
-                                    let pct_last = (price_in_sol_per_token - prev) / denom * 100.0;
-                                    if pct_last.abs() > 0.01 {
-                                        debug!(
-                                            "WSS price for {}: {:.18} -> {:.18} SOL ({:+.4}%)",
-                                            mint, prev, price_in_sol_per_token, pct_last
-                                        );
-                                    }
-                                } else {
-                                    info!(
-                                        "WSS initial price for {}: {:.18} SOL",
-                                        mint, price_in_sol_per_token
+                        // Update price cache immediately (before logging or broadcasting)
+                        {
+                            let mut cache = price_cache.lock().await;
+                            let prev_price = cache.get(mint).map(|(_, p)| *p);
+                            cache.put(mint.clone(), (Instant::now(), price_in_sol_per_token));
+
+                            if let Some(prev) = prev_price {
+                                let denom = if prev.abs() < 1e-18 { 1e-18 } else { prev };
+                                let pct_last = (price_in_sol_per_token - prev) / denom * 100.0;
+                                if pct_last.abs() > 0.01 {
+                                    debug!(
+                                        "WSS price for {}: {:.18} -> {:.18} SOL ({:+.4}%)",
+                                        mint, prev, price_in_sol_per_token, pct_last
                                     );
                                 }
+                            } else {
+                                info!(
+                                    "WSS initial price for {}: {:.18} SOL",
+                                    mint, price_in_sol_per_token
+                                );
                             }
+                        }
+
+                        // Wake the monitor so TP/SL for this mint is evaluated now
+                        crate::monitor::publish_price_tick(&price_tick_tx, mint, price_in_sol_per_token);
 
-                            // Compute PnL from holdings — use lock() to guarantee we get
-                            // the data (try_lock can miss when holdings mutex is contended).
-                            let mut profit_percent: f64 = 0.0;
-                            let mut pnl_sol: f64 = 0.0;
-                            let mut buy_price: f64 = 0.0;
-                            let mut amount: u64 = 0;
-                            {
-                                let holdings_guard = holdings.lock().await;
-                                if let Some(h) = holdings_guard.get(mint) {
-                                    buy_price = h.buy_price;
-                                    amount = h.amount;
-                                    if buy_price.abs() >= 1e-18 {
-                                        profit_percent = (price_in_sol_per_token - buy_price) / buy_price * 100.0;
-                                        // PnL in SOL = (current_price - buy_price) * tokens
-                                        let tokens = amount as f64 / 1_000_000.0;
-                                        pnl_sol = (price_in_sol_per_token - buy_price) * tokens;
-                                    }
+                        // Compute PnL from holdings — use lock() to guarantee we get
+                        // the data (try_lock can miss when holdings mutex is contended).
+                        let mut profit_percent: f64 = 0.0;
+                        let mut pnl_sol: f64 = 0.0;
+                        let mut buy_price: f64 = 0.0;
+                        let mut amount: u64 = 0;
+                        {
+                            let holdings_guard = holdings.lock().await;
+                            if let Some(h) = holdings_guard.get(mint) {
+                                buy_price = h.buy_price;
+                                amount = h.amount;
+                                if buy_price.abs() >= 1e-18 {
+                                    profit_percent = (price_in_sol_per_token - buy_price) / buy_price * 100.0;
+                                    // PnL in SOL = (current_price - buy_price) * tokens
+                                    let tokens = amount as f64 / 1_000_000.0;
+                                    pnl_sol = (price_in_sol_per_token - buy_price) * tokens;
                                 }
                             }
-
-                            // Broadcast price + PnL update to frontend immediately
-                            let _ = ws_tx.send(serde_json::json!({
-                                "type": "price-update",
-                                "mint": mint,
-                                "price": price_in_sol_per_token,
-                                "profit_percent": profit_percent,
-                                "pnl_sol": pnl_sol,
-                                "buy_price": buy_price,
-                                "amount": amount
-                            }).to_string());
-
-                            continue;
                         }
 
-                        // We no longer subscribe to SPL token accounts. All price
-                        // information comes from the bonding_curve PDA stream. If the
-                        // account data doesn't match a known shape above, ignore it.
-
-                        debug!("unrecognised account data shape for sub {} (len={})", sub_id, decoded.len());
+                        // Broadcast price + PnL update to frontend immediately
+                        let _ = ws_tx.send(serde_json::json!({
+                            "type": "price-update",
+                            "mint": mint,
+                            "price": price_in_sol_per_token,
+                            "profit_percent": profit_percent,
+                            "pnl_sol": pnl_sol,
+                            "buy_price": buy_price,
+                            "amount": amount
+                        }).to_string());
+
+                        continue;
                     }
 
+                    // We no longer subscribe to SPL token accounts. All price
+                    // information comes from the bonding_curve PDA stream. If the
+                    // account data doesn't match a known shape above, ignore it.
+
+                    debug!("unrecognised account data shape for sub {} (len={})", sub_id, decoded.len());
                 }
 
-                // ---------- control channel ----------
-                Some(req) = control_rx.recv() => {
-                    match req {
-                        WsRequest::GetHealth { resp } => {
-                            // Check if connection is healthy based on recent activity
-                            let is_healthy = recent_timeouts < 3 && (
-                                last_successful_sub.is_none() || 
-                                last_successful_sub.map(|t| t.elapsed().as_secs() < 300).unwrap_or(false)
-                            );
-                            let _ = resp.send(WsHealth {
-                                active_subs: active_sub_count,
-                                pending_subs: pending_sub.len(),
-                                recent_timeouts,
-                                is_healthy,
-                            });
-                        }
-                        WsRequest::Subscribe { account, mint, resp } => {
-                            // Fast-fail if connection appears unhealthy
-                            if recent_timeouts >= 5 {
-                                warn!("WSS connection unhealthy (recent_timeouts={}), rejecting subscription for {}", recent_timeouts, mint);
-                                let _ = resp.send(Err(format!("WSS connection degraded (timeouts={})", recent_timeouts)));
-                                continue;
-                            }
-                            
-                            if active_sub_count >= settings.max_subs_per_wss {
-                                let _ = resp.send(Err(format!(
-                                    "max subscriptions reached on this WSS ({})",
-                                    settings.max_subs_per_wss
-                                )));
-                            } else {
-                                req_id_counter += 1;
-                                let id = req_id_counter;
-                                let req_json = json!({
-                                    "jsonrpc": "2.0",
-                                    "id": id,
-                                    "method": "accountSubscribe",
-                                    "params": [ account.clone(), { "commitment": "confirmed", "encoding": "base64" } ]
-                                })
-                                .to_string();
-                                if let Err(e) = write.send(Message::Text(req_json)).await {
-                                    error!("subscribe send error for {}: {}", mint, e);
-                                    let _ = resp.send(Err(format!("failed to send subscribe request: {}", e)));
-                                } else {
-                                    // Store pending subscription with timestamp for timeout tracking
-                                    pending_sub.insert(id, (resp, Instant::now()));
-                                    subid_to_mint.insert(
-                                        id as u64, // placeholder – will be overwritten when RPC answers
-                                        (mint.clone(), Instant::now(), account),
-                                    );
-                                    // Increment counter optimistically - will decrement if subscription fails
-                                    active_sub_count += 1;
-                                    debug!("Sent subscribe request for {} (req_id={}, active={}/{})", mint, id, active_sub_count, settings.max_subs_per_wss);
-                                }
-                            }
+            }
+
+            // ---------- control channel ----------
+            Some(req) = control_rx.recv() => {
+                match req {
+                    WsRequest::GetHealth { resp } => {
+                        // Check if connection is healthy based on recent activity
+                        let is_healthy = recent_timeouts < 3 && (
+                            last_successful_sub.is_none() || 
+                            last_successful_sub.map(|t| t.elapsed().as_secs() < 300).unwrap_or(false)
+                        );
+                        let _ = resp.send(WsHealth {
+                            connected: true,
+                            active_subs: active_sub_count,
+                            pending_subs: pending_sub.len(),
+                            recent_timeouts,
+                            is_healthy,
+                        });
+                    }
+                    WsRequest::Subscribe { account, mint, resp } => {
+                        // Fast-fail if connection appears unhealthy
+                        if recent_timeouts >= 5 {
+                            warn!("WSS connection unhealthy (recent_timeouts={}), rejecting subscription for {}", recent_timeouts, mint);
+                            let _ = resp.send(Err(format!("WSS connection degraded (timeouts={})", recent_timeouts)));
+                            continue;
                         }
-                        WsRequest::Unsubscribe { sub_id, resp } => {
+                        
+                        if active_sub_count >= settings.max_subs_per_wss {
+                            let _ = resp.send(Err(format!(
+                                "max subscriptions reached on this WSS ({})",
+                                settings.max_subs_per_wss
+                            )));
+                        } else {
+                            req_id_counter += 1;
+                            let id = req_id_counter;
                             let req_json = json!({
                                 "jsonrpc": "2.0",
-                                "id": -1,
-                                "method": "accountUnsubscribe",
-                                "params": [ sub_id ]
+                                "id": id,
+                                "method": "accountSubscribe",
+                                "params": [ account.clone(), { "commitment": "confirmed", "encoding": "base64" } ]
                             })
                             .to_string();
                             if let Err(e) = write.send(Message::Text(req_json)).await {
-                                error!("failed to send unsubscribe for sub {}: {}", sub_id, e);
-                                let _ = resp.send(Err(format!("failed to send unsubscribe: {}", e)));
+                                error!("subscribe send error for {}: {}", mint, e);
+                                let _ = resp.send(Err(format!("failed to send subscribe request: {}", e)));
                             } else {
-                                // Remove from tracking and decrement counter
-                                if let Some((mint, _, _)) = subid_to_mint.remove(&sub_id) {
-                                    active_sub_count = active_sub_count.saturating_sub(1);
-                                    debug!("Sent unsubscribe for {} sub {} (active={}/{})", mint, sub_id, active_sub_count, settings.max_subs_per_wss);
-                                } else {
-                                    debug!("Sent unsubscribe for unknown sub {} (active={}/{})", sub_id, active_sub_count, settings.max_subs_per_wss);
-                                }
-                                let _ = resp.send(Ok(()));
+                                // Store pending subscription with timestamp for timeout tracking
+                                pending_sub.insert(id, (resp, Instant::now()));
+                                subid_to_mint.insert(
+                                    id as u64, // placeholder – will be overwritten when RPC answers
+                                    (mint.clone(), Instant::now(), account),
+                                );
+                                // Increment counter optimistically - will decrement if subscription fails
+                                active_sub_count += 1;
+                                debug!("Sent subscribe request for {} (req_id={}, active={}/{})", mint, id, active_sub_count, settings.max_subs_per_wss);
                             }
                         }
                     }
-                }
-
-                // ---------- periodic TTL clean-up ----------
-                _ = tokio::time::sleep(std::time::Duration::from_secs(settings.sub_ttl_secs.min(30))) => {
-                    let now = Instant::now();
-                    
-                    // Clean up timed-out pending subscription requests
-                    let timed_out_pending: Vec<i64> = pending_sub
-                        .iter()
-                        .filter_map(|(req_id, (_sender, timestamp))| {
-                            if now.duration_since(*timestamp).as_secs() > settings.wss_subscribe_timeout_secs {
-                                Some(*req_id)
-                            } else {
-                                None
-                            }
+                    WsRequest::SignatureSubscribe { signature, notify } => {
+                        req_id_counter += 1;
+                        let id = req_id_counter;
+                        let req_json = json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "method": "signatureSubscribe",
+                            "params": [ signature.clone(), { "commitment": "confirmed" } ]
                         })
-                        .collect();
-                    
-                    if !timed_out_pending.is_empty() {
-                        recent_timeouts = recent_timeouts.saturating_add(timed_out_pending.len());
-                        warn!("WSS {} timing out {} subscriptions (recent_timeouts={})", wss_url, timed_out_pending.len(), recent_timeouts);
-                    }
-                    
-                    for req_id in timed_out_pending {
-                        if let Some((sender, _)) = pending_sub.remove(&req_id) {
-                            // Decrement active count since this subscription never completed
-                            active_sub_count = active_sub_count.saturating_sub(1);
-                            // Remove placeholder mapping
-                            subid_to_mint.remove(&(req_id as u64));
-                            let _ = sender.send(Err(format!("subscription request timed out after {}s", settings.wss_subscribe_timeout_secs)));
-                            debug!("Cleaned up timed-out pending subscription req_id={} (active={}/{})", req_id, active_sub_count, settings.max_subs_per_wss);
+                        .to_string();
+                        if let Err(e) = write.send(Message::Text(req_json)).await {
+                            let _ = notify.send(Err(format!("failed to send signatureSubscribe: {}", e)));
+                        } else {
+                            pending_sig.insert(id, (signature, notify));
                         }
                     }
-                    
-                    // Clean up stale active subscriptions based on TTL
-                    let to_remove: Vec<u64> = subid_to_mint
-                        .iter()
-                        .filter_map(|(sid, (mint, last, _))| {
-                            // Skip placeholder entries (these are tracked in pending_sub)
-                            if pending_sub.contains_key(&(*sid as i64)) {
-                                return None;
-                            }
-                            if now.duration_since(*last).as_secs() > settings.sub_ttl_secs {
-                                debug!("Subscription {} for {} is stale ({}s since last update, TTL={}s)", sid, mint, now.duration_since(*last).as_secs(), settings.sub_ttl_secs);
-                                Some(*sid)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-
-                    for sid in to_remove {
+                    WsRequest::Unsubscribe { sub_id, resp } => {
                         let req_json = json!({
                             "jsonrpc": "2.0",
                             "id": -1,
                             "method": "accountUnsubscribe",
-                            "params": [ sid ]
+                            "params": [ sub_id ]
                         })
                         .to_string();
                         if let Err(e) = write.send(Message::Text(req_json)).await {
-                            error!("failed to send unsubscribe for stale sub {}: {}", sid, e);
-                        } else if let Some((mint, _, _)) = subid_to_mint.remove(&sid) {
-                            active_sub_count = active_sub_count.saturating_sub(1);
-                            debug!("Unsubscribed stale sub {} for {} (active={}/{})", sid, mint, active_sub_count, settings.max_subs_per_wss);
+                            error!("failed to send unsubscribe for sub {}: {}", sub_id, e);
+                            let _ = resp.send(Err(format!("failed to send unsubscribe: {}", e)));
+                        } else {
+                            // Remove from tracking and decrement counter
+                            if let Some((mint, _, _)) = subid_to_mint.remove(&sub_id) {
+                                active_sub_count = active_sub_count.saturating_sub(1);
+                                debug!("Sent unsubscribe for {} sub {} (active={}/{})", mint, sub_id, active_sub_count, settings.max_subs_per_wss);
+                            } else {
+                                debug!("Sent unsubscribe for unknown sub {} (active={}/{})", sub_id, active_sub_count, settings.max_subs_per_wss);
+                            }
+                            let _ = resp.send(Ok(()));
                         }
                     }
                 }
             }
-        }
 
-        info!("WS connection closed; reconnecting in 2 s …");
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            // ---------- keepalive ----------
+            _ = ping_timer.tick() => {
+                if let Err(e) = write.send(Message::Ping(Vec::new())).await {
+                    return Err(format!("ping failed: {}", e).into());
+                }
+            }
+
+            // ---------- stale-stream watchdog ----------
+            _ = watchdog.tick() => {
+                crate::ws_supervisor::note_activity(wss_url, last_message).await;
+                if last_message.elapsed() > stale_after {
+                    crate::ws_supervisor::note_stale(wss_url).await;
+                    return Err(format!("no message for {}s, stream stalled", last_message.elapsed().as_secs()).into());
+                }
+            }
+
+            // ---------- periodic TTL clean-up ----------
+            _ = cleanup_timer.tick() => {
+                let now = Instant::now();
+
+                // Drop signature watches whose waiter already gave up
+                pending_sig.retain(|_, (_, notify)| !notify.is_closed());
+                let abandoned: Vec<u64> = sig_subs
+                    .iter()
+                    .filter(|(_, (_, notify))| notify.is_closed())
+                    .map(|(sid, _)| *sid)
+                    .collect();
+                for sid in abandoned {
+                    sig_subs.remove(&sid);
+                    let req_json = json!({
+                        "jsonrpc": "2.0",
+                        "id": -1,
+                        "method": "signatureUnsubscribe",
+                        "params": [ sid ]
+                    })
+                    .to_string();
+                    if let Err(e) = write.send(Message::Text(req_json)).await {
+                        debug!("failed to send signatureUnsubscribe for {}: {}", sid, e);
+                    }
+                }
+                
+                // Clean up timed-out pending subscription requests
+                let timed_out_pending: Vec<i64> = pending_sub
+                    .iter()
+                    .filter_map(|(req_id, (_sender, timestamp))| {
+                        if now.duration_since(*timestamp).as_secs() > settings.wss_subscribe_timeout_secs {
+                            Some(*req_id)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                
+                if !timed_out_pending.is_empty() {
+                    recent_timeouts = recent_timeouts.saturating_add(timed_out_pending.len());
+                    warn!("WSS {} timing out {} subscriptions (recent_timeouts={})", wss_url, timed_out_pending.len(), recent_timeouts);
+                }
+                
+                for req_id in timed_out_pending {
+                    if let Some((sender, _)) = pending_sub.remove(&req_id) {
+                        // Decrement active count since this subscription never completed
+                        active_sub_count = active_sub_count.saturating_sub(1);
+                        // Remove placeholder mapping
+                        subid_to_mint.remove(&(req_id as u64));
+                        let _ = sender.send(Err(format!("subscription request timed out after {}s", settings.wss_subscribe_timeout_secs)));
+                        debug!("Cleaned up timed-out pending subscription req_id={} (active={}/{})", req_id, active_sub_count, settings.max_subs_per_wss);
+                    }
+                }
+                
+                // Clean up stale active subscriptions based on TTL
+                let to_remove: Vec<u64> = subid_to_mint
+                    .iter()
+                    .filter_map(|(sid, (mint, last, _))| {
+                        // Skip placeholder entries (these are tracked in pending_sub)
+                        if pending_sub.contains_key(&(*sid as i64)) {
+                            return None;
+                        }
+                        if now.duration_since(*last).as_secs() > settings.sub_ttl_secs {
+                            debug!("Subscription {} for {} is stale ({}s since last update, TTL={}s)", sid, mint, now.duration_since(*last).as_secs(), settings.sub_ttl_secs);
+                            Some(*sid)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+
+                for sid in to_remove {
+                    let req_json = json!({
+                        "jsonrpc": "2.0",
+                        "id": -1,
+                        "method": "accountUnsubscribe",
+                        "params": [ sid ]
+                    })
+                    .to_string();
+                    if let Err(e) = write.send(Message::Text(req_json)).await {
+                        error!("failed to send unsubscribe for stale sub {}: {}", sid, e);
+                    } else if let Some((mint, _, _)) = subid_to_mint.remove(&sid) {
+                        active_sub_count = active_sub_count.saturating_sub(1);
+                        debug!("Unsubscribed stale sub {} for {} (active={}/{})", sid, mint, active_sub_count, settings.max_subs_per_wss);
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,460 @@
+//! Supervision for the Solana WSS and PumpPortal websocket workers.
+//!
+//! `ws::run_ws` and `pumpportal::run_pumpportal_ws` each run a single
+//! connection. The supervisors here restart them with exponential backoff
+//! (`ws_reconnect_base_ms` doubling up to `ws_reconnect_max_secs`). While a
+//! Solana endpoint is down its control channel is still drained: health
+//! queries report it as disconnected and subscribe requests fail fast, so
+//! `select_healthy_wss` routes around it. Every drop is announced on a
+//! channel; `run_migrator` moves the price subscriptions that lived on the
+//! dead connection to a healthy endpoint so no open position loses its feed.
+//!
+//! The workers themselves ping every `ws_ping_interval_secs` and give up on a
+//! stream that stays silent past its stale timeout; they report the age of the
+//! last frame here so it shows up in `/api/health`.
+
+use crate::{
+    models::{Holding, PriceCache},
+    position::PositionRegistry,
+    settings::Settings,
+    ws::{self, WsHealth, WsRequest},
+};
+use log::{debug, info, warn};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+/// A session that stayed up this long resets the backoff.
+const STABLE_SESSION: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    kind: &'static str,
+    connected_since: Option<Instant>,
+    down_since: Option<Instant>,
+    consecutive_failures: u32,
+    restarts: u64,
+    stale_reconnects: u64,
+    last_error: Option<String>,
+    last_message: Option<Instant>,
+}
+
+static ENDPOINTS: Lazy<Mutex<HashMap<String, EndpointState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+async fn register(url: &str, kind: &'static str) {
+    ENDPOINTS.lock().await.entry(url.to_string()).or_default().kind = kind;
+}
+
+/// Called by the workers once the websocket handshake succeeded.
+pub async fn mark_connected(url: &str) {
+    let mut endpoints = ENDPOINTS.lock().await;
+    let state = endpoints.entry(url.to_string()).or_default();
+    if let Some(since) = state.down_since.take() {
+        info!(
+            "Websocket {} back up after {:.1}s",
+            crate::rpc_pool::redact_url(url),
+            since.elapsed().as_secs_f64()
+        );
+    }
+    state.connected_since = Some(Instant::now());
+}
+
+/// Called by the workers' watchdogs (once a second) with the time of the
+/// last frame received.
+pub async fn note_activity(url: &str, last_message: Instant) {
+    ENDPOINTS.lock().await.entry(url.to_string()).or_default().last_message = Some(last_message);
+}
+
+/// Called when a watchdog gives up on a silent stream.
+pub async fn note_stale(url: &str) {
+    let mut endpoints = ENDPOINTS.lock().await;
+    endpoints.entry(url.to_string()).or_default().stale_reconnects += 1;
+    warn!("Websocket {} stalled; forcing reconnect", crate::rpc_pool::redact_url(url));
+}
+
+/// Record a failed session; returns the consecutive failure count used for backoff.
+async fn mark_down(url: &str, error: &str) -> u32 {
+    let mut endpoints = ENDPOINTS.lock().await;
+    let state = endpoints.entry(url.to_string()).or_default();
+    if state.connected_since.take().is_some_and(|t| t.elapsed() >= STABLE_SESSION) {
+        state.consecutive_failures = 0;
+    }
+    state.consecutive_failures += 1;
+    state.restarts += 1;
+    state.down_since.get_or_insert_with(Instant::now);
+    state.last_error = Some(error.to_string());
+    state.consecutive_failures
+}
+
+/// True while at least one connection that can detect new tokens is up:
+/// PumpPortal, or a Solana WSS when it carries the logs feed.
+pub async fn detection_feeds_up(settings: &Settings) -> bool {
+    let logs_feed = !settings.pumpportal_enabled || settings.logs_detection_with_pumpportal;
+    ENDPOINTS.lock().await.values().any(|s| {
+        s.connected_since.is_some()
+            && match s.kind {
+                "pumpportal" => settings.pumpportal_enabled,
+                "solana" => logs_feed,
+                _ => false,
+            }
+    })
+}
+
+/// Delay before the restart following the `failures`-th consecutive failure.
+pub fn backoff(failures: u32, settings: &Settings) -> Duration {
+    let max_ms = settings.ws_reconnect_max_secs.saturating_mul(1000);
+    let factor = 1u64 << failures.saturating_sub(1).min(20);
+    Duration::from_millis(settings.ws_reconnect_base_ms.saturating_mul(factor).min(max_ms))
+}
+
+/// Answer a control request while the connection is down.
+fn refuse(req: WsRequest, url: &str) {
+    match req {
+        WsRequest::GetHealth { resp } => {
+            let _ = resp.send(WsHealth {
+                connected: false,
+                active_subs: 0,
+                pending_subs: 0,
+                recent_timeouts: 0,
+                is_healthy: false,
+            });
+        }
+        WsRequest::Subscribe { mint, resp, .. } => {
+            debug!("Refusing subscription for {} while {} is down", mint, url);
+            let _ = resp.send(Err("WSS endpoint is reconnecting".to_string()));
+        }
+        // The subscription died with the connection
+        WsRequest::Unsubscribe { resp, .. } => {
+            let _ = resp.send(Ok(()));
+        }
+        WsRequest::SignatureSubscribe { notify, .. } => {
+            let _ = notify.send(Err("WSS endpoint is reconnecting".to_string()));
+        }
+    }
+}
+
+/// Run the Solana WSS worker behind `ws_control_senders[idx]` forever. Each drop is
+/// reported on `down_tx` so the migrator can move its subscriptions.
+#[allow(clippy::too_many_arguments)]
+pub async fn supervise_solana_ws(
+    idx: usize,
+    wss_url: String,
+    tx: mpsc::Sender<String>,
+    ws_tx: tokio::sync::broadcast::Sender<String>,
+    seen: Arc<Mutex<LruCache<String, ()>>>,
+    holdings: Arc<Mutex<HashMap<String, Holding>>>,
+    price_cache: Arc<Mutex<PriceCache>>,
+    mut control_rx: mpsc::Receiver<WsRequest>,
+    settings: Arc<Settings>,
+    rpc_client: Arc<RpcClient>,
+    price_tick_tx: crate::monitor::PriceTickSender,
+    down_tx: mpsc::UnboundedSender<usize>,
+) {
+    register(&wss_url, "solana").await;
+    loop {
+        let error = match ws::run_ws(
+            &wss_url,
+            tx.clone(),
+            ws_tx.clone(),
+            seen.clone(),
+            holdings.clone(),
+            price_cache.clone(),
+            &mut control_rx,
+            settings.clone(),
+            rpc_client.clone(),
+            price_tick_tx.clone(),
+        )
+        .await
+        {
+            Ok(()) => "connection closed".to_string(),
+            Err(e) => e.to_string(),
+        };
+        let failures = mark_down(&wss_url, &error).await;
+        let _ = down_tx.send(idx);
+        let delay = backoff(failures, &settings);
+        warn!(
+            "WSS {} down ({}); restart #{} in {:?}",
+            crate::rpc_pool::redact_url(&wss_url),
+            error,
+            failures,
+            delay
+        );
+
+        let restart = tokio::time::sleep(delay);
+        tokio::pin!(restart);
+        loop {
+            tokio::select! {
+                _ = &mut restart => break,
+                req = control_rx.recv() => match req {
+                    Some(req) => refuse(req, &wss_url),
+                    // Every sender is gone: the bot is shutting down
+                    None => return,
+                },
+            }
+        }
+    }
+}
+
+/// Run a PumpPortal worker forever, restarting it with backoff.
+pub async fn supervise_pumpportal(
+    pp_url: String,
+    tx: mpsc::Sender<String>,
+    settings: Arc<Settings>,
+    trades: Option<crate::pumpportal::TradeFeed>,
+) {
+    register(&pp_url, "pumpportal").await;
+    loop {
+        let error = match crate::pumpportal::run_pumpportal_ws(&pp_url, tx.clone(), settings.clone(), trades.clone()).await {
+            Ok(()) => "connection closed".to_string(),
+            Err(e) => e.to_string(),
+        };
+        if tx.is_closed() {
+            return;
+        }
+        let failures = mark_down(&pp_url, &error).await;
+        let delay = backoff(failures, &settings);
+        warn!("PumpPortal {} down ({}); restart #{} in {:?}", pp_url, error, failures, delay);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Select the healthiest WSS endpoint with available slots.
+/// Returns None if all endpoints are degraded or unavailable.
+pub async fn select_healthy_wss(
+    ws_control_senders: &Arc<Vec<mpsc::Sender<WsRequest>>>,
+    settings: &Settings,
+) -> Option<usize> {
+    if ws_control_senders.is_empty() {
+        return None;
+    }
+
+    let mut health_scores: Vec<(usize, i32)> = Vec::new();
+
+    for (idx, sender) in ws_control_senders.iter().enumerate() {
+        let (tx, rx) = oneshot::channel();
+        if sender.send(WsRequest::GetHealth { resp: tx }).await.is_ok() {
+            if let Ok(Ok(h)) = tokio::time::timeout(Duration::from_millis(100), rx).await {
+                if !h.connected {
+                    continue;
+                }
+                let mut score = 100;
+                score -= (h.recent_timeouts as i32) * 30; // Heavy penalty for timeouts
+                if h.active_subs >= settings.max_subs_per_wss {
+                    score -= 1000; // Reject full endpoints
+                }
+                score -= (h.pending_subs as i32) * 15; // Penalty for pending work
+                if h.is_healthy {
+                    score += 50;
+                }
+                health_scores.push((idx, score));
+            }
+        }
+    }
+
+    health_scores.sort_by_key(|h| std::cmp::Reverse(h.1));
+    health_scores
+        .first()
+        .filter(|(_, score)| *score > 0)
+        .map(|(idx, _)| *idx)
+}
+
+/// Move price subscriptions off endpoints reported on `down_rx`.
+pub async fn run_migrator(
+    mut down_rx: mpsc::UnboundedReceiver<usize>,
+    ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    positions: PositionRegistry,
+    settings: Arc<Settings>,
+) {
+    while let Some(idx) = down_rx.recv().await {
+        let stranded: Vec<String> = positions
+            .list()
+            .await
+            .into_iter()
+            .filter(|p| !p.state.is_terminal() && p.subscription.is_some_and(|(i, _)| i == idx))
+            .map(|p| p.mint)
+            .collect();
+        if stranded.is_empty() {
+            continue;
+        }
+        info!("Migrating {} price subscription(s) off WSS #{}", stranded.len(), idx);
+        for mint in stranded {
+            // Dead subscription: clear it without sending an unsubscribe
+            positions.set_subscription(&mint, None).await;
+            crate::tasks::spawn(resubscribe(mint, ws_control_senders.clone(), positions.clone(), settings.clone()));
+        }
+    }
+}
+
+/// Subscribe `mint`'s bonding curve on a healthy endpoint, retrying with
+/// backoff until it succeeds or the position is gone.
+async fn resubscribe(
+    mint: String,
+    ws_control_senders: Arc<Vec<mpsc::Sender<WsRequest>>>,
+    positions: PositionRegistry,
+    settings: Arc<Settings>,
+) {
+    let curve_pda = match (Pubkey::from_str(&mint), Pubkey::from_str(&settings.pump_fun_program)) {
+        (Ok(mint_pk), Ok(program)) => {
+            Pubkey::find_program_address(&[b"bonding-curve", mint_pk.as_ref()], &program).0
+        }
+        _ => return,
+    };
+    let mut attempt: u32 = 0;
+    loop {
+        match positions.state(&mint).await {
+            Some(state) if !state.is_terminal() => {}
+            _ => return,
+        }
+        // Another path (monitor fallback) may have resubscribed meanwhile
+        if positions.subscription(&mint).await.is_some() {
+            return;
+        }
+        if let Some(idx) = select_healthy_wss(&ws_control_senders, &settings).await {
+            let (resp, rx) = oneshot::channel();
+            let request = WsRequest::Subscribe { account: curve_pda.to_string(), mint: mint.clone(), resp };
+            if ws_control_senders[idx].send(request).await.is_ok() {
+                if let Ok(Ok(Ok(sub_id))) =
+                    tokio::time::timeout(Duration::from_secs(settings.wss_subscribe_timeout_secs), rx).await
+                {
+                    if let Some((prev_idx, prev_sub)) = positions.set_subscription(&mint, Some((idx, sub_id))).await {
+                        // Lost the race: release the one we replaced
+                        let (u_tx, _u_rx) = oneshot::channel();
+                        let _ = ws_control_senders[prev_idx]
+                            .send(WsRequest::Unsubscribe { sub_id: prev_sub, resp: u_tx })
+                            .await;
+                    }
+                    info!("Moved price subscription for {} to WSS #{} (sub {})", mint, idx, sub_id);
+                    return;
+                }
+            }
+        }
+        attempt += 1;
+        tokio::time::sleep(backoff(attempt, &settings)).await;
+    }
+}
+
+/// Per-endpoint websocket status, for `/api/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct WsEndpointStatus {
+    pub url: String,
+    pub kind: &'static str,
+    pub connected: bool,
+    pub down_for_secs: Option<u64>,
+    pub restarts: u64,
+    /// Reconnects forced by the stale-stream watchdog
+    pub stale_reconnects: u64,
+    pub last_error: Option<String>,
+    pub last_message_age_ms: Option<u64>,
+}
+
+/// Forget the engine's feed endpoints when it stops.
+pub(crate) async fn reset() {
+    ENDPOINTS.lock().await.clear();
+}
+
+pub async fn status() -> Vec<WsEndpointStatus> {
+    let endpoints = ENDPOINTS.lock().await;
+    let mut out: Vec<WsEndpointStatus> = endpoints
+        .iter()
+        .map(|(url, s)| WsEndpointStatus {
+            url: crate::rpc_pool::redact_url(url),
+            kind: s.kind,
+            connected: s.connected_since.is_some(),
+            down_for_secs: s.down_since.map(|t| t.elapsed().as_secs()),
+            restarts: s.restarts,
+            stale_reconnects: s.stale_reconnects,
+            last_error: s.last_error.clone(),
+            last_message_age_ms: s.last_message.map(|t| t.elapsed().as_millis() as u64),
+        })
+        .collect();
+    out.sort_by(|a, b| a.url.cmp(&b.url));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.ws_reconnect_base_ms = 500;
+        settings.ws_reconnect_max_secs = 5;
+        assert_eq!(backoff(1, &settings), Duration::from_millis(500));
+        assert_eq!(backoff(2, &settings), Duration::from_millis(1_000));
+        assert_eq!(backoff(4, &settings), Duration::from_millis(4_000));
+        assert_eq!(backoff(5, &settings), Duration::from_secs(5));
+        assert_eq!(backoff(60, &settings), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn down_endpoint_is_skipped_by_selection() {
+        let settings = Settings::from_file("config.example.toml").unwrap();
+        let (down_tx, mut down_rx) = mpsc::channel::<WsRequest>(8);
+        let (up_tx, mut up_rx) = mpsc::channel::<WsRequest>(8);
+        tokio::spawn(async move {
+            while let Some(req) = down_rx.recv().await {
+                refuse(req, "ws://down");
+            }
+        });
+        tokio::spawn(async move {
+            while let Some(req) = up_rx.recv().await {
+                if let WsRequest::GetHealth { resp } = req {
+                    let _ = resp.send(WsHealth {
+                        connected: true,
+                        active_subs: 0,
+                        pending_subs: 0,
+                        recent_timeouts: 0,
+                        is_healthy: true,
+                    });
+                }
+            }
+        });
+        let senders = Arc::new(vec![down_tx, up_tx]);
+        assert_eq!(select_healthy_wss(&senders, &settings).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn silent_stream_is_abandoned() {
+        let _turn = crate::tasks::TEST_TURN.lock().await;
+        // Accepts the websocket, then never sends a frame
+        let cluster = crate::mock_cluster::MockCluster::start().await.unwrap();
+        let url = cluster.pumpportal_url();
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.pumpportal_stale_timeout_secs = 1;
+        let (tx, _rx) = mpsc::channel(8);
+
+        let started = Instant::now();
+        let err = crate::pumpportal::run_pumpportal_ws(&url, tx, Arc::new(settings), None).await.unwrap_err();
+        assert!(err.to_string().contains("stalled"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let report = status().await.into_iter().find(|s| s.url == url).unwrap();
+        assert_eq!(report.stale_reconnects, 1);
+        assert!(report.last_message_age_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn stream_of_only_pings_is_stale() {
+        // Keeps the socket busy with control frames but never sends data
+        let cluster = crate::mock_cluster::MockCluster::start().await.unwrap();
+        cluster.ping_pumpportal(Some(Duration::from_millis(200)));
+        let url = cluster.pumpportal_url();
+        let mut settings = Settings::from_file("config.example.toml").unwrap();
+        settings.pumpportal_stale_timeout_secs = 1;
+        let (tx, _rx) = mpsc::channel(8);
+
+        let started = Instant::now();
+        let err = crate::pumpportal::run_pumpportal_ws(&url, tx, Arc::new(settings), None).await.unwrap_err();
+        assert!(err.to_string().contains("stalled"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}